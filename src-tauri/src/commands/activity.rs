@@ -0,0 +1,29 @@
+//! Activity Commands
+//!
+//! 提供活动日志相关的 Tauri Commands。活动由数据库触发器在内容创建、修改、删除时记录。
+
+use crate::commands::project::{AppState, CommandError};
+use crate::models::ActivityEntry;
+use std::sync::Arc;
+use tauri::State;
+
+/// 默认每页条数
+const DEFAULT_FEED_LIMIT: u32 = 50;
+
+/// 获取活动动态（最新的在前）
+///
+/// `project_id` 为空时返回全部项目的动态；传入上一页最后一条的 `id` 作为 `before_id` 继续加载。
+#[tauri::command]
+pub fn activity_feed(
+    project_id: Option<String>,
+    before_id: Option<i64>,
+    limit: Option<u32>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<ActivityEntry>, CommandError> {
+    let entries = state.db.get_activity(
+        project_id.as_deref(),
+        before_id,
+        limit.unwrap_or(DEFAULT_FEED_LIMIT),
+    )?;
+    Ok(entries)
+}
@@ -0,0 +1,454 @@
+//! Project Archive Commands
+//!
+//! 提供项目归档导出、导入相关的 Tauri Commands，用于在不同资料库之间转交项目
+
+use crate::commands::project::{ensure_sources_available, unique_project_name, AppState, CommandError};
+use crate::models::{
+    ArchiveManifest, ContentKind, ContentSnapshot, Project, PROJECT_ARCHIVE_VERSION,
+};
+use crate::services::{is_safe_relative_path, ArchiveService};
+use chrono::Utc;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::State;
+
+/// 归档中数据文件所在目录
+const ARCHIVE_FILES_DIR: &str = "files";
+
+/// 导入项目时找不到原工作空间则归入的工作空间
+const FALLBACK_WORKSPACE: &str = "research";
+
+/// 将项目导出为归档文件（zip）
+///
+/// 归档包含清单、项目全部记录（来源、笔记、画布、思维导图、PPT、对话）与数据文件；
+/// `include_embeddings` 为 true 时同时包含向量索引。
+#[tauri::command]
+pub fn project_export_archive(
+    id: String,
+    path: String,
+    include_embeddings: Option<bool>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<ArchiveManifest, CommandError> {
+    export_project_archive(
+        &state,
+        &id,
+        Path::new(&path),
+        include_embeddings.unwrap_or(false),
+    )
+}
+
+/// 从归档文件导入项目
+///
+/// 所有条目分配新 ID，文件路径改写到新项目目录下，项目重名时追加后缀。
+#[tauri::command]
+pub fn project_import_archive(
+    path: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Project, CommandError> {
+    import_project_archive(&state, Path::new(&path))
+}
+
+fn export_project_archive(
+    state: &AppState,
+    id: &str,
+    output_path: &Path,
+    include_embeddings: bool,
+) -> Result<ArchiveManifest, CommandError> {
+    let snapshot = state.db.get_snapshot(ContentKind::Project, id)?;
+    write_snapshot_archive(state, id, snapshot, output_path, include_embeddings)
+}
+
+/// 将项目快照写入归档文件，快照中的文件路径为项目 `id` 下的磁盘路径
+pub(crate) fn write_snapshot_archive(
+    state: &AppState,
+    id: &str,
+    mut snapshot: ContentSnapshot,
+    output_path: &Path,
+    include_embeddings: bool,
+) -> Result<ArchiveManifest, CommandError> {
+    if !snapshot.sources.is_empty() {
+        ensure_sources_available(state, id)?;
+    }
+    let project_dir = state.file_service.project_dir(id);
+
+    // 数据文件路径改写为归档内路径：项目目录内的文件保留相对路径，其余放入 external/
+    let mut files: Vec<(String, PathBuf)> = Vec::new();
+    snapshot.map_paths(|path| {
+        let disk_path = PathBuf::from(path);
+        let relative = match disk_path.strip_prefix(&project_dir) {
+            Ok(relative) => relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+                .join("/"),
+            Err(_) => format!(
+                "external/{}_{}",
+                files.len(),
+                disk_path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            ),
+        };
+        let name = format!("{}/{}", ARCHIVE_FILES_DIR, relative);
+        if disk_path.is_file() && !files.iter().any(|(existing, _)| *existing == name) {
+            files.push((name.clone(), disk_path));
+        }
+        name
+    });
+
+    if let Some(project) = &mut snapshot.project {
+        project.path = String::new();
+    }
+    if !include_embeddings {
+        for record in &mut snapshot.sources {
+            record.embedding = None;
+        }
+        for record in &mut snapshot.notes {
+            record.embedding = None;
+        }
+    }
+
+    let manifest = ArchiveManifest {
+        version: PROJECT_ARCHIVE_VERSION,
+        exported_at: Utc::now(),
+        include_embeddings,
+        files: files.iter().map(|(name, _)| name.clone()).collect(),
+        snapshot,
+    };
+
+    ArchiveService::write(output_path, &manifest, &files)
+        .map_err(|e| CommandError::Internal(e.to_string()))?;
+
+    Ok(manifest)
+}
+
+fn import_project_archive(state: &AppState, archive_path: &Path) -> Result<Project, CommandError> {
+    import_archive_with(state, archive_path, |project| {
+        project.name = unique_project_name(state, &project.name, "导入")?;
+        Ok(())
+    })
+}
+
+/// 从归档创建新项目，`customize` 在写入数据库前调整项目信息（名称、工作空间等）
+pub(crate) fn import_archive_with(
+    state: &AppState,
+    archive_path: &Path,
+    customize: impl FnOnce(&mut Project) -> Result<(), CommandError>,
+) -> Result<Project, CommandError> {
+    let manifest = ArchiveService::read_manifest(archive_path)
+        .map_err(|e| CommandError::Validation(e.to_string()))?;
+    if manifest.version > PROJECT_ARCHIVE_VERSION {
+        return Err(CommandError::Validation(format!(
+            "归档版本 {} 高于当前支持的版本 {}",
+            manifest.version, PROJECT_ARCHIVE_VERSION
+        )));
+    }
+
+    let mut snapshot = manifest.snapshot;
+    if snapshot.project.is_none() {
+        return Err(CommandError::Validation("归档中缺少项目信息".to_string()));
+    }
+
+    let project_id = uuid::Uuid::new_v4().to_string();
+    let ids = snapshot.reassign_ids(&project_id);
+    let project_dir = state.file_service.project_dir(&project_id);
+
+    // 归档内路径改写到新项目目录，文件名中的旧 ID 替换为新 ID；
+    // 路径必须位于 files/ 下且只由普通路径段组成，否则整个归档视为无效
+    let mut entries: Vec<(String, PathBuf)> = Vec::new();
+    let mut unsafe_path = None;
+    snapshot.map_paths(|name| {
+        let relative = Path::new(name)
+            .strip_prefix(ARCHIVE_FILES_DIR)
+            .ok()
+            .filter(|relative| is_safe_relative_path(relative))
+            .map(|relative| replace_ids(&relative.to_string_lossy(), &ids))
+            .filter(|relative| is_safe_relative_path(Path::new(relative)));
+        let Some(relative) = relative else {
+            unsafe_path.get_or_insert_with(|| name.to_string());
+            return name.to_string();
+        };
+        let dest = project_dir.join(relative);
+        if manifest.files.iter().any(|file| file == name)
+            && !entries.iter().any(|(existing, _)| existing == name)
+        {
+            entries.push((name.to_string(), dest.clone()));
+        }
+        dest.display().to_string()
+    });
+    if let Some(name) = unsafe_path {
+        return Err(CommandError::Validation(format!(
+            "归档中的文件路径不安全: {}",
+            name
+        )));
+    }
+    state.file_service.create_project_dir(&project_id)?;
+
+    let result = ArchiveService::extract(archive_path, &entries)
+        .map_err(|e| CommandError::Validation(e.to_string()))
+        .and_then(|_| {
+            let workspaces = state.db.get_all_workspaces()?;
+            if let Some(project) = &mut snapshot.project {
+                customize(project)?;
+                project.path = project_dir.display().to_string();
+                project.updated_at = Utc::now();
+                if !workspaces.iter().any(|w| w.id == project.workspace) {
+                    project.workspace = FALLBACK_WORKSPACE.to_string();
+                }
+            }
+
+            state
+                .db
+                .transaction(|tx| tx.restore_snapshot(&snapshot))
+                .map_err(CommandError::from)
+        });
+
+    if let Err(e) = result {
+        let _ = state.file_service.delete_project_dir(&project_id);
+        return Err(e);
+    }
+    // 归档未包含向量时由后台按内容生成
+    state.embeddings.request_backfill();
+
+    let project = state.db.get_project(&project_id)?;
+    Ok(project)
+}
+
+/// 将路径中出现的旧 ID 替换为新 ID
+pub(crate) fn replace_ids(path: &str, ids: &HashMap<String, String>) -> String {
+    let mut path = path.to_string();
+    for (old_id, new_id) in ids {
+        if path.contains(old_id.as_str()) {
+            path = path.replace(old_id.as_str(), new_id);
+        }
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::models::{
+        ChatMessage, ChatSession, Citation, MessageRole, MindMap, Note, OutputType, ProjectIcon,
+        Source, SourceType,
+    };
+    use crate::services::{embed_text, FileService};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn create_test_state() -> (Arc<AppState>, TempDir) {
+        let temp_dir = TempDir::new().expect("创建临时目录失败");
+        let db = Database::new_in_memory().expect("创建数据库失败");
+        let file_service = FileService::new(temp_dir.path().join("library"));
+        file_service.init_base_dirs().expect("初始化目录失败");
+
+        let state = Arc::new(AppState::new(db, file_service));
+        (state, temp_dir)
+    }
+
+    /// 创建包含来源、笔记、思维导图与对话的项目
+    fn create_populated_project(state: &Arc<AppState>, name: &str) -> Project {
+        let id = uuid::Uuid::new_v4().to_string();
+        let project_path = state.file_service.create_project_dir(&id).unwrap();
+        let now = Utc::now();
+        let project = Project {
+            id: id.clone(),
+            name: name.to_string(),
+            icon: ProjectIcon::default(),
+            workspace: "research".to_string(),
+            is_starred: true,
+            created_at: now,
+            updated_at: now,
+            sources_count: 1,
+            path: project_path.display().to_string(),
+            archived_at: None,
+        };
+        state.db.insert_project(&project).unwrap();
+
+        let source_id = uuid::Uuid::new_v4().to_string();
+        let source_path = state
+            .file_service
+            .get_sources_dir(&id)
+            .join(format!("{}.md", source_id));
+        fs::write(&source_path, "来源全文").unwrap();
+        let source = Source {
+            id: source_id.clone(),
+            project_id: id.clone(),
+            name: "资料.md".to_string(),
+            source_type: SourceType::Markdown,
+            path: source_path.display().to_string(),
+            size: 12,
+            mime_type: "text/markdown".to_string(),
+            thumbnail_path: None,
+            created_at: now,
+            updated_at: now,
+        };
+        state
+            .db
+            .insert_source_with_content(&source, Some("来源全文"))
+            .unwrap();
+        state
+            .db
+            .upsert_source_embedding(&source_id, &id, &embed_text("来源全文"))
+            .unwrap();
+
+        let note_id = uuid::Uuid::new_v4().to_string();
+        let note_path = state
+            .file_service
+            .get_notes_dir(&id)
+            .join(format!("{}.md", note_id));
+        fs::write(&note_path, "# 读书笔记\n内容").unwrap();
+        let note = Note {
+            id: note_id,
+            project_id: id.clone(),
+            title: "读书笔记".to_string(),
+            path: note_path.display().to_string(),
+            output_type: OutputType::Note,
+            title_locked: false,
+            created_at: now,
+            updated_at: now,
+        };
+        state.db.insert_note(&note).unwrap();
+
+        let mindmap = MindMap {
+            id: uuid::Uuid::new_v4().to_string(),
+            project_id: id.clone(),
+            title: "导图".to_string(),
+            theme: "default".to_string(),
+            layout: "logicalStructure".to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+        state.db.insert_mindmap(&mindmap, "{\"root\":{}}").unwrap();
+
+        let session = ChatSession {
+            id: uuid::Uuid::new_v4().to_string(),
+            project_id: id.clone(),
+            title: "对话".to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+        state.db.insert_chat_session(&session).unwrap();
+        state
+            .db
+            .insert_chat_message(&ChatMessage {
+                id: uuid::Uuid::new_v4().to_string(),
+                session_id: session.id.clone(),
+                role: MessageRole::Assistant,
+                content: "回答 [1]".to_string(),
+                citations: Some(vec![Citation {
+                    index: 1,
+                    source_id,
+                    source_name: "资料.md".to_string(),
+                }]),
+                created_at: now,
+            })
+            .unwrap();
+
+        project
+    }
+
+    #[test]
+    fn test_export_and_import_archive() {
+        let (state, temp) = create_test_state();
+        let original = create_populated_project(&state, "共享项目");
+        let archive_path = temp.path().join("共享项目.zip");
+
+        let manifest = export_project_archive(&state, &original.id, &archive_path, false).unwrap();
+        assert_eq!(manifest.files.len(), 2);
+        assert!(manifest.files.iter().all(|f| f.starts_with("files/")));
+        assert!(manifest.snapshot.sources[0].embedding.is_none());
+
+        // 在另一个资料库中导入
+        let (other, _other_temp) = create_test_state();
+        let imported = import_project_archive(&other, &archive_path).unwrap();
+        assert_ne!(imported.id, original.id);
+        assert_eq!(imported.name, "共享项目");
+        assert!(imported.is_starred);
+        assert_eq!(imported.sources_count, 1);
+
+        let sources = other.db.get_sources_by_project(&imported.id).unwrap();
+        assert_eq!(sources.len(), 1);
+        assert_ne!(sources[0].id, manifest.snapshot.sources[0].source.id);
+        assert!(sources[0].path.starts_with(&imported.path));
+        assert!(sources[0].path.contains(&sources[0].id));
+        assert_eq!(fs::read_to_string(&sources[0].path).unwrap(), "来源全文");
+        assert_eq!(other.db.get_source_content(&sources[0].id).unwrap(), "来源全文");
+
+        let notes = other.db.get_notes_by_project(&imported.id).unwrap();
+        assert_eq!(fs::read_to_string(&notes[0].path).unwrap(), "# 读书笔记\n内容");
+        assert_eq!(other.db.get_mindmaps_by_project(&imported.id).unwrap().len(), 1);
+
+        // 未包含的向量留给后台生成，对话引用指向新的来源 ID
+        assert_eq!(other.db.list_sources_missing_embeddings(10).unwrap().len(), 1);
+        assert_eq!(other.db.list_notes_missing_embeddings(10).unwrap().len(), 1);
+        let sessions = other.db.get_chat_sessions_by_project(&imported.id).unwrap();
+        let messages = other.db.get_chat_messages_by_session(&sessions[0].id).unwrap();
+        let citations = messages[0].citations.as_ref().unwrap();
+        assert_eq!(citations[0].source_id, sources[0].id);
+    }
+
+    #[test]
+    fn test_import_resolves_name_conflict() {
+        let (state, temp) = create_test_state();
+        let original = create_populated_project(&state, "同名项目");
+        let archive_path = temp.path().join("archive.zip");
+        export_project_archive(&state, &original.id, &archive_path, true).unwrap();
+
+        // 导入到同一资料库：名称追加后缀，原项目不受影响
+        let first = import_project_archive(&state, &archive_path).unwrap();
+        let second = import_project_archive(&state, &archive_path).unwrap();
+        assert_eq!(first.name, "同名项目 (导入)");
+        assert_eq!(second.name, "同名项目 (导入 2)");
+        assert_eq!(state.db.get_sources_by_project(&original.id).unwrap().len(), 1);
+        assert_eq!(state.db.get_sources_by_project(&first.id).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_import_rejects_unsafe_paths() {
+        let (state, temp) = create_test_state();
+        let original = create_populated_project(&state, "篡改项目");
+        let archive_path = temp.path().join("original.zip");
+        let manifest = export_project_archive(&state, &original.id, &archive_path, false).unwrap();
+
+        let (other, other_temp) = create_test_state();
+        let outside = other_temp.path().join("outside.md");
+        let unsafe_paths = [
+            outside.display().to_string(),
+            "files/../../outside.md".to_string(),
+            "filesX/outside.md".to_string(),
+            "files".to_string(),
+        ];
+        for (index, path) in unsafe_paths.iter().enumerate() {
+            // 未列入 files 的路径同样需要校验
+            let mut crafted = manifest.clone();
+            crafted.files.clear();
+            crafted.snapshot.notes[0].note.path = path.clone();
+            let crafted_path = temp.path().join(format!("crafted-{}.zip", index));
+            ArchiveService::write(&crafted_path, &crafted, &[]).unwrap();
+
+            assert!(matches!(
+                import_project_archive(&other, &crafted_path),
+                Err(CommandError::Validation(_))
+            ));
+        }
+        assert!(other.db.get_all_projects().unwrap().is_empty());
+        assert_eq!(
+            fs::read_dir(other.file_service.projects_dir()).unwrap().count(),
+            0
+        );
+        assert!(!outside.exists());
+    }
+
+    #[test]
+    fn test_import_rejects_invalid_archive() {
+        let (state, temp) = create_test_state();
+        let bogus = temp.path().join("bogus.zip");
+        fs::write(&bogus, "not a zip").unwrap();
+        assert!(import_project_archive(&state, &bogus).is_err());
+        assert!(state.db.get_all_projects().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,257 @@
+//! Backup Commands
+//!
+//! 提供本地备份（定时备份、手动备份、备份列表与恢复）相关的 Tauri Commands
+
+use crate::commands::project::{AppState, CommandError};
+use crate::models::{BackupInfo, BackupSettings};
+use crate::services::{BackupError, BackupService};
+use chrono::Utc;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::State;
+
+/// 备份设置的 settings 键
+const BACKUP_SETTINGS_KEY: &str = "backup.settings";
+
+/// 定时备份的检查间隔
+const BACKUP_CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// 备份与恢复互斥执行（定时备份与手动操作可能同时触发）
+static BACKUP_LOCK: Mutex<()> = Mutex::new(());
+
+impl From<BackupError> for CommandError {
+    fn from(e: BackupError) -> Self {
+        match e {
+            BackupError::Io(e) => CommandError::Io(e.to_string()),
+            BackupError::Database(e) => CommandError::Database(e),
+            other => CommandError::Validation(other.to_string()),
+        }
+    }
+}
+
+/// 获取备份设置
+#[tauri::command]
+pub fn backup_get_settings(state: State<'_, Arc<AppState>>) -> Result<BackupSettings, CommandError> {
+    backup_settings(&state)
+}
+
+/// 保存备份设置
+#[tauri::command]
+pub fn backup_set_settings(
+    settings: BackupSettings,
+    state: State<'_, Arc<AppState>>,
+) -> Result<BackupSettings, CommandError> {
+    save_backup_settings(&state, settings)
+}
+
+/// 立即备份到设置的备份目录
+#[tauri::command]
+pub fn backup_now(state: State<'_, Arc<AppState>>) -> Result<BackupInfo, CommandError> {
+    let settings = backup_settings(&state)?;
+    let root = backup_root(&settings)?;
+    create_backup(&state, &root, settings.keep)
+}
+
+/// 获取备份目录中的备份（最新的在前）
+#[tauri::command]
+pub fn backup_list(state: State<'_, Arc<AppState>>) -> Result<Vec<BackupInfo>, CommandError> {
+    let settings = backup_settings(&state)?;
+    match settings.directory {
+        Some(directory) => Ok(BackupService::list(Path::new(&directory))?),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// 从备份恢复（先进行完整性检查，检查失败时不修改现有数据）
+#[tauri::command]
+pub fn backup_restore(
+    path: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<BackupInfo, CommandError> {
+    restore_backup(&state, Path::new(&path))
+}
+
+/// 读取备份设置
+pub fn backup_settings(state: &AppState) -> Result<BackupSettings, CommandError> {
+    let settings = state
+        .db
+        .get_setting(BACKUP_SETTINGS_KEY)?
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default();
+    Ok(settings)
+}
+
+fn save_backup_settings(
+    state: &AppState,
+    mut settings: BackupSettings,
+) -> Result<BackupSettings, CommandError> {
+    settings.directory = settings
+        .directory
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty());
+    if settings.interval_hours == 0 {
+        return Err(CommandError::Validation("备份间隔至少为 1 小时".to_string()));
+    }
+    if settings.keep == 0 {
+        return Err(CommandError::Validation("至少保留 1 份备份".to_string()));
+    }
+    if let Some(directory) = &settings.directory {
+        if !Path::new(directory).is_absolute() {
+            return Err(CommandError::Validation(format!(
+                "备份目录必须是绝对路径: {}",
+                directory
+            )));
+        }
+    }
+    if settings.enabled && settings.directory.is_none() {
+        return Err(CommandError::Validation("启用定时备份前请选择备份目录".to_string()));
+    }
+
+    let value =
+        serde_json::to_string(&settings).map_err(|e| CommandError::Internal(e.to_string()))?;
+    state.db.set_setting(BACKUP_SETTINGS_KEY, &value)?;
+    Ok(settings)
+}
+
+fn backup_root(settings: &BackupSettings) -> Result<PathBuf, CommandError> {
+    settings
+        .directory
+        .as_ref()
+        .map(PathBuf::from)
+        .ok_or_else(|| CommandError::Validation("尚未设置备份目录".to_string()))
+}
+
+/// 创建备份并轮换旧备份
+fn create_backup(state: &AppState, root: &Path, keep: u32) -> Result<BackupInfo, CommandError> {
+    let _guard = BACKUP_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let info = BackupService::create(&state.db, &state.file_service.base_path(), root)?;
+    let removed = BackupService::rotate(root, keep as usize)?;
+    eprintln!(
+        "[backup] 已备份到 {}（复制 {} 个文件，删除 {} 份旧备份）",
+        info.path, info.copied_count, removed
+    );
+    Ok(info)
+}
+
+fn restore_backup(state: &AppState, backup_path: &Path) -> Result<BackupInfo, CommandError> {
+    let _guard = BACKUP_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let info = BackupService::restore(&state.db, &state.file_service.base_path(), backup_path)?;
+    eprintln!("[backup] 已从 {} 恢复", info.path);
+    Ok(info)
+}
+
+/// 执行到期的定时备份，未启用或未到期时返回 None
+pub fn run_scheduled_backup(state: &AppState) -> Result<Option<BackupInfo>, CommandError> {
+    let settings = backup_settings(state)?;
+    if !settings.enabled {
+        return Ok(None);
+    }
+    let root = backup_root(&settings)?;
+    let due = match BackupService::list(&root)?.first() {
+        Some(latest) => {
+            Utc::now() - latest.created_at
+                >= chrono::Duration::hours(i64::from(settings.interval_hours))
+        }
+        None => true,
+    };
+    if !due {
+        return Ok(None);
+    }
+    create_backup(state, &root, settings.keep).map(Some)
+}
+
+/// 启动定时备份线程
+pub fn spawn_backup_scheduler(state: Arc<AppState>) {
+    std::thread::spawn(move || loop {
+        if let Err(e) = run_scheduled_backup(&state) {
+            eprintln!("[backup] 定时备份失败: {}", e);
+        }
+        std::thread::sleep(BACKUP_CHECK_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::services::FileService;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn create_test_state() -> (Arc<AppState>, TempDir) {
+        let temp_dir = TempDir::new().expect("创建临时目录失败");
+        let db = Database::new_in_memory().expect("创建数据库失败");
+        let file_service = FileService::new(temp_dir.path().join("library"));
+        file_service.init_base_dirs().expect("初始化目录失败");
+
+        let state = Arc::new(AppState::new(db, file_service));
+        (state, temp_dir)
+    }
+
+    #[test]
+    fn test_backup_settings_validation() {
+        let (state, temp_dir) = create_test_state();
+        assert_eq!(backup_settings(&state).unwrap(), BackupSettings::default());
+
+        let enabled_without_dir = BackupSettings {
+            enabled: true,
+            ..BackupSettings::default()
+        };
+        assert!(save_backup_settings(&state, enabled_without_dir).is_err());
+        let no_generations = BackupSettings {
+            keep: 0,
+            ..BackupSettings::default()
+        };
+        assert!(save_backup_settings(&state, no_generations).is_err());
+
+        let settings = BackupSettings {
+            enabled: true,
+            directory: Some(temp_dir.path().join("backups").display().to_string()),
+            interval_hours: 12,
+            keep: 3,
+        };
+        save_backup_settings(&state, settings.clone()).unwrap();
+        assert_eq!(backup_settings(&state).unwrap(), settings);
+    }
+
+    #[test]
+    fn test_scheduled_backup_runs_when_due() {
+        let (state, temp_dir) = create_test_state();
+        let backup_dir = temp_dir.path().join("backups");
+        fs::write(state.file_service.projects_dir().join("readme.md"), "# 项目").unwrap();
+
+        // 未启用时不备份
+        assert!(run_scheduled_backup(&state).unwrap().is_none());
+
+        save_backup_settings(
+            &state,
+            BackupSettings {
+                enabled: true,
+                directory: Some(backup_dir.display().to_string()),
+                interval_hours: 24,
+                keep: 2,
+            },
+        )
+        .unwrap();
+        let first = run_scheduled_backup(&state).unwrap().expect("首次应执行备份");
+        assert_eq!(first.files_count, 1);
+
+        // 未到期时不重复备份
+        assert!(run_scheduled_backup(&state).unwrap().is_none());
+
+        // 手动备份同样按保留份数轮换
+        for _ in 0..3 {
+            create_backup(&state, &backup_dir, 2).unwrap();
+        }
+        assert_eq!(BackupService::list(&backup_dir).unwrap().len(), 2);
+
+        let latest = BackupService::list(&backup_dir).unwrap().remove(0);
+        fs::write(state.file_service.projects_dir().join("readme.md"), "# 已修改").unwrap();
+        restore_backup(&state, Path::new(&latest.path)).unwrap();
+        assert_eq!(
+            fs::read_to_string(state.file_service.projects_dir().join("readme.md")).unwrap(),
+            "# 项目"
+        );
+    }
+}
@@ -0,0 +1,565 @@
+//! Batch Commands
+//!
+//! 提供来源与笔记的批量操作 Tauri Commands：删除、移动、打标签、笔记转来源。
+//! 每个条目返回独立结果，数据库写入在一个事务中完成，单个条目失败只回滚该条目。
+
+use crate::commands::note::{apply_note_conversion, prepare_note_conversion};
+use crate::commands::project::{
+    ensure_item_writable, ensure_project_writable, AppState, CommandError,
+};
+use crate::commands::trash::prepare_trash;
+use crate::db::DbTx;
+use crate::models::{BatchItemResult, BatchResult, ContentKind};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::State;
+
+/// 批量删除来源或笔记（移入回收站）
+#[tauri::command]
+pub fn batch_delete(
+    kind: ContentKind,
+    ids: Vec<String>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<BatchResult, CommandError> {
+    ensure_batch_kind(kind)?;
+
+    let (result, _) = run_batch(
+        &state,
+        &ids,
+        |id| prepare_trash(&state, kind, id),
+        |tx, pending| pending.apply(tx).map_err(CommandError::from),
+        |pending| pending.rollback(&state),
+    )?;
+    Ok(result)
+}
+
+/// 批量将来源或笔记移动到另一个项目
+#[tauri::command]
+pub fn batch_move(
+    kind: ContentKind,
+    ids: Vec<String>,
+    target_project_id: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<BatchResult, CommandError> {
+    ensure_batch_kind(kind)?;
+    // 验证目标项目存在且未归档
+    ensure_project_writable(&state, &target_project_id)?;
+
+    let (result, _) = run_batch(
+        &state,
+        &ids,
+        |id| prepare_move(&state, kind, id, &target_project_id),
+        |tx, pending| apply_move(tx, kind, pending, &target_project_id),
+        |pending| move_files_back(&state, &pending.files),
+    )?;
+    Ok(result)
+}
+
+/// 批量为来源或笔记添加、移除标签
+#[tauri::command]
+pub fn batch_tag(
+    kind: ContentKind,
+    ids: Vec<String>,
+    add: Vec<String>,
+    remove: Vec<String>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<BatchResult, CommandError> {
+    ensure_batch_kind(kind)?;
+
+    let add = normalize_tags(add);
+    let remove = normalize_tags(remove);
+    if add.is_empty() && remove.is_empty() {
+        return Err(CommandError::Validation("标签不能为空".to_string()));
+    }
+
+    let (result, _) = run_batch(
+        &state,
+        &ids,
+        |id| {
+            // 只校验条目存在且所属项目未归档，标签写入不涉及文件
+            ensure_item_writable(&state, kind, id)?;
+            Ok(id.to_string())
+        },
+        |tx, id| {
+            for tag in &remove {
+                tx.remove_tag(kind, id, tag)?;
+            }
+            for tag in &add {
+                tx.add_tag(kind, id, tag)?;
+            }
+            Ok(())
+        },
+        |_| {},
+    )?;
+    Ok(result)
+}
+
+/// 批量将笔记转换为来源
+#[tauri::command]
+pub fn batch_note_to_source(
+    ids: Vec<String>,
+    delete_original: bool,
+    state: State<'_, Arc<AppState>>,
+) -> Result<BatchResult, CommandError> {
+    let (result, converted) = run_batch(
+        &state,
+        &ids,
+        |id| prepare_note_conversion(&state, id),
+        |tx, pending| apply_note_conversion(tx, pending, delete_original),
+        |pending| {
+            // 事务整体失败时，已提交的来源文件不再被引用
+            let _ = fs::remove_file(&pending.source.path);
+        },
+    )?;
+
+    // 数据库提交后再删除原笔记文件
+    if delete_original {
+        for pending in &converted {
+            let _ = fs::remove_file(&pending.note.path);
+        }
+    }
+
+    Ok(result)
+}
+
+/// 分两阶段执行批量操作
+///
+/// 先逐个准备条目（读取记录、移动或暂存文件），再在一个事务中逐个写入数据库。
+/// 每个条目在独立的保存点中写入，失败时只回滚该条目并撤销其文件操作；
+/// 事务提交失败时撤销全部条目。返回批量结果与写入成功的条目。
+fn run_batch<P>(
+    state: &AppState,
+    ids: &[String],
+    prepare: impl Fn(&str) -> Result<P, CommandError>,
+    apply: impl Fn(&DbTx, &mut P) -> Result<(), CommandError>,
+    rollback: impl Fn(&P),
+) -> Result<(BatchResult, Vec<P>), CommandError> {
+    if ids.is_empty() {
+        return Err(CommandError::Validation("请至少选择一个条目".to_string()));
+    }
+
+    let mut seen = HashSet::new();
+    let mut prepared: Vec<Result<P, String>> = ids
+        .iter()
+        .map(|id| {
+            if !seen.insert(id.as_str()) {
+                return Err(format!("条目重复: {}", id));
+            }
+            prepare(id).map_err(|e| e.to_string())
+        })
+        .collect();
+
+    let committed = state.db.transaction(|tx| -> Result<Vec<Option<String>>, CommandError> {
+        let errors = prepared
+            .iter_mut()
+            .map(|outcome| match outcome {
+                Ok(pending) => tx.savepoint(|tx| apply(tx, pending)).err().map(|e| e.to_string()),
+                Err(e) => Some(e.clone()),
+            })
+            .collect();
+        Ok(errors)
+    });
+
+    let errors = match committed {
+        Ok(errors) => errors,
+        Err(e) => {
+            let message = e.to_string();
+            prepared
+                .iter()
+                .map(|outcome| match outcome {
+                    Ok(_) => Some(message.clone()),
+                    Err(e) => Some(e.clone()),
+                })
+                .collect()
+        }
+    };
+
+    let mut results = Vec::with_capacity(ids.len());
+    let mut succeeded = Vec::new();
+    for ((id, outcome), error) in ids.iter().zip(prepared).zip(errors) {
+        match (outcome, error) {
+            (Ok(pending), None) => {
+                results.push(BatchItemResult::ok(id));
+                succeeded.push(pending);
+            }
+            (outcome, Some(error)) => {
+                if let Ok(pending) = &outcome {
+                    rollback(pending);
+                }
+                results.push(BatchItemResult::failed(id, error));
+            }
+            (Err(error), None) => results.push(BatchItemResult::failed(id, error)),
+        }
+    }
+
+    Ok((BatchResult::new(results), succeeded))
+}
+
+/// 批量操作只支持来源与笔记
+fn ensure_batch_kind(kind: ContentKind) -> Result<(), CommandError> {
+    match kind {
+        ContentKind::Source | ContentKind::Note => Ok(()),
+        _ => Err(CommandError::Validation(format!(
+            "批量操作不支持的类型: {}",
+            kind.as_str()
+        ))),
+    }
+}
+
+/// 去除空白与重复的标签
+fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_string();
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+/// 文件已移到目标项目、尚未写入数据库的条目
+struct PendingMove {
+    id: String,
+    from_project: String,
+    path: String,
+    thumbnail_path: Option<String>,
+    /// 已移动的文件（原路径, 新路径）
+    files: Vec<(PathBuf, PathBuf)>,
+}
+
+fn prepare_move(
+    state: &AppState,
+    kind: ContentKind,
+    id: &str,
+    target_project_id: &str,
+) -> Result<PendingMove, CommandError> {
+    let (from_project, path, thumbnail_path, target_dir) = match kind {
+        ContentKind::Source => {
+            let source = state.db.get_source(id)?;
+            let target_dir = state.file_service.get_sources_dir(target_project_id);
+            (source.project_id, source.path, source.thumbnail_path, target_dir)
+        }
+        _ => {
+            let note = state.db.get_note(id)?;
+            let target_dir = state.file_service.get_notes_dir(target_project_id);
+            (note.project_id, note.path, None, target_dir)
+        }
+    };
+
+    if from_project == target_project_id {
+        return Err(CommandError::Validation("条目已在目标项目中".to_string()));
+    }
+    ensure_project_writable(state, &from_project)?;
+
+    let mut pending = PendingMove {
+        id: id.to_string(),
+        from_project,
+        path: String::new(),
+        thumbnail_path: None,
+        files: Vec::new(),
+    };
+
+    let result = relocate(state, &mut pending.files, &path, &target_dir).and_then(|new_path| {
+        pending.path = new_path;
+        if let Some(thumb_path) = &thumbnail_path {
+            let thumbnails_dir = state.file_service.get_thumbnails_dir(target_project_id);
+            pending.thumbnail_path =
+                Some(relocate(state, &mut pending.files, thumb_path, &thumbnails_dir)?);
+        }
+        Ok(())
+    });
+
+    if let Err(e) = result {
+        move_files_back(state, &pending.files);
+        return Err(e);
+    }
+
+    Ok(pending)
+}
+
+/// 将文件移动到目标目录（保留文件名），返回新路径；原文件不存在时只计算新路径
+fn relocate(
+    state: &AppState,
+    moved: &mut Vec<(PathBuf, PathBuf)>,
+    path: &str,
+    target_dir: &Path,
+) -> Result<String, CommandError> {
+    let from = PathBuf::from(path);
+    let file_name = from
+        .file_name()
+        .ok_or_else(|| CommandError::Validation(format!("无效的文件路径: {}", path)))?;
+    let to = target_dir.join(file_name);
+
+    if to.exists() {
+        return Err(CommandError::Validation(format!(
+            "目标项目中已存在同名文件: {}",
+            to.display()
+        )));
+    }
+
+    if from.exists() {
+        state.file_service.move_path(&from, &to)?;
+        moved.push((from, to.clone()));
+    }
+
+    Ok(to.display().to_string())
+}
+
+fn apply_move(
+    tx: &DbTx,
+    kind: ContentKind,
+    pending: &mut PendingMove,
+    target_project_id: &str,
+) -> Result<(), CommandError> {
+    match kind {
+        ContentKind::Source => {
+            tx.move_source(
+                &pending.id,
+                target_project_id,
+                &pending.path,
+                pending.thumbnail_path.as_deref(),
+            )?;
+            tx.update_project_sources_count(&pending.from_project, -1)?;
+            tx.update_project_sources_count(target_project_id, 1)?;
+        }
+        _ => tx.move_note(&pending.id, target_project_id, &pending.path)?,
+    }
+    Ok(())
+}
+
+/// 将已移动的文件移回原处（失败回滚用）
+fn move_files_back(state: &AppState, files: &[(PathBuf, PathBuf)]) {
+    for (from, to) in files {
+        let _ = state.file_service.move_path(to, from);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::models::{Note, OutputType, Project, ProjectIcon, Source, SourceType};
+    use crate::services::{embed_text, FileService};
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    fn create_test_state() -> (Arc<AppState>, TempDir) {
+        let temp_dir = TempDir::new().expect("创建临时目录失败");
+        let db = Database::new_in_memory().expect("创建数据库失败");
+        let file_service = FileService::new(temp_dir.path().to_path_buf());
+        file_service.init_base_dirs().expect("初始化目录失败");
+
+        let state = Arc::new(AppState::new(db, file_service));
+        (state, temp_dir)
+    }
+
+    fn create_test_project(state: &Arc<AppState>, name: &str) -> Project {
+        let id = uuid::Uuid::new_v4().to_string();
+        let project_path = state.file_service.create_project_dir(&id).unwrap();
+        let project = Project {
+            id,
+            name: name.to_string(),
+            icon: ProjectIcon::default(),
+            workspace: "research".to_string(),
+            is_starred: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            sources_count: 0,
+            path: project_path.display().to_string(),
+            archived_at: None,
+        };
+        state.db.insert_project(&project).unwrap();
+        project
+    }
+
+    fn create_test_note(state: &Arc<AppState>, project: &Project, content: &str) -> Note {
+        let id = uuid::Uuid::new_v4().to_string();
+        let path = state.file_service.get_notes_dir(&project.id).join(format!("{}.md", id));
+        fs::write(&path, content).unwrap();
+        let note = Note {
+            id,
+            project_id: project.id.clone(),
+            title: "测试笔记".to_string(),
+            path: path.display().to_string(),
+            output_type: OutputType::Note,
+            title_locked: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        state.db.insert_note(&note).unwrap();
+        state
+            .db
+            .upsert_note_embedding(&note.id, &project.id, &embed_text(content))
+            .unwrap();
+        note
+    }
+
+    fn create_test_source(state: &Arc<AppState>, project: &Project) -> Source {
+        let id = uuid::Uuid::new_v4().to_string();
+        let path = state.file_service.get_sources_dir(&project.id).join(format!("{}.md", id));
+        fs::write(&path, "来源内容").unwrap();
+        let source = Source {
+            id,
+            project_id: project.id.clone(),
+            name: "来源.md".to_string(),
+            source_type: SourceType::Markdown,
+            path: path.display().to_string(),
+            size: 12,
+            mime_type: "text/markdown".to_string(),
+            thumbnail_path: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        state
+            .db
+            .insert_source_with_content(&source, Some("来源内容"))
+            .unwrap();
+        state.db.update_project_sources_count(&project.id, 1).unwrap();
+        source
+    }
+
+    #[test]
+    fn test_run_batch_reports_per_item_results() {
+        let (state, _temp) = create_test_state();
+        let project = create_test_project(&state, "测试项目");
+        let note = create_test_note(&state, &project, "内容");
+
+        let ids = vec![note.id.clone(), "missing".to_string(), note.id.clone()];
+        let (result, _) = run_batch(
+            &state,
+            &ids,
+            |id| prepare_trash(&state, ContentKind::Note, id),
+            |tx, pending| pending.apply(tx).map_err(CommandError::from),
+            |pending| pending.rollback(&state),
+        )
+        .unwrap();
+
+        assert_eq!(result.succeeded, 1);
+        assert_eq!(result.failed, 2);
+        assert!(result.results[0].success);
+        assert!(!result.results[1].success);
+        // 重复的条目不会被处理两次
+        assert!(result.results[2].error.as_deref().unwrap().contains("重复"));
+        assert!(state.db.get_note(&note.id).is_err());
+        assert_eq!(state.db.get_trash_items(Some(&project.id)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_run_batch_rolls_back_failed_item_only() {
+        let (state, _temp) = create_test_state();
+        let project = create_test_project(&state, "测试项目");
+        let first = create_test_note(&state, &project, "第一篇");
+        let second = create_test_note(&state, &project, "第二篇");
+
+        let ids = vec![first.id.clone(), second.id.clone()];
+        let (result, _) = run_batch(
+            &state,
+            &ids,
+            |id| prepare_trash(&state, ContentKind::Note, id),
+            |tx, pending| {
+                pending.apply(tx)?;
+                // 模拟第二个条目写入失败
+                if pending.item.item_id == second.id {
+                    return Err(CommandError::Internal("写入失败".to_string()));
+                }
+                Ok(())
+            },
+            |pending| pending.rollback(&state),
+        )
+        .unwrap();
+
+        assert_eq!(result.succeeded, 1);
+        assert!(state.db.get_note(&first.id).is_err());
+        // 失败条目的记录、向量与文件都保持原样
+        assert!(state.db.get_note(&second.id).is_ok());
+        assert!(Path::new(&second.path).exists());
+        assert!(state.db.list_notes_missing_embeddings(10).unwrap().is_empty());
+        assert_eq!(state.db.get_trash_items(Some(&project.id)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_move_source_between_projects() {
+        let (state, _temp) = create_test_state();
+        let from = create_test_project(&state, "原项目");
+        let to = create_test_project(&state, "目标项目");
+        let source = create_test_source(&state, &from);
+        state
+            .db
+            .upsert_source_embedding(&source.id, &from.id, &embed_text("来源内容"))
+            .unwrap();
+
+        let ids = vec![source.id.clone()];
+        let (result, _) = run_batch(
+            &state,
+            &ids,
+            |id| prepare_move(&state, ContentKind::Source, id, &to.id),
+            |tx, pending| apply_move(tx, ContentKind::Source, pending, &to.id),
+            |pending| move_files_back(&state, &pending.files),
+        )
+        .unwrap();
+        assert_eq!(result.succeeded, 1);
+
+        let moved = state.db.get_source(&source.id).unwrap();
+        assert_eq!(moved.project_id, to.id);
+        assert!(Path::new(&moved.path).exists());
+        assert!(!Path::new(&source.path).exists());
+        assert_eq!(state.db.get_project(&from.id).unwrap().sources_count, 0);
+        assert_eq!(state.db.get_project(&to.id).unwrap().sources_count, 1);
+
+        // 移动到所在项目会失败
+        let (result, _) = run_batch(
+            &state,
+            &ids,
+            |id| prepare_move(&state, ContentKind::Source, id, &to.id),
+            |tx, pending| apply_move(tx, ContentKind::Source, pending, &to.id),
+            |pending| move_files_back(&state, &pending.files),
+        )
+        .unwrap();
+        assert_eq!(result.failed, 1);
+    }
+
+    #[test]
+    fn test_tags_follow_trash_and_restore() {
+        let (state, _temp) = create_test_state();
+        let project = create_test_project(&state, "测试项目");
+        let note = create_test_note(&state, &project, "内容");
+
+        state
+            .db
+            .transaction(|tx| -> Result<(), CommandError> {
+                tx.add_tag(ContentKind::Note, &note.id, "会议")?;
+                tx.add_tag(ContentKind::Note, &note.id, "周报")?;
+                tx.add_tag(ContentKind::Note, &note.id, "会议")?;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(state.db.get_tags(ContentKind::Note, &note.id).unwrap(), vec!["会议", "周报"]);
+        assert_eq!(state.db.get_project_tags(&project.id).unwrap(), vec!["会议", "周报"]);
+
+        let pending = prepare_trash(&state, ContentKind::Note, &note.id).unwrap();
+        state.db.transaction(|tx| pending.apply(tx)).unwrap();
+        assert!(state.db.get_tags(ContentKind::Note, &note.id).unwrap().is_empty());
+
+        let (_, snapshot, _) = state.db.get_trash_entry(&pending.item.id).unwrap();
+        assert_eq!(snapshot.tags.len(), 2);
+        state.db.transaction(|tx| tx.restore_snapshot(&snapshot)).unwrap();
+        assert_eq!(
+            state.db.get_tagged_item_ids(ContentKind::Note, "周报").unwrap(),
+            vec![note.id.clone()]
+        );
+    }
+
+    #[test]
+    fn test_normalize_tags() {
+        let tags = normalize_tags(vec![
+            " 会议 ".to_string(),
+            "".to_string(),
+            "会议".to_string(),
+            "周报".to_string(),
+        ]);
+        assert_eq!(tags, vec!["会议", "周报"]);
+    }
+}
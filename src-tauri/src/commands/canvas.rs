@@ -2,10 +2,14 @@
 //!
 //! 提供画布相关的 Tauri Commands
 
-use crate::commands::project::{AppState, CommandError};
-use crate::models::{Canvas, CanvasData};
+use crate::commands::project::{
+    ensure_item_writable, ensure_project_writable, AppState, CommandError,
+};
+use crate::commands::trash::move_to_trash;
+use crate::models::{Canvas, CanvasData, ContentKind};
 use chrono::Utc;
 use std::fs;
+use std::path::Path;
 use std::sync::Arc;
 use tauri::State;
 
@@ -50,8 +54,8 @@ pub fn canvas_create(
     title: String,
     state: State<'_, Arc<AppState>>,
 ) -> Result<Canvas, CommandError> {
-    // 验证项目存在
-    let _ = state.db.get_project(&project_id)?;
+    // 验证项目存在且未归档
+    ensure_project_writable(&state, &project_id)?;
 
     let id = uuid::Uuid::new_v4().to_string();
     let now = Utc::now();
@@ -68,8 +72,7 @@ pub fn canvas_create(
     let data_path = canvas_dir.join(format!("{}.json", id));
     let json = serde_json::to_string_pretty(&canvas_data)
         .map_err(|e| CommandError::Internal(format!("序列化画布数据失败: {}", e)))?;
-    fs::write(&data_path, json)
-        .map_err(|e| CommandError::Io(format!("保存画布数据失败: {}", e)))?;
+    let staged = state.file_service.stage_write(&data_path, json.as_bytes())?;
 
     // 创建元数据记录
     let canvas = Canvas {
@@ -81,13 +84,18 @@ pub fn canvas_create(
         updated_at: now,
     };
 
-    state.db.insert_canvas(&canvas)?;
+    // 元数据与数据文件一起落盘，文件提交失败时回滚
+    state.db.transaction(|tx| -> Result<(), CommandError> {
+        tx.insert_canvas(&canvas)?;
+        staged.commit()?;
+        Ok(())
+    })?;
 
     Ok(canvas)
 }
 
 /// 从画布数据中提取文本内容（用于全文搜索）
-fn extract_canvas_text(data: &CanvasData) -> String {
+pub(crate) fn extract_canvas_text(data: &CanvasData) -> String {
     let mut texts = Vec::new();
 
     for element in &data.elements {
@@ -114,26 +122,39 @@ pub fn canvas_save(
     data: CanvasData,
     state: State<'_, Arc<AppState>>,
 ) -> Result<Canvas, CommandError> {
-    let canvas = state.db.get_canvas(&id)?;
+    save_canvas(&state, &id, &data)
+}
+
+pub(crate) fn save_canvas(
+    state: &AppState,
+    id: &str,
+    data: &CanvasData,
+) -> Result<Canvas, CommandError> {
+    ensure_item_writable(state, ContentKind::Canvas, id)?;
 
-    // 更新 JSON 文件
-    let json = serde_json::to_string_pretty(&data)
+    let canvas = state.db.get_canvas(id)?;
+
+    // 暂存 JSON 文件，数据库更新成功后再替换正式文件
+    let json = serde_json::to_string_pretty(data)
         .map_err(|e| CommandError::Internal(format!("序列化画布数据失败: {}", e)))?;
-    fs::write(&canvas.path, json)
-        .map_err(|e| CommandError::Io(format!("保存画布数据失败: {}", e)))?;
-
-    // 提取文本内容并更新全文搜索索引
-    let text_content = extract_canvas_text(&data);
-    if let Err(e) = state.db.update_canvas_text_content(&id, &text_content) {
-        // 记录警告但不中断保存流程
-        eprintln!("[WARN] 更新画布搜索索引失败: {}", e);
-    }
+    let staged = state
+        .file_service
+        .stage_write(Path::new(&canvas.path), json.as_bytes())?;
+
+    let text_content = extract_canvas_text(data);
+    state.db.transaction(|tx| -> Result<(), CommandError> {
+        // 更新全文搜索索引（失败时整体回滚，不留下与文件不一致的索引）
+        tx.update_canvas_text_content(id, &text_content)?;
 
-    // 更新 updated_at
-    state.db.touch_canvas(&id)?;
+        // 更新 updated_at
+        tx.touch_canvas(id)?;
+
+        staged.commit()?;
+        Ok(())
+    })?;
 
     // 返回更新后的元数据
-    let updated = state.db.get_canvas(&id)?;
+    let updated = state.db.get_canvas(id)?;
     Ok(updated)
 }
 
@@ -144,6 +165,8 @@ pub fn canvas_rename(
     title: String,
     state: State<'_, Arc<AppState>>,
 ) -> Result<Canvas, CommandError> {
+    ensure_item_writable(&state, ContentKind::Canvas, &id)?;
+
     state.db.update_canvas_title(&id, &title)?;
     let canvas = state.db.get_canvas(&id)?;
     Ok(canvas)
@@ -155,16 +178,8 @@ pub fn canvas_delete(
     id: String,
     state: State<'_, Arc<AppState>>,
 ) -> Result<(), CommandError> {
-    let canvas = state.db.get_canvas(&id)?;
-
-    // 删除 JSON 数据文件
-    if std::path::Path::new(&canvas.path).exists() {
-        let _ = fs::remove_file(&canvas.path);
-    }
-
-    // 删除数据库记录
-    state.db.delete_canvas(&id)?;
-
+    // 移入回收站（JSON 数据文件随记录一起可恢复）
+    move_to_trash(&state, ContentKind::Canvas, &id)?;
     Ok(())
 }
 
@@ -182,7 +197,7 @@ mod tests {
         let file_service = FileService::new(temp_dir.path().to_path_buf());
         file_service.init_base_dirs().expect("初始化目录失败");
 
-        let state = Arc::new(AppState { db, file_service });
+        let state = Arc::new(AppState::new(db, file_service));
         (state, temp_dir)
     }
 
@@ -200,6 +215,7 @@ mod tests {
             updated_at: now,
             sources_count: 0,
             path: project_path.display().to_string(),
+            archived_at: None,
         };
         state.db.insert_project(&project).unwrap();
         project
@@ -1,7 +1,10 @@
 //! 对话相关命令
 
-use crate::commands::project::{AppState, CommandError};
-use crate::models::{ChatMessage, ChatSession, Citation, MessageRole};
+use crate::commands::project::{
+    ensure_item_writable, ensure_project_writable, AppState, CommandError,
+};
+use crate::commands::trash::move_to_trash;
+use crate::models::{ChatMessage, ChatSession, Citation, ContentKind, MessageRole};
 use chrono::Utc;
 use std::sync::Arc;
 use tauri::State;
@@ -34,6 +37,8 @@ pub fn chat_session_create(
     project_id: String,
     title: Option<String>,
 ) -> Result<ChatSession, CommandError> {
+    ensure_project_writable(&state, &project_id)?;
+
     let session = ChatSession {
         id: uuid::Uuid::new_v4().to_string(),
         project_id,
@@ -53,6 +58,8 @@ pub fn chat_session_rename(
     id: String,
     title: String,
 ) -> Result<(), CommandError> {
+    ensure_item_writable(&state, ContentKind::ChatSession, &id)?;
+
     state
         .db
         .update_chat_session_title(&id, &title)
@@ -65,10 +72,9 @@ pub fn chat_session_delete(
     state: State<'_, Arc<AppState>>,
     id: String,
 ) -> Result<(), CommandError> {
-    state
-        .db
-        .delete_chat_session(&id)
-        .map_err(CommandError::from)
+    // 移入回收站（会话消息随会话一起可恢复）
+    move_to_trash(&state, ContentKind::ChatSession, &id)?;
+    Ok(())
 }
 
 /// 获取对话消息列表
@@ -93,6 +99,8 @@ pub fn chat_message_save(
     content: String,
     citations: Option<Vec<Citation>>,
 ) -> Result<ChatMessage, CommandError> {
+    ensure_item_writable(&state, ContentKind::ChatSession, &session_id)?;
+
     let message = ChatMessage {
         id,
         session_id: session_id.clone(),
@@ -116,6 +124,10 @@ pub fn chat_message_delete(
     state: State<'_, Arc<AppState>>,
     id: String,
 ) -> Result<(), CommandError> {
+    if let Some(session_id) = state.db.get_chat_message_session_id(&id)? {
+        ensure_item_writable(&state, ContentKind::ChatSession, &session_id)?;
+    }
+
     state
         .db
         .delete_chat_message(&id)
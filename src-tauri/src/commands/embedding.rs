@@ -0,0 +1,359 @@
+//! Embedding Commands
+//!
+//! 后台向量生成：分批补齐缺失向量的来源与笔记，批间限速，进度通过回调（前端事件）通知
+
+use crate::commands::project::{AppState, CommandError};
+use crate::models::EmbeddingProgress;
+use crate::services::{embed_text, EMBEDDING_MODEL};
+use std::fs;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tauri::State;
+
+/// 向量生成进度事件名，负载为 `EmbeddingProgress`
+pub const EMBEDDING_PROGRESS_EVENT: &str = "embedding-progress";
+
+/// 记录已有向量所用向量化方法的设置项
+const EMBEDDING_MODEL_KEY: &str = "embedding.model";
+/// 每批生成的条目数
+const EMBEDDING_BATCH_SIZE: u32 = 32;
+/// 两批之间的间隔，避免长时间占用数据库与 CPU
+const EMBEDDING_BATCH_INTERVAL: Duration = Duration::from_millis(200);
+/// 空闲时检查缺失向量的间隔（语义搜索发现缺失向量时会提前唤醒）
+const EMBEDDING_IDLE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 后台向量生成的唤醒信号、本轮进度与停止标记（由 `AppState` 持有）
+#[derive(Default)]
+pub struct EmbeddingWorker {
+    /// 唤醒请求标记
+    requested: Mutex<bool>,
+    signal: Condvar,
+    /// 本轮已生成的条目数（本轮结束后归零）
+    round_embedded: AtomicU32,
+    stopped: AtomicBool,
+}
+
+impl EmbeddingWorker {
+    /// 唤醒后台向量生成，立即开始下一轮
+    pub fn request_backfill(&self) {
+        if let Ok(mut requested) = self.requested.lock() {
+            *requested = true;
+            self.signal.notify_one();
+        }
+    }
+
+    /// 停止后台向量生成（当前批次完成后退出）
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        self.request_backfill();
+    }
+
+    fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    /// 等待唤醒请求，最多等待 `timeout`
+    fn wait_for_request(&self, timeout: Duration) {
+        match self.requested.lock() {
+            Ok(guard) => {
+                if let Ok((mut guard, _)) =
+                    self.signal
+                        .wait_timeout_while(guard, timeout, |requested| !*requested)
+                {
+                    *guard = false;
+                }
+            }
+            Err(_) => std::thread::sleep(timeout),
+        }
+    }
+}
+
+/// 获取向量生成进度：后台本轮已生成数与剩余数（有缺失向量时唤醒后台生成）
+#[tauri::command]
+pub fn embedding_status(
+    state: State<'_, Arc<AppState>>,
+) -> Result<EmbeddingProgress, CommandError> {
+    embedding_progress(&state)
+}
+
+pub(crate) fn embedding_progress(state: &AppState) -> Result<EmbeddingProgress, CommandError> {
+    let pending = state.db.count_missing_embeddings()?;
+    if pending > 0 {
+        state.embeddings.request_backfill();
+    }
+    Ok(EmbeddingProgress {
+        embedded: state.embeddings.round_embedded.load(Ordering::Relaxed),
+        pending,
+    })
+}
+
+/// 启动后台向量生成
+///
+/// 每批最多生成 `EMBEDDING_BATCH_SIZE` 条，批间暂停 `EMBEDDING_BATCH_INTERVAL`。
+/// 待生成的条目直接从数据库查询，退出应用或切换资料库后从剩余条目继续。
+/// 每批完成后以本轮累计生成数与剩余数调用 `on_progress`，剩余为 0 时本轮结束。
+/// 调用 `state.embeddings.stop()` 后线程在当前批次完成后退出。
+pub fn spawn_embedding_worker<F>(state: Arc<AppState>, on_progress: F) -> JoinHandle<()>
+where
+    F: Fn(EmbeddingProgress) + Send + 'static,
+{
+    std::thread::spawn(move || {
+        let worker = &state.embeddings;
+        let mut embedded = 0;
+        while !worker.is_stopped() {
+            match run_embedding_batch(&state, EMBEDDING_BATCH_SIZE) {
+                Ok(progress) if progress.embedded > 0 => {
+                    embedded += progress.embedded;
+                    worker.round_embedded.store(embedded, Ordering::Relaxed);
+                    on_progress(EmbeddingProgress {
+                        embedded,
+                        pending: progress.pending,
+                    });
+                    if progress.pending > 0 {
+                        std::thread::sleep(EMBEDDING_BATCH_INTERVAL);
+                        continue;
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("[embedding] 生成向量失败: {}", e),
+            }
+            embedded = 0;
+            worker.round_embedded.store(0, Ordering::Relaxed);
+            worker.wait_for_request(EMBEDDING_IDLE_INTERVAL);
+        }
+    })
+}
+
+/// 生成一批缺失的向量（先来源后笔记），返回本批生成数与剩余数
+///
+/// 向量化方法变更时先清空已有向量，全部重新生成。
+/// 没有可向量化文本的条目写入空向量（检索时跳过），避免每批重复处理。
+pub(crate) fn run_embedding_batch(
+    state: &AppState,
+    batch_size: u32,
+) -> Result<EmbeddingProgress, CommandError> {
+    sync_embedding_model(state)?;
+
+    let mut embedded = 0;
+    let sources = state.db.list_sources_missing_embeddings(batch_size)?;
+    let remaining = batch_size.saturating_sub(sources.len() as u32);
+    for (id, project_id, name, text_content) in sources {
+        let input = text_content
+            .as_deref()
+            .filter(|content| !content.trim().is_empty())
+            .unwrap_or(name.as_str());
+        match state
+            .db
+            .upsert_source_embedding(&id, &project_id, &embed_text(input))
+        {
+            Ok(()) => embedded += 1,
+            Err(e) => eprintln!("[embedding] 来源 {} 向量写入失败: {}", id, e),
+        }
+    }
+
+    if remaining > 0 {
+        for (id, project_id, title, path) in state.db.list_notes_missing_embeddings(remaining)? {
+            let content = fs::read_to_string(&path).unwrap_or_default();
+            let input = if content.trim().is_empty() {
+                title.as_str()
+            } else {
+                content.as_str()
+            };
+            match state
+                .db
+                .upsert_note_embedding(&id, &project_id, &embed_text(input))
+            {
+                Ok(()) => embedded += 1,
+                Err(e) => eprintln!("[embedding] 笔记 {} 向量写入失败: {}", id, e),
+            }
+        }
+    }
+
+    Ok(EmbeddingProgress {
+        embedded,
+        pending: state.db.count_missing_embeddings()?,
+    })
+}
+
+/// 向量化方法与已有向量不一致时清空向量（未记录时视为一致）
+fn sync_embedding_model(state: &AppState) -> Result<(), CommandError> {
+    match state.db.get_setting(EMBEDDING_MODEL_KEY)? {
+        Some(model) if model == EMBEDDING_MODEL => return Ok(()),
+        Some(model) => {
+            eprintln!(
+                "[embedding] 向量化方法已从 {} 变更为 {}，重新生成向量",
+                model, EMBEDDING_MODEL
+            );
+            state.db.clear_embeddings()?;
+        }
+        None => {}
+    }
+    state.db.set_setting(EMBEDDING_MODEL_KEY, EMBEDDING_MODEL)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::models::{Note, OutputType, Project, ProjectIcon, Source, SourceType};
+    use crate::services::FileService;
+    use chrono::Utc;
+    use std::sync::mpsc;
+    use tempfile::TempDir;
+
+    fn create_test_state() -> (Arc<AppState>, TempDir) {
+        let temp_dir = TempDir::new().expect("创建临时目录失败");
+        let db = Database::new_in_memory().expect("创建数据库失败");
+        let file_service = FileService::new(temp_dir.path().to_path_buf());
+        file_service.init_base_dirs().expect("初始化目录失败");
+
+        let state = Arc::new(AppState::new(db, file_service));
+        (state, temp_dir)
+    }
+
+    /// 创建含 3 个来源与 2 篇笔记（均无向量）的项目
+    fn create_test_project(state: &AppState) -> Project {
+        let id = uuid::Uuid::new_v4().to_string();
+        let project_path = state.file_service.create_project_dir(&id).unwrap();
+        let now = Utc::now();
+        let project = Project {
+            id: id.clone(),
+            name: "向量生成".to_string(),
+            icon: ProjectIcon::default(),
+            workspace: "research".to_string(),
+            is_starred: false,
+            created_at: now,
+            updated_at: now,
+            sources_count: 0,
+            path: project_path.display().to_string(),
+            archived_at: None,
+        };
+        state.db.insert_project(&project).unwrap();
+
+        // 最后一个来源没有可向量化的文本
+        for (source_id, name, content) in [
+            ("s1", "检索综述", Some("倒数排名融合")),
+            ("s2", "部署手册", None),
+            ("s3", "——", None),
+        ] {
+            let source = Source {
+                id: source_id.to_string(),
+                project_id: id.clone(),
+                name: name.to_string(),
+                source_type: SourceType::Markdown,
+                path: project_path
+                    .join(format!("{}.md", source_id))
+                    .display()
+                    .to_string(),
+                size: 0,
+                mime_type: "text/markdown".to_string(),
+                thumbnail_path: None,
+                created_at: now,
+                updated_at: now,
+            };
+            state
+                .db
+                .insert_source_with_content(&source, content)
+                .unwrap();
+        }
+        for note_id in ["n1", "n2"] {
+            let path = project_path.join(format!("{}.md", note_id));
+            fs::write(&path, "向量检索笔记").unwrap();
+            state
+                .db
+                .insert_note(&Note {
+                    id: note_id.to_string(),
+                    project_id: id.clone(),
+                    title: note_id.to_string(),
+                    path: path.display().to_string(),
+                    output_type: OutputType::Note,
+                    title_locked: false,
+                    created_at: now,
+                    updated_at: now,
+                })
+                .unwrap();
+        }
+        project
+    }
+
+    #[test]
+    fn test_embedding_batches_resume() {
+        let (state, _temp) = create_test_state();
+        create_test_project(&state);
+        assert_eq!(state.db.count_missing_embeddings().unwrap(), 5);
+
+        let first = run_embedding_batch(&state, 2).unwrap();
+        assert_eq!(
+            first,
+            EmbeddingProgress {
+                embedded: 2,
+                pending: 3
+            }
+        );
+        // 没有文本的来源写入空向量后不再计为缺失
+        let second = run_embedding_batch(&state, 2).unwrap();
+        assert_eq!(second.pending, 1);
+        assert!(state
+            .db
+            .list_sources_missing_embeddings(10)
+            .unwrap()
+            .is_empty());
+        assert_eq!(run_embedding_batch(&state, 2).unwrap().pending, 0);
+        assert_eq!(
+            run_embedding_batch(&state, 2).unwrap(),
+            EmbeddingProgress::default()
+        );
+        assert_eq!(
+            state
+                .db
+                .get_setting(EMBEDDING_MODEL_KEY)
+                .unwrap()
+                .as_deref(),
+            Some(EMBEDDING_MODEL)
+        );
+
+        // 向量化方法变更后全部重新生成
+        state.db.set_setting(EMBEDDING_MODEL_KEY, "legacy").unwrap();
+        let progress = run_embedding_batch(&state, 3).unwrap();
+        assert_eq!(
+            progress,
+            EmbeddingProgress {
+                embedded: 3,
+                pending: 2
+            }
+        );
+        assert_eq!(run_embedding_batch(&state, 3).unwrap().pending, 0);
+    }
+
+    #[test]
+    fn test_embedding_worker_reports_progress() {
+        let (state, _temp) = create_test_state();
+        create_test_project(&state);
+
+        let (sender, receiver) = mpsc::channel();
+        let status_state = state.clone();
+        let worker = spawn_embedding_worker(state.clone(), move |progress| {
+            // 查询进度时返回后台本轮的累计生成数
+            let status = embedding_progress(&status_state).unwrap();
+            let _ = sender.send((progress, status));
+        });
+        state.embeddings.request_backfill();
+
+        let mut last = EmbeddingProgress::default();
+        while last.pending > 0 || last.embedded == 0 {
+            let (progress, status) = receiver
+                .recv_timeout(Duration::from_secs(10))
+                .expect("未收到进度");
+            assert_eq!(status, progress);
+            last = progress;
+        }
+        assert_eq!(last.embedded, 5);
+        assert_eq!(state.db.count_missing_embeddings().unwrap(), 0);
+
+        state.embeddings.stop();
+        worker.join().unwrap();
+    }
+}
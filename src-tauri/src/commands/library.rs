@@ -0,0 +1,248 @@
+//! Library Commands
+//!
+//! 提供资料库（独立的数据目录与数据库）的创建、列表与切换相关的 Tauri Commands
+
+use crate::commands::project::{AppState, CommandError};
+use crate::commands::trash::purge_expired_trash;
+use crate::db::Database;
+use crate::models::{CreateLibraryData, Library};
+use crate::services::{db_path_for, FileService, LibraryError, LibraryRegistry, DEFAULT_LIBRARY_ID};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::State;
+
+impl From<LibraryError> for CommandError {
+    fn from(e: LibraryError) -> Self {
+        match e {
+            LibraryError::Io(e) => CommandError::Io(e.to_string()),
+            other => CommandError::Validation(other.to_string()),
+        }
+    }
+}
+
+/// 获取全部资料库
+#[tauri::command]
+pub fn library_list(libraries: State<'_, LibraryRegistry>) -> Result<Vec<Library>, CommandError> {
+    Ok(libraries.list())
+}
+
+/// 获取当前打开的资料库
+#[tauri::command]
+pub fn library_current(libraries: State<'_, LibraryRegistry>) -> Result<Library, CommandError> {
+    Ok(libraries.current())
+}
+
+/// 创建资料库（不会切换到新资料库）
+#[tauri::command]
+pub fn library_create(
+    data: CreateLibraryData,
+    libraries: State<'_, LibraryRegistry>,
+) -> Result<Library, CommandError> {
+    let library = libraries.create(&data.name, data.data_dir.as_deref())?;
+    eprintln!("[library_create] 创建资料库: {} ({})", library.name, library.data_dir);
+    Ok(library)
+}
+
+/// 切换到资料库，并记录为下次启动时打开的资料库
+#[tauri::command]
+pub fn library_open(
+    id: String,
+    state: State<'_, Arc<AppState>>,
+    libraries: State<'_, LibraryRegistry>,
+) -> Result<Library, CommandError> {
+    open_library(&state, &libraries, &id)
+}
+
+/// 在运行时切换资料库
+///
+/// 先在新数据目录中准备好目录结构与数据库，再在同一把锁内替换当前连接与数据目录；
+/// 准备失败时仍停留在原资料库，替换后的步骤失败时恢复原连接与数据目录。
+pub(crate) fn open_library(
+    state: &AppState,
+    libraries: &LibraryRegistry,
+    id: &str,
+) -> Result<Library, CommandError> {
+    let library = libraries.get(id)?;
+    let (db, file_service) = open_library_at(&library)?;
+
+    let previous_dir = state.file_service.base_path();
+    let previous = state
+        .db
+        .swap(db, || state.file_service.set_base_path(file_service.base_path()))?;
+    let library = match libraries.mark_opened(id) {
+        Ok(library) => library,
+        Err(e) => {
+            state
+                .db
+                .swap(previous, || state.file_service.set_base_path(previous_dir))?;
+            return Err(e.into());
+        }
+    };
+    eprintln!("[library_open] 已切换到资料库: {}", library.name);
+
+    match purge_expired_trash(state) {
+        Ok(count) if count > 0 => eprintln!("[library_open] 已清理 {} 个过期回收站条目", count),
+        Ok(_) => {}
+        Err(e) => eprintln!("[library_open] 清理回收站失败: {}", e),
+    }
+    Ok(library)
+}
+
+/// 启动时打开资料库
+///
+/// `requested` 为启动参数指定的资料库（ID 或名称），未指定或找不到时打开上次打开的资料库。
+/// 无法打开时（如加密卷未挂载）回退到默认资料库，且不改变上次打开的记录。
+pub fn open_startup_library(
+    libraries: &LibraryRegistry,
+    requested: Option<&str>,
+) -> Result<(Library, Database, FileService), CommandError> {
+    let library = match requested.map(|name| libraries.find(name)) {
+        Some(Ok(library)) => library,
+        Some(Err(e)) => {
+            eprintln!("[library] {}，打开上次打开的资料库", e);
+            libraries.last_opened()
+        }
+        None => libraries.last_opened(),
+    };
+
+    match open_library_at(&library) {
+        Ok((db, file_service)) => {
+            let library = libraries.mark_opened(&library.id)?;
+            Ok((library, db, file_service))
+        }
+        Err(e) if library.id != DEFAULT_LIBRARY_ID => {
+            eprintln!("[library] 无法打开资料库 {}: {}，回退到默认资料库", library.name, e);
+            let (db, file_service) = open_library_at(&libraries.get(DEFAULT_LIBRARY_ID)?)?;
+            let library = libraries.set_current(DEFAULT_LIBRARY_ID)?;
+            Ok((library, db, file_service))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// 打开资料库的数据库与文件服务
+fn open_library_at(library: &Library) -> Result<(Database, FileService), CommandError> {
+    let data_dir = PathBuf::from(&library.data_dir);
+    let db_path = prepare_library_dirs(&data_dir)?;
+
+    let db = Database::new(&db_path)?;
+    let file_service = FileService::new(data_dir);
+    file_service.init_base_dirs()?;
+
+    // 数据库中的文件路径相对于数据目录保存（首次运行时迁移旧的绝对路径）
+    let migrated = db.set_data_dir(&file_service.base_path())?;
+    if migrated > 0 {
+        eprintln!("[library] 已将 {} 条记录的文件路径迁移为相对路径", migrated);
+    }
+    Ok((db, file_service))
+}
+
+/// 确保资料库的数据库目录存在，返回数据库路径
+fn prepare_library_dirs(data_dir: &Path) -> Result<PathBuf, CommandError> {
+    let db_path = db_path_for(data_dir);
+    if let Some(parent) = db_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| CommandError::Io(e.to_string()))?;
+    }
+    Ok(db_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Project, ProjectIcon};
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    fn insert_project(state: &AppState, name: &str) -> Project {
+        let id = uuid::Uuid::new_v4().to_string();
+        let path = state.file_service.create_project_dir(&id).unwrap();
+        let project = Project {
+            id,
+            name: name.to_string(),
+            icon: ProjectIcon::default(),
+            workspace: "research".to_string(),
+            is_starred: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            sources_count: 0,
+            path: path.display().to_string(),
+            archived_at: None,
+        };
+        state.db.insert_project(&project).unwrap();
+        project
+    }
+
+    #[test]
+    fn test_switch_library_at_runtime() {
+        let temp_dir = TempDir::new().unwrap();
+        let registry =
+            LibraryRegistry::load(temp_dir.path(), temp_dir.path().join("DeskLab")).unwrap();
+
+        let (library, db, file_service) = open_startup_library(&registry, None).unwrap();
+        assert_eq!(library.id, DEFAULT_LIBRARY_ID);
+        let state = AppState::new(db, file_service);
+        let default_project = insert_project(&state, "默认项目");
+
+        let work = registry.create("工作", None).unwrap();
+        open_library(&state, &registry, &work.id).unwrap();
+        assert_eq!(state.file_service.base_path(), PathBuf::from(&work.data_dir));
+        assert!(state.db.get_all_projects().unwrap().is_empty());
+        let work_project = insert_project(&state, "工作项目");
+        assert!(work_project.path.starts_with(&work.data_dir));
+
+        // 切回默认资料库后数据互不影响
+        open_library(&state, &registry, DEFAULT_LIBRARY_ID).unwrap();
+        let projects = state.db.get_all_projects().unwrap();
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].id, default_project.id);
+        assert_eq!(projects[0].path, default_project.path);
+
+        // 打开不存在的资料库失败，当前资料库不变
+        assert!(open_library(&state, &registry, "missing").is_err());
+        assert_eq!(registry.current().id, DEFAULT_LIBRARY_ID);
+        assert_eq!(state.db.get_all_projects().unwrap().len(), 1);
+
+        // 切换后无法记录上次打开的资料库时恢复原数据库与数据目录
+        let default_dir = state.file_service.base_path();
+        let temp_config = temp_dir.path().join("libraries.json.tmp");
+        fs::create_dir(&temp_config).unwrap();
+        assert!(open_library(&state, &registry, &work.id).is_err());
+        assert_eq!(state.file_service.base_path(), default_dir);
+        let projects = state.db.get_all_projects().unwrap();
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].id, default_project.id);
+        assert_eq!(projects[0].path, default_project.path);
+        fs::remove_dir(&temp_config).unwrap();
+    }
+
+    #[test]
+    fn test_startup_opens_last_library_and_falls_back() {
+        let temp_dir = TempDir::new().unwrap();
+        let registry =
+            LibraryRegistry::load(temp_dir.path(), temp_dir.path().join("DeskLab")).unwrap();
+        let work = registry.create("工作", None).unwrap();
+        registry.mark_opened(&work.id).unwrap();
+
+        let (library, _, _) = open_startup_library(&registry, None).unwrap();
+        assert_eq!(library.id, work.id);
+        let (library, _, _) = open_startup_library(&registry, Some("不存在")).unwrap();
+        assert_eq!(library.id, work.id);
+        let (library, _, _) = open_startup_library(&registry, Some(DEFAULT_LIBRARY_ID)).unwrap();
+        assert_eq!(library.id, DEFAULT_LIBRARY_ID);
+
+        // 数据目录不可用时回退到默认资料库，且保留上次打开的记录
+        let unmounted_dir = temp_dir.path().join("volume");
+        let unmounted = registry
+            .create("加密卷", Some(unmounted_dir.to_str().unwrap()))
+            .unwrap();
+        registry.mark_opened(&unmounted.id).unwrap();
+        fs::remove_dir_all(&unmounted_dir).unwrap();
+        fs::write(&unmounted_dir, "unmounted").unwrap();
+
+        let (library, _, _) = open_startup_library(&registry, None).unwrap();
+        assert_eq!(library.id, DEFAULT_LIBRARY_ID);
+        assert_eq!(registry.current().id, DEFAULT_LIBRARY_ID);
+        assert_eq!(registry.last_opened().id, unmounted.id);
+    }
+}
@@ -0,0 +1,662 @@
+//! Library Maintenance Commands
+//!
+//! 提供资料库一致性检查与修复相关的 Tauri Commands。
+//! 文件与数据库记录分开写入，异常退出或手动改动数据目录后两者可能不一致：
+//! 记录指向不存在的文件、目录中留下没有记录的文件、记录所属的项目已不存在、计数与全文索引漂移等。
+
+use crate::commands::project::{AppState, CommandError};
+use crate::models::{
+    ContentKind, LibraryCheckReport, LibraryIssue, LibraryIssueKind, LibraryRepairReport,
+};
+use chrono::Utc;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::State;
+use walkdir::WalkDir;
+
+/// 孤立文件移入的目录（位于数据目录下）
+const LOST_FOUND_DIR: &str = "lost+found";
+
+/// 操作系统生成的文件，不视为孤立文件
+const IGNORED_FILE_NAMES: [&str; 3] = [".DS_Store", "Thumbs.db", "desktop.ini"];
+
+/// 检查资料库一致性（只读）
+#[tauri::command]
+pub fn library_check(state: State<'_, Arc<AppState>>) -> Result<LibraryCheckReport, CommandError> {
+    Ok(check_library(&state)?.0)
+}
+
+/// 修复资料库中能自动修复的问题，返回修复结果与复查后仍存在的问题
+///
+/// 孤立文件不会被删除，而是移到数据目录下的 lost+found/ 中。
+#[tauri::command]
+pub fn library_repair(
+    state: State<'_, Arc<AppState>>,
+) -> Result<LibraryRepairReport, CommandError> {
+    repair_library(&state)
+}
+
+/// 问题对应的修复操作
+#[derive(Debug, Clone)]
+enum Repair {
+    CreateProjectDir(String),
+    ClearThumbnail(ContentKind, String),
+    RecreateNote { path: PathBuf, title: String },
+    MoveToLostFound(PathBuf),
+    DeleteDanglingRows,
+    DeleteTrashItem(String),
+    RecountSources(String),
+    ClearCitations(String),
+    DeleteSyncRecord(String),
+    RebuildFts(&'static str),
+}
+
+struct Finding {
+    issue: LibraryIssue,
+    repair: Option<Repair>,
+}
+
+fn finding(
+    kind: LibraryIssueKind,
+    project_id: Option<String>,
+    item_id: Option<String>,
+    path: Option<String>,
+    detail: String,
+    repair: Option<Repair>,
+) -> Finding {
+    Finding {
+        issue: LibraryIssue {
+            kind,
+            project_id,
+            item_id,
+            path,
+            detail,
+            repairable: repair.is_some(),
+        },
+        repair,
+    }
+}
+
+fn io_error(e: impl std::fmt::Display) -> CommandError {
+    CommandError::Io(e.to_string())
+}
+
+fn check_library(state: &AppState) -> Result<(LibraryCheckReport, Vec<Finding>), CommandError> {
+    let mut findings = Vec::new();
+    let mut files_checked = 0;
+
+    // 项目目录
+    let projects = state.db.get_all_projects()?;
+    let project_ids: HashSet<&str> = projects.iter().map(|p| p.id.as_str()).collect();
+    // 来源文件已压缩归档的项目（压缩包存在即视为来源文件完整）
+    let packed_sources: HashSet<PathBuf> = projects
+        .iter()
+        .map(|p| state.file_service.archived_sources_path(&p.id))
+        .filter(|path| path.is_file())
+        .collect();
+    for project in &projects {
+        if !state.file_service.project_dir(&project.id).is_dir() {
+            findings.push(finding(
+                LibraryIssueKind::MissingProjectDir,
+                Some(project.id.clone()),
+                Some(project.id.clone()),
+                Some(
+                    state
+                        .file_service
+                        .project_dir(&project.id)
+                        .display()
+                        .to_string(),
+                ),
+                format!("项目「{}」的目录不存在", project.name),
+                Some(Repair::CreateProjectDir(project.id.clone())),
+            ));
+        }
+    }
+
+    // 记录引用的文件
+    let references = state.db.list_file_references()?;
+    let referenced: HashSet<PathBuf> = references.iter().map(|r| PathBuf::from(&r.path)).collect();
+    for reference in &references {
+        files_checked += 1;
+        let path = Path::new(&reference.path);
+        let packed = reference.kind == ContentKind::Source
+            && !reference.thumbnail
+            && packed_sources.contains(
+                &state
+                    .file_service
+                    .archived_sources_path(&reference.project_id),
+            );
+        if packed {
+            continue;
+        }
+        if !path.is_file() {
+            let repair = if reference.thumbnail {
+                Some(Repair::ClearThumbnail(
+                    reference.kind,
+                    reference.item_id.clone(),
+                ))
+            } else if reference.kind == ContentKind::Note {
+                Some(Repair::RecreateNote {
+                    path: path.to_path_buf(),
+                    title: reference.title.clone(),
+                })
+            } else {
+                None
+            };
+            findings.push(finding(
+                LibraryIssueKind::MissingFile,
+                Some(reference.project_id.clone()),
+                Some(reference.item_id.clone()),
+                Some(reference.path.clone()),
+                format!("「{}」引用的文件不存在", reference.title),
+                repair,
+            ));
+        } else if matches!(
+            reference.kind,
+            ContentKind::Canvas | ContentKind::Presentation
+        ) && !reference.thumbnail
+        {
+            let valid = fs::read(path)
+                .map(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).is_ok())
+                .unwrap_or(false);
+            if !valid {
+                findings.push(finding(
+                    LibraryIssueKind::BadJson,
+                    Some(reference.project_id.clone()),
+                    Some(reference.item_id.clone()),
+                    Some(reference.path.clone()),
+                    format!("「{}」的数据文件无法解析", reference.title),
+                    None,
+                ));
+            }
+        }
+    }
+
+    // 项目目录中的孤立文件（项目回收站目录由回收站记录管理，不检查）
+    let projects_dir = state.file_service.projects_dir();
+    if projects_dir.is_dir() {
+        for entry in fs::read_dir(&projects_dir).map_err(io_error)? {
+            let path = entry.map_err(io_error)?.path();
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if IGNORED_FILE_NAMES.contains(&name.as_str()) {
+                continue;
+            }
+            if !path.is_dir() {
+                findings.push(orphan_file(&path, None));
+                continue;
+            }
+            if !project_ids.contains(name.as_str()) {
+                findings.push(finding(
+                    LibraryIssueKind::OrphanProjectDir,
+                    None,
+                    None,
+                    Some(path.display().to_string()),
+                    "目录没有对应的项目".to_string(),
+                    Some(Repair::MoveToLostFound(path.clone())),
+                ));
+                continue;
+            }
+            let walker = WalkDir::new(&path)
+                .into_iter()
+                .filter_entry(|e| !(e.depth() == 1 && e.file_name() == ".trash"));
+            for entry in walker {
+                let entry = entry.map_err(io_error)?;
+                if !entry.file_type().is_file()
+                    || IGNORED_FILE_NAMES.contains(&entry.file_name().to_string_lossy().as_ref())
+                {
+                    continue;
+                }
+                files_checked += 1;
+                if !referenced.contains(entry.path()) && !packed_sources.contains(entry.path()) {
+                    findings.push(orphan_file(entry.path(), Some(name.clone())));
+                }
+            }
+        }
+    }
+
+    // 悬空记录
+    for row in state.db.find_dangling_rows()? {
+        findings.push(finding(
+            LibraryIssueKind::DanglingRow,
+            row.project_id,
+            Some(row.key),
+            None,
+            format!("{}: {}", row.table, row.detail),
+            Some(Repair::DeleteDanglingRows),
+        ));
+    }
+    for item in state.db.get_all_trash_items()? {
+        if !Path::new(&item.trash_path).exists() {
+            findings.push(finding(
+                LibraryIssueKind::DanglingRow,
+                Some(item.project_id),
+                Some(item.id.clone()),
+                Some(item.trash_path),
+                format!("回收站条目「{}」的文件目录不存在", item.title),
+                Some(Repair::DeleteTrashItem(item.id)),
+            ));
+        }
+    }
+
+    // 计数漂移
+    for (project_id, stored, actual) in state.db.find_sources_count_drift()? {
+        findings.push(finding(
+            LibraryIssueKind::CountDrift,
+            Some(project_id.clone()),
+            Some(project_id.clone()),
+            None,
+            format!("来源数量记录为 {}，实际为 {}", stored, actual),
+            Some(Repair::RecountSources(project_id)),
+        ));
+    }
+
+    // 无法解析的 JSON 字段
+    for row in state.db.find_bad_json()? {
+        let repair = match row.table {
+            "chat_messages" => Some(Repair::ClearCitations(row.key.clone())),
+            "sync_records" => Some(Repair::DeleteSyncRecord(row.key.clone())),
+            _ => None,
+        };
+        findings.push(finding(
+            LibraryIssueKind::BadJson,
+            None,
+            Some(row.key),
+            None,
+            format!("{}.{} 无法解析", row.table, row.column),
+            repair,
+        ));
+    }
+
+    // 全文索引
+    for table in state.db.check_fts()? {
+        findings.push(finding(
+            LibraryIssueKind::FtsDrift,
+            None,
+            None,
+            None,
+            format!("全文索引 {} 与数据表不一致", table),
+            Some(Repair::RebuildFts(table)),
+        ));
+    }
+
+    let report = LibraryCheckReport {
+        checked_at: Utc::now(),
+        projects_checked: projects.len(),
+        files_checked,
+        issues: findings.iter().map(|f| f.issue.clone()).collect(),
+    };
+    Ok((report, findings))
+}
+
+fn orphan_file(path: &Path, project_id: Option<String>) -> Finding {
+    finding(
+        LibraryIssueKind::OrphanFile,
+        project_id,
+        None,
+        Some(path.display().to_string()),
+        "文件没有对应的记录".to_string(),
+        Some(Repair::MoveToLostFound(path.to_path_buf())),
+    )
+}
+
+fn repair_library(state: &AppState) -> Result<LibraryRepairReport, CommandError> {
+    let (_, mut findings) = check_library(state)?;
+    // 先补建项目目录，之后才能重建其中的笔记文件
+    findings.sort_by_key(|f| !matches!(f.repair, Some(Repair::CreateProjectDir(_))));
+
+    let data_dir = state.file_service.base_path();
+    let lost_found = data_dir
+        .join(LOST_FOUND_DIR)
+        .join(Utc::now().format("%Y%m%d-%H%M%S").to_string());
+    let mut moved_files = false;
+    let mut dangling_deleted = false;
+    let mut rebuilt: HashSet<&str> = HashSet::new();
+    let mut repaired = Vec::new();
+
+    for Finding { issue, repair } in findings {
+        let Some(repair) = repair else {
+            continue;
+        };
+        let result: Result<(), CommandError> = match &repair {
+            Repair::CreateProjectDir(id) => state
+                .file_service
+                .create_project_dir(id)
+                .map(|_| ())
+                .map_err(CommandError::from),
+            Repair::ClearThumbnail(kind, id) => state
+                .db
+                .clear_thumbnail(*kind, id)
+                .map_err(CommandError::from),
+            Repair::RecreateNote { path, title } => path
+                .parent()
+                .map(fs::create_dir_all)
+                .transpose()
+                .map_err(io_error)
+                .and_then(|_| {
+                    state
+                        .file_service
+                        .write_atomic(path, format!("# {}\n", title).as_bytes())
+                        .map_err(CommandError::from)
+                }),
+            Repair::MoveToLostFound(path) => {
+                let relative = path.strip_prefix(&data_dir).unwrap_or(path);
+                let dest = lost_found.join(relative);
+                moved_files = true;
+                dest.parent()
+                    .map(fs::create_dir_all)
+                    .transpose()
+                    .map_err(io_error)
+                    .and_then(|_| {
+                        state
+                            .file_service
+                            .move_path(path, &dest)
+                            .map_err(CommandError::from)
+                    })
+            }
+            Repair::DeleteDanglingRows => {
+                if dangling_deleted {
+                    Ok(())
+                } else {
+                    dangling_deleted = true;
+                    state
+                        .db
+                        .delete_dangling_rows()
+                        .map(|_| ())
+                        .map_err(CommandError::from)
+                }
+            }
+            Repair::DeleteTrashItem(id) => state
+                .db
+                .transaction(|tx| tx.delete_trash_item(id))
+                .map_err(CommandError::from),
+            Repair::RecountSources(project_id) => state
+                .db
+                .recount_project_sources(project_id)
+                .map_err(CommandError::from),
+            Repair::ClearCitations(id) => state
+                .db
+                .clear_message_citations(id)
+                .map_err(CommandError::from),
+            Repair::DeleteSyncRecord(key) => match key.split_once('/') {
+                Some((kind, item_id)) => state
+                    .db
+                    .delete_sync_record(kind, item_id)
+                    .map_err(CommandError::from),
+                None => Ok(()),
+            },
+            Repair::RebuildFts(table) => {
+                if rebuilt.insert(table) {
+                    state.db.rebuild_fts(table).map_err(CommandError::from)
+                } else {
+                    Ok(())
+                }
+            }
+        };
+        match result {
+            Ok(()) => repaired.push(issue),
+            Err(e) => eprintln!("[library_repair] 修复失败 {:?}: {}", repair, e),
+        }
+    }
+
+    let (report, _) = check_library(state)?;
+    eprintln!(
+        "[library_repair] 修复 {} 个问题，剩余 {} 个",
+        repaired.len(),
+        report.issues.len()
+    );
+    Ok(LibraryRepairReport {
+        repaired,
+        remaining: report.issues,
+        lost_found_dir: moved_files.then(|| lost_found.display().to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::models::{Canvas, Note, OutputType, Project, ProjectIcon, Source, SourceType};
+    use crate::services::{embed_text, FileService};
+    use tempfile::TempDir;
+
+    fn create_test_state() -> (Arc<AppState>, TempDir) {
+        let temp_dir = TempDir::new().expect("创建临时目录失败");
+        let db = Database::new_in_memory().expect("创建数据库失败");
+        let file_service = FileService::new(temp_dir.path().join("library"));
+        file_service.init_base_dirs().expect("初始化目录失败");
+
+        let state = Arc::new(AppState::new(db, file_service));
+        (state, temp_dir)
+    }
+
+    struct Fixture {
+        project: Project,
+        source: Source,
+        note: Note,
+        canvas: Canvas,
+    }
+
+    /// 创建包含来源、笔记与画布的完整项目
+    fn create_fixture(state: &AppState) -> Fixture {
+        let id = uuid::Uuid::new_v4().to_string();
+        let project_path = state.file_service.create_project_dir(&id).unwrap();
+        let now = Utc::now();
+        let project = Project {
+            id: id.clone(),
+            name: "检查项目".to_string(),
+            icon: ProjectIcon::default(),
+            workspace: "research".to_string(),
+            is_starred: false,
+            created_at: now,
+            updated_at: now,
+            sources_count: 1,
+            path: project_path.display().to_string(),
+            archived_at: None,
+        };
+        state.db.insert_project(&project).unwrap();
+
+        let source_path = state.file_service.get_sources_dir(&id).join("source-1.md");
+        fs::write(&source_path, "来源全文").unwrap();
+        let source = Source {
+            id: "source-1".to_string(),
+            project_id: id.clone(),
+            name: "资料.md".to_string(),
+            source_type: SourceType::Markdown,
+            path: source_path.display().to_string(),
+            size: 12,
+            mime_type: "text/markdown".to_string(),
+            thumbnail_path: None,
+            created_at: now,
+            updated_at: now,
+        };
+        state
+            .db
+            .insert_source_with_content(&source, Some("来源全文"))
+            .unwrap();
+        state
+            .db
+            .upsert_source_embedding(&source.id, &id, &embed_text("来源全文"))
+            .unwrap();
+
+        let note_path = state.file_service.get_notes_dir(&id).join("note-1.md");
+        fs::write(&note_path, "# 笔记").unwrap();
+        let note = Note {
+            id: "note-1".to_string(),
+            project_id: id.clone(),
+            title: "笔记".to_string(),
+            path: note_path.display().to_string(),
+            output_type: OutputType::Note,
+            title_locked: false,
+            created_at: now,
+            updated_at: now,
+        };
+        state.db.insert_note(&note).unwrap();
+
+        let canvas_dir = project_path.join("canvases");
+        fs::create_dir_all(&canvas_dir).unwrap();
+        let canvas_path = canvas_dir.join("canvas-1.json");
+        fs::write(&canvas_path, "{\"elements\":[]}").unwrap();
+        let canvas = Canvas {
+            id: "canvas-1".to_string(),
+            project_id: id.clone(),
+            title: "画布".to_string(),
+            path: canvas_path.display().to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+        state.db.insert_canvas(&canvas).unwrap();
+
+        Fixture {
+            project,
+            source,
+            note,
+            canvas,
+        }
+    }
+
+    fn kinds(issues: &[LibraryIssue]) -> Vec<LibraryIssueKind> {
+        issues.iter().map(|issue| issue.kind).collect()
+    }
+
+    #[test]
+    fn test_healthy_library_has_no_issues() {
+        let (state, _temp) = create_test_state();
+        create_fixture(&state);
+
+        let (report, _) = check_library(&state).unwrap();
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+        assert_eq!(report.projects_checked, 1);
+        // 3 个引用 + 目录中的 3 个文件
+        assert_eq!(report.files_checked, 6);
+    }
+
+    #[test]
+    fn test_check_detects_corruption() {
+        let (state, _temp) = create_test_state();
+        let fixture = create_fixture(&state);
+        let project_dir = state.file_service.project_dir(&fixture.project.id);
+
+        // 记录指向的文件被删除、画布文件损坏
+        fs::remove_file(&fixture.note.path).unwrap();
+        fs::write(&fixture.canvas.path, "{not json").unwrap();
+        // 目录中残留没有记录的文件与项目目录
+        fs::write(project_dir.join("sources").join("stray.pdf"), "orphan").unwrap();
+        fs::create_dir_all(state.file_service.project_dir("ghost").join("notes")).unwrap();
+        // 标签比来源活得久，计数漂移
+        state
+            .db
+            .transaction(|tx| tx.add_tag(ContentKind::Source, "deleted-source", "旧标签"))
+            .unwrap();
+        state
+            .db
+            .update_project_sources_count(&fixture.project.id, 2)
+            .unwrap();
+
+        let (report, _) = check_library(&state).unwrap();
+        let found = kinds(&report.issues);
+        for kind in [
+            LibraryIssueKind::MissingFile,
+            LibraryIssueKind::BadJson,
+            LibraryIssueKind::OrphanFile,
+            LibraryIssueKind::OrphanProjectDir,
+            LibraryIssueKind::DanglingRow,
+            LibraryIssueKind::CountDrift,
+        ] {
+            assert_eq!(
+                found.iter().filter(|k| **k == kind).count(),
+                1,
+                "{:?}: {:?}",
+                kind,
+                report.issues
+            );
+        }
+        let orphan = report
+            .issues
+            .iter()
+            .find(|issue| issue.kind == LibraryIssueKind::OrphanFile)
+            .unwrap();
+        assert!(orphan.path.as_ref().unwrap().ends_with("stray.pdf"));
+        let bad_canvas = report
+            .issues
+            .iter()
+            .find(|issue| issue.kind == LibraryIssueKind::BadJson)
+            .unwrap();
+        assert!(!bad_canvas.repairable);
+    }
+
+    #[test]
+    fn test_repair_fixes_what_it_can() {
+        let (state, _temp) = create_test_state();
+        let fixture = create_fixture(&state);
+        let project_dir = state.file_service.project_dir(&fixture.project.id);
+
+        fs::remove_file(&fixture.note.path).unwrap();
+        fs::remove_file(&fixture.source.path).unwrap();
+        fs::write(&fixture.canvas.path, "{not json").unwrap();
+        fs::write(project_dir.join("notes").join("stray.md"), "orphan").unwrap();
+        fs::create_dir_all(state.file_service.project_dir("ghost")).unwrap();
+        state
+            .db
+            .transaction(|tx| tx.add_tag(ContentKind::Source, "deleted-source", "旧标签"))
+            .unwrap();
+        state
+            .db
+            .update_project_sources_count(&fixture.project.id, 2)
+            .unwrap();
+
+        let report = repair_library(&state).unwrap();
+        assert_eq!(report.repaired.len(), 5, "{:?}", report.repaired);
+
+        // 来源文件与画布数据无法自动恢复
+        assert_eq!(
+            kinds(&report.remaining),
+            vec![LibraryIssueKind::MissingFile, LibraryIssueKind::BadJson]
+        );
+        assert_eq!(
+            report.remaining[0].item_id.as_deref(),
+            Some(fixture.source.id.as_str())
+        );
+
+        // 笔记文件按标题重建，孤立文件移到 lost+found
+        assert_eq!(fs::read_to_string(&fixture.note.path).unwrap(), "# 笔记\n");
+        let lost_found = PathBuf::from(report.lost_found_dir.unwrap());
+        assert!(lost_found
+            .join("projects")
+            .join(&fixture.project.id)
+            .join("notes")
+            .join("stray.md")
+            .is_file());
+        assert!(lost_found.join("projects").join("ghost").is_dir());
+        assert!(!state.file_service.project_dir("ghost").exists());
+        assert_eq!(
+            state
+                .db
+                .get_project(&fixture.project.id)
+                .unwrap()
+                .sources_count,
+            1
+        );
+        assert!(state.db.find_dangling_rows().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_repair_recreates_missing_project_dir() {
+        let (state, _temp) = create_test_state();
+        let fixture = create_fixture(&state);
+        let project_dir = state.file_service.project_dir(&fixture.project.id);
+        fs::remove_dir_all(&project_dir).unwrap();
+
+        let (report, _) = check_library(&state).unwrap();
+        assert!(kinds(&report.issues).contains(&LibraryIssueKind::MissingProjectDir));
+
+        let report = repair_library(&state).unwrap();
+        assert!(project_dir.join("sources").is_dir());
+        assert_eq!(fs::read_to_string(&fixture.note.path).unwrap(), "# 笔记\n");
+        assert!(!kinds(&report.remaining).contains(&LibraryIssueKind::MissingProjectDir));
+    }
+}
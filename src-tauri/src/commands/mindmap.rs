@@ -2,8 +2,11 @@
 //!
 //! 提供思维导图相关的 Tauri Commands
 
-use crate::commands::project::{AppState, CommandError};
-use crate::models::{MindMap, MindMapData};
+use crate::commands::project::{
+    ensure_item_writable, ensure_project_writable, AppState, CommandError,
+};
+use crate::commands::trash::move_to_trash;
+use crate::models::{ContentKind, MindMap, MindMapData};
 use chrono::Utc;
 use std::sync::Arc;
 use tauri::State;
@@ -48,8 +51,8 @@ pub fn mindmap_create(
     initial_data: Option<MindMapData>,
     state: State<'_, Arc<AppState>>,
 ) -> Result<MindMap, CommandError> {
-    // 验证项目存在
-    let _ = state.db.get_project(&project_id)?;
+    // 验证项目存在且未归档
+    ensure_project_writable(&state, &project_id)?;
 
     let id = uuid::Uuid::new_v4().to_string();
     let now = Utc::now();
@@ -82,22 +85,33 @@ pub fn mindmap_save(
     data: MindMapData,
     state: State<'_, Arc<AppState>>,
 ) -> Result<MindMap, CommandError> {
+    save_mindmap(&state, &id, &data)
+}
+
+pub(crate) fn save_mindmap(
+    state: &AppState,
+    id: &str,
+    data: &MindMapData,
+) -> Result<MindMap, CommandError> {
+    ensure_item_writable(state, ContentKind::Mindmap, id)?;
+
     // 序列化数据
-    let data_str = serde_json::to_string(&data)
+    let data_str = serde_json::to_string(data)
         .map_err(|e| CommandError::Internal(format!("序列化思维导图数据失败: {}", e)))?;
 
-    // 更新数据
-    state.db.update_mindmap_data(&id, &data_str)?;
-
-    // 更新主题和布局
+    // 在同一事务中更新数据、主题和布局
     let theme = data.theme.as_ref().map(|t| t.template.as_str());
     let layout = data.layout.as_deref();
-    if theme.is_some() || layout.is_some() {
-        state.db.update_mindmap_meta(&id, None, theme, layout)?;
-    }
+    state.db.transaction(|tx| -> Result<(), CommandError> {
+        tx.update_mindmap_data(id, &data_str)?;
+        if theme.is_some() || layout.is_some() {
+            tx.update_mindmap_meta(id, None, theme, layout)?;
+        }
+        Ok(())
+    })?;
 
     // 返回更新后的元数据
-    let updated = state.db.get_mindmap(&id)?;
+    let updated = state.db.get_mindmap(id)?;
     Ok(updated)
 }
 
@@ -108,6 +122,8 @@ pub fn mindmap_rename(
     title: String,
     state: State<'_, Arc<AppState>>,
 ) -> Result<MindMap, CommandError> {
+    ensure_item_writable(&state, ContentKind::Mindmap, &id)?;
+
     state.db.update_mindmap_title(&id, &title)?;
     let mindmap = state.db.get_mindmap(&id)?;
     Ok(mindmap)
@@ -120,6 +136,8 @@ pub fn mindmap_set_theme(
     theme: String,
     state: State<'_, Arc<AppState>>,
 ) -> Result<MindMap, CommandError> {
+    ensure_item_writable(&state, ContentKind::Mindmap, &id)?;
+
     state.db.update_mindmap_meta(&id, None, Some(&theme), None)?;
     let mindmap = state.db.get_mindmap(&id)?;
     Ok(mindmap)
@@ -132,6 +150,8 @@ pub fn mindmap_set_layout(
     layout: String,
     state: State<'_, Arc<AppState>>,
 ) -> Result<MindMap, CommandError> {
+    ensure_item_writable(&state, ContentKind::Mindmap, &id)?;
+
     state.db.update_mindmap_meta(&id, None, None, Some(&layout))?;
     let mindmap = state.db.get_mindmap(&id)?;
     Ok(mindmap)
@@ -143,7 +163,8 @@ pub fn mindmap_delete(
     id: String,
     state: State<'_, Arc<AppState>>,
 ) -> Result<(), CommandError> {
-    state.db.delete_mindmap(&id)?;
+    // 移入回收站
+    move_to_trash(&state, ContentKind::Mindmap, &id)?;
     Ok(())
 }
 
@@ -161,7 +182,7 @@ mod tests {
         let file_service = FileService::new(temp_dir.path().to_path_buf());
         file_service.init_base_dirs().expect("初始化目录失败");
 
-        let state = Arc::new(AppState { db, file_service });
+        let state = Arc::new(AppState::new(db, file_service));
         (state, temp_dir)
     }
 
@@ -179,6 +200,7 @@ mod tests {
             updated_at: now,
             sources_count: 0,
             path: project_path.display().to_string(),
+            archived_at: None,
         };
         state.db.insert_project(&project).unwrap();
         project
@@ -1,25 +1,49 @@
 //! Tauri Commands 模块
 
+pub mod activity;
 pub mod apikey;
+pub mod archive;
+pub mod backup;
+pub mod batch;
 pub mod canvas;
 pub mod chat;
+pub mod embedding;
 pub mod export;
+pub mod library;
+pub mod maintenance;
 pub mod mindmap;
 pub mod note;
 pub mod ppt;
 pub mod project;
+pub mod saved_search;
 pub mod search;
 pub mod source;
+pub mod sync;
+pub mod tag;
+pub mod template;
+pub mod trash;
 pub mod workspace;
 
+pub use activity::*;
 pub use apikey::*;
+pub use archive::*;
+pub use backup::*;
+pub use batch::*;
 pub use canvas::*;
 pub use chat::*;
+pub use embedding::*;
 pub use export::*;
+pub use library::*;
+pub use maintenance::*;
 pub use mindmap::*;
 pub use note::*;
 pub use ppt::*;
 pub use project::*;
+pub use saved_search::*;
 pub use search::*;
 pub use source::*;
+pub use sync::*;
+pub use tag::*;
+pub use template::*;
+pub use trash::*;
 pub use workspace::*;
@@ -2,11 +2,18 @@
 //!
 //! 提供笔记管理相关的 Tauri Commands
 
-use crate::commands::project::{AppState, CommandError};
-use crate::models::{Note, OutputType, Source, SourceType};
-use crate::services::embed_text;
+use crate::commands::project::{
+    ensure_item_writable, ensure_project_writable, AppState, CommandError,
+};
+use crate::commands::trash::{move_to_trash, prepare_trash, PendingTrash};
+use crate::db::DbTx;
+use crate::models::{ContentKind, Note, OutputType, Source, SourceType};
+use crate::services::{
+    embed_text, merge_documents, resolve_note_title, split_sections, StagedFile,
+};
 use chrono::Utc;
 use std::fs;
+use std::path::Path;
 use std::sync::Arc;
 use tauri::State;
 
@@ -38,8 +45,8 @@ pub fn note_create(
     output_type: Option<String>,
     state: State<'_, Arc<AppState>>,
 ) -> Result<Note, CommandError> {
-    // 验证项目存在
-    state.db.get_project(&project_id)?;
+    // 验证项目存在且未归档
+    ensure_project_writable(&state, &project_id)?;
 
     let id = uuid::Uuid::new_v4().to_string();
     let title = title.unwrap_or_else(|| "未命名笔记".to_string());
@@ -51,9 +58,9 @@ pub fn note_create(
     let notes_dir = state.file_service.get_notes_dir(&project_id);
     fs::create_dir_all(&notes_dir).map_err(|e| CommandError::Io(e.to_string()))?;
 
-    // 创建笔记文件
+    // 暂存空白笔记文件，数据库记录写入成功后再提交
     let file_path = notes_dir.join(format!("{}.md", id));
-    fs::write(&file_path, "").map_err(|e| CommandError::Io(e.to_string()))?;
+    let staged = state.file_service.stage_write(&file_path, b"")?;
 
     let now = Utc::now();
     let note = Note {
@@ -62,12 +69,18 @@ pub fn note_create(
         title,
         path: file_path.display().to_string(),
         output_type,
+        title_locked: false,
         created_at: now,
         updated_at: now,
     };
 
-    // 保存到数据库
-    state.db.insert_note(&note)?;
+    // 保存到数据库，文件提交失败时回滚
+    state.db.transaction(|tx| -> Result<(), CommandError> {
+        tx.insert_note(&note)?;
+        tx.update_note_content(&note.id, "")?;
+        staged.commit()?;
+        Ok(())
+    })?;
 
     Ok(note)
 }
@@ -90,28 +103,43 @@ pub fn note_save(
     content: String,
     state: State<'_, Arc<AppState>>,
 ) -> Result<(), CommandError> {
-    let note = state.db.get_note(&id)?;
+    save_note(&state, &id, &content)
+}
 
-    // 写入文件
-    fs::write(&note.path, &content).map_err(|e| CommandError::Io(e.to_string()))?;
+/// 保存笔记内容：写入文件并更新标题、正文索引与向量（内容变化时更新修改时间）
+pub(crate) fn save_note(state: &AppState, id: &str, content: &str) -> Result<(), CommandError> {
+    let note = state.db.get_note(id)?;
+    ensure_project_writable(state, &note.project_id)?;
 
-    // 只有当内容中明确有标题时才更新标题（避免覆盖用户手动设置的标题）
-    if let Some(title) = extract_title(&content) {
-        state.db.update_note_title(&id, &title)?;
-    }
+    // 先将内容暂存到临时文件，数据库更新成功后再替换正式文件
+    let staged = state
+        .file_service
+        .stage_write(Path::new(&note.path), content.as_bytes())?;
 
-    // 更新向量索引
-    let embedding = embed_text(&content);
-    if embedding.is_empty() {
-        let _ = state.db.delete_note_embedding(&id);
+    // 标题未锁定且内容中明确有标题时才更新标题（避免覆盖用户手动设置的标题）
+    let title = if note.title_locked {
+        None
     } else {
-        if let Err(e) = state
-            .db
-            .upsert_note_embedding(&id, &note.project_id, &embedding)
-        {
+        resolve_note_title(content).filter(|title| *title != note.title)
+    };
+    let embedding = embed_text(content);
+
+    state.db.transaction(|tx| -> Result<(), CommandError> {
+        if let Some(title) = &title {
+            tx.update_note_title(id, title)?;
+        }
+        tx.update_note_content(id, content)?;
+
+        // 更新向量索引
+        if embedding.is_empty() {
+            tx.delete_note_embedding(id)?;
+        } else if let Err(e) = tx.upsert_note_embedding(id, &note.project_id, &embedding) {
             eprintln!("[WARN] 笔记向量写入失败: {}", e);
         }
-    }
+
+        staged.commit()?;
+        Ok(())
+    })?;
 
     Ok(())
 }
@@ -119,17 +147,8 @@ pub fn note_save(
 /// 删除笔记
 #[tauri::command]
 pub fn note_delete(id: String, state: State<'_, Arc<AppState>>) -> Result<(), CommandError> {
-    let note = state.db.get_note(&id)?;
-
-    // 从数据库删除
-    state.db.delete_note(&id)?;
-
-    // 删除向量索引
-    let _ = state.db.delete_note_embedding(&id);
-
-    // 删除文件
-    let _ = fs::remove_file(&note.path);
-
+    // 移入回收站（文件与向量索引随记录一起可恢复）
+    move_to_trash(&state, ContentKind::Note, &id)?;
     Ok(())
 }
 
@@ -140,23 +159,38 @@ pub fn note_rename(
     title: String,
     state: State<'_, Arc<AppState>>,
 ) -> Result<Note, CommandError> {
-    // 验证笔记存在
-    state.db.get_note(&id)?;
+    ensure_item_writable(&state, ContentKind::Note, &id)?;
 
-    // 更新标题
-    state.db.update_note_title(&id, &title)?;
+    // 更新标题并锁定，之后保存内容不会覆盖手动设置的标题
+    state.db.rename_note(&id, &title)?;
 
     // 返回更新后的笔记
     let note = state.db.get_note(&id)?;
     Ok(note)
 }
 
-/// 从内容中提取标题
-fn extract_title(content: &str) -> Option<String> {
-    content
-        .lines()
-        .find(|line| line.starts_with("# "))
-        .map(|line| line.trim_start_matches("# ").to_string())
+/// 解除笔记标题锁定，并按当前内容重新解析标题
+#[tauri::command]
+pub fn note_unlock_title(id: String, state: State<'_, Arc<AppState>>) -> Result<Note, CommandError> {
+    let note = state.db.get_note(&id)?;
+    ensure_project_writable(&state, &note.project_id)?;
+    state.db.update_note_title_locked(&id, false)?;
+
+    let content = fs::read_to_string(&note.path).unwrap_or_default();
+    if let Some(title) = resolve_note_title(&content) {
+        state.db.update_note_title(&id, &title)?;
+    }
+
+    let note = state.db.get_note(&id)?;
+    Ok(note)
+}
+
+/// 获取笔记的有效标题：锁定时使用已保存的标题，否则优先使用内容中解析出的标题
+fn effective_title(note: &Note, content: &str) -> String {
+    if note.title_locked {
+        return note.title.clone();
+    }
+    resolve_note_title(content).unwrap_or_else(|| note.title.clone())
 }
 
 /// 将笔记转换为来源
@@ -171,8 +205,41 @@ pub fn note_to_source(
         note_id, delete_original
     );
 
+    let mut pending = prepare_note_conversion(&state, &note_id)?;
+
+    // 在一个事务中写入来源、向量索引、sources_count，并按需删除原笔记；
+    // 来源文件提交失败时整体回滚
+    state
+        .db
+        .transaction(|tx| apply_note_conversion(tx, &mut pending, delete_original))?;
+
+    // 数据库提交后再删除原笔记文件
+    if delete_original {
+        let _ = fs::remove_file(&pending.note.path);
+        println!("[note_to_source] 已删除原笔记: {}", note_id);
+    }
+
+    println!("[note_to_source] 转换成功: source_id={}", pending.source.id);
+    Ok(pending.source)
+}
+
+/// 笔记转来源的准备结果（来源文件已暂存，尚未写入数据库）
+pub(crate) struct PendingConversion {
+    pub note: Note,
+    pub source: Source,
+    content: String,
+    embedding: Vec<f32>,
+    staged: Option<StagedFile>,
+}
+
+/// 读取笔记内容并暂存来源文件
+pub(crate) fn prepare_note_conversion(
+    state: &AppState,
+    note_id: &str,
+) -> Result<PendingConversion, CommandError> {
     // 1. 获取笔记信息
-    let note = state.db.get_note(&note_id)?;
+    let note = state.db.get_note(note_id)?;
+    ensure_project_writable(state, &note.project_id)?;
 
     // 2. 读取笔记内容
     let content = fs::read_to_string(&note.path).map_err(|e| CommandError::Io(e.to_string()))?;
@@ -186,8 +253,7 @@ pub fn note_to_source(
     // 3. 生成来源 ID 和文件名
     let source_id = uuid::Uuid::new_v4().to_string();
     // 移除文件名中的非法字符
-    let safe_title: String = note
-        .title
+    let safe_title: String = effective_title(&note, &content)
         .chars()
         .map(|c| {
             if ['/', '\\', ':', '*', '?', '"', '<', '>', '|'].contains(&c) {
@@ -199,15 +265,15 @@ pub fn note_to_source(
         .collect();
     let file_name = format!("{}.md", safe_title);
 
-    // 4. 创建来源目录和文件
+    // 4. 创建来源目录，暂存来源文件
     let sources_dir = state.file_service.get_sources_dir(&note.project_id);
     fs::create_dir_all(&sources_dir).map_err(|e| CommandError::Io(e.to_string()))?;
 
     let dest_filename = format!("{}.md", source_id);
     let source_path = sources_dir.join(&dest_filename);
-
-    // 写入 Markdown 内容
-    fs::write(&source_path, &content).map_err(|e| CommandError::Io(e.to_string()))?;
+    let staged = state
+        .file_service
+        .stage_write(&source_path, content.as_bytes())?;
 
     // 获取文件大小
     let size = content.len() as i64;
@@ -215,7 +281,7 @@ pub fn note_to_source(
     // 5. 创建 Source 对象
     let now = Utc::now();
     let source = Source {
-        id: source_id.clone(),
+        id: source_id,
         project_id: note.project_id.clone(),
         name: file_name,
         source_type: SourceType::Markdown,
@@ -226,42 +292,224 @@ pub fn note_to_source(
         created_at: now,
         updated_at: now,
     };
+    let embedding = embed_text(&content);
 
-    // 6. 保存到数据库（带文本内容）
-    state
-        .db
-        .insert_source_with_content(&source, Some(&content))?;
+    Ok(PendingConversion {
+        note,
+        source,
+        content,
+        embedding,
+        staged: Some(staged),
+    })
+}
 
-    // 7. 写入向量索引
-    let embedding = embed_text(&content);
-    if !embedding.is_empty() {
-        if let Err(e) = state
-            .db
-            .upsert_source_embedding(&source_id, &note.project_id, &embedding)
-        {
+/// 在事务中写入来源、向量索引与 sources_count，按需删除原笔记记录，最后提交来源文件
+pub(crate) fn apply_note_conversion(
+    tx: &DbTx,
+    pending: &mut PendingConversion,
+    delete_original: bool,
+) -> Result<(), CommandError> {
+    let source = &pending.source;
+    tx.insert_source_with_content(source, Some(&pending.content))?;
+
+    if !pending.embedding.is_empty() {
+        if let Err(e) = tx.upsert_source_embedding(&source.id, &source.project_id, &pending.embedding) {
             eprintln!("[WARN] 来源向量写入失败: {}", e);
         }
     }
 
-    // 8. 更新项目 sources_count
-    let _ = state.db.update_project_sources_count(&note.project_id, 1);
+    tx.update_project_sources_count(&source.project_id, 1)?;
 
-    // 9. 如果需要删除原笔记
     if delete_original {
-        // 从数据库删除
-        state.db.delete_note(&note_id)?;
+        tx.delete_note(&pending.note.id)?;
+        tx.delete_note_embedding(&pending.note.id)?;
+    }
 
-        // 删除向量索引
-        let _ = state.db.delete_note_embedding(&note_id);
+    if let Some(staged) = pending.staged.take() {
+        staged.commit()?;
+    }
+    Ok(())
+}
 
-        // 删除文件
-        let _ = fs::remove_file(&note.path);
+/// 合并多篇笔记为一篇新笔记
+///
+/// 按传入顺序拼接内容，每篇笔记前插入一级标题作为分隔；
+/// `delete_originals` 为 true 时原笔记移入回收站。
+#[tauri::command]
+pub fn note_merge(
+    ids: Vec<String>,
+    title: String,
+    delete_originals: Option<bool>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Note, CommandError> {
+    if ids.len() < 2 {
+        return Err(CommandError::Validation("至少需要选择两篇笔记".to_string()));
+    }
+    let title = title.trim().to_string();
+    if title.is_empty() {
+        return Err(CommandError::Validation("笔记标题不能为空".to_string()));
+    }
 
-        println!("[note_to_source] 已删除原笔记: {}", note_id);
+    let mut notes: Vec<Note> = Vec::with_capacity(ids.len());
+    for id in &ids {
+        if notes.iter().any(|n| n.id == *id) {
+            return Err(CommandError::Validation(format!("笔记重复: {}", id)));
+        }
+        notes.push(state.db.get_note(id)?);
+    }
+    let project_id = notes[0].project_id.clone();
+    if notes.iter().any(|n| n.project_id != project_id) {
+        return Err(CommandError::Validation(
+            "只能合并同一项目中的笔记".to_string(),
+        ));
+    }
+    ensure_project_writable(&state, &project_id)?;
+
+    let mut documents = Vec::with_capacity(notes.len());
+    for note in &notes {
+        let content = fs::read_to_string(&note.path).map_err(|e| CommandError::Io(e.to_string()))?;
+        documents.push((effective_title(note, &content), content));
     }
+    let content = merge_documents(&documents);
 
-    println!("[note_to_source] 转换成功: source_id={}", source_id);
-    Ok(source)
+    // 合并后的首个一级标题是第一篇笔记的标题，因此锁定用户指定的标题
+    let replaced = if delete_originals.unwrap_or(false) {
+        ids.as_slice()
+    } else {
+        &[]
+    };
+    let merged = create_notes(&state, &project_id, vec![(title, content, true)], replaced)?
+        .remove(0);
+
+    Ok(merged)
+}
+
+/// 按标题拆分笔记，每个段落生成一篇新笔记
+///
+/// 级别不高于 `heading_level` 的标题都会开始新段落，第一个标题之前的内容沿用原笔记标题；
+/// `delete_original` 为 true 时原笔记移入回收站。
+#[tauri::command]
+pub fn note_split(
+    id: String,
+    heading_level: u8,
+    delete_original: Option<bool>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<Note>, CommandError> {
+    if !(1..=6).contains(&heading_level) {
+        return Err(CommandError::Validation(
+            "标题级别必须在 1 到 6 之间".to_string(),
+        ));
+    }
+
+    let note = state.db.get_note(&id)?;
+    ensure_project_writable(&state, &note.project_id)?;
+    let content = fs::read_to_string(&note.path).map_err(|e| CommandError::Io(e.to_string()))?;
+
+    let sections = split_sections(&content, heading_level as usize);
+    if sections.len() < 2 {
+        return Err(CommandError::Validation(
+            "笔记中没有可拆分的标题".to_string(),
+        ));
+    }
+
+    let original_title = effective_title(&note, &content);
+    let drafts = sections
+        .into_iter()
+        .map(|section| {
+            let title = section
+                .title
+                .filter(|t| !t.is_empty())
+                .unwrap_or_else(|| original_title.clone());
+            (title, section.content, false)
+        })
+        .collect();
+    let replaced = if delete_original.unwrap_or(false) {
+        std::slice::from_ref(&id)
+    } else {
+        &[]
+    };
+    create_notes(&state, &note.project_id, drafts, replaced)
+}
+
+/// 在一个事务中批量创建笔记（标题、内容、是否锁定标题），同时写入正文与向量索引
+///
+/// `replaced` 中的原笔记在同一事务中移入回收站（合并、拆分时使用），任一步失败时都不生效。
+pub(crate) fn create_notes(
+    state: &AppState,
+    project_id: &str,
+    drafts: Vec<(String, String, bool)>,
+    replaced: &[String],
+) -> Result<Vec<Note>, CommandError> {
+    let notes_dir = state.file_service.get_notes_dir(project_id);
+    fs::create_dir_all(&notes_dir).map_err(|e| CommandError::Io(e.to_string()))?;
+
+    let mut trash: Vec<PendingTrash> = Vec::with_capacity(replaced.len());
+    for id in replaced {
+        match prepare_trash(state, ContentKind::Note, id) {
+            Ok(pending) => trash.push(pending),
+            Err(e) => {
+                for pending in &trash {
+                    pending.rollback(state);
+                }
+                return Err(e);
+            }
+        }
+    }
+
+    let now = Utc::now();
+    let mut pending = Vec::with_capacity(drafts.len());
+    for (title, content, title_locked) in drafts {
+        let id = uuid::Uuid::new_v4().to_string();
+        let file_path = notes_dir.join(format!("{}.md", id));
+        let staged = state
+            .file_service
+            .stage_write(&file_path, content.as_bytes())?;
+        let note = Note {
+            id,
+            project_id: project_id.to_string(),
+            title,
+            path: file_path.display().to_string(),
+            output_type: OutputType::Note,
+            title_locked,
+            created_at: now,
+            updated_at: now,
+        };
+        let embedding = embed_text(&content);
+        pending.push((note, content, staged, embedding));
+    }
+
+    let notes: Vec<Note> = pending.iter().map(|(note, _, _, _)| note.clone()).collect();
+
+    // 记录与向量写入成功后再提交文件，任一文件提交失败时数据库整体回滚
+    let result = state.db.transaction(|tx| -> Result<(), CommandError> {
+        for (note, content, _, embedding) in &pending {
+            tx.insert_note(note)?;
+            tx.update_note_content(&note.id, content)?;
+            if !embedding.is_empty() {
+                tx.upsert_note_embedding(&note.id, project_id, embedding)?;
+            }
+        }
+        for pending in &trash {
+            pending.apply(tx)?;
+        }
+        for (_, _, staged, _) in pending {
+            staged.commit()?;
+        }
+        Ok(())
+    });
+
+    if let Err(e) = result {
+        // 清理回滚前已提交的文件，原笔记文件移回原处
+        for note in &notes {
+            let _ = fs::remove_file(&note.path);
+        }
+        for pending in &trash {
+            pending.rollback(state);
+        }
+        return Err(e);
+    }
+
+    Ok(notes)
 }
 
 #[cfg(test)]
@@ -278,7 +526,7 @@ mod tests {
         let file_service = FileService::new(temp_dir.path().to_path_buf());
         file_service.init_base_dirs().expect("初始化目录失败");
 
-        let state = Arc::new(AppState { db, file_service });
+        let state = Arc::new(AppState::new(db, file_service));
         (state, temp_dir)
     }
 
@@ -295,23 +543,93 @@ mod tests {
             updated_at: Utc::now(),
             sources_count: 0,
             path: project_path.display().to_string(),
+            archived_at: None,
         };
         state.db.insert_project(&project).unwrap();
         project
     }
 
     #[test]
-    fn test_extract_title() {
-        assert_eq!(
-            extract_title("# 我的笔记\n\n这是内容"),
-            Some("我的笔记".to_string())
-        );
-        assert_eq!(
-            extract_title("## 二级标题\n# 一级标题"),
-            Some("一级标题".to_string())
-        );
-        assert_eq!(extract_title("没有标题的内容"), None);
-        assert_eq!(extract_title(""), None);
+    fn test_effective_title() {
+        let mut note = Note {
+            id: "note-title".to_string(),
+            project_id: "project".to_string(),
+            title: "手动标题".to_string(),
+            path: "/test/note.md".to_string(),
+            output_type: OutputType::Note,
+            title_locked: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        let content = "```\n# 代码注释\n```\n内容标题\n===\n";
+        assert_eq!(effective_title(&note, content), "内容标题");
+        assert_eq!(effective_title(&note, "没有标题"), "手动标题");
+
+        note.title_locked = true;
+        assert_eq!(effective_title(&note, content), "手动标题");
+    }
+
+    #[test]
+    fn test_note_rename_locks_title() {
+        let (state, _temp) = create_test_state();
+        let project = create_test_project(&state, "测试项目");
+
+        let note = Note {
+            id: "note-lock".to_string(),
+            project_id: project.id.clone(),
+            title: "原标题".to_string(),
+            path: "/test/note.md".to_string(),
+            output_type: OutputType::Note,
+            title_locked: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        state.db.insert_note(&note).unwrap();
+        assert!(!state.db.get_note("note-lock").unwrap().title_locked);
+
+        state.db.rename_note("note-lock", "手动标题").unwrap();
+        let retrieved = state.db.get_note("note-lock").unwrap();
+        assert_eq!(retrieved.title, "手动标题");
+        assert!(retrieved.title_locked);
+
+        state.db.update_note_title_locked("note-lock", false).unwrap();
+        assert!(!state.db.get_note("note-lock").unwrap().title_locked);
+
+        assert!(state.db.rename_note("missing", "标题").is_err());
+    }
+
+    #[test]
+    fn test_note_save_updates_modified_time() {
+        let (state, _temp) = create_test_state();
+        let project = create_test_project(&state, "测试项目");
+        let old = chrono::DateTime::parse_from_rfc3339("2020-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let note = Note {
+            id: "note-save".to_string(),
+            project_id: project.id.clone(),
+            title: "会议记录".to_string(),
+            path: format!("{}/notes/note-save.md", project.path),
+            output_type: OutputType::Note,
+            title_locked: false,
+            created_at: old,
+            updated_at: old,
+        };
+        state.db.insert_note(&note).unwrap();
+
+        // 正文没有标题时也更新修改时间
+        save_note(&state, "note-save", "本周讨论了发布计划").unwrap();
+        let saved = state.db.get_note("note-save").unwrap();
+        assert_eq!(saved.title, "会议记录");
+        assert!(saved.updated_at > old);
+
+        // 内容未变化时不更新；仅写入索引时不更新
+        let touched = saved.updated_at;
+        save_note(&state, "note-save", "本周讨论了发布计划").unwrap();
+        assert_eq!(state.db.get_note("note-save").unwrap().updated_at, touched);
+        state.db.index_note_content("note-save", "外部修改").unwrap();
+        assert_eq!(state.db.get_note("note-save").unwrap().updated_at, touched);
     }
 
     #[test]
@@ -326,6 +644,7 @@ mod tests {
             title: "测试笔记".to_string(),
             path: "/test/note.md".to_string(),
             output_type: OutputType::Note,
+            title_locked: false,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -356,6 +675,7 @@ mod tests {
                 title: format!("笔记 {}", i),
                 path: format!("/test/note{}.md", i),
                 output_type: OutputType::Note,
+                title_locked: false,
                 created_at: Utc::now(),
                 updated_at: Utc::now(),
             };
@@ -377,6 +697,7 @@ mod tests {
             title: "原标题".to_string(),
             path: "/test/note.md".to_string(),
             output_type: OutputType::Note,
+            title_locked: false,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -399,6 +720,7 @@ mod tests {
             title: "待删除笔记".to_string(),
             path: "/test/note.md".to_string(),
             output_type: OutputType::Note,
+            title_locked: false,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -422,6 +744,7 @@ mod tests {
             title: "资料摘要".to_string(),
             path: "/test/summary.md".to_string(),
             output_type: OutputType::Summary,
+            title_locked: false,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -431,4 +754,92 @@ mod tests {
         let retrieved = state.db.get_note("note-summary").unwrap();
         assert_eq!(retrieved.output_type, OutputType::Summary);
     }
+
+    #[test]
+    fn test_create_notes_for_split_sections() {
+        let (state, _temp) = create_test_state();
+        let project = create_test_project(&state, "测试项目");
+
+        let content = "# 周报\n\n## 进展\n完成了检索功能\n\n## 计划\n下周发布版本\n";
+        let drafts = split_sections(content, 2)
+            .into_iter()
+            .map(|section| (section.title.unwrap(), section.content, false))
+            .collect();
+        let notes = create_notes(&state, &project.id, drafts, &[]).unwrap();
+
+        let titles: Vec<_> = notes.iter().map(|n| n.title.as_str()).collect();
+        assert_eq!(titles, vec!["周报", "进展", "计划"]);
+        assert_eq!(state.db.get_notes_by_project(&project.id).unwrap().len(), 3);
+        assert_eq!(
+            fs::read_to_string(&notes[2].path).unwrap(),
+            "## 计划\n下周发布版本\n"
+        );
+
+        // 每篇新笔记都写入了向量索引
+        assert!(state.db.list_notes_missing_embeddings(10).unwrap().is_empty());
+        let results = state
+            .db
+            .search_notes_semantic(&embed_text("下周发布版本"), &["发布"], None, 1, false)
+            .unwrap();
+        assert_eq!(results[0].id, notes[2].id);
+        assert_eq!(results[0].snippets[0].highlights.len(), 1);
+    }
+
+    #[test]
+    fn test_create_merged_note_locks_title() {
+        let (state, _temp) = create_test_state();
+        let project = create_test_project(&state, "测试项目");
+
+        let content = merge_documents(&[
+            ("周一会议".to_string(), "讨论排期".to_string()),
+            ("周二会议".to_string(), "决定上线".to_string()),
+        ]);
+        let merged = create_notes(
+            &state,
+            &project.id,
+            vec![("会议汇总".to_string(), content, true)],
+            &[],
+        )
+        .unwrap()
+        .remove(0);
+
+        let retrieved = state.db.get_note(&merged.id).unwrap();
+        assert_eq!(retrieved.title, "会议汇总");
+        assert!(retrieved.title_locked);
+        let saved = fs::read_to_string(&merged.path).unwrap();
+        assert_eq!(effective_title(&retrieved, &saved), "会议汇总");
+        assert_eq!(state.db.search_notes("会议汇总", None, 10, false).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_create_notes_trashes_replaced_notes_together() {
+        let (state, _temp) = create_test_state();
+        let project = create_test_project(&state, "测试项目");
+        let originals = create_notes(
+            &state,
+            &project.id,
+            vec![
+                ("周一会议".to_string(), "讨论排期".to_string(), false),
+                ("周二会议".to_string(), "决定上线".to_string(), false),
+            ],
+            &[],
+        )
+        .unwrap();
+        let ids: Vec<String> = originals.iter().map(|n| n.id.clone()).collect();
+        let draft = || vec![("会议汇总".to_string(), "汇总".to_string(), true)];
+
+        // 任一原笔记无法移入回收站时不创建新笔记，已移走的文件移回原处
+        let missing = vec![ids[0].clone(), "missing".to_string()];
+        assert!(create_notes(&state, &project.id, draft(), &missing).is_err());
+        assert_eq!(state.db.get_notes_by_project(&project.id).unwrap().len(), 2);
+        assert_eq!(fs::read_to_string(&originals[0].path).unwrap(), "讨论排期");
+        assert!(state.db.get_all_trash_items().unwrap().is_empty());
+
+        let merged = create_notes(&state, &project.id, draft(), &ids).unwrap();
+        let notes = state.db.get_notes_by_project(&project.id).unwrap();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].id, merged[0].id);
+        assert_eq!(state.db.get_all_trash_items().unwrap().len(), 2);
+        assert!(!Path::new(&originals[0].path).exists());
+    }
 }
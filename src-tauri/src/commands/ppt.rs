@@ -2,9 +2,12 @@
 //!
 //! 提供 PPT 演示文稿相关的 Tauri Commands
 
-use crate::commands::project::{AppState, CommandError};
-use crate::models::{Presentation, PptData, PptOutline, PptistSlide, PptistElement, SlideBackground, PptTheme};
-use crate::services::PptExportService;
+use crate::commands::project::{
+    ensure_item_writable, ensure_project_writable, AppState, CommandError,
+};
+use crate::commands::trash::move_to_trash;
+use crate::models::{ContentKind, Presentation, PptData, PptOutline, PptistSlide, PptistElement, SlideBackground, PptTheme};
+use crate::services::{extract_ppt_text, PptExportService};
 use chrono::Utc;
 use std::fs;
 use std::path::Path;
@@ -53,8 +56,17 @@ pub fn ppt_create(
     outline: Option<PptOutline>,
     state: State<'_, Arc<AppState>>,
 ) -> Result<Presentation, CommandError> {
-    // 验证项目存在
-    let _ = state.db.get_project(&project_id)?;
+    create_ppt(&state, project_id, title, outline)
+}
+
+fn create_ppt(
+    state: &AppState,
+    project_id: String,
+    title: String,
+    outline: Option<PptOutline>,
+) -> Result<Presentation, CommandError> {
+    // 验证项目存在且未归档
+    ensure_project_writable(state, &project_id)?;
 
     let id = uuid::Uuid::new_v4().to_string();
     let now = Utc::now();
@@ -75,8 +87,7 @@ pub fn ppt_create(
     let data_path = ppt_dir.join(format!("{}.json", id));
     let json = serde_json::to_string_pretty(&ppt_data)
         .map_err(|e| CommandError::Internal(format!("序列化 PPT 数据失败: {}", e)))?;
-    fs::write(&data_path, json)
-        .map_err(|e| CommandError::Io(format!("保存 PPT 数据失败: {}", e)))?;
+    let staged = state.file_service.stage_write(&data_path, json.as_bytes())?;
 
     // 创建元数据记录
     let presentation = Presentation {
@@ -90,7 +101,13 @@ pub fn ppt_create(
         updated_at: now,
     };
 
-    state.db.insert_presentation(&presentation)?;
+    // 元数据与数据文件一起落盘，文件提交失败时回滚
+    state.db.transaction(|tx| -> Result<(), CommandError> {
+        tx.insert_presentation(&presentation)?;
+        tx.update_presentation_text_content(&id, &extract_ppt_text(&ppt_data))?;
+        staged.commit()?;
+        Ok(())
+    })?;
 
     Ok(presentation)
 }
@@ -102,16 +119,28 @@ pub fn ppt_save(
     data: PptData,
     state: State<'_, Arc<AppState>>,
 ) -> Result<Presentation, CommandError> {
+    save_ppt(&state, id, data)
+}
+
+fn save_ppt(state: &AppState, id: String, data: PptData) -> Result<Presentation, CommandError> {
+    ensure_item_writable(state, ContentKind::Presentation, &id)?;
+
     let presentation = state.db.get_presentation(&id)?;
 
-    // 更新 JSON 文件
+    // 暂存 JSON 文件，数据库更新成功后再替换正式文件
     let json = serde_json::to_string_pretty(&data)
         .map_err(|e| CommandError::Internal(format!("序列化 PPT 数据失败: {}", e)))?;
-    fs::write(&presentation.data_path, json)
-        .map_err(|e| CommandError::Io(format!("保存 PPT 数据失败: {}", e)))?;
-
-    // 更新数据库中的幻灯片数量
-    state.db.update_presentation_slide_count(&id, data.slides.len() as i32)?;
+    let staged = state
+        .file_service
+        .stage_write(Path::new(&presentation.data_path), json.as_bytes())?;
+
+    // 更新数据库中的幻灯片数量与搜索文本，文件提交失败时回滚
+    state.db.transaction(|tx| -> Result<(), CommandError> {
+        tx.update_presentation_slide_count(&id, data.slides.len() as i32)?;
+        tx.update_presentation_text_content(&id, &extract_ppt_text(&data))?;
+        staged.commit()?;
+        Ok(())
+    })?;
 
     // 返回更新后的元数据
     let updated = state.db.get_presentation(&id)?;
@@ -125,6 +154,8 @@ pub fn ppt_rename(
     title: String,
     state: State<'_, Arc<AppState>>,
 ) -> Result<Presentation, CommandError> {
+    ensure_item_writable(&state, ContentKind::Presentation, &id)?;
+
     state.db.update_presentation_title(&id, &title)?;
     let presentation = state.db.get_presentation(&id)?;
     Ok(presentation)
@@ -136,23 +167,8 @@ pub fn ppt_delete(
     id: String,
     state: State<'_, Arc<AppState>>,
 ) -> Result<(), CommandError> {
-    let presentation = state.db.get_presentation(&id)?;
-
-    // 删除 JSON 数据文件
-    if std::path::Path::new(&presentation.data_path).exists() {
-        let _ = fs::remove_file(&presentation.data_path);
-    }
-
-    // 删除缩略图
-    if let Some(thumb_path) = &presentation.thumbnail_path {
-        if std::path::Path::new(thumb_path).exists() {
-            let _ = fs::remove_file(thumb_path);
-        }
-    }
-
-    // 删除数据库记录
-    state.db.delete_presentation(&id)?;
-
+    // 移入回收站（数据文件与缩略图随记录一起可恢复）
+    move_to_trash(&state, ContentKind::Presentation, &id)?;
     Ok(())
 }
 
@@ -259,6 +275,7 @@ fn create_title_slide(title: &str, subtitle: Option<&str>, theme: &PptTheme) ->
             color: Some(theme.background_color.clone()),
             image: None,
         }),
+        remark: None,
     }
 }
 
@@ -305,6 +322,7 @@ fn create_content_slide(outline: &crate::models::SlideOutline, theme: &PptTheme)
             color: Some(theme.background_color.clone()),
             image: None,
         }),
+        remark: outline.notes.clone(),
     }
 }
 
@@ -387,7 +405,7 @@ mod tests {
         let file_service = FileService::new(temp_dir.path().to_path_buf());
         file_service.init_base_dirs().expect("初始化目录失败");
 
-        let state = Arc::new(AppState { db, file_service });
+        let state = Arc::new(AppState::new(db, file_service));
         (state, temp_dir)
     }
 
@@ -405,6 +423,7 @@ mod tests {
             updated_at: now,
             sources_count: 0,
             path: project_path.display().to_string(),
+            archived_at: None,
         };
         state.db.insert_project(&project).unwrap();
         project
@@ -536,6 +555,7 @@ mod tests {
             id: uuid::Uuid::new_v4().to_string(),
             elements: vec![],
             background: None,
+            remark: None,
         });
 
         let json = serde_json::to_string_pretty(&updated_data).unwrap();
@@ -551,6 +571,36 @@ mod tests {
         assert_eq!(loaded_data.slides.len(), 2);
     }
 
+    #[test]
+    fn test_ppt_text_indexed_for_search() {
+        let (state, _temp) = create_test_state();
+        let project = create_test_project(&state);
+
+        let outline = PptOutline {
+            title: "季度汇报".to_string(),
+            subtitle: None,
+            slides: vec![SlideOutline {
+                title: "核心指标".to_string(),
+                layout: SlideLayout::Content,
+                points: vec!["留存提升".to_string()],
+                notes: Some("先讲结论".to_string()),
+            }],
+        };
+        let presentation =
+            create_ppt(&state, project.id.clone(), "季度汇报".to_string(), Some(outline)).unwrap();
+        let search = |query: &str| state.db.search_presentations(query, None, 10, false).unwrap();
+        assert_eq!(search("留存提升")[0].id, presentation.id);
+        assert_eq!(search("先讲结论").len(), 1);
+
+        // 保存后按新内容索引
+        let content = fs::read_to_string(&presentation.data_path).unwrap();
+        let mut data: PptData = serde_json::from_str(&content).unwrap();
+        data.slides[1].remark = Some("<p>补充竞品对比</p>".to_string());
+        save_ppt(&state, presentation.id.clone(), data).unwrap();
+        assert!(search("先讲结论").is_empty());
+        assert_eq!(search("竞品对比").len(), 1);
+    }
+
     #[test]
     fn test_ppt_rename() {
         let (state, _temp) = create_test_state();
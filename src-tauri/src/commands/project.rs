@@ -2,10 +2,15 @@
 //!
 //! 提供项目相关的 Tauri Commands
 
+use crate::commands::archive::replace_ids;
+use crate::commands::embedding::EmbeddingWorker;
+use crate::commands::trash::move_to_trash;
 use crate::db::{Database, DbError};
-use crate::models::{CreateProjectData, Project};
-use crate::services::{FileError, FileService};
+use crate::models::{ContentKind, CreateProjectData, Project, ProjectStats};
+use crate::services::{ArchiveService, FileError, FileService};
 use chrono::Utc;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tauri::State;
 
@@ -13,6 +18,19 @@ use tauri::State;
 pub struct AppState {
     pub db: Database,
     pub file_service: FileService,
+    /// 后台向量生成的唤醒信号与进度
+    pub embeddings: EmbeddingWorker,
+}
+
+impl AppState {
+    /// 创建应用状态
+    pub fn new(db: Database, file_service: FileService) -> Self {
+        Self {
+            db,
+            file_service,
+            embeddings: EmbeddingWorker::default(),
+        }
+    }
 }
 
 /// Command 错误类型
@@ -30,6 +48,8 @@ pub enum CommandError {
     NotFound(String),
     #[error("验证错误: {0}")]
     Validation(String),
+    #[error("项目已归档（只读），请先取消归档: {0}")]
+    ProjectArchived(String),
     #[error("内部错误: {0}")]
     Internal(String),
 }
@@ -43,11 +63,20 @@ impl serde::Serialize for CommandError {
     }
 }
 
-/// 获取所有项目
+/// 获取所有项目（默认不含归档项目，`include_archived` 为 true 时一并返回）
 #[tauri::command]
-pub fn project_list(state: State<'_, Arc<AppState>>) -> Result<Vec<Project>, CommandError> {
+pub fn project_list(
+    include_archived: Option<bool>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<Project>, CommandError> {
     eprintln!("[project_list] 开始获取所有项目");
-    let projects = state.db.get_all_projects()?;
+    let include_archived = include_archived.unwrap_or(false);
+    let projects: Vec<Project> = state
+        .db
+        .get_all_projects()?
+        .into_iter()
+        .filter(|p| include_archived || p.archived_at.is_none())
+        .collect();
     eprintln!("[project_list] 成功获取 {} 个项目", projects.len());
     for p in &projects {
         eprintln!("[project_list] - 项目: id={}, name={}, workspace={}", p.id, p.name, p.workspace);
@@ -98,6 +127,7 @@ pub fn project_create(
         updated_at: now,
         sources_count: 0,
         path: project_path.display().to_string(),
+        archived_at: None,
     };
 
     // 保存到数据库
@@ -107,6 +137,29 @@ pub fn project_create(
     Ok(project)
 }
 
+/// 生成不与现有项目重名的名称，重名时追加 "(标签)"、"(标签 2)" 等后缀
+pub(crate) fn unique_project_name(
+    state: &AppState,
+    name: &str,
+    label: &str,
+) -> Result<String, CommandError> {
+    if !state.db.project_name_exists(name)? {
+        return Ok(name.to_string());
+    }
+    let mut index = 1;
+    loop {
+        let candidate = if index == 1 {
+            format!("{} ({})", name, label)
+        } else {
+            format!("{} ({} {})", name, label, index)
+        };
+        if !state.db.project_name_exists(&candidate)? {
+            return Ok(candidate);
+        }
+        index += 1;
+    }
+}
+
 /// 重命名项目
 #[tauri::command]
 pub fn project_rename(
@@ -114,17 +167,23 @@ pub fn project_rename(
     name: String,
     state: State<'_, Arc<AppState>>,
 ) -> Result<Project, CommandError> {
+    rename_project(&state, &id, &name)
+}
+
+fn rename_project(state: &AppState, id: &str, name: &str) -> Result<Project, CommandError> {
+    ensure_project_writable(state, id)?;
+
     // 检查新名称是否与其他项目冲突
-    let current = state.db.get_project(&id)?;
-    if current.name != name && state.db.project_name_exists(&name)? {
-        return Err(CommandError::NameExists(name));
+    let current = state.db.get_project(id)?;
+    if current.name != name && state.db.project_name_exists(name)? {
+        return Err(CommandError::NameExists(name.to_string()));
     }
 
     // 更新名称
-    state.db.update_project_name(&id, &name)?;
+    state.db.update_project_name(id, name)?;
 
     // 返回更新后的项目
-    let project = state.db.get_project(&id)?;
+    let project = state.db.get_project(id)?;
     Ok(project)
 }
 
@@ -134,13 +193,8 @@ pub fn project_delete(id: String, state: State<'_, Arc<AppState>>) -> Result<(),
     // 检查项目是否存在（会抛出 NotFound 错误）
     let _ = state.db.get_project(&id)?;
 
-    // 删除项目目录
-    if state.file_service.project_exists(&id) {
-        state.file_service.delete_project_dir(&id)?;
-    }
-
-    // 从数据库删除
-    state.db.delete_project(&id)?;
+    // 移入回收站（项目目录与全部记录可恢复）
+    move_to_trash(&state, ContentKind::Project, &id)?;
 
     Ok(())
 }
@@ -152,8 +206,267 @@ pub fn project_star(
     starred: bool,
     state: State<'_, Arc<AppState>>,
 ) -> Result<Project, CommandError> {
-    state.db.update_project_starred(&id, starred)?;
-    let project = state.db.get_project(&id)?;
+    star_project(&state, &id, starred)
+}
+
+fn star_project(state: &AppState, id: &str, starred: bool) -> Result<Project, CommandError> {
+    ensure_project_writable(state, id)?;
+    state.db.update_project_starred(id, starred)?;
+    let project = state.db.get_project(id)?;
+    Ok(project)
+}
+
+/// 归档项目
+///
+/// 归档项目只读：内容与名称、星标、所属工作空间都不能修改（可以取消归档、删除、复制与导出），
+/// 不出现在默认的项目列表与全局搜索中，也不再补齐向量；
+/// `compress` 为 true 时把来源文件（缩略图除外）压缩为项目目录下的 sources.zip，取消归档时解压。
+#[tauri::command]
+pub fn project_archive(
+    id: String,
+    compress: Option<bool>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Project, CommandError> {
+    archive_project(&state, &id, compress.unwrap_or(false))
+}
+
+/// 取消归档（解压已压缩的来源文件）
+#[tauri::command]
+pub fn project_unarchive(
+    id: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Project, CommandError> {
+    unarchive_project(&state, &id)
+}
+
+/// 归档项目不允许修改，修改项目或其内容的命令先调用此检查
+pub(crate) fn ensure_project_writable(state: &AppState, project_id: &str) -> Result<(), CommandError> {
+    let project = state.db.get_project(project_id)?;
+    if project.archived_at.is_some() {
+        return Err(CommandError::ProjectArchived(project.name));
+    }
+    Ok(())
+}
+
+/// 检查条目所属项目未归档
+pub(crate) fn ensure_item_writable(
+    state: &AppState,
+    kind: ContentKind,
+    id: &str,
+) -> Result<(), CommandError> {
+    let project_id = state.db.get_item_project_id(kind, id)?;
+    ensure_project_writable(state, &project_id)
+}
+
+/// 需要读取来源原文件的操作（复制、导出等）在来源被压缩时不可用
+pub(crate) fn ensure_sources_available(state: &AppState, project_id: &str) -> Result<(), CommandError> {
+    if state.file_service.archived_sources_path(project_id).exists() {
+        return Err(CommandError::Validation(
+            "项目的来源文件已压缩归档，请先取消归档".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn archive_project(state: &AppState, id: &str, compress: bool) -> Result<Project, CommandError> {
+    ensure_project_writable(state, id)?;
+
+    // 先写好压缩包，记录更新成功后再删除原文件
+    let sources_dir = state.file_service.get_sources_dir(id);
+    let packed = state.file_service.archived_sources_path(id);
+    let compress = compress && sources_dir.is_dir();
+    if compress {
+        ArchiveService::pack_dir(&sources_dir, &packed, &["thumbnails"])
+            .map_err(|e| CommandError::Io(e.to_string()))?;
+    }
+    if let Err(e) = state.db.update_project_archived(id, Some(Utc::now())) {
+        if compress {
+            let _ = fs::remove_file(&packed);
+        }
+        return Err(e.into());
+    }
+    if compress {
+        remove_packed_sources(&sources_dir)?;
+    }
+
+    let project = state.db.get_project(id)?;
+    Ok(project)
+}
+
+/// 删除已压缩的来源文件（保留缩略图目录）
+fn remove_packed_sources(sources_dir: &Path) -> Result<(), CommandError> {
+    for entry in fs::read_dir(sources_dir).map_err(|e| CommandError::Io(e.to_string()))? {
+        let path = entry.map_err(|e| CommandError::Io(e.to_string()))?.path();
+        let result = if path.is_dir() {
+            if path.file_name().is_some_and(|name| name == "thumbnails") {
+                continue;
+            }
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        result.map_err(|e| CommandError::Io(e.to_string()))?;
+    }
+    Ok(())
+}
+
+fn unarchive_project(state: &AppState, id: &str) -> Result<Project, CommandError> {
+    let project = state.db.get_project(id)?;
+    if project.archived_at.is_none() {
+        return Err(CommandError::Validation(format!("项目未归档: {}", project.name)));
+    }
+
+    unpack_archived_sources(state, id)?;
+    state.db.update_project_archived(id, None)?;
+
+    let project = state.db.get_project(id)?;
+    Ok(project)
+}
+
+/// 解压归档时压缩的来源文件并删除压缩包（没有压缩包时不做任何事）
+///
+/// 取消归档时使用；同步写入或删除归档项目中的来源前也先解压，避免与压缩包中的旧文件不一致。
+pub(crate) fn unpack_archived_sources(state: &AppState, project_id: &str) -> Result<(), CommandError> {
+    let packed = state.file_service.archived_sources_path(project_id);
+    if packed.exists() {
+        ArchiveService::unpack_dir(&packed, &state.file_service.get_sources_dir(project_id))
+            .map_err(|e| CommandError::Io(e.to_string()))?;
+        fs::remove_file(&packed).map_err(|e| CommandError::Io(e.to_string()))?;
+    }
+    Ok(())
+}
+
+/// 获取项目统计
+///
+/// 数量由数据库记录实时计算；发现 `sources_count` 与实际来源数量不一致时一并修正。
+#[tauri::command]
+pub fn project_stats(
+    id: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<ProjectStats, CommandError> {
+    project_statistics(&state, &id)
+}
+
+fn project_statistics(state: &AppState, id: &str) -> Result<ProjectStats, CommandError> {
+    let project = state.db.get_project(id)?;
+    let mut stats = state.db.get_project_stats(id)?;
+    if project.sources_count != stats.sources_count {
+        eprintln!(
+            "[project_stats] 修正来源数量: {} -> {}",
+            project.sources_count, stats.sources_count
+        );
+        state.db.recount_project_sources(id)?;
+    }
+    stats.storage_bytes = state.file_service.project_size(id)?;
+    Ok(stats)
+}
+
+/// 把项目移到另一个工作空间
+#[tauri::command]
+pub fn project_move(
+    id: String,
+    workspace_id: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Project, CommandError> {
+    move_project(&state, &id, &workspace_id)
+}
+
+fn move_project(state: &AppState, id: &str, workspace_id: &str) -> Result<Project, CommandError> {
+    ensure_project_writable(state, id)?;
+    // 目标工作空间必须存在
+    state.db.get_workspace(workspace_id)?;
+    state.db.update_project_workspace(id, workspace_id)?;
+    let project = state.db.get_project(id)?;
+    Ok(project)
+}
+
+/// 复制项目
+///
+/// 项目下的全部记录与数据文件深拷贝到新项目，所有条目分配新 ID；
+/// `include_sources` 为 false 时不复制来源，对话保留但去掉消息中的来源引用。
+#[tauri::command]
+pub fn project_duplicate(
+    id: String,
+    new_name: String,
+    include_sources: bool,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Project, CommandError> {
+    duplicate_project(&state, &id, &new_name, include_sources)
+}
+
+fn duplicate_project(
+    state: &AppState,
+    id: &str,
+    new_name: &str,
+    include_sources: bool,
+) -> Result<Project, CommandError> {
+    let new_name = new_name.trim();
+    if new_name.is_empty() {
+        return Err(CommandError::Validation("项目名称不能为空".to_string()));
+    }
+    if state.db.project_name_exists(new_name)? {
+        return Err(CommandError::NameExists(new_name.to_string()));
+    }
+
+    if include_sources {
+        ensure_sources_available(state, id)?;
+    }
+    let mut snapshot = state.db.get_snapshot(ContentKind::Project, id)?;
+    if !include_sources {
+        snapshot.remove_sources();
+    }
+
+    let new_id = uuid::Uuid::new_v4().to_string();
+    let ids = snapshot.reassign_ids(&new_id);
+    let source_dir = state.file_service.project_dir(id);
+    let project_dir = state.file_service.create_project_dir(&new_id)?;
+
+    // 项目目录内的文件保留相对路径，其余文件复制到 external/；文件名中的旧 ID 替换为新 ID
+    let mut copies: Vec<(PathBuf, PathBuf)> = Vec::new();
+    snapshot.map_paths(|path| {
+        let from = PathBuf::from(path);
+        let relative = match from.strip_prefix(&source_dir) {
+            Ok(relative) => relative.to_path_buf(),
+            Err(_) => Path::new("external").join(from.file_name().unwrap_or_default()),
+        };
+        let dest = project_dir.join(replace_ids(&relative.to_string_lossy(), &ids));
+        if from.is_file() && !copies.iter().any(|(_, existing)| *existing == dest) {
+            copies.push((from, dest.clone()));
+        }
+        dest.display().to_string()
+    });
+
+    let result = copies
+        .iter()
+        .try_for_each(|(from, dest)| {
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(from, dest).map(|_| ())
+        })
+        .map_err(|e| CommandError::Io(e.to_string()))
+        .and_then(|_| {
+            if let Some(project) = &mut snapshot.project {
+                let now = Utc::now();
+                project.name = new_name.to_string();
+                project.path = project_dir.display().to_string();
+                project.is_starred = false;
+                project.archived_at = None;
+                project.created_at = now;
+                project.updated_at = now;
+            }
+            state
+                .db
+                .transaction(|tx| tx.restore_snapshot(&snapshot))
+                .map_err(CommandError::from)
+        });
+
+    if let Err(e) = result {
+        let _ = state.file_service.delete_project_dir(&new_id);
+        return Err(e);
+    }
+
+    let project = state.db.get_project(&new_id)?;
     Ok(project)
 }
 
@@ -169,7 +482,7 @@ mod tests {
         let file_service = FileService::new(temp_dir.path().to_path_buf());
         file_service.init_base_dirs().expect("初始化目录失败");
 
-        let state = Arc::new(AppState { db, file_service });
+        let state = Arc::new(AppState::new(db, file_service));
         (state, temp_dir)
     }
 
@@ -205,6 +518,7 @@ mod tests {
             updated_at: now,
             sources_count: 0,
             path: project_path.display().to_string(),
+            archived_at: None,
         };
 
         state.db.insert_project(&project).unwrap();
@@ -232,6 +546,7 @@ mod tests {
             updated_at: Utc::now(),
             sources_count: 0,
             path: project_path.display().to_string(),
+            archived_at: None,
         };
         state.db.insert_project(&project).unwrap();
 
@@ -259,6 +574,7 @@ mod tests {
             updated_at: Utc::now(),
             sources_count: 0,
             path: project_path.display().to_string(),
+            archived_at: None,
         };
         state.db.insert_project(&project).unwrap();
 
@@ -288,6 +604,7 @@ mod tests {
             updated_at: Utc::now(),
             sources_count: 0,
             path: project_path.display().to_string(),
+            archived_at: None,
         };
         state.db.insert_project(&project).unwrap();
 
@@ -317,6 +634,7 @@ mod tests {
             updated_at: Utc::now(),
             sources_count: 0,
             path: project_path.display().to_string(),
+            archived_at: None,
         };
         state.db.insert_project(&project1).unwrap();
 
@@ -324,4 +642,335 @@ mod tests {
         assert!(state.db.project_name_exists("唯一名称").unwrap());
         assert!(!state.db.project_name_exists("其他名称").unwrap());
     }
+
+    #[test]
+    fn test_project_move() {
+        let (state, _temp) = create_test_state();
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let project_path = state.file_service.create_project_dir(&id).unwrap();
+        let project = Project {
+            id: id.clone(),
+            name: "移动项目".to_string(),
+            icon: ProjectIcon::default(),
+            workspace: "research".to_string(),
+            is_starred: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            sources_count: 0,
+            path: project_path.display().to_string(),
+            archived_at: None,
+        };
+        state.db.insert_project(&project).unwrap();
+
+        let moved = move_project(&state, &id, "personal").unwrap();
+        assert_eq!(moved.workspace, "personal");
+
+        // 目标工作空间不存在时不修改项目
+        assert!(move_project(&state, &id, "missing").is_err());
+        assert_eq!(state.db.get_project(&id).unwrap().workspace, "personal");
+        assert!(move_project(&state, "missing", "research").is_err());
+    }
+
+    #[test]
+    fn test_project_duplicate() {
+        use crate::models::{
+            ChatMessage, ChatSession, Citation, MessageRole, Note, OutputType, Source, SourceType,
+        };
+
+        let (state, _temp) = create_test_state();
+        let id = uuid::Uuid::new_v4().to_string();
+        let project_path = state.file_service.create_project_dir(&id).unwrap();
+        let now = Utc::now();
+        let project = Project {
+            id: id.clone(),
+            name: "原项目".to_string(),
+            icon: ProjectIcon::default(),
+            workspace: "research".to_string(),
+            is_starred: true,
+            created_at: now,
+            updated_at: now,
+            sources_count: 1,
+            path: project_path.display().to_string(),
+            archived_at: None,
+        };
+        state.db.insert_project(&project).unwrap();
+
+        let source_id = uuid::Uuid::new_v4().to_string();
+        let source_path = state
+            .file_service
+            .get_sources_dir(&id)
+            .join(format!("{}.md", source_id));
+        fs::write(&source_path, "来源全文").unwrap();
+        let source = Source {
+            id: source_id.clone(),
+            project_id: id.clone(),
+            name: "资料.md".to_string(),
+            source_type: SourceType::Markdown,
+            path: source_path.display().to_string(),
+            size: 12,
+            mime_type: "text/markdown".to_string(),
+            thumbnail_path: None,
+            created_at: now,
+            updated_at: now,
+        };
+        state
+            .db
+            .insert_source_with_content(&source, Some("来源全文"))
+            .unwrap();
+
+        let note_id = uuid::Uuid::new_v4().to_string();
+        let note_path = state
+            .file_service
+            .get_notes_dir(&id)
+            .join(format!("{}.md", note_id));
+        fs::write(&note_path, "# 笔记").unwrap();
+        state
+            .db
+            .insert_note(&Note {
+                id: note_id.clone(),
+                project_id: id.clone(),
+                title: "笔记".to_string(),
+                path: note_path.display().to_string(),
+                output_type: OutputType::Note,
+                title_locked: false,
+                created_at: now,
+                updated_at: now,
+            })
+            .unwrap();
+
+        let session = ChatSession {
+            id: uuid::Uuid::new_v4().to_string(),
+            project_id: id.clone(),
+            title: "对话".to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+        state.db.insert_chat_session(&session).unwrap();
+        state
+            .db
+            .insert_chat_message(&ChatMessage {
+                id: uuid::Uuid::new_v4().to_string(),
+                session_id: session.id.clone(),
+                role: MessageRole::Assistant,
+                content: "见资料 [1]".to_string(),
+                citations: Some(vec![Citation {
+                    index: 1,
+                    source_id: source_id.clone(),
+                    source_name: "资料.md".to_string(),
+                }]),
+                created_at: now,
+            })
+            .unwrap();
+
+        let copy = duplicate_project(&state, &id, "副本", true).unwrap();
+        assert_ne!(copy.id, id);
+        assert_eq!(copy.name, "副本");
+        assert!(!copy.is_starred);
+        assert_eq!(copy.sources_count, 1);
+
+        let sources = state.db.get_sources_by_project(&copy.id).unwrap();
+        assert_eq!(sources.len(), 1);
+        assert_ne!(sources[0].id, source_id);
+        assert!(sources[0].path.starts_with(&copy.path));
+        assert!(sources[0].path.contains(&sources[0].id));
+        assert_eq!(fs::read_to_string(&sources[0].path).unwrap(), "来源全文");
+        assert_eq!(state.db.get_source_content(&sources[0].id).unwrap(), "来源全文");
+
+        let notes = state.db.get_notes_by_project(&copy.id).unwrap();
+        assert_ne!(notes[0].id, note_id);
+        assert!(notes[0].path.starts_with(&copy.path));
+        assert_eq!(fs::read_to_string(&notes[0].path).unwrap(), "# 笔记");
+
+        // 修改副本不影响原项目
+        fs::write(&notes[0].path, "# 已修改").unwrap();
+        assert_eq!(fs::read_to_string(&note_path).unwrap(), "# 笔记");
+
+        let skeleton = duplicate_project(&state, &id, "骨架", false).unwrap();
+        assert_eq!(skeleton.sources_count, 0);
+        assert!(state.db.get_sources_by_project(&skeleton.id).unwrap().is_empty());
+        assert_eq!(state.db.get_notes_by_project(&skeleton.id).unwrap().len(), 1);
+        // 不复制来源时对话保留，来源引用去掉
+        let sessions = state.db.get_chat_sessions_by_project(&skeleton.id).unwrap();
+        assert_eq!(sessions.len(), 1);
+        let messages = state.db.get_chat_messages_by_session(&sessions[0].id).unwrap();
+        assert_eq!(messages[0].content, "见资料 [1]");
+        assert!(messages[0].citations.is_none());
+        let sessions = state.db.get_chat_sessions_by_project(&copy.id).unwrap();
+        let messages = state.db.get_chat_messages_by_session(&sessions[0].id).unwrap();
+        assert_eq!(
+            messages[0].citations.as_ref().unwrap()[0].source_id,
+            state.db.get_sources_by_project(&copy.id).unwrap()[0].id
+        );
+
+        assert!(matches!(
+            duplicate_project(&state, &id, "副本", false),
+            Err(CommandError::NameExists(_))
+        ));
+        assert!(duplicate_project(&state, "missing", "另一个", false).is_err());
+        assert_eq!(state.db.get_all_projects().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_project_stats() {
+        use crate::models::{Source, SourceType};
+
+        let (state, _temp) = create_test_state();
+        let id = uuid::Uuid::new_v4().to_string();
+        let project_path = state.file_service.create_project_dir(&id).unwrap();
+        let project = Project {
+            id: id.clone(),
+            name: "统计项目".to_string(),
+            icon: ProjectIcon::default(),
+            workspace: "research".to_string(),
+            is_starred: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            // 与实际来源数量不一致
+            sources_count: 5,
+            path: project_path.display().to_string(),
+            archived_at: None,
+        };
+        state.db.insert_project(&project).unwrap();
+
+        for (name, source_type) in [
+            ("a.pdf", SourceType::Pdf),
+            ("b.pdf", SourceType::Pdf),
+            ("c.md", SourceType::Markdown),
+        ] {
+            let path = state.file_service.get_sources_dir(&id).join(name);
+            fs::write(&path, "0123456789").unwrap();
+            let source = Source {
+                id: uuid::Uuid::new_v4().to_string(),
+                project_id: id.clone(),
+                name: name.to_string(),
+                source_type,
+                path: path.display().to_string(),
+                size: 10,
+                mime_type: "application/octet-stream".to_string(),
+                thumbnail_path: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            };
+            state.db.insert_source_with_content(&source, None).unwrap();
+        }
+
+        let stats = project_statistics(&state, &id).unwrap();
+        assert_eq!(stats.sources_count, 3);
+        assert_eq!(stats.sources_by_type.get("pdf"), Some(&2));
+        assert_eq!(stats.sources_by_type.get("markdown"), Some(&1));
+        assert_eq!(stats.notes_count, 0);
+        assert_eq!(stats.chat_messages_count, 0);
+        assert_eq!(stats.storage_bytes, 30);
+
+        // 存储的来源数量已修正
+        assert_eq!(state.db.get_project(&id).unwrap().sources_count, 3);
+        assert!(project_statistics(&state, "missing").is_err());
+    }
+
+    #[test]
+    fn test_project_archive() {
+        use crate::commands::trash::move_to_trash;
+        use crate::models::{Source, SourceType};
+
+        let (state, _temp) = create_test_state();
+        let id = uuid::Uuid::new_v4().to_string();
+        let project_path = state.file_service.create_project_dir(&id).unwrap();
+        let now = Utc::now();
+        state
+            .db
+            .insert_project(&Project {
+                id: id.clone(),
+                name: "已完成项目".to_string(),
+                icon: ProjectIcon::default(),
+                workspace: "research".to_string(),
+                is_starred: false,
+                created_at: now,
+                updated_at: now,
+                sources_count: 1,
+                path: project_path.display().to_string(),
+                archived_at: None,
+            })
+            .unwrap();
+
+        let sources_dir = state.file_service.get_sources_dir(&id);
+        let source_path = sources_dir.join("报告.md");
+        fs::write(&source_path, "结项报告").unwrap();
+        let thumbnail = state.file_service.get_thumbnails_dir(&id).join("a_thumb.jpg");
+        fs::create_dir_all(thumbnail.parent().unwrap()).unwrap();
+        fs::write(&thumbnail, "jpg").unwrap();
+        let source = Source {
+            id: uuid::Uuid::new_v4().to_string(),
+            project_id: id.clone(),
+            name: "报告.md".to_string(),
+            source_type: SourceType::Markdown,
+            path: source_path.display().to_string(),
+            size: 12,
+            mime_type: "text/markdown".to_string(),
+            thumbnail_path: None,
+            created_at: now,
+            updated_at: now,
+        };
+        state
+            .db
+            .insert_source_with_content(&source, Some("结项报告"))
+            .unwrap();
+
+        let archived = archive_project(&state, &id, true).unwrap();
+        assert!(archived.archived_at.is_some());
+        assert!(state.file_service.archived_sources_path(&id).is_file());
+        assert!(!source_path.exists());
+        assert!(thumbnail.exists());
+
+        // 默认搜索不包含归档项目，也不再补齐向量
+        assert!(state.db.search_projects("已完成", None, 10, false).unwrap().is_empty());
+        assert_eq!(state.db.search_projects("已完成", None, 10, true).unwrap().len(), 1);
+        assert!(state.db.search_sources("结项", None, 10, false).unwrap().is_empty());
+        assert!(state.db.list_sources_missing_embeddings(10).unwrap().is_empty());
+
+        // 归档项目只读
+        assert!(matches!(
+            ensure_project_writable(&state, &id),
+            Err(CommandError::ProjectArchived(_))
+        ));
+        assert!(matches!(
+            move_to_trash(&state, ContentKind::Source, &source.id),
+            Err(CommandError::ProjectArchived(_))
+        ));
+        assert!(matches!(
+            archive_project(&state, &id, false),
+            Err(CommandError::ProjectArchived(_))
+        ));
+        // 名称、星标与所属工作空间同样不能修改
+        assert!(matches!(
+            rename_project(&state, &id, "新名称"),
+            Err(CommandError::ProjectArchived(_))
+        ));
+        assert!(matches!(
+            star_project(&state, &id, true),
+            Err(CommandError::ProjectArchived(_))
+        ));
+        assert!(matches!(
+            move_project(&state, &id, "personal"),
+            Err(CommandError::ProjectArchived(_))
+        ));
+        let unchanged = state.db.get_project(&id).unwrap();
+        assert_eq!(unchanged.name, "已完成项目");
+        assert!(!unchanged.is_starred);
+        assert_eq!(unchanged.workspace, "research");
+        // 来源已压缩时不能复制来源
+        assert!(duplicate_project(&state, &id, "副本", true).is_err());
+        let skeleton = duplicate_project(&state, &id, "骨架", false).unwrap();
+        assert!(skeleton.archived_at.is_none());
+
+        let restored = unarchive_project(&state, &id).unwrap();
+        assert!(restored.archived_at.is_none());
+        assert_eq!(fs::read_to_string(&source_path).unwrap(), "结项报告");
+        assert!(!state.file_service.archived_sources_path(&id).exists());
+        assert!(ensure_item_writable(&state, ContentKind::Source, &source.id).is_ok());
+        assert_eq!(state.db.list_sources_missing_embeddings(10).unwrap().len(), 1);
+        assert!(unarchive_project(&state, &id).is_err());
+        assert!(star_project(&state, &id, true).unwrap().is_starred);
+        assert_eq!(rename_project(&state, &id, "新名称").unwrap().name, "新名称");
+    }
 }
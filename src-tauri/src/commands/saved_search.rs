@@ -0,0 +1,325 @@
+//! Saved Search Commands
+//!
+//! 提供保存的搜索（智能集合）相关的 Tauri Commands：保存查询与过滤条件，按需运行
+
+use crate::commands::project::{AppState, CommandError};
+use crate::commands::search::structured_search;
+use crate::models::{SavedSearch, SavedSearchRun, SearchSort};
+use crate::services::SearchQuery;
+use chrono::Utc;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tauri::State;
+
+/// 运行保存的搜索时默认返回的结果数
+const DEFAULT_RUN_LIMIT: u32 = 100;
+
+/// 获取全部保存的搜索
+#[tauri::command]
+pub fn saved_search_list(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<SavedSearch>, CommandError> {
+    let searches = state.db.list_saved_searches()?;
+    Ok(searches)
+}
+
+/// 保存搜索
+///
+/// `query` 使用搜索框的查询语法，类型、项目、分类、标签、扩展名等过滤条件都写在查询中；
+/// `project_id` 限定搜索范围。`track_new` 为 true 时，运行结果会报告上次运行以来的新命中。
+#[tauri::command]
+pub fn saved_search_create(
+    name: String,
+    query: String,
+    project_id: Option<String>,
+    include_archived: Option<bool>,
+    sort: Option<SearchSort>,
+    track_new: Option<bool>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<SavedSearch, CommandError> {
+    let now = Utc::now();
+    let search = SavedSearch {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        query,
+        project_id,
+        include_archived: include_archived.unwrap_or(false),
+        sort: sort.unwrap_or_default(),
+        track_new: track_new.unwrap_or(false),
+        last_run_at: None,
+        created_at: now,
+        updated_at: now,
+    };
+    create_saved_search(&state, search)
+}
+
+/// 更新保存的搜索的名称与条件（运行记录与时间字段不受影响）
+#[tauri::command]
+pub fn saved_search_update(
+    search: SavedSearch,
+    state: State<'_, Arc<AppState>>,
+) -> Result<SavedSearch, CommandError> {
+    update_saved_search(&state, search)
+}
+
+/// 删除保存的搜索
+#[tauri::command]
+pub fn saved_search_delete(
+    id: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<(), CommandError> {
+    state.db.delete_saved_search(&id)?;
+    Ok(())
+}
+
+/// 运行保存的搜索，返回当前命中的结果（默认最多 100 条）
+#[tauri::command]
+pub fn saved_search_run(
+    id: String,
+    limit: Option<u32>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<SavedSearchRun, CommandError> {
+    run_saved_search(&state, &id, limit.unwrap_or(DEFAULT_RUN_LIMIT))
+}
+
+/// 校验名称、查询语法与搜索范围，返回规范化后的保存的搜索
+fn validate(state: &AppState, mut search: SavedSearch) -> Result<SavedSearch, CommandError> {
+    search.name = search.name.trim().to_string();
+    if search.name.is_empty() {
+        return Err(CommandError::Validation("搜索名称不能为空".to_string()));
+    }
+    search.query = search.query.trim().to_string();
+    if SearchQuery::parse(&search.query)?.is_none() {
+        return Err(CommandError::Validation("搜索条件不能为空".to_string()));
+    }
+    if let Some(project_id) = &search.project_id {
+        state.db.get_project(project_id)?;
+    }
+    Ok(search)
+}
+
+fn create_saved_search(state: &AppState, search: SavedSearch) -> Result<SavedSearch, CommandError> {
+    let search = validate(state, search)?;
+    state.db.insert_saved_search(&search)?;
+    Ok(search)
+}
+
+fn update_saved_search(state: &AppState, search: SavedSearch) -> Result<SavedSearch, CommandError> {
+    let search = validate(state, search)?;
+    state.db.update_saved_search(&search)?;
+    Ok(state.db.get_saved_search(&search.id)?)
+}
+
+/// 运行保存的搜索
+///
+/// 开启 `track_new` 时记录本次命中的条目，与上次记录比较得出新命中；
+/// 只比较返回的前 `limit` 条，排在其后的条目重新进入前列时也算新命中。
+fn run_saved_search(
+    state: &AppState,
+    id: &str,
+    limit: u32,
+) -> Result<SavedSearchRun, CommandError> {
+    let mut search = state.db.get_saved_search(id)?;
+    let results = structured_search(
+        state,
+        &search.query,
+        search.project_id.as_deref(),
+        limit,
+        0,
+        search.sort,
+        search.include_archived,
+    )?;
+
+    let mut new_ids = Vec::new();
+    let mut matches = None;
+    if search.track_new {
+        let keys: Vec<String> = results
+            .iter()
+            .map(|result| format!("{}:{}", result.result_type, result.id))
+            .collect();
+        if let Some(previous) = state.db.get_saved_search_matches(id)? {
+            let previous: HashSet<String> = previous.into_iter().collect();
+            new_ids = results
+                .iter()
+                .zip(&keys)
+                .filter(|(_, key)| !previous.contains(*key))
+                .map(|(result, _)| result.id.clone())
+                .collect();
+        }
+        matches = Some(keys);
+    }
+
+    let now = Utc::now();
+    state
+        .db
+        .record_saved_search_run(id, now, matches.as_deref())?;
+    let since = search.last_run_at.replace(now);
+
+    Ok(SavedSearchRun {
+        search,
+        results,
+        since,
+        new_ids,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{Database, DbError};
+    use crate::models::{Note, OutputType, Project, ProjectIcon};
+    use crate::services::FileService;
+    use tempfile::TempDir;
+
+    fn create_test_state() -> (Arc<AppState>, TempDir) {
+        let temp_dir = TempDir::new().expect("创建临时目录失败");
+        let db = Database::new_in_memory().expect("创建数据库失败");
+        let file_service = FileService::new(temp_dir.path().to_path_buf());
+        file_service.init_base_dirs().expect("初始化目录失败");
+
+        let state = Arc::new(AppState::new(db, file_service));
+        (state, temp_dir)
+    }
+
+    fn create_test_project(state: &AppState, name: &str, workspace: &str) -> Project {
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = Utc::now();
+        let project = Project {
+            id: id.clone(),
+            name: name.to_string(),
+            icon: ProjectIcon::default(),
+            workspace: workspace.to_string(),
+            is_starred: false,
+            created_at: now,
+            updated_at: now,
+            sources_count: 0,
+            path: state
+                .file_service
+                .projects_dir()
+                .join(&id)
+                .display()
+                .to_string(),
+            archived_at: None,
+        };
+        state.db.insert_project(&project).unwrap();
+        project
+    }
+
+    fn insert_note(state: &AppState, project: &Project, id: &str, title: &str) {
+        let now = Utc::now();
+        state
+            .db
+            .insert_note(&Note {
+                id: id.to_string(),
+                project_id: project.id.clone(),
+                title: title.to_string(),
+                path: format!("/test/{}.md", id),
+                output_type: OutputType::Note,
+                title_locked: false,
+                created_at: now,
+                updated_at: now,
+            })
+            .unwrap();
+    }
+
+    fn saved_search(name: &str, query: &str, track_new: bool) -> SavedSearch {
+        let now = Utc::now();
+        SavedSearch {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            query: query.to_string(),
+            project_id: None,
+            include_archived: false,
+            sort: SearchSort::Relevance,
+            track_new,
+            last_run_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_saved_search_reports_new_matches() {
+        let (state, _temp) = create_test_state();
+        let legal = create_test_project(&state, "合同审查", "development");
+        let other = create_test_project(&state, "读书笔记", "personal");
+        insert_note(&state, &legal, "n1", "采购合同要点");
+        insert_note(&state, &other, "n2", "合同法读书笔记");
+
+        let search = create_saved_search(
+            &state,
+            saved_search(" 开发合同 ", "type:note workspace:开发 合同", true),
+        )
+        .unwrap();
+        assert_eq!(search.name, "开发合同");
+        assert_eq!(
+            state.db.list_saved_searches().unwrap(),
+            vec![search.clone()]
+        );
+
+        // 首次运行没有可比较的记录
+        let first = run_saved_search(&state, &search.id, 10).unwrap();
+        let ids: Vec<&str> = first.results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["n1"]);
+        assert!(first.since.is_none());
+        assert!(first.new_ids.is_empty());
+        assert!(first.search.last_run_at.is_some());
+
+        insert_note(&state, &legal, "n3", "租赁合同模板");
+        let second = run_saved_search(&state, &search.id, 10).unwrap();
+        assert_eq!(second.results.len(), 2);
+        assert_eq!(second.since, first.search.last_run_at);
+        assert_eq!(second.new_ids, vec!["n3"]);
+        assert!(run_saved_search(&state, &search.id, 10)
+            .unwrap()
+            .new_ids
+            .is_empty());
+
+        // 修改查询条件后重新开始比较；只改名称不影响
+        let mut renamed = second.search.clone();
+        renamed.name = "合同".to_string();
+        update_saved_search(&state, renamed).unwrap();
+        assert!(state
+            .db
+            .get_saved_search_matches(&search.id)
+            .unwrap()
+            .is_some());
+        let mut widened = state.db.get_saved_search(&search.id).unwrap();
+        widened.query = "type:note 合同".to_string();
+        update_saved_search(&state, widened).unwrap();
+        let third = run_saved_search(&state, &search.id, 10).unwrap();
+        assert_eq!(third.results.len(), 3);
+        assert!(third.new_ids.is_empty());
+
+        state.db.delete_saved_search(&search.id).unwrap();
+        assert!(matches!(
+            run_saved_search(&state, &search.id, 10),
+            Err(CommandError::Database(DbError::NotFound(_)))
+        ));
+    }
+
+    #[test]
+    fn test_saved_search_validation() {
+        let (state, _temp) = create_test_state();
+        let invalid = |name: &str, query: &str| {
+            create_saved_search(&state, saved_search(name, query, false)).is_err()
+        };
+        assert!(invalid("  ", "合同"));
+        assert!(invalid("空查询", "   "));
+        assert!(invalid("语法错误", "type:video"));
+
+        let mut scoped = saved_search("范围", "合同", false);
+        scoped.project_id = Some("missing".to_string());
+        assert!(create_saved_search(&state, scoped).is_err());
+
+        // 未开启 track_new 时不记录命中
+        let search = create_saved_search(&state, saved_search("不跟踪", "合同", false)).unwrap();
+        let run = run_saved_search(&state, &search.id, 10).unwrap();
+        assert!(run.new_ids.is_empty());
+        assert!(state
+            .db
+            .get_saved_search_matches(&search.id)
+            .unwrap()
+            .is_none());
+    }
+}
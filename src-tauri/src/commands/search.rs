@@ -2,88 +2,285 @@
 //!
 //! 提供搜索和最近访问相关的 Tauri Commands
 
+use crate::commands::canvas::extract_canvas_text;
 use crate::commands::project::{AppState, CommandError};
-use crate::models::{RecentAccess, SearchResult};
-use crate::services::embed_text;
-use std::collections::HashSet;
+use crate::db::FTS_TABLES;
+use crate::models::{
+    CanvasData, ContentKind, RecentAccess, RelatedItems, SearchResult, SearchSort,
+    SemanticSearchResults,
+};
+use crate::services::{
+    embed_text, extract_ppt_file_text, fuse_rankings, HybridWeights, QueryError, SearchQuery,
+};
+use std::cmp::Ordering;
 use std::fs;
+use std::path::Path;
 use std::sync::Arc;
 use tauri::State;
 
-/// 全局搜索（搜索项目、来源、笔记）
+/// 全局搜索（搜索项目、来源、笔记、画布、思维导图、PPT 与对话）
+///
+/// 查询支持字段过滤、短语、排除与 OR（语法见 `services::search_query`）。
+/// 通过 `offset` 分页，`sort` 为 relevance（默认）或 date。
+/// 默认不包含归档项目中的内容，`include_archived` 为 true 时一并搜索。
+/// 指定 `project_id` 时只搜索该项目（即使已归档）。
 #[tauri::command]
 pub fn search_global(
     query: String,
+    project_id: Option<String>,
     limit: Option<u32>,
+    offset: Option<u32>,
+    sort: Option<SearchSort>,
+    include_archived: Option<bool>,
     state: State<'_, Arc<AppState>>,
 ) -> Result<Vec<SearchResult>, CommandError> {
-    let limit = limit.unwrap_or(20);
-    let results = state.db.search_all(&query, limit)?;
+    structured_search(
+        &state,
+        &query,
+        project_id.as_deref(),
+        limit.unwrap_or(20),
+        offset.unwrap_or(0),
+        sort.unwrap_or_default(),
+        include_archived.unwrap_or(false),
+    )
+}
+
+pub(crate) fn structured_search(
+    state: &AppState,
+    query: &str,
+    project_id: Option<&str>,
+    limit: u32,
+    offset: u32,
+    sort: SearchSort,
+    include_archived: bool,
+) -> Result<Vec<SearchResult>, CommandError> {
+    check_scope(state, project_id)?;
+    let Some(query) = SearchQuery::parse(query)? else {
+        return Ok(Vec::new());
+    };
+    let results = state
+        .db
+        .search_all(&query, project_id, limit, offset, sort, include_archived)?;
     Ok(results)
 }
 
-/// 搜索来源
+/// 验证搜索范围中的项目存在
+fn check_scope(state: &AppState, project_id: Option<&str>) -> Result<(), CommandError> {
+    if let Some(project_id) = project_id {
+        state.db.get_project(project_id)?;
+    }
+    Ok(())
+}
+
+impl From<QueryError> for CommandError {
+    fn from(e: QueryError) -> Self {
+        CommandError::Validation(format!("搜索语法错误: {}", e))
+    }
+}
+
+/// 搜索来源，指定 `project_id` 时只搜索该项目
 #[tauri::command]
 pub fn search_sources(
     query: String,
+    project_id: Option<String>,
     limit: Option<u32>,
+    include_archived: Option<bool>,
     state: State<'_, Arc<AppState>>,
 ) -> Result<Vec<SearchResult>, CommandError> {
     let limit = limit.unwrap_or(20);
-    let results = state.db.search_sources(&query, limit)?;
+    check_scope(&state, project_id.as_deref())?;
+    let results = state.db.search_sources(
+        &query,
+        project_id.as_deref(),
+        limit,
+        include_archived.unwrap_or(false),
+    )?;
     Ok(results)
 }
 
-/// 语义搜索（基于向量索引）
+/// 语义搜索（混合检索：全文检索与向量检索按排名融合）
+///
+/// 结果的 `scoreBreakdown` 给出两路检索各自的排名与得分。指定 `project_id` 时只搜索该项目。
+/// 缺失的向量由后台生成，搜索不等待：生成完成前 `indexing` 为 true，未生成向量的条目只参与全文检索。
 #[tauri::command]
 pub fn search_semantic(
     query: String,
+    project_id: Option<String>,
     limit: Option<u32>,
+    include_archived: Option<bool>,
     state: State<'_, Arc<AppState>>,
-) -> Result<Vec<SearchResult>, CommandError> {
-    let limit = limit.unwrap_or(20);
-
-    // 补齐缺失向量
-    ensure_embeddings(&state)?;
+) -> Result<SemanticSearchResults, CommandError> {
+    semantic_search(
+        &state,
+        &query,
+        project_id.as_deref(),
+        limit.unwrap_or(20),
+        include_archived.unwrap_or(false),
+    )
+}
 
-    let embedding = embed_text(&query);
-    if embedding.is_empty() {
-        return Ok(Vec::new());
+fn semantic_search(
+    state: &AppState,
+    query: &str,
+    project_id: Option<&str>,
+    limit: u32,
+    include_archived: bool,
+) -> Result<SemanticSearchResults, CommandError> {
+    check_scope(state, project_id)?;
+    let pending = state.db.count_missing_embeddings()?;
+    if pending > 0 {
+        state.embeddings.request_backfill();
     }
 
-    let per_type_limit = (limit / 3).max(2);
-    let mut results = Vec::new();
-    let mut seen = HashSet::new();
+    let results = hybrid_search(state, query, project_id, limit, include_archived)?;
+    Ok(SemanticSearchResults {
+        results,
+        indexing: pending > 0,
+        pending,
+    })
+}
+
+/// 相关条目推荐：与来源或笔记最相似的来源与笔记（不含自身），按类型分组，每组默认 5 条
+///
+/// 默认只在条目所属项目中查找，`library` 为 true 时在整个资料库中查找。
+#[tauri::command]
+pub fn related_items(
+    kind: ContentKind,
+    id: String,
+    limit: Option<u32>,
+    library: Option<bool>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<RelatedItems, CommandError> {
+    find_related(&state, kind, &id, limit.unwrap_or(5), library.unwrap_or(false))
+}
 
-    let mut push_result = |result: SearchResult| {
-        let key = format!("{}:{}", result.result_type, result.id);
-        if seen.insert(key) {
-            results.push(result);
+fn find_related(
+    state: &AppState,
+    kind: ContentKind,
+    id: &str,
+    limit: u32,
+    library: bool,
+) -> Result<RelatedItems, CommandError> {
+    match kind {
+        ContentKind::Source => {
+            state.db.get_source(id)?;
         }
-    };
+        ContentKind::Note => {
+            state.db.get_note(id)?;
+        }
+        _ => {
+            return Err(CommandError::Validation(
+                "只有来源和笔记支持相关推荐".to_string(),
+            ))
+        }
+    }
+    Ok(state.db.related_items(kind, id, library, limit)?)
+}
 
-    for result in state.db.search_projects(&query, per_type_limit)? {
-        push_result(result);
+/// 每路检索参与融合的候选数下限
+const HYBRID_CANDIDATES: u32 = 50;
+
+/// 混合检索：每种内容类型的全文检索结果与向量检索结果各为一个排名，按 RRF 融合
+fn hybrid_search(
+    state: &AppState,
+    query: &str,
+    project_id: Option<&str>,
+    limit: u32,
+    include_archived: bool,
+) -> Result<Vec<SearchResult>, CommandError> {
+    // 各路候选数不按类型均分，避免某类结果被挤出
+    let pool = limit.max(HYBRID_CANDIDATES);
+    let db = &state.db;
+
+    let lexical = vec![
+        db.search_projects(query, project_id, pool, include_archived)?,
+        db.search_sources(query, project_id, pool, include_archived)?,
+        db.search_notes(query, project_id, pool, include_archived)?,
+        db.search_canvases(query, project_id, pool, include_archived)?,
+        db.search_mindmaps(query, project_id, pool, include_archived)?,
+        db.search_presentations(query, project_id, pool, include_archived)?,
+        db.search_chats(query, project_id, pool, include_archived)?,
+    ];
+
+    // 向量相似度在同一空间中可比，来源与笔记合并为一个排名
+    let embedding = embed_text(query);
+    let terms: Vec<&str> = query.split_whitespace().collect();
+    let mut semantic = Vec::new();
+    if !embedding.is_empty() {
+        semantic.extend(db.search_sources_semantic(
+            &embedding,
+            &terms,
+            project_id,
+            pool,
+            include_archived,
+        )?);
+        semantic.extend(db.search_notes_semantic(
+            &embedding,
+            &terms,
+            project_id,
+            pool,
+            include_archived,
+        )?);
+        semantic.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+        semantic.truncate(pool as usize);
     }
 
-    for result in state.db.search_sources_semantic(&embedding, per_type_limit)? {
-        push_result(result);
+    Ok(fuse_rankings(
+        lexical,
+        vec![semantic],
+        HybridWeights::default(),
+        limit as usize,
+    ))
+}
+
+/// 重建全文索引
+///
+/// 项目、来源、笔记、思维导图与对话索引按数据表重建；笔记正文、画布与 PPT 文本重新从数据文件读取。
+/// 返回重建的索引表名。
+#[tauri::command]
+pub fn search_rebuild_index(state: State<'_, Arc<AppState>>) -> Result<Vec<String>, CommandError> {
+    rebuild_search_index(&state)
+}
+
+fn rebuild_search_index(state: &AppState) -> Result<Vec<String>, CommandError> {
+    for table in FTS_TABLES {
+        state.db.rebuild_fts(table)?;
     }
 
-    for result in state.db.search_notes_semantic(&embedding, per_type_limit)? {
-        push_result(result);
+    // 笔记正文以文件为准重新同步（内容未变化的笔记不会重写索引）
+    for id in state.db.list_content_ids(ContentKind::Note)? {
+        let note = state.db.get_note(&id)?;
+        match fs::read_to_string(&note.path) {
+            Ok(content) => state.db.index_note_content(&id, &content)?,
+            Err(_) => eprintln!("[search_rebuild_index] 跳过无法读取的笔记文件: {}", note.path),
+        }
     }
 
-    for result in state.db.search_sources(&query, per_type_limit)? {
-        push_result(result);
+    for id in state.db.list_content_ids(ContentKind::Canvas)? {
+        let canvas = state.db.get_canvas(&id)?;
+        let data = fs::read_to_string(&canvas.path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<CanvasData>(&content).ok());
+        match data {
+            Some(data) => state
+                .db
+                .update_canvas_text_content(&id, &extract_canvas_text(&data))?,
+            None => eprintln!("[search_rebuild_index] 跳过无法读取的画布数据: {}", canvas.path),
+        }
     }
 
-    for result in state.db.search_notes(&query, per_type_limit)? {
-        push_result(result);
+    for id in state.db.list_content_ids(ContentKind::Presentation)? {
+        let presentation = state.db.get_presentation(&id)?;
+        match extract_ppt_file_text(Path::new(&presentation.data_path)) {
+            Ok(text) => state.db.update_presentation_text_content(&id, &text)?,
+            Err(_) => eprintln!(
+                "[search_rebuild_index] 跳过无法读取的 PPT 数据: {}",
+                presentation.data_path
+            ),
+        }
     }
 
-    results.truncate(limit as usize);
-    Ok(results)
+    Ok(FTS_TABLES.iter().map(|table| table.to_string()).collect())
 }
 
 /// 获取最近访问记录
@@ -109,54 +306,10 @@ pub fn recent_add(
     Ok(())
 }
 
-fn ensure_embeddings(state: &State<'_, Arc<AppState>>) -> Result<(), CommandError> {
-    backfill_source_embeddings(state)?;
-    backfill_note_embeddings(state)?;
-    Ok(())
-}
-
-fn backfill_source_embeddings(state: &State<'_, Arc<AppState>>) -> Result<(), CommandError> {
-    let sources = state.db.list_sources_missing_embeddings()?;
-    for (id, project_id, name, text_content) in sources {
-        let input = text_content
-            .as_deref()
-            .and_then(|content| if content.trim().is_empty() { None } else { Some(content) })
-            .unwrap_or(name.as_str());
-        let embedding = embed_text(input);
-        if embedding.is_empty() {
-            continue;
-        }
-        let _ = state
-            .db
-            .upsert_source_embedding(&id, &project_id, &embedding);
-    }
-    Ok(())
-}
-
-fn backfill_note_embeddings(state: &State<'_, Arc<AppState>>) -> Result<(), CommandError> {
-    let notes = state.db.list_notes_missing_embeddings()?;
-    for (id, project_id, title, path) in notes {
-        let content = fs::read_to_string(&path).unwrap_or_default();
-        let input = if content.trim().is_empty() {
-            title.as_str()
-        } else {
-            content.as_str()
-        };
-        let embedding = embed_text(input);
-        if embedding.is_empty() {
-            continue;
-        }
-        let _ = state
-            .db
-            .upsert_note_embedding(&id, &project_id, &embedding);
-    }
-    Ok(())
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::db::Database;
+    use crate::db::{Database, DbError};
     use crate::models::{Project, ProjectIcon};
     use crate::services::FileService;
     use chrono::Utc;
@@ -168,7 +321,7 @@ mod tests {
         let file_service = FileService::new(temp_dir.path().to_path_buf());
         file_service.init_base_dirs().expect("初始化目录失败");
 
-        let state = Arc::new(AppState { db, file_service });
+        let state = Arc::new(AppState::new(db, file_service));
         (state, temp_dir)
     }
 
@@ -185,6 +338,7 @@ mod tests {
             updated_at: Utc::now(),
             sources_count: 0,
             path: project_path.display().to_string(),
+            archived_at: None,
         };
         state.db.insert_project(&project).unwrap();
         project
@@ -193,7 +347,7 @@ mod tests {
     #[test]
     fn test_search_empty() {
         let (state, _temp) = create_test_state();
-        let results = state.db.search_projects("测试", 10).unwrap();
+        let results = state.db.search_projects("测试", None, 10, false).unwrap();
         assert!(results.is_empty());
     }
 
@@ -207,7 +361,7 @@ mod tests {
         create_test_project(&state, "其他项目");
 
         // 搜索 "学习"
-        let results = state.db.search_projects("学习", 10).unwrap();
+        let results = state.db.search_projects("学习", None, 10, false).unwrap();
         assert_eq!(results.len(), 2);
     }
 
@@ -266,4 +420,415 @@ mod tests {
         let accesses = state.db.get_recent_accesses(20).unwrap();
         assert_eq!(accesses.len(), 10);
     }
+
+    #[test]
+    fn test_rebuild_search_index() {
+        use crate::models::Canvas;
+
+        let (state, _temp) = create_test_state();
+        let project = create_test_project(&state, "白板项目");
+
+        let path = std::path::Path::new(&project.path).join("canvases").join("c1.json");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(
+            &path,
+            r#"{"elements":[{"type":"text","text":"季度路线图草案"},{"type":"rectangle"}]}"#,
+        )
+        .unwrap();
+        state
+            .db
+            .insert_canvas(&Canvas {
+                id: "c1".to_string(),
+                project_id: project.id.clone(),
+                title: "规划白板".to_string(),
+                path: path.display().to_string(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .unwrap();
+
+        // 新建画布只有标题进入索引，文本在保存或重建索引时提取
+        assert_eq!(state.db.search_canvases("规划白板", None, 10, false).unwrap().len(), 1);
+        assert!(state.db.search_canvases("路线图", None, 10, false).unwrap().is_empty());
+
+        let rebuilt = rebuild_search_index(&state).unwrap();
+        assert_eq!(rebuilt.len(), 7);
+        let results = state.db.search_canvases("路线图", None, 10, false).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "c1");
+        assert!(state.db.check_fts().unwrap().is_empty());
+    }
+
+    /// 小型标注语料：(类型, ID, 标题, 正文)
+    const CORPUS: [(&str, &str, &str, &str); 6] = [
+        ("source", "s-ml", "机器学习入门.md", "介绍监督学习、无监督学习，以及如何部署机器学习模型到生产环境。"),
+        ("source", "s-db", "数据库索引.md", "B+ 树索引与倒排索引的原理，以及 SQLite FTS5 的使用。"),
+        ("source", "s-cook", "家常菜谱.md", "红烧肉与番茄炒蛋的做法，包含火候与调味技巧。"),
+        ("note", "n-rust", "Rust 所有权", "借用检查器保证内存安全，生命周期标注说明引用的有效范围。"),
+        ("note", "n-trip", "旅行计划", "十月去云南大理和丽江，预订客栈与往返机票。"),
+        ("note", "n-rank", "检索排序方案", "混合检索将 bm25 排名与向量相似度排名按 RRF 融合。"),
+    ];
+
+    /// 查询与期望排在第一的条目
+    const LABELLED_QUERIES: [(&str, &str); 6] = [
+        ("倒排索引", "s-db"),
+        ("机器学习模型部署", "s-ml"),
+        ("内存安全", "n-rust"),
+        ("大理丽江", "n-trip"),
+        ("RRF 融合", "n-rank"),
+        ("番茄炒蛋做法", "s-cook"),
+    ];
+
+    fn load_corpus(state: &AppState, project: &Project) {
+        use crate::models::{Note, OutputType, Source, SourceType};
+
+        for (kind, id, title, text) in CORPUS {
+            let embedding = embed_text(text);
+            if kind == "source" {
+                let source = Source {
+                    id: id.to_string(),
+                    project_id: project.id.clone(),
+                    name: title.to_string(),
+                    source_type: SourceType::Markdown,
+                    path: format!("{}/sources/{}.md", project.path, id),
+                    size: 0,
+                    mime_type: "text/markdown".to_string(),
+                    thumbnail_path: None,
+                    created_at: Utc::now(),
+                    updated_at: Utc::now(),
+                };
+                state.db.insert_source_with_content(&source, Some(text)).unwrap();
+                state.db.upsert_source_embedding(id, &project.id, &embedding).unwrap();
+            } else {
+                let note = Note {
+                    id: id.to_string(),
+                    project_id: project.id.clone(),
+                    title: title.to_string(),
+                    path: format!("{}/notes/{}.md", project.path, id),
+                    output_type: OutputType::Note,
+                    title_locked: false,
+                    created_at: Utc::now(),
+                    updated_at: Utc::now(),
+                };
+                state.db.insert_note(&note).unwrap();
+                state.db.update_note_content(id, text).unwrap();
+                state.db.upsert_note_embedding(id, &project.id, &embedding).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_hybrid_search_labelled_corpus() {
+        let (state, _temp) = create_test_state();
+        let project = create_test_project(&state, "检索评测");
+        load_corpus(&state, &project);
+
+        let mut lexical_hits = 0;
+        for (query, expected) in LABELLED_QUERIES {
+            let results = hybrid_search(&state, query, None, 5, false).unwrap();
+            assert_eq!(results[0].id, expected, "查询「{}」的首条结果不符", query);
+
+            let breakdown = results[0].score_breakdown.as_ref().unwrap();
+            assert!(breakdown.semantic_rank.is_some());
+            if breakdown.lexical_rank.is_some() {
+                lexical_hits += 1;
+            }
+            // 得分降序且归一化到 [0, 1]
+            assert!(results
+                .windows(2)
+                .all(|pair| pair[0].score >= pair[1].score));
+            assert!(results.iter().all(|r| r.score.unwrap() <= 1.0));
+        }
+
+        // 词序或虚词不同的查询全文检索无法命中，由向量检索补足
+        assert!(lexical_hits >= 3 && lexical_hits < LABELLED_QUERIES.len());
+    }
+
+    #[test]
+    fn test_hybrid_search_keeps_every_type() {
+        let (state, _temp) = create_test_state();
+        let project = create_test_project(&state, "检索排序方案汇总");
+        load_corpus(&state, &project);
+
+        // 项目名称只有全文检索一路，仍应出现在结果中
+        let results = hybrid_search(&state, "检索排序方案", None, 3, false).unwrap();
+        assert_eq!(results[0].id, "n-rank");
+        assert!(results.iter().any(|r| r.result_type == "project"));
+        assert!(hybrid_search(&state, "  ", None, 5, false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_semantic_search_while_indexing() {
+        use crate::commands::embedding::run_embedding_batch;
+        use crate::models::{Note, OutputType};
+
+        let (state, _temp) = create_test_state();
+        let project = create_test_project(&state, "检索评测");
+        load_corpus(&state, &project);
+        let ready = semantic_search(&state, "倒排索引", None, 5, false).unwrap();
+        assert!(!ready.indexing);
+        assert_eq!(ready.results[0].id, "s-db");
+
+        // 新笔记尚未生成向量：不等待，只由全文检索命中
+        state
+            .db
+            .insert_note(&Note {
+                id: "n-new".to_string(),
+                project_id: project.id.clone(),
+                title: "向量生成队列".to_string(),
+                path: format!("{}/notes/n-new.md", project.path),
+                output_type: OutputType::Note,
+                title_locked: false,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .unwrap();
+        let partial = semantic_search(&state, "向量生成队列", None, 5, false).unwrap();
+        assert!(partial.indexing);
+        assert_eq!(partial.pending, 1);
+        let hit = partial.results.iter().find(|r| r.id == "n-new").unwrap();
+        assert_eq!(hit.score_breakdown.as_ref().unwrap().semantic_rank, None);
+
+        run_embedding_batch(&state, 10).unwrap();
+        let complete = semantic_search(&state, "向量生成队列", None, 5, false).unwrap();
+        assert!(!complete.indexing);
+        assert_eq!(complete.pending, 0);
+        let hit = complete.results.iter().find(|r| r.id == "n-new").unwrap();
+        assert!(hit.score_breakdown.as_ref().unwrap().semantic_rank.is_some());
+    }
+
+    #[test]
+    fn test_related_items() {
+        use crate::models::{Source, SourceType};
+
+        let (state, _temp) = create_test_state();
+        let project = create_test_project(&state, "检索评测");
+        load_corpus(&state, &project);
+
+        let related = find_related(&state, ContentKind::Source, "s-db", 2, false).unwrap();
+        assert!(related.sources.len() <= 2 && related.notes.len() <= 2);
+        assert!(related.sources.iter().all(|r| r.result_type == "source" && r.id != "s-db"));
+        assert!(related.notes.iter().all(|r| r.result_type == "note"));
+        assert!(related
+            .notes
+            .windows(2)
+            .all(|pair| pair[0].score >= pair[1].score));
+
+        // 向量更新后缓存失效
+        let db_text = CORPUS[1].3;
+        state
+            .db
+            .upsert_note_embedding("n-trip", &project.id, &embed_text(db_text))
+            .unwrap();
+        let related = find_related(&state, ContentKind::Source, "s-db", 2, false).unwrap();
+        assert_eq!(related.notes[0].id, "n-trip");
+
+        // 其他项目的条目只在整个资料库范围内出现
+        let other = create_test_project(&state, "其他项目");
+        let source = Source {
+            id: "s-other".to_string(),
+            project_id: other.id.clone(),
+            name: "索引副本.md".to_string(),
+            source_type: SourceType::Markdown,
+            path: format!("{}/sources/s-other.md", other.path),
+            size: 0,
+            mime_type: "text/markdown".to_string(),
+            thumbnail_path: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        state.db.insert_source_with_content(&source, Some(db_text)).unwrap();
+        state
+            .db
+            .upsert_source_embedding("s-other", &other.id, &embed_text(db_text))
+            .unwrap();
+        let in_project = find_related(&state, ContentKind::Note, "n-trip", 5, false).unwrap();
+        assert!(in_project.sources.iter().all(|r| r.id != "s-other"));
+        assert!(in_project.notes.iter().all(|r| r.id != "n-trip"));
+        let library = find_related(&state, ContentKind::Note, "n-trip", 5, true).unwrap();
+        // s-db 与 s-other 向量相同，并列第一
+        let top: Vec<&str> = library.sources[..2].iter().map(|r| r.id.as_str()).collect();
+        assert!(top.contains(&"s-other") && top.contains(&"s-db"));
+
+        // 归档或取消归档项目后缓存失效
+        state
+            .db
+            .update_project_archived(&other.id, Some(Utc::now()))
+            .unwrap();
+        let library = find_related(&state, ContentKind::Note, "n-trip", 5, true).unwrap();
+        assert!(library.sources.iter().all(|r| r.id != "s-other"));
+        state.db.update_project_archived(&other.id, None).unwrap();
+        let library = find_related(&state, ContentKind::Note, "n-trip", 5, true).unwrap();
+        assert!(library.sources.iter().any(|r| r.id == "s-other"));
+
+        assert!(matches!(
+            find_related(&state, ContentKind::Canvas, "c1", 5, false),
+            Err(CommandError::Validation(_))
+        ));
+        assert!(matches!(
+            find_related(&state, ContentKind::Note, "missing", 5, false),
+            Err(CommandError::Database(DbError::NotFound(_)))
+        ));
+    }
+
+    #[test]
+    fn test_search_project_scope() {
+        use crate::models::{Source, SourceType};
+
+        let (state, _temp) = create_test_state();
+        let project = create_test_project(&state, "检索评测");
+        load_corpus(&state, &project);
+        let other = create_test_project(&state, "归档资料");
+        let text = "倒排索引的压缩与合并策略。";
+        state
+            .db
+            .insert_source_with_content(
+                &Source {
+                    id: "s-other".to_string(),
+                    project_id: other.id.clone(),
+                    name: "索引压缩.md".to_string(),
+                    source_type: SourceType::Markdown,
+                    path: format!("{}/sources/s-other.md", other.path),
+                    size: 0,
+                    mime_type: "text/markdown".to_string(),
+                    thumbnail_path: None,
+                    created_at: Utc::now(),
+                    updated_at: Utc::now(),
+                },
+                Some(text),
+            )
+            .unwrap();
+        state
+            .db
+            .upsert_source_embedding("s-other", &other.id, &embed_text(text))
+            .unwrap();
+        state
+            .db
+            .update_project_archived(&other.id, Some(Utc::now()))
+            .unwrap();
+
+        let ids = |results: Vec<SearchResult>| -> Vec<String> {
+            let mut ids: Vec<String> = results.into_iter().map(|r| r.id).collect();
+            ids.sort();
+            ids
+        };
+
+        // 不指定范围时不包含归档项目；指定归档项目时只搜索该项目
+        let all = structured_search(
+            &state,
+            "倒排索引",
+            None,
+            20,
+            0,
+            SearchSort::Relevance,
+            false,
+        );
+        assert_eq!(ids(all.unwrap()), vec!["s-db"]);
+        let scoped = structured_search(
+            &state,
+            "倒排索引",
+            Some(&other.id),
+            20,
+            0,
+            SearchSort::Relevance,
+            false,
+        );
+        assert_eq!(ids(scoped.unwrap()), vec!["s-other"]);
+
+        // 向量检索同样只返回范围内的条目
+        let results =
+            hybrid_search(&state, "机器学习模型部署", Some(&project.id), 20, false).unwrap();
+        assert!(results.iter().all(|r| r.project_id == project.id));
+        let results =
+            hybrid_search(&state, "机器学习模型部署", Some(&other.id), 20, false).unwrap();
+        assert!(results
+            .iter()
+            .all(|r| r.id == "s-other" || r.id == other.id));
+        assert!(results.iter().any(|r| r.id == "s-other"));
+
+        assert!(matches!(
+            structured_search(
+                &state,
+                "索引",
+                Some("missing"),
+                20,
+                0,
+                SearchSort::Relevance,
+                false
+            ),
+            Err(CommandError::Database(DbError::NotFound(_)))
+        ));
+    }
+
+    #[test]
+    fn test_structured_search_filters() {
+        let (state, _temp) = create_test_state();
+        let project = create_test_project(&state, "检索评测");
+        load_corpus(&state, &project);
+        let other = create_test_project(&state, "其他项目");
+        state
+            .db
+            .transaction(|tx| tx.add_tag(ContentKind::Note, "n-rust", "待读"))
+            .unwrap();
+
+        let ids = |query: &str| -> Vec<String> {
+            structured_search(&state, query, None, 20, 0, SearchSort::Relevance, false)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.id)
+                .collect()
+        };
+
+        assert_eq!(ids("索引 type:source"), vec!["s-db"]);
+        assert_eq!(ids("type:note tag:待读"), vec!["n-rust"]);
+        assert_eq!(ids("ext:md type:source -菜谱").len(), 2);
+        assert_eq!(ids("\"倒排索引的原理\""), vec!["s-db"]);
+        assert!(ids("\"索引的倒排\"").is_empty());
+        assert_eq!(ids("番茄 OR 借用检查器"), vec!["n-rust", "s-cook"]);
+        assert_eq!(ids("检索评测"), vec![project.id.clone()]);
+
+        // 项目按名称或 ID 过滤，日期按更新日过滤
+        assert_eq!(ids(&format!("project:\"{}\" 学习", project.name)), vec!["s-ml"]);
+        assert!(ids(&format!("project:{} 学习", other.id)).is_empty());
+        let today = Utc::now().format("%Y-%m-%d").to_string();
+        assert_eq!(ids(&format!("type:note after:{}", today)).len(), 3);
+        assert!(ids(&format!("type:note before:{}", today)).is_empty());
+
+        assert!(matches!(
+            structured_search(&state, "type:video", None, 20, 0, SearchSort::Relevance, false),
+            Err(CommandError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_structured_search_pagination() {
+        let (state, _temp) = create_test_state();
+        let project = create_test_project(&state, "检索评测");
+        load_corpus(&state, &project);
+
+        // 只有过滤条件时按日期排序、逐页读取不重复
+        let all: Vec<String> =
+            structured_search(&state, "-type:project", None, 20, 0, SearchSort::Date, false)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.id)
+                .collect();
+        assert_eq!(all.len(), CORPUS.len());
+
+        let mut paged = Vec::new();
+        for page in 0..3 {
+            let results =
+                structured_search(&state, "-type:project", None, 2, page * 2, SearchSort::Date, false)
+                    .unwrap();
+            assert_eq!(results.len(), 2);
+            assert!(results
+                .windows(2)
+                .all(|pair| pair[0].updated_at >= pair[1].updated_at));
+            paged.extend(results.into_iter().map(|r| r.id));
+        }
+        assert_eq!(paged, all);
+        assert!(structured_search(&state, "-type:project", None, 2, 6, SearchSort::Date, false)
+            .unwrap()
+            .is_empty());
+    }
 }
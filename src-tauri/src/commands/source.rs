@@ -2,8 +2,12 @@
 //!
 //! 提供来源文件管理相关的 Tauri Commands
 
-use crate::commands::project::{AppState, CommandError};
-use crate::models::{FailedImport, ImportResult, Source, SourceType};
+use crate::commands::project::{
+    ensure_project_writable, ensure_sources_available, AppState, CommandError,
+};
+use crate::commands::trash::move_to_trash;
+use crate::db::find_matches;
+use crate::models::{ContentKind, FailedImport, ImportResult, Source, SourceType, TextMatch};
 use crate::services::{embed_text, extract_docx_text, extract_pdf_text};
 use chrono::Utc;
 use std::fs;
@@ -102,8 +106,8 @@ async fn import_files_internal(
     let mut success = Vec::new();
     let mut failed = Vec::new();
 
-    // 验证项目存在
-    state.db.get_project(project_id)?;
+    // 验证项目存在且未归档
+    ensure_project_writable(state, project_id)?;
 
     // 获取来源目录
     let sources_dir = state.file_service.get_sources_dir(project_id);
@@ -282,26 +286,8 @@ pub fn source_get(id: String, state: State<'_, Arc<AppState>>) -> Result<Source,
 /// 删除来源
 #[tauri::command]
 pub fn source_delete(id: String, state: State<'_, Arc<AppState>>) -> Result<(), CommandError> {
-    // 获取来源信息用于删除文件
-    let source = state.db.get_source(&id)?;
-
-    // 从数据库删除并获取 project_id
-    let project_id = state.db.delete_source(&id)?;
-
-    // 删除源文件
-    let _ = fs::remove_file(&source.path);
-
-    // 删除缩略图
-    if let Some(thumb_path) = source.thumbnail_path {
-        let _ = fs::remove_file(&thumb_path);
-    }
-
-    // 删除向量索引
-    let _ = state.db.delete_source_embedding(&id);
-
-    // 更新 sources_count
-    let _ = state.db.update_project_sources_count(&project_id, -1);
-
+    // 移入回收站（源文件、提取文本与向量索引可恢复，sources_count 同步减一）
+    move_to_trash(&state, ContentKind::Source, &id)?;
     Ok(())
 }
 
@@ -311,17 +297,37 @@ pub fn source_get_content(
     id: String,
     state: State<'_, Arc<AppState>>,
 ) -> Result<String, CommandError> {
-    let source = state.db.get_source(&id)?;
+    load_source_content(&state, &id)
+}
+
+/// 在来源文本中查找，返回全部命中位置（与 `source_get_content` 返回的文本对应）
+#[tauri::command]
+pub fn source_find_in(
+    id: String,
+    query: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<TextMatch>, CommandError> {
+    find_in_source(&state, &id, &query)
+}
+
+fn find_in_source(state: &AppState, id: &str, query: &str) -> Result<Vec<TextMatch>, CommandError> {
+    let content = load_source_content(state, id)?;
+    Ok(find_matches(&content, query))
+}
+
+fn load_source_content(state: &AppState, id: &str) -> Result<String, CommandError> {
+    let source = state.db.get_source(id)?;
 
     // 对于 Markdown 文件，直接读取文件内容（保持最新）
     if source.source_type == SourceType::Markdown {
+        ensure_sources_available(state, &source.project_id)?;
         let content = fs::read_to_string(&source.path)
             .map_err(|e| CommandError::Io(e.to_string()))?;
         return Ok(content);
     }
 
     // PDF/Word 从数据库读取已提取的文本
-    let content = state.db.get_source_content(&id)?;
+    let content = state.db.get_source_content(id)?;
     Ok(content)
 }
 
@@ -369,9 +375,9 @@ fn get_mime_type(source_type: &SourceType, ext: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::db::Database;
+    use crate::db::{Database, DbError};
     use crate::models::{Project, ProjectIcon};
-    use crate::services::FileService;
+    use crate::services::{FileService, PAGE_BREAK};
     use tempfile::TempDir;
 
     fn create_test_state() -> (Arc<AppState>, TempDir) {
@@ -380,7 +386,7 @@ mod tests {
         let file_service = FileService::new(temp_dir.path().to_path_buf());
         file_service.init_base_dirs().expect("初始化目录失败");
 
-        let state = Arc::new(AppState { db, file_service });
+        let state = Arc::new(AppState::new(db, file_service));
         (state, temp_dir)
     }
 
@@ -397,6 +403,7 @@ mod tests {
             updated_at: Utc::now(),
             sources_count: 0,
             path: project_path.display().to_string(),
+            archived_at: None,
         };
         state.db.insert_project(&project).unwrap();
         project
@@ -478,4 +485,54 @@ mod tests {
         state.db.delete_source("test-source-2").unwrap();
         assert!(state.db.get_source("test-source-2").is_err());
     }
+
+    #[test]
+    fn test_find_in_source() {
+        let (state, _temp) = create_test_state();
+        let project = create_test_project(&state, "测试项目");
+
+        // Markdown 以文件内容为准
+        let path = Path::new(&project.path).join("sources").join("notes.md");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "# 索引\n\n倒排索引与 B+ 树索引").unwrap();
+        let markdown = Source {
+            id: "md".to_string(),
+            project_id: project.id.clone(),
+            name: "notes.md".to_string(),
+            source_type: SourceType::Markdown,
+            path: path.display().to_string(),
+            size: 0,
+            mime_type: "text/markdown".to_string(),
+            thumbnail_path: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        state.db.insert_source(&markdown).unwrap();
+
+        let matches = find_in_source(&state, "md", "索引").unwrap();
+        let starts: Vec<usize> = matches.iter().map(|found| found.start).collect();
+        assert_eq!(starts, vec![2, 8, 16]);
+        assert!(matches.iter().all(|found| found.end - found.start == 2));
+
+        // PDF 使用提取的文本，命中位置带页码
+        let pdf = Source {
+            id: "pdf".to_string(),
+            name: "report.pdf".to_string(),
+            source_type: SourceType::Pdf,
+            path: "/test/report.pdf".to_string(),
+            mime_type: "application/pdf".to_string(),
+            ..markdown
+        };
+        let text = format!("封面{}目录{}第一章 索引", PAGE_BREAK, PAGE_BREAK);
+        state.db.insert_source_with_content(&pdf, Some(&text)).unwrap();
+        let matches = find_in_source(&state, "pdf", "索引").unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].page, Some(3));
+
+        assert!(find_in_source(&state, "pdf", " ").unwrap().is_empty());
+        assert!(matches!(
+            find_in_source(&state, "missing", "索引"),
+            Err(CommandError::Database(DbError::NotFound(_)))
+        ));
+    }
 }
@@ -0,0 +1,1310 @@
+//! Sync Commands
+//!
+//! 通过共享文件夹在多台设备之间同步资料库。
+//!
+//! 每台设备把本机记录的新版本追加到自己的变更日志，并读取其他设备的日志：
+//! - 变更基于本机已知的版本且本机未修改时直接应用；
+//! - 两端并发修改笔记时按字段三方合并（标题、正文、标签），正文冲突时保留较新的版本，
+//!   另一版本保存为冲突副本；
+//! - 其他内容并发修改时后写者优先，画布与思维导图的并发修改会在同步结果中报告；
+//! - 一端删除、另一端修改时保留修改。
+
+use crate::commands::note::create_notes;
+use crate::commands::project::{unpack_archived_sources, AppState, CommandError};
+use crate::commands::trash::move_to_trash_unchecked;
+use crate::db::{DbError, PathMapper};
+use crate::models::{
+    ContentKind, ContentSnapshot, SyncChange, SyncConflict, SyncFile, SyncReport, SyncResolution,
+    SyncSettings,
+};
+use crate::services::{
+    hash_bytes, hash_file, is_safe_relative_path, StagedFile, SyncError, SyncFolder,
+};
+use chrono::Utc;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::State;
+
+/// 同步设置的 settings 键
+const SYNC_SETTINGS_KEY: &str = "sync.settings";
+
+/// 本机设备 ID 的 settings 键
+const SYNC_DEVICE_ID_KEY: &str = "sync.device_id";
+
+/// 推送顺序：项目先于其下内容，其他设备按日志顺序应用时项目总是先被创建
+const SYNC_KINDS: [ContentKind; 7] = [
+    ContentKind::Project,
+    ContentKind::Source,
+    ContentKind::Note,
+    ContentKind::Canvas,
+    ContentKind::Mindmap,
+    ContentKind::Presentation,
+    ContentKind::ChatSession,
+];
+
+/// 同一时间只运行一次同步
+static SYNC_LOCK: Mutex<()> = Mutex::new(());
+
+type RecordKey = (ContentKind, String);
+
+impl From<SyncError> for CommandError {
+    fn from(e: SyncError) -> Self {
+        match e {
+            SyncError::Io(e) => CommandError::Io(e.to_string()),
+            other => CommandError::Validation(other.to_string()),
+        }
+    }
+}
+
+/// 获取同步设置
+#[tauri::command]
+pub fn sync_get_settings(state: State<'_, Arc<AppState>>) -> Result<SyncSettings, CommandError> {
+    sync_settings(&state)
+}
+
+/// 保存同步设置
+#[tauri::command]
+pub fn sync_set_settings(
+    settings: SyncSettings,
+    state: State<'_, Arc<AppState>>,
+) -> Result<SyncSettings, CommandError> {
+    save_sync_settings(&state, settings)
+}
+
+/// 与共享文件夹同步
+#[tauri::command]
+pub fn sync_now(state: State<'_, Arc<AppState>>) -> Result<SyncReport, CommandError> {
+    let settings = sync_settings(&state)?;
+    let folder = settings
+        .folder
+        .ok_or_else(|| CommandError::Validation("尚未设置同步文件夹".to_string()))?;
+    sync_library(&state, Path::new(&folder))
+}
+
+/// 读取同步设置
+pub fn sync_settings(state: &AppState) -> Result<SyncSettings, CommandError> {
+    let mut settings: SyncSettings = state
+        .db
+        .get_setting(SYNC_SETTINGS_KEY)?
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default();
+    if settings.device_name.trim().is_empty() {
+        settings.device_name = default_device_name();
+    }
+    Ok(settings)
+}
+
+fn save_sync_settings(
+    state: &AppState,
+    mut settings: SyncSettings,
+) -> Result<SyncSettings, CommandError> {
+    settings.folder = settings
+        .folder
+        .map(|f| f.trim().to_string())
+        .filter(|f| !f.is_empty());
+    if let Some(folder) = &settings.folder {
+        if !Path::new(folder).is_absolute() {
+            return Err(CommandError::Validation(format!(
+                "同步文件夹必须是绝对路径: {}",
+                folder
+            )));
+        }
+    }
+    settings.device_name = settings.device_name.trim().to_string();
+    if settings.device_name.is_empty() {
+        settings.device_name = default_device_name();
+    }
+
+    let value =
+        serde_json::to_string(&settings).map_err(|e| CommandError::Internal(e.to_string()))?;
+    state.db.set_setting(SYNC_SETTINGS_KEY, &value)?;
+    Ok(settings)
+}
+
+fn default_device_name() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| "DeskLab".to_string())
+}
+
+/// 本机设备 ID（首次同步时生成，每个资料库独立）
+fn device_id(state: &AppState) -> Result<String, CommandError> {
+    if let Some(id) = state.db.get_setting(SYNC_DEVICE_ID_KEY)? {
+        return Ok(id);
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    state.db.set_setting(SYNC_DEVICE_ID_KEY, &id)?;
+    Ok(id)
+}
+
+/// 本机记录的当前版本
+struct LocalVersion {
+    /// 数据库中的记录（绝对路径）
+    record: ContentSnapshot,
+    hash: String,
+}
+
+/// 同步一次：先应用其他设备的变更，再推送本机的变更
+pub(crate) fn sync_library(
+    state: &AppState,
+    folder_path: &Path,
+) -> Result<SyncReport, CommandError> {
+    let _guard = SYNC_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let settings = sync_settings(state)?;
+    let device_id = device_id(state)?;
+    let folder = SyncFolder::open(folder_path)?;
+    let mut engine = SyncEngine {
+        state,
+        folder: &folder,
+        mapper: PathMapper::new(state.file_service.base_path()),
+        device_name: settings.device_name,
+        bases: state
+            .db
+            .get_sync_records()?
+            .into_iter()
+            .map(|change| ((change.kind, change.item_id.clone()), change))
+            .collect(),
+        report: SyncReport {
+            synced_at: Utc::now(),
+            ..SyncReport::default()
+        },
+    };
+
+    for device in folder.devices()? {
+        if device != device_id {
+            engine.pull(&device)?;
+        }
+    }
+    engine.push(&device_id)?;
+
+    let report = engine.report;
+    eprintln!(
+        "[sync] 推送 {} 条变更，应用 {} 条变更，{} 个冲突",
+        report.pushed,
+        report.pulled,
+        report.conflicts.len()
+    );
+    Ok(report)
+}
+
+struct SyncEngine<'a> {
+    state: &'a AppState,
+    folder: &'a SyncFolder,
+    mapper: PathMapper,
+    device_name: String,
+    /// 每条记录上次同步时的版本
+    bases: HashMap<RecordKey, SyncChange>,
+    report: SyncReport,
+}
+
+impl SyncEngine<'_> {
+    /// 按日志顺序应用设备的变更
+    ///
+    /// 数据文件尚未传到共享文件夹时停在该变更，下次同步再继续。
+    fn pull(&mut self, device: &str) -> Result<(), CommandError> {
+        let cursor = self.state.db.get_sync_cursor(device)?;
+        for change in self.folder.read_changes(device, cursor)? {
+            if !change
+                .files
+                .iter()
+                .all(|file| self.folder.has_blob(&file.blob))
+            {
+                eprintln!(
+                    "[sync] 设备 {} 的数据文件尚未同步完成，稍后继续",
+                    change.device_name
+                );
+                break;
+            }
+            let seq = change.seq;
+            self.apply_incoming(change)?;
+            self.state
+                .db
+                .transaction(|tx| tx.set_sync_cursor(device, seq))?;
+        }
+        Ok(())
+    }
+
+    fn apply_incoming(&mut self, change: SyncChange) -> Result<(), CommandError> {
+        if let Err(e) = check_change_paths(&change) {
+            let message = format!(
+                "已跳过设备 {} 的变更（{} {}）: {}",
+                change.device_name,
+                change.kind.as_str(),
+                change.item_id,
+                e
+            );
+            eprintln!("[sync] {}", message);
+            self.report.errors.push(message);
+            return Ok(());
+        }
+
+        // 本机压缩了来源的归档项目：先解压，来源文件与压缩前一致后再比较与应用
+        if change.kind == ContentKind::Source {
+            if let Some(project_id) = self.packed_project(change.kind, &change.item_id)? {
+                unpack_archived_sources(self.state, &project_id)?;
+            }
+        }
+
+        let key = (change.kind, change.item_id.clone());
+        let local = self.local_version(change.kind, &change.item_id)?;
+        let base_hash = self.bases.get(&key).map(|base| base.hash.clone());
+        let local_hash = local.as_ref().map(|l| l.hash.clone()).unwrap_or_default();
+
+        if local_hash == change.hash {
+            // 两端已一致
+            return self.record_base(change);
+        }
+        let descends = change.base_hash == base_hash;
+        let local_changed =
+            Some(&local_hash) != base_hash.as_ref() && !(local.is_none() && base_hash.is_none());
+
+        if change.is_deletion() {
+            match local {
+                None => {}
+                Some(local) if local_changed || !descends => {
+                    self.report_conflict(
+                        &change,
+                        &local.record,
+                        SyncResolution::KeptLocalEdit,
+                        false,
+                        None,
+                    );
+                }
+                Some(_) => {
+                    // 归档只限制本机的编辑，另一设备的删除照常应用
+                    move_to_trash_unchecked(self.state, change.kind, &change.item_id)?;
+                    self.report.pulled += 1;
+                }
+            }
+            return self.record_base(change);
+        }
+
+        match local {
+            // 本机未修改（或本机已删除、另一端仍在修改）：直接应用
+            None => self.apply_version(&change, None, None)?,
+            Some(local) if descends && !local_changed => {
+                self.apply_version(&change, Some(&local.record), None)?
+            }
+            Some(local) if change.kind == ContentKind::Note => self.merge_note(&change, &local)?,
+            Some(local) => {
+                let local_at = local.record.updated_at(change.kind).unwrap_or_default();
+                let remote_won = change.changed_at > local_at;
+                if remote_won {
+                    self.apply_version(&change, Some(&local.record), None)?;
+                }
+                self.report_conflict(
+                    &change,
+                    &local.record,
+                    SyncResolution::LastWriterWins,
+                    remote_won,
+                    None,
+                );
+            }
+        }
+        self.record_base(change)
+    }
+
+    /// 两端并发修改笔记：标题、正文、标签分别三方合并，正文冲突时生成冲突副本
+    fn merge_note(
+        &mut self,
+        change: &SyncChange,
+        local: &LocalVersion,
+    ) -> Result<(), CommandError> {
+        let ancestor = match &change.base_hash {
+            Some(hash) => self.find_version(change.kind, &change.item_id, hash)?,
+            None => None,
+        };
+        let ancestor = ancestor
+            .as_ref()
+            .map(|c| self.remote_note(c))
+            .transpose()?
+            .flatten();
+        let Some(remote) = self.remote_note(change)? else {
+            return Ok(());
+        };
+        let Some(mine) = local_note(&local.record)? else {
+            return Ok(());
+        };
+
+        let local_at = local
+            .record
+            .updated_at(ContentKind::Note)
+            .unwrap_or_default();
+        let remote_won = change.changed_at > local_at;
+        let (winner, loser) = if remote_won {
+            (&remote, &mine)
+        } else {
+            (&mine, &remote)
+        };
+
+        let base = ancestor.as_ref();
+        let title = merge_value(base.map(|b| &b.title), &mine.title, &remote.title)
+            .unwrap_or(&winner.title)
+            .clone();
+        let content =
+            merge_value(base.map(|b| &b.content), &mine.content, &remote.content).cloned();
+        let tags = merge_tags(base.map(|b| &b.tags), &mine.tags, &remote.tags);
+
+        let merged = NoteVersion {
+            title,
+            content: content.clone().unwrap_or_else(|| winner.content.clone()),
+            tags,
+        };
+        self.apply_version(change, Some(&local.record), Some(&merged))?;
+
+        match content {
+            Some(_) => self.report_conflict(
+                change,
+                &local.record,
+                SyncResolution::Merged,
+                remote_won,
+                None,
+            ),
+            None => {
+                let project_id = change
+                    .snapshot
+                    .as_ref()
+                    .and_then(|s| s.describe(ContentKind::Note))
+                    .map(|(project_id, _)| project_id)
+                    .unwrap_or_default();
+                let loser_device = if remote_won {
+                    &self.device_name
+                } else {
+                    &change.device_name
+                };
+                let copy_title = format!("{}（冲突副本 · {}）", loser.title, loser_device);
+                let copies = create_notes(
+                    self.state,
+                    &project_id,
+                    vec![(copy_title, loser.content.clone(), true)],
+                    &[],
+                )?;
+                let copy_id = copies.first().map(|note| note.id.clone());
+                self.report_conflict(
+                    change,
+                    &local.record,
+                    SyncResolution::ConflictCopy,
+                    remote_won,
+                    copy_id,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// 把另一端的版本写入本机（数据文件先暂存，数据库更新成功后提交）
+    ///
+    /// `note` 不为空时用合并后的笔记标题、正文与标签代替另一端的版本。
+    fn apply_version(
+        &mut self,
+        change: &SyncChange,
+        local: Option<&ContentSnapshot>,
+        note: Option<&NoteVersion>,
+    ) -> Result<(), CommandError> {
+        let Some(mut snapshot) = change.snapshot.clone() else {
+            return Ok(());
+        };
+        let kind = change.kind;
+        if kind != ContentKind::Project {
+            let project_id = snapshot.describe(kind).map(|(project_id, _)| project_id);
+            let project_exists = project_id
+                .map(|id| self.state.db.get_project(&id).is_ok())
+                .unwrap_or(false);
+            if !project_exists {
+                // 项目已在本机删除，下次推送时该记录会作为删除同步到其他设备
+                return Ok(());
+            }
+        }
+        // 归档项目同样应用另一设备的修改（归档状态本身也随项目同步）；
+        // 另一设备取消归档时解压本机压缩的来源
+        if let Some(project) = &snapshot.project {
+            if project.archived_at.is_none() {
+                unpack_archived_sources(self.state, &project.id)?;
+            }
+        }
+        self.mapper.resolve_snapshot(&mut snapshot);
+
+        // 暂存数据文件
+        let mut staged: Vec<StagedFile> = Vec::new();
+        let mut note_content = None;
+        for file in &change.files {
+            let dest = PathBuf::from(self.mapper.resolve(&file.path));
+            let is_note_file = kind == ContentKind::Note
+                && snapshot
+                    .notes
+                    .first()
+                    .map(|r| r.note.path == dest.display().to_string())
+                    == Some(true);
+            let bytes = match note {
+                Some(merged) if is_note_file => merged.content.clone().into_bytes(),
+                _ => self.folder.read_blob(&file.blob)?,
+            };
+            if is_note_file {
+                note_content = Some(String::from_utf8_lossy(&bytes).to_string());
+            }
+            staged.push(self.state.file_service.stage_write(&dest, &bytes)?);
+        }
+
+        // 向量不随变更同步：正文未变时沿用本机向量，否则留空由后台生成
+        for record in &mut snapshot.sources {
+            record.embedding = local
+                .and_then(|local| {
+                    local.sources.iter().find(|r| {
+                        r.source.id == record.source.id && r.text_content == record.text_content
+                    })
+                })
+                .and_then(|r| r.embedding.clone());
+        }
+        for record in &mut snapshot.notes {
+            if let Some(merged) = note {
+                record.note.title = merged.title.clone();
+            }
+            // 正文索引与写入的文件一致（合并时与另一端的版本不同）
+            if note_content.is_some() {
+                record.text_content = note_content.clone();
+            }
+            record.embedding = local
+                .and_then(|local| {
+                    local.notes.iter().find(|r| {
+                        r.note.id == record.note.id && r.text_content == record.text_content
+                    })
+                })
+                .and_then(|r| r.embedding.clone());
+        }
+        let missing_embeddings = snapshot.sources.iter().any(|r| r.embedding.is_none())
+            || snapshot.notes.iter().any(|r| r.embedding.is_none());
+        if let Some(merged) = note {
+            snapshot.tags.retain(|tag| tag.kind != ContentKind::Note);
+            for tag in &merged.tags {
+                snapshot.tags.push(crate::models::ItemTag {
+                    kind: ContentKind::Note,
+                    item_id: change.item_id.clone(),
+                    tag: tag.clone(),
+                });
+            }
+        }
+
+        if let Some(project) = &snapshot.project {
+            fs::create_dir_all(&project.path).map_err(|e| CommandError::Io(e.to_string()))?;
+        }
+        let new_paths: HashSet<String> = snapshot.file_paths().into_iter().collect();
+        self.state
+            .db
+            .transaction(|tx| -> Result<(), CommandError> {
+                match (&snapshot.project, local) {
+                    (Some(project), Some(_)) => tx.update_project(project)?,
+                    (Some(project), None) => tx.insert_project(project)?,
+                    (None, Some(local)) => tx.replace_snapshot(local, &snapshot)?,
+                    (None, None) => tx.restore_snapshot(&snapshot)?,
+                }
+                if kind == ContentKind::Source {
+                    let mut projects: Vec<&str> = snapshot
+                        .sources
+                        .iter()
+                        .map(|r| r.source.project_id.as_str())
+                        .collect();
+                    if let Some(local) = local {
+                        projects.extend(local.sources.iter().map(|r| r.source.project_id.as_str()));
+                    }
+                    for project_id in projects {
+                        tx.recount_project_sources(project_id)?;
+                    }
+                }
+                for file in staged {
+                    file.commit()?;
+                }
+                // 写入快照时数据文件尚未替换，按同步来的数据文件重新提取 PPT 搜索文本
+                for presentation in &snapshot.presentations {
+                    tx.index_presentation_file(presentation)?;
+                }
+                Ok(())
+            })?;
+        if missing_embeddings {
+            self.state.embeddings.request_backfill();
+        }
+
+        // 清理本机旧版本中不再引用的文件
+        if let Some(local) = local {
+            for path in local.file_paths() {
+                if !new_paths.contains(&path) {
+                    let _ = fs::remove_file(&path);
+                }
+            }
+        }
+        self.report.pulled += 1;
+        Ok(())
+    }
+
+    /// 推送本机有变化的记录与已删除的记录
+    fn push(&mut self, device_id: &str) -> Result<(), CommandError> {
+        let mut seq = self.folder.last_seq(device_id)?;
+        let mut outgoing = Vec::new();
+        let mut present = HashSet::new();
+
+        for kind in SYNC_KINDS {
+            for id in self.state.db.list_content_ids(kind)? {
+                present.insert((kind, id.clone()));
+                // 压缩在归档包中的来源文件不可读，归档期间也不会修改，不推送
+                if self.packed_project(kind, &id)?.is_some() {
+                    continue;
+                }
+                let Some(local) = self.local_version(kind, &id)? else {
+                    continue;
+                };
+                let base = self.bases.get(&(kind, id.clone()));
+                if base.map(|b| &b.hash) == Some(&local.hash) {
+                    continue;
+                }
+
+                let mut files = Vec::new();
+                for path in local.record.file_paths() {
+                    if Path::new(&path).is_file() {
+                        files.push(SyncFile {
+                            path: self.mapper.to_stored(&path),
+                            blob: self.folder.put_blob(Path::new(&path))?,
+                        });
+                    }
+                }
+                let snapshot = self.portable(&local.record);
+                seq += 1;
+                outgoing.push(SyncChange {
+                    seq,
+                    device_id: device_id.to_string(),
+                    device_name: self.device_name.clone(),
+                    kind,
+                    item_id: id,
+                    changed_at: local.record.updated_at(kind).unwrap_or_else(Utc::now),
+                    hash: version_hash(&snapshot, &files)?,
+                    base_hash: base.map(|b| b.hash.clone()),
+                    snapshot: Some(snapshot),
+                    files,
+                });
+            }
+        }
+
+        let mut deleted: Vec<&SyncChange> = self
+            .bases
+            .iter()
+            .filter(|(key, base)| !base.hash.is_empty() && !present.contains(*key))
+            .map(|(_, base)| base)
+            .collect();
+        deleted.sort_by_key(|base| (kind_order(base.kind), base.item_id.clone()));
+        for base in deleted {
+            seq += 1;
+            outgoing.push(SyncChange {
+                seq,
+                device_id: device_id.to_string(),
+                device_name: self.device_name.clone(),
+                kind: base.kind,
+                item_id: base.item_id.clone(),
+                changed_at: Utc::now(),
+                hash: String::new(),
+                base_hash: Some(base.hash.clone()),
+                snapshot: None,
+                files: Vec::new(),
+            });
+        }
+
+        self.folder.append_changes(device_id, &outgoing)?;
+        self.state.db.transaction(|tx| -> Result<(), DbError> {
+            for change in &outgoing {
+                tx.set_sync_record(change)?;
+            }
+            Ok(())
+        })?;
+        self.report.pushed += outgoing.len();
+        for change in outgoing {
+            self.bases
+                .insert((change.kind, change.item_id.clone()), change);
+        }
+        Ok(())
+    }
+
+    /// 读取本机记录的当前版本，记录不存在时返回 None
+    /// 来源所属项目的来源文件已压缩（归档）时返回项目 ID
+    fn packed_project(&self, kind: ContentKind, id: &str) -> Result<Option<String>, CommandError> {
+        if kind != ContentKind::Source {
+            return Ok(None);
+        }
+        let project_id = match self.state.db.get_item_project_id(kind, id) {
+            Ok(project_id) => project_id,
+            Err(DbError::NotFound(_)) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let packed = self
+            .state
+            .file_service
+            .archived_sources_path(&project_id)
+            .exists();
+        Ok(packed.then_some(project_id))
+    }
+
+    fn local_version(
+        &self,
+        kind: ContentKind,
+        id: &str,
+    ) -> Result<Option<LocalVersion>, CommandError> {
+        let record = match self.state.db.get_record_snapshot(kind, id) {
+            Ok(record) => record,
+            Err(DbError::NotFound(_)) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let mut files = Vec::new();
+        for path in record.file_paths() {
+            if Path::new(&path).is_file() {
+                files.push(SyncFile {
+                    path: self.mapper.to_stored(&path),
+                    blob: hash_file(Path::new(&path))?,
+                });
+            }
+        }
+        let hash = version_hash(&self.portable(&record), &files)?;
+        Ok(Some(LocalVersion { record, hash }))
+    }
+
+    /// 转换为可在其他设备上应用的形式：相对路径、不含向量与来源数量
+    fn portable(&self, record: &ContentSnapshot) -> ContentSnapshot {
+        let mut snapshot = record.clone();
+        self.mapper.store_snapshot(&mut snapshot);
+        for record in &mut snapshot.sources {
+            record.embedding = None;
+        }
+        for record in &mut snapshot.notes {
+            record.embedding = None;
+        }
+        if let Some(project) = &mut snapshot.project {
+            project.sources_count = 0;
+        }
+        snapshot
+    }
+
+    /// 在共享文件夹的变更日志中查找记录的指定版本（用作三方合并的共同祖先）
+    fn find_version(
+        &self,
+        kind: ContentKind,
+        id: &str,
+        hash: &str,
+    ) -> Result<Option<SyncChange>, CommandError> {
+        let key = (kind, id.to_string());
+        if let Some(base) = self.bases.get(&key).filter(|base| base.hash == hash) {
+            return Ok(Some(base.clone()));
+        }
+        for device in self.folder.devices()? {
+            let found = self
+                .folder
+                .read_changes(&device, 0)?
+                .into_iter()
+                .find(|c| c.kind == kind && c.item_id == id && c.hash == hash);
+            if found.is_some() {
+                return Ok(found);
+            }
+        }
+        Ok(None)
+    }
+
+    /// 变更中的笔记版本（标题、正文、标签）
+    fn remote_note(&self, change: &SyncChange) -> Result<Option<NoteVersion>, CommandError> {
+        let Some(record) = change.snapshot.as_ref().and_then(|s| s.notes.first()) else {
+            return Ok(None);
+        };
+        let content = match change.files.iter().find(|f| f.path == record.note.path) {
+            Some(file) => String::from_utf8_lossy(&self.folder.read_blob(&file.blob)?).to_string(),
+            None => String::new(),
+        };
+        Ok(Some(NoteVersion {
+            title: record.note.title.clone(),
+            content,
+            tags: note_tags(change.snapshot.as_ref()),
+        }))
+    }
+
+    fn record_base(&mut self, change: SyncChange) -> Result<(), CommandError> {
+        self.state
+            .db
+            .transaction(|tx| tx.set_sync_record(&change))?;
+        self.bases
+            .insert((change.kind, change.item_id.clone()), change);
+        Ok(())
+    }
+
+    fn report_conflict(
+        &mut self,
+        change: &SyncChange,
+        local: &ContentSnapshot,
+        resolution: SyncResolution,
+        remote_won: bool,
+        copy_id: Option<String>,
+    ) {
+        let title = local
+            .describe(change.kind)
+            .map(|(_, title)| title)
+            .unwrap_or_default();
+        self.report.conflicts.push(SyncConflict {
+            kind: change.kind,
+            item_id: change.item_id.clone(),
+            title,
+            remote_device: change.device_name.clone(),
+            resolution,
+            remote_won,
+            copy_id,
+        });
+    }
+}
+
+/// 笔记参与合并的字段
+struct NoteVersion {
+    title: String,
+    content: String,
+    tags: BTreeSet<String>,
+}
+
+fn local_note(record: &ContentSnapshot) -> Result<Option<NoteVersion>, CommandError> {
+    let Some(note) = record.notes.first() else {
+        return Ok(None);
+    };
+    let content = match fs::read_to_string(&note.note.path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(CommandError::Io(e.to_string())),
+    };
+    Ok(Some(NoteVersion {
+        title: note.note.title.clone(),
+        content,
+        tags: note_tags(Some(record)),
+    }))
+}
+
+fn note_tags(snapshot: Option<&ContentSnapshot>) -> BTreeSet<String> {
+    snapshot
+        .map(|s| {
+            s.tags
+                .iter()
+                .filter(|tag| tag.kind == ContentKind::Note)
+                .map(|tag| tag.tag.clone())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 三方合并单个字段：只有一端修改时取修改后的值，两端修改不同时返回 None
+fn merge_value<'v, T: PartialEq>(base: Option<&T>, local: &'v T, remote: &'v T) -> Option<&'v T> {
+    if local == remote || base == Some(remote) {
+        Some(local)
+    } else if base == Some(local) {
+        Some(remote)
+    } else {
+        None
+    }
+}
+
+/// 三方合并标签集合：任一端删除的标签被删除，任一端新增的标签被保留
+fn merge_tags(
+    base: Option<&BTreeSet<String>>,
+    local: &BTreeSet<String>,
+    remote: &BTreeSet<String>,
+) -> BTreeSet<String> {
+    let empty = BTreeSet::new();
+    let base = base.unwrap_or(&empty);
+    local
+        .union(remote)
+        .filter(|tag| {
+            if base.contains(*tag) {
+                local.contains(*tag) && remote.contains(*tag)
+            } else {
+                true
+            }
+        })
+        .cloned()
+        .collect()
+}
+
+/// 校验另一端变更中的路径：只能是 `projects/{项目 ID}/` 下由普通路径段组成的相对路径
+///
+/// 变更日志来自共享文件夹，不可信；路径不合法的变更整体跳过。
+fn check_change_paths(change: &SyncChange) -> Result<(), String> {
+    let Some(snapshot) = &change.snapshot else {
+        return Ok(());
+    };
+    let Some((project_id, _)) = snapshot.describe(change.kind) else {
+        return Ok(());
+    };
+    let project_dir = Path::new("projects").join(&project_id);
+    if Path::new(&project_id).components().count() != 1 || !is_safe_relative_path(&project_dir) {
+        return Err(format!("无效的项目 ID: {}", project_id));
+    }
+    if let Some(project) = &snapshot.project {
+        if Path::new(&project.path) != project_dir {
+            return Err(format!("无效的项目目录: {}", project.path));
+        }
+    }
+
+    let paths = snapshot
+        .file_paths()
+        .into_iter()
+        .chain(change.files.iter().map(|file| file.path.clone()));
+    for path in paths.filter(|path| !path.is_empty()) {
+        let relative = Path::new(&path);
+        if !is_safe_relative_path(relative)
+            || !relative.starts_with(&project_dir)
+            || relative == project_dir
+        {
+            return Err(format!("不安全的文件路径: {}", path));
+        }
+    }
+    Ok(())
+}
+
+/// 记录版本的内容哈希（记录快照与数据文件内容）
+fn version_hash(snapshot: &ContentSnapshot, files: &[SyncFile]) -> Result<String, CommandError> {
+    let mut input =
+        serde_json::to_string(snapshot).map_err(|e| CommandError::Internal(e.to_string()))?;
+    for file in files {
+        input.push('\n');
+        input.push_str(&file.path);
+        input.push(':');
+        input.push_str(&file.blob);
+    }
+    Ok(hash_bytes(input.as_bytes()))
+}
+
+fn kind_order(kind: ContentKind) -> usize {
+    SYNC_KINDS
+        .iter()
+        .position(|k| *k == kind)
+        .unwrap_or(SYNC_KINDS.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::canvas::save_canvas;
+    use crate::commands::mindmap::save_mindmap;
+    use crate::commands::note::save_note;
+    use crate::commands::project::archive_project;
+    use crate::commands::trash::move_to_trash;
+    use crate::db::Database;
+    use crate::models::{
+        Canvas, CanvasData, MindMap, MindMapData, Note, Presentation, Project, ProjectIcon, Source,
+        SourceType,
+    };
+    use crate::services::FileService;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn create_test_state(temp_dir: &TempDir, name: &str) -> Arc<AppState> {
+        let data_dir = temp_dir.path().join(name);
+        let db = Database::new_in_memory().expect("创建数据库失败");
+        db.set_data_dir(&data_dir).expect("设置数据目录失败");
+        let file_service = FileService::new(data_dir);
+        file_service.init_base_dirs().expect("初始化目录失败");
+
+        let state = Arc::new(AppState::new(db, file_service));
+        save_sync_settings(
+            &state,
+            SyncSettings {
+                folder: None,
+                device_name: name.to_string(),
+            },
+        )
+        .unwrap();
+        state
+    }
+
+    fn create_project(state: &AppState) -> Project {
+        let id = uuid::Uuid::new_v4().to_string();
+        let path = state.file_service.create_project_dir(&id).unwrap();
+        let project = Project {
+            id,
+            name: "同步项目".to_string(),
+            icon: ProjectIcon::default(),
+            workspace: "research".to_string(),
+            is_starred: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            sources_count: 0,
+            path: path.display().to_string(),
+            archived_at: None,
+        };
+        state.db.insert_project(&project).unwrap();
+        project
+    }
+
+    fn note_content(state: &AppState, id: &str) -> String {
+        fs::read_to_string(state.db.get_note(id).unwrap().path).unwrap()
+    }
+
+    /// 修改时间精确到秒，等待足够久使后一次编辑的修改时间更新
+    fn wait_for_next_second() {
+        std::thread::sleep(Duration::from_millis(1100));
+    }
+
+    fn mindmap_data(text: &str) -> MindMapData {
+        serde_json::from_value(serde_json::json!({ "root": { "data": { "text": text } } })).unwrap()
+    }
+
+    fn canvas_data(text: &str) -> CanvasData {
+        CanvasData {
+            elements: vec![serde_json::json!({ "type": "text", "text": text })],
+            app_state: None,
+            files: None,
+        }
+    }
+
+    fn create_note(state: &AppState, project_id: &str, title: &str, content: &str) -> Note {
+        create_notes(
+            state,
+            project_id,
+            vec![(title.to_string(), content.to_string(), false)],
+            &[],
+        )
+        .unwrap()
+        .remove(0)
+    }
+
+    #[test]
+    fn test_sync_creates_updates_and_deletes() {
+        let temp_dir = TempDir::new().unwrap();
+        let shared = temp_dir.path().join("shared");
+        let desktop = create_test_state(&temp_dir, "台式机");
+        let laptop = create_test_state(&temp_dir, "笔记本");
+
+        let project = create_project(&desktop);
+        let note = create_note(&desktop, &project.id, "周报", "本周进展");
+        let data_path = desktop
+            .file_service
+            .project_dir(&project.id)
+            .join("presentations/p1.json");
+        fs::create_dir_all(data_path.parent().unwrap()).unwrap();
+        fs::write(
+            &data_path,
+            serde_json::json!({
+                "slides": [{"id": "s1", "elements": [], "remark": "<p>先讲结论</p>"}]
+            })
+            .to_string(),
+        )
+        .unwrap();
+        desktop
+            .db
+            .insert_presentation(&Presentation {
+                id: uuid::Uuid::new_v4().to_string(),
+                project_id: project.id.clone(),
+                title: "季度汇报".to_string(),
+                data_path: data_path.display().to_string(),
+                thumbnail_path: None,
+                slide_count: 1,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .unwrap();
+        let report = sync_library(&desktop, &shared).unwrap();
+        assert_eq!(report.pushed, 3);
+
+        let report = sync_library(&laptop, &shared).unwrap();
+        assert_eq!(report.pulled, 3);
+        assert!(report.conflicts.is_empty());
+        let synced = laptop.db.get_note(&note.id).unwrap();
+        assert_eq!(synced.title, "周报");
+        assert!(synced
+            .path
+            .starts_with(&laptop.file_service.base_path().display().to_string()));
+        assert_eq!(note_content(&laptop, &note.id), "本周进展");
+        // PPT 搜索文本从同步来的数据文件提取
+        let results = laptop
+            .db
+            .search_presentations("先讲结论", Some(&project.id), 10, false)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        // 应用的变更不会被再次推送
+        assert_eq!(sync_library(&laptop, &shared).unwrap().pushed, 0);
+
+        // 笔记本上修改后同步回台式机
+        save_note(&laptop, &note.id, "本周进展\n\n- 完成同步").unwrap();
+        assert_eq!(sync_library(&laptop, &shared).unwrap().pushed, 1);
+        assert_eq!(sync_library(&desktop, &shared).unwrap().pulled, 1);
+        assert_eq!(note_content(&desktop, &note.id), "本周进展\n\n- 完成同步");
+        let results = desktop
+            .db
+            .search_notes("完成同步", Some(&project.id), 10, false)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+
+        // 删除会同步到另一台设备的回收站
+        move_to_trash(&desktop, ContentKind::Note, &note.id).unwrap();
+        assert_eq!(sync_library(&desktop, &shared).unwrap().pushed, 1);
+        sync_library(&laptop, &shared).unwrap();
+        assert!(laptop.db.get_note(&note.id).is_err());
+        assert_eq!(laptop.db.get_trash_items(None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_concurrent_note_edits_merge_or_copy() {
+        let temp_dir = TempDir::new().unwrap();
+        let shared = temp_dir.path().join("shared");
+        let desktop = create_test_state(&temp_dir, "台式机");
+        let laptop = create_test_state(&temp_dir, "笔记本");
+
+        let project = create_project(&desktop);
+        let note = create_note(&desktop, &project.id, "周报", "原文");
+        sync_library(&desktop, &shared).unwrap();
+        sync_library(&laptop, &shared).unwrap();
+
+        // 一端改标题、一端改正文：自动合并
+        desktop.db.rename_note(&note.id, "周报（定稿）").unwrap();
+        save_note(&laptop, &note.id, "原文\n\n笔记本补充").unwrap();
+        sync_library(&desktop, &shared).unwrap();
+        let report = sync_library(&laptop, &shared).unwrap();
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].resolution, SyncResolution::Merged);
+        assert_eq!(laptop.db.get_note(&note.id).unwrap().title, "周报（定稿）");
+        assert_eq!(note_content(&laptop, &note.id), "原文\n\n笔记本补充");
+
+        // 合并结果同步回台式机后两端一致，正文索引与合并后的正文一致
+        sync_library(&desktop, &shared).unwrap();
+        assert_eq!(desktop.db.get_note(&note.id).unwrap().title, "周报（定稿）");
+        assert_eq!(note_content(&desktop, &note.id), "原文\n\n笔记本补充");
+        for state in [&desktop, &laptop] {
+            let results = state
+                .db
+                .search_notes("笔记本补充", Some(&project.id), 10, false)
+                .unwrap();
+            assert_eq!(results.len(), 1);
+        }
+
+        // 两端同时修改正文：保留较新的版本，另一版本成为冲突副本
+        wait_for_next_second();
+        save_note(&laptop, &note.id, "笔记本版本").unwrap();
+        wait_for_next_second();
+        save_note(&desktop, &note.id, "台式机版本").unwrap();
+        sync_library(&desktop, &shared).unwrap();
+        let report = sync_library(&laptop, &shared).unwrap();
+        assert_eq!(report.conflicts.len(), 1);
+        let conflict = &report.conflicts[0];
+        assert_eq!(conflict.resolution, SyncResolution::ConflictCopy);
+        assert!(conflict.remote_won);
+        assert_eq!(note_content(&laptop, &note.id), "台式机版本");
+        let copy_id = conflict.copy_id.clone().unwrap();
+        assert_eq!(note_content(&laptop, &copy_id), "笔记本版本");
+        assert!(laptop
+            .db
+            .get_note(&copy_id)
+            .unwrap()
+            .title
+            .contains("冲突副本 · 笔记本"));
+
+        // 冲突副本同步到另一台设备
+        sync_library(&laptop, &shared).unwrap();
+        sync_library(&desktop, &shared).unwrap();
+        assert_eq!(note_content(&desktop, &note.id), "台式机版本");
+        assert_eq!(note_content(&desktop, &copy_id), "笔记本版本");
+    }
+
+    #[test]
+    fn test_concurrent_mindmap_and_canvas_edits_are_detected() {
+        let temp_dir = TempDir::new().unwrap();
+        let shared = temp_dir.path().join("shared");
+        let desktop = create_test_state(&temp_dir, "台式机");
+        let laptop = create_test_state(&temp_dir, "笔记本");
+
+        let project = create_project(&desktop);
+        let mindmap = MindMap {
+            id: uuid::Uuid::new_v4().to_string(),
+            project_id: project.id.clone(),
+            title: "导图".to_string(),
+            theme: "default".to_string(),
+            layout: "logicalStructure".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        desktop
+            .db
+            .insert_mindmap(&mindmap, "{\"root\":{}}")
+            .unwrap();
+        let canvas_path = desktop
+            .file_service
+            .project_dir(&project.id)
+            .join("canvas/c1.json");
+        fs::write(&canvas_path, "{}").unwrap();
+        let canvas = Canvas {
+            id: uuid::Uuid::new_v4().to_string(),
+            project_id: project.id.clone(),
+            title: "画布".to_string(),
+            path: canvas_path.display().to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        desktop.db.insert_canvas(&canvas).unwrap();
+        sync_library(&desktop, &shared).unwrap();
+        sync_library(&laptop, &shared).unwrap();
+
+        // 台式机先修改、笔记本后修改：较新的笔记本版本保留
+        wait_for_next_second();
+        save_mindmap(&desktop, &mindmap.id, &mindmap_data("台式机")).unwrap();
+        save_canvas(&desktop, &canvas.id, &canvas_data("台式机")).unwrap();
+        wait_for_next_second();
+        save_mindmap(&laptop, &mindmap.id, &mindmap_data("笔记本")).unwrap();
+        save_canvas(&laptop, &canvas.id, &canvas_data("笔记本")).unwrap();
+
+        sync_library(&desktop, &shared).unwrap();
+        let report = sync_library(&laptop, &shared).unwrap();
+        let kinds: HashSet<ContentKind> = report.conflicts.iter().map(|c| c.kind).collect();
+        assert!(kinds.contains(&ContentKind::Mindmap));
+        assert!(kinds.contains(&ContentKind::Canvas));
+        assert!(report
+            .conflicts
+            .iter()
+            .all(|c| c.resolution == SyncResolution::LastWriterWins && !c.remote_won));
+
+        // 较新的笔记本版本同步回台式机
+        sync_library(&laptop, &shared).unwrap();
+        sync_library(&desktop, &shared).unwrap();
+        let canvas_json = fs::read_to_string(&canvas_path).unwrap();
+        assert!(canvas_json.contains("笔记本"));
+        let mindmap_json = desktop.db.get_mindmap_data(&mindmap.id).unwrap();
+        assert!(mindmap_json.contains("笔记本"));
+    }
+
+    #[test]
+    fn test_sync_into_archived_project() {
+        let temp_dir = TempDir::new().unwrap();
+        let shared = temp_dir.path().join("shared");
+        let desktop = create_test_state(&temp_dir, "台式机");
+        let laptop = create_test_state(&temp_dir, "笔记本");
+
+        let project = create_project(&desktop);
+        let note = create_note(&desktop, &project.id, "周报", "本周进展");
+        let mut sources = Vec::new();
+        for name in ["a.md", "b.md"] {
+            let path = desktop.file_service.get_sources_dir(&project.id).join(name);
+            fs::write(&path, name).unwrap();
+            let source = Source {
+                id: uuid::Uuid::new_v4().to_string(),
+                project_id: project.id.clone(),
+                name: name.to_string(),
+                source_type: SourceType::Markdown,
+                path: path.display().to_string(),
+                size: 4,
+                mime_type: "text/markdown".to_string(),
+                thumbnail_path: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            };
+            desktop
+                .db
+                .insert_source_with_content(&source, Some(name))
+                .unwrap();
+            sources.push(source);
+        }
+        sync_library(&desktop, &shared).unwrap();
+        sync_library(&laptop, &shared).unwrap();
+
+        // 笔记本上归档并压缩来源，台式机随后修改笔记、删除来源
+        archive_project(&laptop, &project.id, true).unwrap();
+        assert!(laptop
+            .file_service
+            .archived_sources_path(&project.id)
+            .exists());
+        save_note(&desktop, &note.id, "归档后的修改").unwrap();
+        move_to_trash(&desktop, ContentKind::Source, &sources[0].id).unwrap();
+        sync_library(&desktop, &shared).unwrap();
+
+        // 归档项目照常应用另一设备的修改与删除，压缩包先解压，后续同步不受影响
+        let report = sync_library(&laptop, &shared).unwrap();
+        assert!(report.errors.is_empty());
+        assert_eq!(note_content(&laptop, &note.id), "归档后的修改");
+        assert!(laptop.db.get_source(&sources[0].id).is_err());
+        assert!(!laptop
+            .file_service
+            .archived_sources_path(&project.id)
+            .exists());
+        let kept = laptop.db.get_source(&sources[1].id).unwrap();
+        assert_eq!(fs::read_to_string(&kept.path).unwrap(), "b.md");
+        assert!(sync_library(&laptop, &shared).is_ok());
+        // 归档状态随项目同步到另一设备
+        sync_library(&desktop, &shared).unwrap();
+        assert!(desktop
+            .db
+            .get_project(&project.id)
+            .unwrap()
+            .archived_at
+            .is_some());
+    }
+
+    #[test]
+    fn test_sync_skips_changes_with_unsafe_paths() {
+        let temp_dir = TempDir::new().unwrap();
+        let shared = temp_dir.path().join("shared");
+        let desktop = create_test_state(&temp_dir, "台式机");
+        let laptop = create_test_state(&temp_dir, "笔记本");
+
+        let project = create_project(&desktop);
+        let note = create_note(&desktop, &project.id, "周报", "本周进展");
+        sync_library(&desktop, &shared).unwrap();
+
+        // 伪造一台设备，发布路径越出项目目录的笔记变更
+        let folder = SyncFolder::open(&shared).unwrap();
+        let device = folder.devices().unwrap().remove(0);
+        let original = folder
+            .read_changes(&device, 0)
+            .unwrap()
+            .into_iter()
+            .find(|change| change.kind == ContentKind::Note)
+            .unwrap();
+        let outside = temp_dir.path().join("outside.md").display().to_string();
+        let unsafe_paths = [
+            "../../outside.md".to_string(),
+            format!("projects/{}/../../outside.md", project.id),
+            outside.clone(),
+            format!("projects/{}", project.id),
+        ];
+        let forged: Vec<SyncChange> = unsafe_paths
+            .iter()
+            .enumerate()
+            .map(|(index, path)| {
+                let mut change = original.clone();
+                change.seq = index as u64 + 1;
+                change.device_id = "forged".to_string();
+                change.item_id = uuid::Uuid::new_v4().to_string();
+                let snapshot = change.snapshot.as_mut().unwrap();
+                snapshot.notes[0].note.id = change.item_id.clone();
+                snapshot.notes[0].note.path = path.clone();
+                change.files[0].path = path.clone();
+                change
+            })
+            .collect();
+        folder.append_changes("forged", &forged).unwrap();
+
+        let report = sync_library(&laptop, &shared).unwrap();
+        assert_eq!(report.errors.len(), unsafe_paths.len());
+        assert_eq!(
+            laptop.db.get_notes_by_project(&project.id).unwrap().len(),
+            1
+        );
+        assert_eq!(note_content(&laptop, &note.id), "本周进展");
+        assert!(!Path::new(&outside).exists());
+        assert!(!temp_dir.path().join("笔记本/../outside.md").exists());
+
+        // 跳过的变更不会重复报告
+        assert!(sync_library(&laptop, &shared).unwrap().errors.is_empty());
+    }
+
+    #[test]
+    fn test_merge_helpers() {
+        assert_eq!(merge_value(Some(&1), &1, &2), Some(&2));
+        assert_eq!(merge_value(Some(&1), &3, &1), Some(&3));
+        assert_eq!(merge_value(Some(&1), &3, &3), Some(&3));
+        assert_eq!(merge_value(Some(&1), &2, &3), None);
+        assert_eq!(merge_value(None, &2, &3), None);
+
+        let set = |tags: &[&str]| tags.iter().map(|t| t.to_string()).collect::<BTreeSet<_>>();
+        let merged = merge_tags(
+            Some(&set(&["a", "b"])),
+            &set(&["a", "c"]),
+            &set(&["a", "b", "d"]),
+        );
+        assert_eq!(merged, set(&["a", "c", "d"]));
+    }
+}
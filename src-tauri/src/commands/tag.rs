@@ -0,0 +1,43 @@
+//! Tag Commands
+//!
+//! 提供来源与笔记标签查询相关的 Tauri Commands（批量打标签见 batch 模块）
+
+use crate::commands::project::{AppState, CommandError};
+use crate::models::ContentKind;
+use std::sync::Arc;
+use tauri::State;
+
+/// 获取条目的标签
+#[tauri::command]
+pub fn tag_get(
+    kind: ContentKind,
+    id: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<String>, CommandError> {
+    let tags = state.db.get_tags(kind, &id)?;
+    Ok(tags)
+}
+
+/// 获取项目中使用过的全部标签
+#[tauri::command]
+pub fn tag_list(
+    project_id: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<String>, CommandError> {
+    // 验证项目存在
+    state.db.get_project(&project_id)?;
+
+    let tags = state.db.get_project_tags(&project_id)?;
+    Ok(tags)
+}
+
+/// 获取带有指定标签的条目 ID
+#[tauri::command]
+pub fn tag_find(
+    kind: ContentKind,
+    tag: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<String>, CommandError> {
+    let ids = state.db.get_tagged_item_ids(kind, &tag)?;
+    Ok(ids)
+}
@@ -0,0 +1,353 @@
+//! Project Template Commands
+//!
+//! 提供项目模板相关的 Tauri Commands。模板以项目归档的形式保存在资料库的 templates/ 目录下，
+//! 从模板创建项目等同于导入归档（全部条目分配新 ID）。
+
+use crate::commands::archive::{import_archive_with, write_snapshot_archive};
+use crate::commands::project::{AppState, CommandError};
+use crate::models::{ContentKind, Project, ProjectTemplate};
+use crate::services::ArchiveService;
+use chrono::Utc;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::State;
+
+/// 获取资料库中的项目模板（按名称排序）
+#[tauri::command]
+pub fn template_list(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<ProjectTemplate>, CommandError> {
+    list_templates(&state)
+}
+
+/// 将项目保存为模板
+///
+/// `include_sources` 为 false 时模板不含来源，只包含笔记、画布、思维导图、PPT 与对话（去掉来源引用）。
+#[tauri::command]
+pub fn template_save(
+    project_id: String,
+    name: String,
+    include_sources: Option<bool>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<ProjectTemplate, CommandError> {
+    save_template(&state, &project_id, &name, include_sources.unwrap_or(false))
+}
+
+/// 删除项目模板
+#[tauri::command]
+pub fn template_delete(id: String, state: State<'_, Arc<AppState>>) -> Result<(), CommandError> {
+    delete_template(&state, &id)
+}
+
+/// 从模板创建项目
+#[tauri::command]
+pub fn project_create_from_template(
+    template_id: String,
+    name: String,
+    workspace: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Project, CommandError> {
+    create_from_template(&state, &template_id, &name, &workspace)
+}
+
+fn list_templates(state: &AppState) -> Result<Vec<ProjectTemplate>, CommandError> {
+    let dir = state.file_service.templates_dir();
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut templates = Vec::new();
+    for entry in fs::read_dir(&dir).map_err(|e| CommandError::Io(e.to_string()))? {
+        let path = entry.map_err(|e| CommandError::Io(e.to_string()))?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("zip") {
+            continue;
+        }
+        let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        match read_template(id, &path) {
+            Ok(template) => templates.push(template),
+            Err(e) => eprintln!(
+                "[template_list] 跳过无法读取的模板 {}: {}",
+                path.display(),
+                e
+            ),
+        }
+    }
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(templates)
+}
+
+fn read_template(id: &str, path: &Path) -> Result<ProjectTemplate, CommandError> {
+    let manifest =
+        ArchiveService::read_manifest(path).map_err(|e| CommandError::Validation(e.to_string()))?;
+    let snapshot = &manifest.snapshot;
+    Ok(ProjectTemplate {
+        id: id.to_string(),
+        name: snapshot
+            .project
+            .as_ref()
+            .map(|p| p.name.clone())
+            .unwrap_or_default(),
+        created_at: manifest.exported_at,
+        sources_count: snapshot.sources.len(),
+        notes_count: snapshot.notes.len(),
+        canvases_count: snapshot.canvases.len(),
+        mindmaps_count: snapshot.mindmaps.len(),
+        presentations_count: snapshot.presentations.len(),
+    })
+}
+
+/// 模板文件路径（模板 ID 只能由字母、数字与 '-' 组成）
+fn template_path(state: &AppState, id: &str) -> Result<PathBuf, CommandError> {
+    let valid = !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    let path = state
+        .file_service
+        .templates_dir()
+        .join(format!("{}.zip", id));
+    if !valid || !path.is_file() {
+        return Err(CommandError::NotFound(format!("模板 {} 不存在", id)));
+    }
+    Ok(path)
+}
+
+fn delete_template(state: &AppState, id: &str) -> Result<(), CommandError> {
+    let path = template_path(state, id)?;
+    fs::remove_file(&path).map_err(|e| CommandError::Io(e.to_string()))?;
+    Ok(())
+}
+
+fn save_template(
+    state: &AppState,
+    project_id: &str,
+    name: &str,
+    include_sources: bool,
+) -> Result<ProjectTemplate, CommandError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(CommandError::Validation("模板名称不能为空".to_string()));
+    }
+    if list_templates(state)?.iter().any(|t| t.name == name) {
+        return Err(CommandError::Validation(format!("模板 {} 已存在", name)));
+    }
+
+    let mut snapshot = state.db.get_snapshot(ContentKind::Project, project_id)?;
+    if !include_sources {
+        snapshot.remove_sources();
+    }
+    if let Some(project) = &mut snapshot.project {
+        project.name = name.to_string();
+        project.is_starred = false;
+        project.archived_at = None;
+    }
+
+    let dir = state.file_service.templates_dir();
+    fs::create_dir_all(&dir).map_err(|e| CommandError::Io(e.to_string()))?;
+    let id = uuid::Uuid::new_v4().to_string();
+    let path = dir.join(format!("{}.zip", id));
+    write_snapshot_archive(state, project_id, snapshot, &path, true)?;
+    read_template(&id, &path)
+}
+
+fn create_from_template(
+    state: &AppState,
+    template_id: &str,
+    name: &str,
+    workspace: &str,
+) -> Result<Project, CommandError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(CommandError::Validation("项目名称不能为空".to_string()));
+    }
+    if state.db.project_name_exists(name)? {
+        return Err(CommandError::NameExists(name.to_string()));
+    }
+    state.db.get_workspace(workspace)?;
+    let path = template_path(state, template_id)?;
+
+    import_archive_with(state, &path, |project| {
+        project.name = name.to_string();
+        project.workspace = workspace.to_string();
+        project.is_starred = false;
+        project.archived_at = None;
+        project.created_at = Utc::now();
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::models::{MindMap, Note, OutputType, ProjectIcon, Source, SourceType};
+    use crate::services::FileService;
+    use tempfile::TempDir;
+
+    fn create_test_state() -> (Arc<AppState>, TempDir) {
+        let temp_dir = TempDir::new().expect("创建临时目录失败");
+        let db = Database::new_in_memory().expect("创建数据库失败");
+        let file_service = FileService::new(temp_dir.path().join("library"));
+        file_service.init_base_dirs().expect("初始化目录失败");
+
+        let state = Arc::new(AppState::new(db, file_service));
+        (state, temp_dir)
+    }
+
+    /// 创建包含一个来源、一篇笔记与一个思维导图的项目
+    fn create_skeleton_project(state: &AppState) -> Project {
+        let id = uuid::Uuid::new_v4().to_string();
+        let project_path = state.file_service.create_project_dir(&id).unwrap();
+        let now = Utc::now();
+        let project = Project {
+            id: id.clone(),
+            name: "客户项目".to_string(),
+            icon: ProjectIcon::default(),
+            workspace: "research".to_string(),
+            is_starred: true,
+            created_at: now,
+            updated_at: now,
+            sources_count: 1,
+            path: project_path.display().to_string(),
+            archived_at: None,
+        };
+        state.db.insert_project(&project).unwrap();
+
+        let source_path = state.file_service.get_sources_dir(&id).join("brief.md");
+        fs::write(&source_path, "客户简报").unwrap();
+        let source = Source {
+            id: uuid::Uuid::new_v4().to_string(),
+            project_id: id.clone(),
+            name: "brief.md".to_string(),
+            source_type: SourceType::Markdown,
+            path: source_path.display().to_string(),
+            size: 12,
+            mime_type: "text/markdown".to_string(),
+            thumbnail_path: None,
+            created_at: now,
+            updated_at: now,
+        };
+        state
+            .db
+            .insert_source_with_content(&source, Some("客户简报"))
+            .unwrap();
+
+        let note_id = uuid::Uuid::new_v4().to_string();
+        let note_path = state
+            .file_service
+            .get_notes_dir(&id)
+            .join(format!("{}.md", note_id));
+        fs::write(&note_path, "# 访谈提纲").unwrap();
+        state
+            .db
+            .insert_note(&Note {
+                id: note_id,
+                project_id: id.clone(),
+                title: "访谈提纲".to_string(),
+                path: note_path.display().to_string(),
+                output_type: OutputType::Note,
+                title_locked: false,
+                created_at: now,
+                updated_at: now,
+            })
+            .unwrap();
+
+        state
+            .db
+            .insert_mindmap(
+                &MindMap {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    project_id: id.clone(),
+                    title: "需求导图".to_string(),
+                    theme: "default".to_string(),
+                    layout: "logicalStructure".to_string(),
+                    created_at: now,
+                    updated_at: now,
+                },
+                "{\"root\":{}}",
+            )
+            .unwrap();
+
+        project
+    }
+
+    #[test]
+    fn test_save_and_list_templates() {
+        let (state, _temp) = create_test_state();
+        let project = create_skeleton_project(&state);
+        assert!(list_templates(&state).unwrap().is_empty());
+
+        let template = save_template(&state, &project.id, "咨询项目骨架", false).unwrap();
+        assert_eq!(template.name, "咨询项目骨架");
+        assert_eq!(template.sources_count, 0);
+        assert_eq!(template.notes_count, 1);
+        assert_eq!(template.mindmaps_count, 1);
+
+        let with_sources = save_template(&state, &project.id, "含资料", true).unwrap();
+        assert_eq!(with_sources.sources_count, 1);
+
+        let names: Vec<String> = list_templates(&state)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(names, vec!["含资料", "咨询项目骨架"]);
+
+        // 模板名称不能重复
+        assert!(save_template(&state, &project.id, "含资料", false).is_err());
+        assert!(save_template(&state, &project.id, " ", false).is_err());
+    }
+
+    #[test]
+    fn test_create_project_from_template() {
+        let (state, _temp) = create_test_state();
+        let original = create_skeleton_project(&state);
+        let template = save_template(&state, &original.id, "咨询项目骨架", false).unwrap();
+
+        let project = create_from_template(&state, &template.id, "新客户", "personal").unwrap();
+        assert_ne!(project.id, original.id);
+        assert_eq!(project.name, "新客户");
+        assert_eq!(project.workspace, "personal");
+        assert!(!project.is_starred);
+        assert_eq!(project.sources_count, 0);
+
+        assert!(state
+            .db
+            .get_sources_by_project(&project.id)
+            .unwrap()
+            .is_empty());
+        let notes = state.db.get_notes_by_project(&project.id).unwrap();
+        assert_eq!(notes.len(), 1);
+        assert!(notes[0].path.starts_with(&project.path));
+        assert_eq!(fs::read_to_string(&notes[0].path).unwrap(), "# 访谈提纲");
+        assert_eq!(
+            state.db.get_mindmaps_by_project(&project.id).unwrap().len(),
+            1
+        );
+
+        // 原项目不受影响
+        assert_eq!(
+            state.db.get_notes_by_project(&original.id).unwrap().len(),
+            1
+        );
+
+        // 名称冲突、工作空间或模板不存在
+        assert!(matches!(
+            create_from_template(&state, &template.id, "新客户", "personal"),
+            Err(CommandError::NameExists(_))
+        ));
+        assert!(create_from_template(&state, &template.id, "另一个", "missing").is_err());
+        assert!(create_from_template(&state, "../secret", "另一个", "personal").is_err());
+    }
+
+    #[test]
+    fn test_delete_template() {
+        let (state, _temp) = create_test_state();
+        let project = create_skeleton_project(&state);
+        let template = save_template(&state, &project.id, "骨架", false).unwrap();
+
+        delete_template(&state, &template.id).unwrap();
+        assert!(list_templates(&state).unwrap().is_empty());
+        assert!(delete_template(&state, &template.id).is_err());
+    }
+}
@@ -0,0 +1,607 @@
+//! Trash Commands
+//!
+//! 提供回收站相关的 Tauri Commands：删除的条目先移入回收站，可恢复或彻底删除
+
+use crate::commands::project::{
+    ensure_item_writable, ensure_project_writable, unique_project_name, AppState, CommandError,
+};
+use crate::db::{DbError, DbTx};
+use crate::models::{ContentKind, ContentSnapshot, TrashItem, TrashedFile};
+use chrono::{Duration, Utc};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::State;
+
+/// 回收站保留天数的设置键
+const TRASH_RETENTION_KEY: &str = "trash.retention_days";
+
+/// 默认回收站保留天数
+pub const DEFAULT_TRASH_RETENTION_DAYS: u32 = 30;
+
+/// 回收站目录中的元数据文件名
+const TRASH_META_FILE: &str = "meta.json";
+
+/// 回收站目录中的元数据（与数据库记录一致，便于手动恢复）
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TrashMeta<'a> {
+    item: &'a TrashItem,
+    snapshot: &'a ContentSnapshot,
+    files: &'a [TrashedFile],
+}
+
+/// 获取回收站条目列表
+#[tauri::command]
+pub fn trash_list(
+    project_id: Option<String>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<TrashItem>, CommandError> {
+    let items = state.db.get_trash_items(project_id.as_deref())?;
+    Ok(items)
+}
+
+/// 从回收站恢复条目
+#[tauri::command]
+pub fn trash_restore(id: String, state: State<'_, Arc<AppState>>) -> Result<TrashItem, CommandError> {
+    restore_from_trash(&state, &id)
+}
+
+/// 彻底删除回收站条目
+#[tauri::command]
+pub fn trash_purge(id: String, state: State<'_, Arc<AppState>>) -> Result<(), CommandError> {
+    let (item, _, _) = state.db.get_trash_entry(&id)?;
+    purge_trash_item(&state, &item)
+}
+
+/// 清空回收站（指定项目时只清空该项目内的条目）
+#[tauri::command]
+pub fn trash_empty(
+    project_id: Option<String>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<u32, CommandError> {
+    let items = state.db.get_trash_items(project_id.as_deref())?;
+    let mut purged = 0;
+    for item in &items {
+        purge_trash_item(&state, item)?;
+        purged += 1;
+    }
+    Ok(purged)
+}
+
+/// 彻底删除超过保留期限的回收站条目
+#[tauri::command]
+pub fn trash_purge_expired(state: State<'_, Arc<AppState>>) -> Result<u32, CommandError> {
+    purge_expired_trash(&state)
+}
+
+/// 获取回收站保留天数
+#[tauri::command]
+pub fn trash_get_retention_days(state: State<'_, Arc<AppState>>) -> Result<u32, CommandError> {
+    trash_retention_days(&state)
+}
+
+/// 设置回收站保留天数（0 表示不自动清理）
+#[tauri::command]
+pub fn trash_set_retention_days(
+    days: u32,
+    state: State<'_, Arc<AppState>>,
+) -> Result<(), CommandError> {
+    state
+        .db
+        .set_setting(TRASH_RETENTION_KEY, &days.to_string())?;
+    Ok(())
+}
+
+/// 读取回收站保留天数
+pub fn trash_retention_days(state: &AppState) -> Result<u32, CommandError> {
+    let days = state
+        .db
+        .get_setting(TRASH_RETENTION_KEY)?
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS);
+    Ok(days)
+}
+
+/// 将条目移入回收站（供各删除命令调用）
+///
+/// 先把数据文件移到回收站目录，再在一个事务中删除记录并写入回收站条目；
+/// 事务失败时把文件移回原处。
+pub(crate) fn move_to_trash(
+    state: &AppState,
+    kind: ContentKind,
+    id: &str,
+) -> Result<TrashItem, CommandError> {
+    commit_trash(state, prepare_trash(state, kind, id)?)
+}
+
+/// 将条目移入回收站，不检查所属项目是否归档
+///
+/// 同步应用其他设备的删除时使用：归档只限制本机的编辑，同步来的删除照常生效。
+pub(crate) fn move_to_trash_unchecked(
+    state: &AppState,
+    kind: ContentKind,
+    id: &str,
+) -> Result<TrashItem, CommandError> {
+    commit_trash(state, stage_trash(state, kind, id)?)
+}
+
+fn commit_trash(state: &AppState, pending: PendingTrash) -> Result<TrashItem, CommandError> {
+    if let Err(e) = state.db.transaction(|tx| pending.apply(tx)) {
+        pending.rollback(state);
+        return Err(e.into());
+    }
+    Ok(pending.item)
+}
+
+/// 文件已移入回收站、尚未写入数据库的回收站条目
+pub(crate) struct PendingTrash {
+    pub item: TrashItem,
+    snapshot: ContentSnapshot,
+    files: Vec<TrashedFile>,
+}
+
+impl PendingTrash {
+    /// 在事务中删除原记录并写入回收站条目
+    pub(crate) fn apply(&self, tx: &DbTx) -> Result<(), DbError> {
+        tx.delete_snapshot(&self.snapshot)?;
+        if self.item.kind == ContentKind::Source {
+            tx.update_project_sources_count(&self.item.project_id, -1)?;
+        }
+        tx.insert_trash_item(&self.item, &self.snapshot, &self.files)
+    }
+
+    /// 撤销：把文件移回原处并删除回收站目录
+    pub(crate) fn rollback(&self, state: &AppState) {
+        move_files_back(state, &self.files);
+        let _ = fs::remove_dir_all(&self.item.trash_path);
+    }
+}
+
+/// 把条目的数据文件移到回收站目录，返回待写入数据库的回收站条目
+///
+/// 归档项目可以整体删除，但不能删除其中的条目。
+pub(crate) fn prepare_trash(
+    state: &AppState,
+    kind: ContentKind,
+    id: &str,
+) -> Result<PendingTrash, CommandError> {
+    if kind != ContentKind::Project {
+        ensure_item_writable(state, kind, id)?;
+    }
+    stage_trash(state, kind, id)
+}
+
+/// 同 `prepare_trash`，但不检查所属项目是否归档
+fn stage_trash(
+    state: &AppState,
+    kind: ContentKind,
+    id: &str,
+) -> Result<PendingTrash, CommandError> {
+    let snapshot = state.db.get_snapshot(kind, id)?;
+    let (project_id, title) = snapshot
+        .describe(kind)
+        .ok_or_else(|| CommandError::NotFound(id.to_string()))?;
+
+    let trash_id = uuid::Uuid::new_v4().to_string();
+    let trash_dir = if kind == ContentKind::Project {
+        state.file_service.library_trash_dir().join(&trash_id)
+    } else {
+        state.file_service.project_trash_dir(&project_id).join(&trash_id)
+    };
+    fs::create_dir_all(&trash_dir).map_err(|e| CommandError::Io(e.to_string()))?;
+
+    // 需要移动的文件：整个项目目录，或条目引用的数据文件
+    let originals: Vec<PathBuf> = if kind == ContentKind::Project {
+        vec![state.file_service.project_dir(&project_id)]
+    } else {
+        snapshot.file_paths().into_iter().map(PathBuf::from).collect()
+    };
+
+    let mut files = Vec::new();
+    for (index, original) in originals.iter().enumerate() {
+        if !original.exists() {
+            continue;
+        }
+        let file_name = original
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let trashed = trash_dir.join("files").join(format!("{}_{}", index, file_name));
+        if let Err(e) = state.file_service.move_path(original, &trashed) {
+            move_files_back(state, &files);
+            let _ = fs::remove_dir_all(&trash_dir);
+            return Err(e.into());
+        }
+        files.push(TrashedFile {
+            original: original.display().to_string(),
+            trashed: trashed.display().to_string(),
+        });
+    }
+
+    let pending = PendingTrash {
+        item: TrashItem {
+            id: trash_id,
+            project_id,
+            kind,
+            item_id: id.to_string(),
+            title,
+            trash_path: trash_dir.display().to_string(),
+            deleted_at: Utc::now(),
+        },
+        snapshot,
+        files,
+    };
+
+    if let Err(e) = write_trash_meta(
+        state,
+        &trash_dir,
+        &pending.item,
+        &pending.snapshot,
+        &pending.files,
+    ) {
+        pending.rollback(state);
+        return Err(e);
+    }
+
+    Ok(pending)
+}
+
+/// 从回收站恢复条目
+fn restore_from_trash(state: &AppState, id: &str) -> Result<TrashItem, CommandError> {
+    let (item, mut snapshot, files) = state.db.get_trash_entry(id)?;
+
+    if item.kind == ContentKind::Project {
+        // 恢复的项目与现有项目重名时追加后缀
+        if let Some(project) = snapshot.project.as_mut() {
+            project.name = unique_project_name(state, &project.name, "恢复")?;
+        }
+    } else if state.db.get_project(&item.project_id).is_err() {
+        return Err(CommandError::Validation(
+            "所属项目已被删除，请先恢复项目".to_string(),
+        ));
+    } else {
+        ensure_project_writable(state, &item.project_id)?;
+    }
+
+    // 原位置已有文件时不覆盖
+    if let Some(conflict) = files.iter().find(|f| Path::new(&f.original).exists()) {
+        return Err(CommandError::Validation(format!(
+            "原位置已存在文件: {}",
+            conflict.original
+        )));
+    }
+
+    // 文件移回原位置
+    let mut restored = Vec::new();
+    for file in &files {
+        if let Err(e) = state
+            .file_service
+            .move_path(Path::new(&file.trashed), Path::new(&file.original))
+        {
+            move_files_to_trash(state, &restored);
+            return Err(e.into());
+        }
+        restored.push(file.clone());
+    }
+
+    let result = state.db.transaction(|tx| -> Result<(), CommandError> {
+        tx.restore_snapshot(&snapshot)?;
+        if item.kind == ContentKind::Source {
+            tx.update_project_sources_count(&item.project_id, 1)?;
+        }
+        tx.delete_trash_item(&item.id)?;
+        Ok(())
+    });
+
+    if let Err(e) = result {
+        move_files_to_trash(state, &restored);
+        return Err(e);
+    }
+
+    let _ = fs::remove_dir_all(&item.trash_path);
+    Ok(item)
+}
+
+/// 彻底删除回收站条目（项目条目会连同其内部的回收站条目一起删除）
+fn purge_trash_item(state: &AppState, item: &TrashItem) -> Result<(), CommandError> {
+    state.db.transaction(|tx| -> Result<(), CommandError> {
+        tx.delete_trash_item(&item.id)?;
+        if item.kind == ContentKind::Project {
+            tx.delete_trash_items_by_project(&item.project_id)?;
+        }
+        Ok(())
+    })?;
+
+    let trash_dir = Path::new(&item.trash_path);
+    if trash_dir.exists() {
+        fs::remove_dir_all(trash_dir).map_err(|e| CommandError::Io(e.to_string()))?;
+    }
+    Ok(())
+}
+
+/// 彻底删除超过保留期限的回收站条目，返回删除数量
+pub fn purge_expired_trash(state: &AppState) -> Result<u32, CommandError> {
+    let days = trash_retention_days(state)?;
+    if days == 0 {
+        return Ok(0);
+    }
+
+    let cutoff = Utc::now() - Duration::days(days as i64);
+    let mut purged = 0;
+    for item in state.db.get_all_trash_items()? {
+        if item.deleted_at >= cutoff {
+            continue;
+        }
+        // 已删除项目内部的条目随项目一起清理
+        if item.kind != ContentKind::Project && state.db.get_project(&item.project_id).is_err() {
+            continue;
+        }
+        // 项目条目清理时会连带删除内部条目，这里跳过已不存在的条目
+        match purge_trash_item(state, &item) {
+            Ok(()) => purged += 1,
+            Err(CommandError::Database(DbError::NotFound(_))) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(purged)
+}
+
+fn write_trash_meta(
+    state: &AppState,
+    trash_dir: &Path,
+    item: &TrashItem,
+    snapshot: &ContentSnapshot,
+    files: &[TrashedFile],
+) -> Result<(), CommandError> {
+    let meta = TrashMeta {
+        item,
+        snapshot,
+        files,
+    };
+    let json = serde_json::to_string_pretty(&meta)
+        .map_err(|e| CommandError::Internal(format!("序列化回收站元数据失败: {}", e)))?;
+    state
+        .file_service
+        .write_atomic(&trash_dir.join(TRASH_META_FILE), json.as_bytes())?;
+    Ok(())
+}
+
+/// 将已移入回收站的文件移回原处（失败回滚用）
+fn move_files_back(state: &AppState, files: &[TrashedFile]) {
+    for file in files {
+        let _ = state
+            .file_service
+            .move_path(Path::new(&file.trashed), Path::new(&file.original));
+    }
+}
+
+/// 将已恢复的文件重新移回回收站（失败回滚用）
+fn move_files_to_trash(state: &AppState, files: &[TrashedFile]) {
+    for file in files {
+        let _ = state
+            .file_service
+            .move_path(Path::new(&file.original), Path::new(&file.trashed));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::models::{Note, OutputType, Project, ProjectIcon, Source, SourceType};
+    use crate::services::{embed_text, FileService};
+    use tempfile::TempDir;
+
+    fn create_test_state() -> (Arc<AppState>, TempDir) {
+        let temp_dir = TempDir::new().expect("创建临时目录失败");
+        let db = Database::new_in_memory().expect("创建数据库失败");
+        let file_service = FileService::new(temp_dir.path().to_path_buf());
+        file_service.init_base_dirs().expect("初始化目录失败");
+
+        let state = Arc::new(AppState::new(db, file_service));
+        (state, temp_dir)
+    }
+
+    fn create_test_project(state: &Arc<AppState>, name: &str) -> Project {
+        let id = uuid::Uuid::new_v4().to_string();
+        let project_path = state.file_service.create_project_dir(&id).unwrap();
+        let project = Project {
+            id,
+            name: name.to_string(),
+            icon: ProjectIcon::default(),
+            workspace: "research".to_string(),
+            is_starred: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            sources_count: 0,
+            path: project_path.display().to_string(),
+            archived_at: None,
+        };
+        state.db.insert_project(&project).unwrap();
+        project
+    }
+
+    fn create_test_note(state: &Arc<AppState>, project: &Project, content: &str) -> Note {
+        let id = uuid::Uuid::new_v4().to_string();
+        let path = state.file_service.get_notes_dir(&project.id).join(format!("{}.md", id));
+        fs::write(&path, content).unwrap();
+        let note = Note {
+            id,
+            project_id: project.id.clone(),
+            title: "测试笔记".to_string(),
+            path: path.display().to_string(),
+            output_type: OutputType::Note,
+            title_locked: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        state.db.insert_note(&note).unwrap();
+        state
+            .db
+            .upsert_note_embedding(&note.id, &project.id, &embed_text(content))
+            .unwrap();
+        note
+    }
+
+    fn create_test_source(state: &Arc<AppState>, project: &Project) -> Source {
+        let id = uuid::Uuid::new_v4().to_string();
+        let path = state.file_service.get_sources_dir(&project.id).join(format!("{}.md", id));
+        fs::write(&path, "来源内容").unwrap();
+        let source = Source {
+            id,
+            project_id: project.id.clone(),
+            name: "来源.md".to_string(),
+            source_type: SourceType::Markdown,
+            path: path.display().to_string(),
+            size: 12,
+            mime_type: "text/markdown".to_string(),
+            thumbnail_path: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        state
+            .db
+            .insert_source_with_content(&source, Some("来源内容"))
+            .unwrap();
+        state.db.update_project_sources_count(&project.id, 1).unwrap();
+        source
+    }
+
+    #[test]
+    fn test_note_trash_and_restore() {
+        let (state, _temp) = create_test_state();
+        let project = create_test_project(&state, "测试项目");
+        let note = create_test_note(&state, &project, "# 会议记录\n内容");
+
+        let item = move_to_trash(&state, ContentKind::Note, &note.id).unwrap();
+        assert_eq!(item.kind, ContentKind::Note);
+        assert_eq!(item.title, "测试笔记");
+        assert!(state.db.get_note(&note.id).is_err());
+        assert!(!Path::new(&note.path).exists());
+        assert!(Path::new(&item.trash_path).join(TRASH_META_FILE).exists());
+        assert!(state.db.list_notes_missing_embeddings(10).unwrap().is_empty());
+
+        let listed = state.db.get_trash_items(Some(&project.id)).unwrap();
+        assert_eq!(listed.len(), 1);
+
+        restore_from_trash(&state, &item.id).unwrap();
+        assert_eq!(state.db.get_note(&note.id).unwrap().title, "测试笔记");
+        assert_eq!(fs::read_to_string(&note.path).unwrap(), "# 会议记录\n内容");
+        // 向量随笔记一起恢复
+        assert!(state.db.list_notes_missing_embeddings(10).unwrap().is_empty());
+        assert!(state.db.get_trash_items(None).unwrap().is_empty());
+        assert!(!Path::new(&item.trash_path).exists());
+    }
+
+    #[test]
+    fn test_source_trash_updates_count() {
+        let (state, _temp) = create_test_state();
+        let project = create_test_project(&state, "测试项目");
+        let source = create_test_source(&state, &project);
+        assert_eq!(state.db.get_project(&project.id).unwrap().sources_count, 1);
+
+        let item = move_to_trash(&state, ContentKind::Source, &source.id).unwrap();
+        assert_eq!(state.db.get_project(&project.id).unwrap().sources_count, 0);
+        assert!(state.db.get_source(&source.id).is_err());
+
+        restore_from_trash(&state, &item.id).unwrap();
+        assert_eq!(state.db.get_project(&project.id).unwrap().sources_count, 1);
+        assert_eq!(state.db.get_source_content(&source.id).unwrap(), "来源内容");
+        assert!(Path::new(&source.path).exists());
+    }
+
+    #[test]
+    fn test_project_trash_and_restore() {
+        let (state, _temp) = create_test_state();
+        let project = create_test_project(&state, "完整项目");
+        let note = create_test_note(&state, &project, "笔记内容");
+        let source = create_test_source(&state, &project);
+
+        // 先删除一条笔记到项目回收站，再删除整个项目
+        let note_item = move_to_trash(&state, ContentKind::Note, &note.id).unwrap();
+        let project_item = move_to_trash(&state, ContentKind::Project, &project.id).unwrap();
+
+        assert!(state.db.get_project(&project.id).is_err());
+        assert!(state.db.get_source(&source.id).is_err());
+        assert!(!state.file_service.project_exists(&project.id));
+
+        // 项目内部条目随项目隐藏，且不能单独恢复
+        let visible = state.db.get_trash_items(None).unwrap();
+        assert_eq!(visible.len(), 1);
+        assert_eq!(visible[0].id, project_item.id);
+        assert!(restore_from_trash(&state, &note_item.id).is_err());
+
+        restore_from_trash(&state, &project_item.id).unwrap();
+        let restored = state.db.get_project(&project.id).unwrap();
+        assert_eq!(restored.name, "完整项目");
+        assert_eq!(restored.sources_count, 1);
+        assert_eq!(state.db.get_source_content(&source.id).unwrap(), "来源内容");
+        assert!(Path::new(&source.path).exists());
+
+        // 项目恢复后，内部条目重新可见并可恢复
+        restore_from_trash(&state, &note_item.id).unwrap();
+        assert_eq!(fs::read_to_string(&note.path).unwrap(), "笔记内容");
+    }
+
+    #[test]
+    fn test_restore_project_name_conflict() {
+        let (state, _temp) = create_test_state();
+        let project = create_test_project(&state, "同名项目");
+        let item = move_to_trash(&state, ContentKind::Project, &project.id).unwrap();
+
+        create_test_project(&state, "同名项目");
+        restore_from_trash(&state, &item.id).unwrap();
+        assert_eq!(state.db.get_project(&project.id).unwrap().name, "同名项目 (恢复)");
+    }
+
+    /// 将回收站条目的删除时间提前 `days` 天
+    fn backdate_trash_item(state: &AppState, id: &str, days: i64) {
+        let (mut item, snapshot, files) = state.db.get_trash_entry(id).unwrap();
+        item.deleted_at -= Duration::days(days);
+        state
+            .db
+            .transaction(|tx| -> Result<(), DbError> {
+                tx.delete_trash_item(id)?;
+                tx.insert_trash_item(&item, &snapshot, &files)
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_purge_and_expire() {
+        let (state, _temp) = create_test_state();
+        let project = create_test_project(&state, "测试项目");
+        let note = create_test_note(&state, &project, "内容");
+        let other = create_test_note(&state, &project, "内容");
+        let kept = create_test_note(&state, &project, "内容");
+
+        let item = move_to_trash(&state, ContentKind::Note, &note.id).unwrap();
+        purge_trash_item(&state, &item).unwrap();
+        assert!(state.db.get_trash_items(None).unwrap().is_empty());
+        assert!(!Path::new(&item.trash_path).exists());
+        assert!(state.db.get_trash_entry(&item.id).is_err());
+
+        // 未过期的条目不会被自动清理
+        let item = move_to_trash(&state, ContentKind::Note, &other.id).unwrap();
+        assert!(Path::new(&item.trash_path).exists());
+        assert_eq!(purge_expired_trash(&state).unwrap(), 0);
+
+        // 超过保留期限的条目连同回收站目录一起删除
+        state.db.set_setting(TRASH_RETENTION_KEY, "7").unwrap();
+        backdate_trash_item(&state, &item.id, 8);
+        assert_eq!(purge_expired_trash(&state).unwrap(), 1);
+        assert!(state.db.get_trash_entry(&item.id).is_err());
+        assert!(!Path::new(&item.trash_path).exists());
+        assert!(state.db.get_trash_items(None).unwrap().is_empty());
+
+        // 保留 0 天表示不自动清理
+        let item = move_to_trash(&state, ContentKind::Note, &kept.id).unwrap();
+        backdate_trash_item(&state, &item.id, 365);
+        state.db.set_setting(TRASH_RETENTION_KEY, "0").unwrap();
+        assert_eq!(trash_retention_days(&state).unwrap(), 0);
+        assert_eq!(purge_expired_trash(&state).unwrap(), 0);
+        assert_eq!(state.db.get_trash_items(None).unwrap().len(), 1);
+    }
+}
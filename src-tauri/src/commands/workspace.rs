@@ -14,48 +14,160 @@ pub fn workspace_list(state: State<'_, Arc<AppState>>) -> Result<Vec<Workspace>,
     Ok(workspaces)
 }
 
-/// 创建工作空间
+/// 创建工作空间（`parent_id` 为空时创建在顶层）
 #[tauri::command]
 pub fn workspace_create(
     name: String,
+    parent_id: Option<String>,
     state: State<'_, Arc<AppState>>,
 ) -> Result<Workspace, CommandError> {
-    let id = uuid::Uuid::new_v4().to_string();
+    create_workspace(&state, &name, parent_id)
+}
+
+/// 重命名工作空间
+#[tauri::command]
+pub fn workspace_rename(
+    id: String,
+    name: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Workspace, CommandError> {
+    let name = validate_name(&name)?;
+    state.db.rename_workspace(&id, &name)?;
+    Ok(state.db.get_workspace(&id)?)
+}
+
+/// 移动工作空间到另一个上级下（`parent_id` 为空时移到顶层）
+#[tauri::command]
+pub fn workspace_set_parent(
+    id: String,
+    parent_id: Option<String>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Workspace, CommandError> {
+    set_parent(&state, &id, parent_id.as_deref())
+}
+
+/// 调整工作空间顺序，返回调整后的完整列表
+#[tauri::command]
+pub fn workspace_reorder(
+    ids: Vec<String>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<Workspace>, CommandError> {
+    state.db.reorder_workspaces(&ids)?;
+    Ok(state.db.get_all_workspaces()?)
+}
+
+/// 删除工作空间
+///
+/// 工作空间下还有项目时需要指定 `reassign_to`，项目会移到该工作空间。
+#[tauri::command]
+pub fn workspace_delete(
+    id: String,
+    reassign_to: Option<String>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<(), CommandError> {
+    delete_workspace(&state, &id, reassign_to.as_deref())
+}
+
+fn validate_name(name: &str) -> Result<String, CommandError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(CommandError::Validation("工作空间名称不能为空".to_string()));
+    }
+    Ok(name.to_string())
+}
+
+fn create_workspace(
+    state: &AppState,
+    name: &str,
+    parent_id: Option<String>,
+) -> Result<Workspace, CommandError> {
+    let name = validate_name(name)?;
+    if let Some(parent_id) = &parent_id {
+        state.db.get_workspace(parent_id)?;
+    }
 
     // 获取当前最大排序值
     let workspaces = state.db.get_all_workspaces()?;
     let max_order = workspaces.iter().map(|w| w.order).max().unwrap_or(0);
 
     let workspace = Workspace {
-        id: id.clone(),
+        id: uuid::Uuid::new_v4().to_string(),
         name,
         is_system: false,
         order: max_order + 1,
+        parent_id,
     };
 
     state.db.insert_workspace(&workspace)?;
     Ok(workspace)
 }
 
-/// 删除工作空间
-#[tauri::command]
-pub fn workspace_delete(id: String, state: State<'_, Arc<AppState>>) -> Result<(), CommandError> {
-    // 检查是否有项目在该工作空间
-    let count = state.db.count_projects_in_workspace(&id)?;
-    if count > 0 {
-        return Err(CommandError::Database(crate::db::DbError::NotFound(
-            format!("工作空间下还有 {} 个项目，无法删除", count),
-        )));
+fn set_parent(
+    state: &AppState,
+    id: &str,
+    parent_id: Option<&str>,
+) -> Result<Workspace, CommandError> {
+    state.db.get_workspace(id)?;
+    if let Some(parent_id) = parent_id {
+        // 沿上级链向上查找，避免把工作空间移到自己或自己的子分类下
+        let mut ancestor = Some(state.db.get_workspace(parent_id)?);
+        while let Some(workspace) = ancestor {
+            if workspace.id == id {
+                return Err(CommandError::Validation(
+                    "不能把工作空间移到自身或其子分类下".to_string(),
+                ));
+            }
+            ancestor = match workspace.parent_id {
+                Some(parent) => Some(state.db.get_workspace(&parent)?),
+                None => None,
+            };
+        }
+    }
+    state.db.set_workspace_parent(id, parent_id)?;
+    Ok(state.db.get_workspace(id)?)
+}
+
+fn delete_workspace(
+    state: &AppState,
+    id: &str,
+    reassign_to: Option<&str>,
+) -> Result<(), CommandError> {
+    if reassign_to == Some(id) {
+        return Err(CommandError::Validation(
+            "项目不能移到正在删除的工作空间".to_string(),
+        ));
     }
 
-    state.db.delete_workspace(&id)?;
-    Ok(())
+    // 检查与删除在同一事务中，避免检查后有项目移入
+    state.db.transaction(|tx| -> Result<(), CommandError> {
+        if tx.get_workspace(id)?.is_system {
+            return Err(CommandError::Validation("系统分类不能删除".to_string()));
+        }
+        match reassign_to {
+            Some(target) => {
+                tx.get_workspace(target)?;
+            }
+            None => {
+                // 检查是否有项目在该工作空间
+                let count = tx.count_projects_in_workspace(id)?;
+                if count > 0 {
+                    return Err(CommandError::Validation(format!(
+                        "工作空间下还有 {} 个项目，请选择要移入的工作空间",
+                        count
+                    )));
+                }
+            }
+        }
+        tx.delete_workspace_reassigning(id, reassign_to)?;
+        Ok(())
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::db::Database;
+    use crate::db::{Database, DbError};
+    use crate::models::{Project, ProjectIcon};
     use crate::services::FileService;
     use tempfile::TempDir;
 
@@ -65,7 +177,7 @@ mod tests {
         let file_service = FileService::new(temp_dir.path().to_path_buf());
         file_service.init_base_dirs().expect("初始化目录失败");
 
-        let state = Arc::new(AppState { db, file_service });
+        let state = Arc::new(AppState::new(db, file_service));
         (state, temp_dir)
     }
 
@@ -91,6 +203,7 @@ mod tests {
             name: "自定义分类".to_string(),
             is_system: false,
             order: 100,
+            parent_id: None,
         };
 
         state.db.insert_workspace(&workspace).unwrap();
@@ -110,11 +223,12 @@ mod tests {
             name: "待删除分类".to_string(),
             is_system: false,
             order: 100,
+            parent_id: None,
         };
         state.db.insert_workspace(&workspace).unwrap();
 
         // 删除
-        state.db.delete_workspace("custom").unwrap();
+        delete_workspace(&state, "custom", None).unwrap();
 
         let workspaces = state.db.get_all_workspaces().unwrap();
         assert_eq!(workspaces.len(), 4);
@@ -126,7 +240,117 @@ mod tests {
         let (state, _temp) = create_test_state();
 
         // 尝试删除系统工作空间应该失败
-        let result = state.db.delete_workspace("default");
-        assert!(result.is_err());
+        let result = delete_workspace(&state, "default", None);
+        assert!(matches!(result, Err(CommandError::Validation(_))));
+        let result = delete_workspace(&state, "default", Some("personal"));
+        assert!(matches!(result, Err(CommandError::Validation(_))));
+        assert!(state.db.get_workspace("default").is_ok());
+    }
+
+    fn insert_project(state: &AppState, name: &str, workspace: &str) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = chrono::Utc::now();
+        state
+            .db
+            .insert_project(&Project {
+                id: id.clone(),
+                name: name.to_string(),
+                icon: ProjectIcon::default(),
+                workspace: workspace.to_string(),
+                is_starred: false,
+                created_at: now,
+                updated_at: now,
+                sources_count: 0,
+                path: state.file_service.projects_dir().join(&id).display().to_string(),
+                archived_at: None,
+            })
+            .unwrap();
+        id
+    }
+
+    #[test]
+    fn test_workspace_rename() {
+        let (state, _temp) = create_test_state();
+        let workspace = create_workspace(&state, "论文", None).unwrap();
+
+        state.db.rename_workspace(&workspace.id, "论文写作").unwrap();
+        assert_eq!(state.db.get_workspace(&workspace.id).unwrap().name, "论文写作");
+
+        // 与其他工作空间重名
+        let result = state.db.rename_workspace(&workspace.id, "研究");
+        assert!(matches!(result, Err(DbError::AlreadyExists(_))));
+        assert!(validate_name("  ").is_err());
+    }
+
+    #[test]
+    fn test_workspace_reorder() {
+        let (state, _temp) = create_test_state();
+        let ids = |state: &AppState| {
+            state
+                .db
+                .get_all_workspaces()
+                .unwrap()
+                .into_iter()
+                .map(|w| w.id)
+                .collect::<Vec<_>>()
+        };
+
+        state
+            .db
+            .reorder_workspaces(&["personal".to_string(), "research".to_string()])
+            .unwrap();
+        assert_eq!(ids(&state), vec!["default", "personal", "development", "research"]);
+
+        let orders: Vec<i32> = state
+            .db
+            .get_all_workspaces()
+            .unwrap()
+            .iter()
+            .map(|w| w.order)
+            .collect();
+        assert_eq!(orders, vec![0, 1, 2, 3]);
+
+        assert!(state.db.reorder_workspaces(&["missing".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_nested_workspaces() {
+        let (state, _temp) = create_test_state();
+        let parent = create_workspace(&state, "课题", Some("research".to_string())).unwrap();
+        let child = create_workspace(&state, "实验", Some(parent.id.clone())).unwrap();
+        assert_eq!(child.parent_id.as_deref(), Some(parent.id.as_str()));
+        assert!(create_workspace(&state, "孤立", Some("missing".to_string())).is_err());
+
+        // 不能形成循环
+        assert!(set_parent(&state, &parent.id, Some(&child.id)).is_err());
+        assert!(set_parent(&state, &parent.id, Some(&parent.id)).is_err());
+
+        let moved = set_parent(&state, &child.id, None).unwrap();
+        assert!(moved.parent_id.is_none());
+        set_parent(&state, &child.id, Some(&parent.id)).unwrap();
+
+        // 删除上级后子分类挂到原上级的上级下
+        delete_workspace(&state, &parent.id, None).unwrap();
+        assert_eq!(
+            state.db.get_workspace(&child.id).unwrap().parent_id.as_deref(),
+            Some("research")
+        );
+    }
+
+    #[test]
+    fn test_workspace_delete_with_projects() {
+        let (state, _temp) = create_test_state();
+        let workspace = create_workspace(&state, "临时", None).unwrap();
+        let project_id = insert_project(&state, "项目", &workspace.id);
+
+        let result = delete_workspace(&state, &workspace.id, None);
+        assert!(matches!(result, Err(CommandError::Validation(_))));
+        assert!(delete_workspace(&state, &workspace.id, Some("missing")).is_err());
+        assert!(delete_workspace(&state, &workspace.id, Some(&workspace.id)).is_err());
+        assert!(state.db.get_workspace(&workspace.id).is_ok());
+
+        delete_workspace(&state, &workspace.id, Some("personal")).unwrap();
+        assert!(state.db.get_workspace(&workspace.id).is_err());
+        assert_eq!(state.db.get_project(&project_id).unwrap().workspace, "personal");
     }
 }
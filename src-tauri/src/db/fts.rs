@@ -0,0 +1,744 @@
+//! 全文检索查询编译
+//!
+//! 将搜索查询语法树（见 `services::search_query`）编译为 SQL 条件与得分表达式。
+//!
+//! 全文索引使用 FTS5 的 trigram 分词器，中文无需分词即可按子串匹配，并可用 `bm25()` 排序。
+//! trigram 只能匹配至少 3 个字符的词，更短的词（如两字中文词）改用 `LIKE` 过滤，
+//! 此时没有 bm25 分数，按命中列的权重计分。
+//!
+//! 每个文本条件编译为独立的子查询，因此可以任意组合 AND / OR / NOT 与字段过滤；
+//! 得分只由未被排除的文本计算。摘要从命中的正文中截取多段，并给出命中词的位置。
+
+use crate::models::{SearchSnippet, TextMatch};
+use crate::services::{QueryFilter, QueryNode, SearchQuery, PAGE_BREAK};
+use rusqlite::types::Value;
+
+/// trigram 分词器可检索的最短词长（字符数）
+const TRIGRAM_MIN_CHARS: usize = 3;
+
+/// 摘要长度（字符数）
+const SNIPPET_CHARS: usize = 120;
+
+/// 每条结果最多返回的摘要段数
+pub(crate) const MAX_SNIPPETS: usize = 3;
+
+/// 文档内查找时命中位置两侧保留的上下文字符数
+const FIND_CONTEXT_CHARS: usize = 30;
+
+/// 可全文检索的内容类型
+pub(crate) struct FtsTarget {
+    pub result_type: &'static str,
+    /// 依次返回 ID、标题、项目 ID、项目名称、正文（用于截取摘要，可为 NULL）、更新时间的 SELECT 列
+    pub select: &'static str,
+    /// FROM 子句，须关联别名为 p 的 projects 表
+    pub from: &'static str,
+    pub fts_table: &'static str,
+    /// 数据表中与索引表关联的列
+    pub key: &'static str,
+    /// 索引表中对应的列
+    pub fts_key: &'static str,
+    /// 短词 LIKE 过滤的列及权重
+    pub like_columns: &'static [(&'static str, f64)],
+    /// bm25 各列权重（与索引表的列顺序一致）
+    pub bm25_weights: &'static [f64],
+    /// 更新时间列（日期过滤与同分排序）
+    pub updated_at: &'static str,
+    /// 条目 ID 列与标签类型（`item_tags.kind`），不支持标签的类型为 None
+    pub tags: Option<(&'static str, &'static str)>,
+    /// 文件路径列（按扩展名过滤），没有文件的类型为 None
+    pub path: Option<&'static str>,
+}
+
+pub(crate) const PROJECT_FTS: FtsTarget = FtsTarget {
+    result_type: "project",
+    select: "p.id, p.name, p.id, p.name, NULL, p.updated_at",
+    from: "projects p",
+    fts_table: "projects_fts",
+    key: "p.rowid",
+    fts_key: "rowid",
+    like_columns: &[("p.name", 1.0)],
+    bm25_weights: &[1.0],
+    updated_at: "p.updated_at",
+    tags: None,
+    path: None,
+};
+
+pub(crate) const SOURCE_FTS: FtsTarget = FtsTarget {
+    result_type: "source",
+    select: "s.id, s.name, s.project_id, p.name, s.text_content, s.updated_at",
+    from: "sources s JOIN projects p ON s.project_id = p.id",
+    fts_table: "sources_fts",
+    key: "s.rowid",
+    fts_key: "rowid",
+    like_columns: &[("s.name", 10.0), ("s.text_content", 1.0)],
+    bm25_weights: &[10.0, 1.0],
+    updated_at: "s.updated_at",
+    tags: Some(("s.id", "source")),
+    path: Some("s.path"),
+};
+
+pub(crate) const NOTE_FTS: FtsTarget = FtsTarget {
+    result_type: "note",
+    select: "n.id, n.title, n.project_id, p.name, n.text_content, n.updated_at",
+    from: "notes n JOIN projects p ON n.project_id = p.id",
+    fts_table: "notes_fts",
+    key: "n.rowid",
+    fts_key: "rowid",
+    like_columns: &[("n.title", 10.0), ("n.text_content", 1.0)],
+    bm25_weights: &[10.0, 1.0],
+    updated_at: "n.updated_at",
+    tags: Some(("n.id", "note")),
+    path: Some("n.path"),
+};
+
+pub(crate) const CANVAS_FTS: FtsTarget = FtsTarget {
+    result_type: "canvas",
+    select: "c.id, c.title, c.project_id, p.name, canvases_fts.text_content, c.updated_at",
+    from: "canvases c JOIN projects p ON c.project_id = p.id
+           JOIN canvases_fts ON canvases_fts.canvas_id = c.id",
+    fts_table: "canvases_fts",
+    key: "c.id",
+    fts_key: "canvas_id",
+    like_columns: &[
+        ("canvases_fts.title", 10.0),
+        ("canvases_fts.text_content", 1.0),
+    ],
+    bm25_weights: &[0.0, 10.0, 1.0],
+    updated_at: "c.updated_at",
+    tags: Some(("c.id", "canvas")),
+    path: None,
+};
+
+pub(crate) const MINDMAP_FTS: FtsTarget = FtsTarget {
+    result_type: "mindmap",
+    select: "m.id, m.title, m.project_id, p.name, mindmaps_fts.text_content, m.updated_at",
+    from: "mindmaps m JOIN projects p ON m.project_id = p.id
+           JOIN mindmaps_fts ON mindmaps_fts.mindmap_id = m.id",
+    fts_table: "mindmaps_fts",
+    key: "m.id",
+    fts_key: "mindmap_id",
+    like_columns: &[
+        ("mindmaps_fts.title", 10.0),
+        ("mindmaps_fts.text_content", 1.0),
+    ],
+    bm25_weights: &[0.0, 10.0, 1.0],
+    updated_at: "m.updated_at",
+    tags: Some(("m.id", "mindmap")),
+    path: None,
+};
+
+pub(crate) const PRESENTATION_FTS: FtsTarget = FtsTarget {
+    result_type: "presentation",
+    select: "pr.id, pr.title, pr.project_id, p.name, presentations_fts.text_content, pr.updated_at",
+    from: "presentations pr JOIN projects p ON pr.project_id = p.id
+           JOIN presentations_fts ON presentations_fts.presentation_id = pr.id",
+    fts_table: "presentations_fts",
+    key: "pr.id",
+    fts_key: "presentation_id",
+    like_columns: &[
+        ("presentations_fts.title", 10.0),
+        ("presentations_fts.text_content", 1.0),
+    ],
+    bm25_weights: &[0.0, 10.0, 1.0],
+    updated_at: "pr.updated_at",
+    tags: Some(("pr.id", "presentation")),
+    path: None,
+};
+
+/// 对话按会话索引，正文为会话内全部消息
+pub(crate) const CHAT_FTS: FtsTarget = FtsTarget {
+    result_type: "chat",
+    select: "cs.id, cs.title, cs.project_id, p.name, chat_fts.text_content, cs.updated_at",
+    from: "chat_sessions cs JOIN projects p ON cs.project_id = p.id
+           JOIN chat_fts ON chat_fts.session_id = cs.id",
+    fts_table: "chat_fts",
+    key: "cs.id",
+    fts_key: "session_id",
+    like_columns: &[("chat_fts.title", 10.0), ("chat_fts.text_content", 1.0)],
+    bm25_weights: &[0.0, 10.0, 1.0],
+    updated_at: "cs.updated_at",
+    tags: Some(("cs.id", "chat")),
+    path: None,
+};
+
+/// 一次检索需要拼入 SQL 的片段与参数
+///
+/// 片段中的参数使用 `?1`、`?2` 等编号，调用方追加的参数从 `next_param` 开始编号。
+pub(crate) struct FtsClauses {
+    /// WHERE 条件
+    pub condition: String,
+    /// 得分表达式（越大越相关）
+    pub score: String,
+    pub params: Vec<Value>,
+    pub next_param: usize,
+}
+
+/// 将查询编译为一种内容类型上的检索片段
+///
+/// 有长词时按 bm25 计分，只有短词时按命中列的权重计分，只有过滤条件时得分为 0。
+pub(crate) fn compile(query: &SearchQuery, target: &FtsTarget) -> FtsClauses {
+    let mut compiler = Compiler {
+        target,
+        params: Vec::new(),
+    };
+    let condition = compiler.node(&query.root);
+    let score = compiler.score(&query.positive_terms());
+    let next_param = compiler.params.len() + 1;
+    FtsClauses {
+        condition,
+        score,
+        params: compiler.params,
+        next_param,
+    }
+}
+
+struct Compiler<'a> {
+    target: &'a FtsTarget,
+    params: Vec<Value>,
+}
+
+impl Compiler<'_> {
+    /// 追加一个参数，返回其编号
+    fn param(&mut self, value: String) -> usize {
+        self.params.push(Value::Text(value));
+        self.params.len()
+    }
+
+    fn node(&mut self, node: &QueryNode) -> String {
+        let target = self.target;
+        match node {
+            QueryNode::Text(text) if is_long(text) => {
+                let index = self.param(match_phrase(text));
+                format!(
+                    "{key} IN (SELECT {fts_key} FROM {fts} WHERE {fts} MATCH ?{index})",
+                    key = target.key,
+                    fts_key = target.fts_key,
+                    fts = target.fts_table,
+                )
+            }
+            QueryNode::Text(text) => {
+                let index = self.param(format!("%{}%", escape_like(text)));
+                let matches: Vec<String> = target
+                    .like_columns
+                    .iter()
+                    .map(|(column, _)| format!("{} LIKE ?{} ESCAPE '\\'", column, index))
+                    .collect();
+                format!("({})", matches.join(" OR "))
+            }
+            QueryNode::Filter(filter) => self.filter(filter),
+            // 列为 NULL 时条件结果为 NULL，取反前按不满足处理
+            QueryNode::Not(inner) => format!("NOT IFNULL({}, 0)", self.node(inner)),
+            QueryNode::And(nodes) => self.join(nodes, " AND "),
+            QueryNode::Or(nodes) => self.join(nodes, " OR "),
+        }
+    }
+
+    fn join(&mut self, nodes: &[QueryNode], separator: &str) -> String {
+        let parts: Vec<String> = nodes.iter().map(|node| self.node(node)).collect();
+        format!("({})", parts.join(separator))
+    }
+
+    fn filter(&mut self, filter: &QueryFilter) -> String {
+        let target = self.target;
+        match filter {
+            QueryFilter::Type(kind) => {
+                if kind == target.result_type { "1" } else { "0" }.to_string()
+            }
+            QueryFilter::Project(project) => {
+                let index = self.param(project.clone());
+                format!("(p.name = ?{0} COLLATE NOCASE OR p.id = ?{0})", index)
+            }
+            QueryFilter::Workspace(workspace) => {
+                let index = self.param(workspace.clone());
+                format!(
+                    "(p.workspace = ?{0}
+                      OR p.workspace IN (SELECT id FROM workspaces WHERE name = ?{0} COLLATE NOCASE))",
+                    index
+                )
+            }
+            QueryFilter::Tag(tag) => match target.tags {
+                Some((id, kind)) => {
+                    let index = self.param(tag.clone());
+                    format!(
+                        "EXISTS (SELECT 1 FROM item_tags t WHERE t.kind = '{}' AND t.item_id = {}
+                                 AND t.tag = ?{} COLLATE NOCASE)",
+                        kind, id, index
+                    )
+                }
+                None => "0".to_string(),
+            },
+            // 更新时间可能是 RFC 3339 或 SQLite datetime 格式，前 10 个字符都是日期
+            QueryFilter::After(date) => {
+                let index = self.param(date.format("%Y-%m-%d").to_string());
+                format!("SUBSTR({}, 1, 10) >= ?{}", target.updated_at, index)
+            }
+            QueryFilter::Before(date) => {
+                let index = self.param(date.format("%Y-%m-%d").to_string());
+                format!("SUBSTR({}, 1, 10) < ?{}", target.updated_at, index)
+            }
+            QueryFilter::Ext(ext) => match target.path {
+                Some(path) => {
+                    let index = self.param(format!("%.{}", escape_like(ext)));
+                    format!("{} LIKE ?{} ESCAPE '\\'", path, index)
+                }
+                None => "0".to_string(),
+            },
+        }
+    }
+
+    fn score(&mut self, terms: &[&str]) -> String {
+        let target = self.target;
+        let long: Vec<String> = terms
+            .iter()
+            .filter(|term| is_long(term))
+            .map(|term| match_phrase(term))
+            .collect();
+
+        if !long.is_empty() {
+            // 命中任一文本即参与 bm25 计分；bm25 越小越相关，取负值作为得分
+            let index = self.param(long.join(" OR "));
+            let weights: Vec<String> = target
+                .bm25_weights
+                .iter()
+                .map(|weight| format!("{:.1}", weight))
+                .collect();
+            return format!(
+                "IFNULL((SELECT -bm25({fts}, {weights}) FROM {fts}
+                         WHERE {fts} MATCH ?{index} AND {fts_key} = {key}), 0.0)",
+                fts = target.fts_table,
+                weights = weights.join(", "),
+                fts_key = target.fts_key,
+                key = target.key,
+            );
+        }
+
+        let mut scores = Vec::new();
+        for term in terms {
+            let index = self.param(format!("%{}%", escape_like(term)));
+            scores.extend(target.like_columns.iter().map(|(column, weight)| {
+                format!(
+                    "(CASE WHEN {} LIKE ?{} ESCAPE '\\' THEN {:.1} ELSE 0.0 END)",
+                    column, index, weight
+                )
+            }));
+        }
+        if scores.is_empty() {
+            "0.0".to_string()
+        } else {
+            scores.join(" + ")
+        }
+    }
+}
+
+fn is_long(text: &str) -> bool {
+    text.chars().count() >= TRIGRAM_MIN_CHARS
+}
+
+/// FTS5 短语：双引号包裹，内部双引号转义
+fn match_phrase(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+/// 从正文中截取命中词附近的摘要，至多 `max` 段，按出现顺序排列；正文不含任何词时返回空
+///
+/// 每段以首个未被前一段覆盖的命中词为中心截取 `SNIPPET_CHARS` 个字符，段内的其他命中词
+/// 一并标出，各段互不重叠。与 trigram 分词器一致，ASCII 字母不区分大小写。
+/// 空白折叠为单个空格，被截断的一侧加 `…`。正文含分页符（PDF）时给出页码。
+///
+/// FTS5 的 `snippet()` 只能用于 MATCH 查询且每条结果只有一段，短词的 LIKE 命中
+/// 与向量检索结果无法使用，因此统一在这里截取。
+pub(crate) fn snippets(text: &str, terms: &[&str], max: usize) -> Vec<SearchSnippet> {
+    let hits = find_hits(text, terms);
+    if hits.is_empty() {
+        return Vec::new();
+    }
+
+    let boundaries: Vec<usize> = text.char_indices().map(|(index, _)| index).collect();
+    let to_char = |byte: usize| boundaries.partition_point(|index| *index < byte);
+    let total = boundaries.len();
+    let paged = text.contains(PAGE_BREAK);
+
+    let mut snippets = Vec::new();
+    let mut covered = 0;
+    let mut next_hit = 0;
+    while snippets.len() < max {
+        let Some(index) = (next_hit..hits.len()).find(|index| to_char(hits[*index].0) >= covered)
+        else {
+            break;
+        };
+        let (first, first_len) = hits[index];
+        let hit_start = to_char(first);
+        let hit_end = to_char(first + first_len);
+
+        // 以命中词为中心，靠近首尾时整体平移，且不与前一段重叠
+        let centre = (hit_start + hit_end) / 2;
+        let mut start = centre.saturating_sub(SNIPPET_CHARS / 2).max(covered);
+        let mut end = (start + SNIPPET_CHARS).min(total);
+        if end - start < SNIPPET_CHARS {
+            start = end.saturating_sub(SNIPPET_CHARS).max(covered);
+        }
+
+        // 段内命中词，跨越段尾的命中词补全
+        let mut ranges = Vec::new();
+        next_hit = index;
+        while let Some((at, len)) = hits.get(next_hit).copied() {
+            let range = (to_char(at), to_char(at + len));
+            if range.0 >= end {
+                break;
+            }
+            end = end.max(range.1);
+            ranges.push(range);
+            next_hit += 1;
+        }
+
+        let page = paged.then(|| text[..first].matches(PAGE_BREAK).count() as u32 + 1);
+        snippets.push(render_snippet(text, &boundaries, start, end, &ranges, page));
+        covered = end;
+    }
+
+    snippets
+}
+
+/// 查找正文中 `query` 的全部出现位置（按原样匹配，ASCII 字母不区分大小写），用于文档内查找
+pub(crate) fn find_matches(text: &str, query: &str) -> Vec<TextMatch> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let boundaries: Vec<usize> = text.char_indices().map(|(index, _)| index).collect();
+    let to_char = |byte: usize| boundaries.partition_point(|index| *index < byte);
+    let paged = text.contains(PAGE_BREAK);
+
+    // 逐个命中计算页码，只需向后扫描一次
+    let mut page = 1;
+    let mut counted = 0;
+    find_hits(text, &[query])
+        .into_iter()
+        .map(|(at, len)| {
+            page += text[counted..at].matches(PAGE_BREAK).count() as u32;
+            counted = at;
+
+            let start = to_char(at);
+            let end = to_char(at + len);
+            let context = render_snippet(
+                text,
+                &boundaries,
+                start.saturating_sub(FIND_CONTEXT_CHARS),
+                (end + FIND_CONTEXT_CHARS).min(boundaries.len()),
+                &[(start, end)],
+                None,
+            );
+            TextMatch {
+                start,
+                end,
+                page: paged.then_some(page),
+                context,
+            }
+        })
+        .collect()
+}
+
+/// 正文开头的摘要（正文未命中时使用），正文为空时返回 None
+pub(crate) fn leading_snippet(text: &str) -> Option<SearchSnippet> {
+    let boundaries: Vec<usize> = text.char_indices().map(|(index, _)| index).collect();
+    let end = boundaries.len().min(SNIPPET_CHARS);
+    let snippet = render_snippet(
+        text,
+        &boundaries,
+        0,
+        end,
+        &[],
+        text.contains(PAGE_BREAK).then_some(1),
+    );
+    (!snippet.text.is_empty()).then_some(snippet)
+}
+
+/// 将摘要的命中位置用 `<mark>` 标记
+pub(crate) fn mark(snippet: &SearchSnippet) -> String {
+    let mut marked = String::with_capacity(snippet.text.len());
+    let mut ranges = snippet.highlights.iter().peekable();
+    let mut open = false;
+    for (index, c) in snippet.text.chars().enumerate() {
+        if open && ranges.peek().is_some_and(|range| range.1 == index) {
+            marked.push_str("</mark>");
+            ranges.next();
+            open = false;
+        }
+        if !open && ranges.peek().is_some_and(|range| range.0 == index) {
+            marked.push_str("<mark>");
+            open = true;
+        }
+        marked.push(c);
+    }
+    if open {
+        marked.push_str("</mark>");
+    }
+    marked
+}
+
+/// 正文中所有命中词的位置（字节偏移与长度），互不重叠；同一位置有多个词命中时取最长的
+fn find_hits(text: &str, terms: &[&str]) -> Vec<(usize, usize)> {
+    // 只转换 ASCII 字母，字节位置与原文一致
+    let haystack = text.to_ascii_lowercase();
+    let terms: Vec<String> = terms
+        .iter()
+        .map(|term| term.to_ascii_lowercase())
+        .filter(|term| !term.trim().is_empty())
+        .collect();
+
+    let mut hits = Vec::new();
+    let mut pos = 0;
+    while pos < haystack.len() {
+        let next = terms
+            .iter()
+            .filter_map(|term| {
+                haystack[pos..]
+                    .find(term.as_str())
+                    .map(|offset| (pos + offset, term.len()))
+            })
+            .min_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+        let Some((at, len)) = next else {
+            break;
+        };
+        hits.push((at, len));
+        pos = at + len;
+    }
+    hits
+}
+
+/// 截取 `[start, end)`（字符）并折叠空白，`ranges` 为其中的命中位置（字符）
+fn render_snippet(
+    text: &str,
+    boundaries: &[usize],
+    start: usize,
+    end: usize,
+    ranges: &[(usize, usize)],
+    page: Option<u32>,
+) -> SearchSnippet {
+    let byte = |index: usize| boundaries.get(index).copied().unwrap_or(text.len());
+
+    let mut output = String::new();
+    let mut length = 0;
+    let mut highlights = Vec::new();
+    if start > 0 {
+        output.push('…');
+        length += 1;
+    }
+
+    let mut ranges = ranges.iter().peekable();
+    let mut pending_space = false;
+    // 命中词的起点在写出其第一个非空白字符时记录
+    let mut opening: Option<usize> = None;
+    let mut opened: Option<usize> = None;
+    for (offset, c) in text[byte(start)..byte(end)].chars().enumerate() {
+        let index = start + offset;
+        if let Some(range) = ranges.peek() {
+            if index == range.1 {
+                if let Some(from) = opened.take() {
+                    highlights.push((from, length));
+                }
+                opening = None;
+                ranges.next();
+            }
+        }
+        if ranges.peek().is_some_and(|range| range.0 == index) {
+            opening = Some(index);
+        }
+
+        if c.is_whitespace() {
+            pending_space = length > 0;
+            continue;
+        }
+        if pending_space {
+            // 命中词内部的空白保留在高亮范围内
+            output.push(' ');
+            length += 1;
+            pending_space = false;
+        }
+        if opening.take().is_some() {
+            opened = Some(length);
+        }
+        output.push(c);
+        length += 1;
+    }
+    if let Some(from) = opened {
+        highlights.push((from, length));
+    }
+    if end < boundaries.len() {
+        output.push('…');
+    }
+
+    SearchSnippet {
+        text: output,
+        highlights,
+        page,
+    }
+}
+
+/// 转义 LIKE 模式中的通配符
+fn escape_like(term: &str) -> String {
+    let mut escaped = String::with_capacity(term.len());
+    for c in term.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile_numbers_params() {
+        let query = SearchQuery::plain("学习 transformer").unwrap();
+        let clauses = compile(&query, &PROJECT_FTS);
+        assert_eq!(
+            clauses.condition,
+            "((p.name LIKE ?1 ESCAPE '\\') AND p.rowid IN \
+             (SELECT rowid FROM projects_fts WHERE projects_fts MATCH ?2))"
+        );
+        assert_eq!(
+            clauses.params[2],
+            Value::Text("\"transformer\"".to_string())
+        );
+        assert!(clauses
+            .score
+            .starts_with("IFNULL((SELECT -bm25(projects_fts, 1.0)"));
+        assert_eq!(clauses.next_param, 4);
+
+        let short = SearchQuery::plain("5%").unwrap();
+        let clauses = compile(&short, &PROJECT_FTS);
+        assert_eq!(clauses.params[0], Value::Text("%5\\%%".to_string()));
+        assert_eq!(
+            clauses.score,
+            "(CASE WHEN p.name LIKE ?2 ESCAPE '\\' THEN 1.0 ELSE 0.0 END)"
+        );
+    }
+
+    #[test]
+    fn test_compile_filters() {
+        let query = SearchQuery::parse(r#"-type:note tag:待读 ext:PDF "知识 图谱""#)
+            .unwrap()
+            .unwrap();
+        let clauses = compile(&query, &PROJECT_FTS);
+        // 项目没有标签与文件，对应条件恒为假
+        assert!(clauses
+            .condition
+            .starts_with("(NOT IFNULL(0, 0) AND 0 AND 0 AND"));
+
+        let clauses = compile(&query, &SOURCE_FTS);
+        assert!(clauses
+            .condition
+            .contains("t.kind = 'source' AND t.item_id = s.id"));
+        assert!(clauses.condition.contains("s.path LIKE ?2"));
+        assert_eq!(clauses.params[1], Value::Text("%.pdf".to_string()));
+        assert_eq!(clauses.params[2], Value::Text("\"知识 图谱\"".to_string()));
+        assert_eq!(clauses.score.matches("MATCH").count(), 1);
+
+        let filters_only = SearchQuery::parse("after:2026-01-01").unwrap().unwrap();
+        assert_eq!(compile(&filters_only, &NOTE_FTS).score, "0.0");
+    }
+
+    #[test]
+    fn test_snippets_mark_terms() {
+        let terms = ["RUST", "内存"];
+        let found = snippets(
+            "# 笔记\n\nRust 的内存模型与 rust 所有权",
+            &terms,
+            MAX_SNIPPETS,
+        );
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].text, "# 笔记 Rust 的内存模型与 rust 所有权");
+        assert_eq!(found[0].highlights, vec![(5, 9), (11, 13), (17, 21)]);
+        assert_eq!(found[0].page, None);
+        assert_eq!(
+            mark(&found[0]),
+            "# 笔记 <mark>Rust</mark> 的<mark>内存</mark>模型与 <mark>rust</mark> 所有权"
+        );
+        assert!(snippets("没有命中", &terms, MAX_SNIPPETS).is_empty());
+
+        // 同一位置取最长的命中词
+        let phrase = snippets("前言\n\n知识 图谱", &["知识 图谱", "知识"], 1);
+        assert_eq!(mark(&phrase[0]), "前言 <mark>知识 图谱</mark>");
+    }
+
+    #[test]
+    fn test_snippets_centre_on_each_match() {
+        let text = format!(
+            "{}内存{}内存{}",
+            "前".repeat(100),
+            "中".repeat(300),
+            "后".repeat(200)
+        );
+        let found = snippets(&text, &["内存"], MAX_SNIPPETS);
+        assert_eq!(found.len(), 2);
+        for snippet in &found {
+            assert!(snippet.text.starts_with('…') && snippet.text.ends_with('…'));
+            // 命中词位于摘要中间
+            let (start, end) = snippet.highlights[0];
+            assert_eq!(
+                snippet
+                    .text
+                    .chars()
+                    .skip(start)
+                    .take(end - start)
+                    .collect::<String>(),
+                "内存"
+            );
+            assert!((58..=62).contains(&start));
+        }
+
+        // 超出段数上限的命中不再截取，相邻命中合并到同一段
+        let dense = "内存 ".repeat(200);
+        let found = snippets(&dense, &["内存"], 2);
+        assert_eq!(found.len(), 2);
+        assert!(found[0].highlights.len() > 30);
+        assert!(!found[0].text.starts_with('…'));
+    }
+
+    #[test]
+    fn test_find_matches_positions() {
+        let text = format!("Rust 入门{}第二章 rust 所有权\n\nRUST 生命周期", PAGE_BREAK);
+        let matches = find_matches(&text, " rust ");
+        let positions: Vec<(usize, usize, Option<u32>)> = matches
+            .iter()
+            .map(|found| (found.start, found.end, found.page))
+            .collect();
+        assert_eq!(
+            positions,
+            vec![(0, 4, Some(1)), (12, 16, Some(2)), (22, 26, Some(2))]
+        );
+        // 位置以原文字符计
+        assert_eq!(text.chars().skip(12).take(4).collect::<String>(), "rust");
+        assert_eq!(
+            mark(&matches[0].context),
+            "<mark>Rust</mark> 入门 第二章 rust 所有权 RUST 生命周期"
+        );
+
+        assert!(find_matches(&text, "  ").is_empty());
+        assert!(find_matches("没有分页的正文", "分页")[0].page.is_none());
+    }
+
+    #[test]
+    fn test_snippets_report_pages() {
+        let text = format!("第一页{}第二页 索引{}第三页 索引", PAGE_BREAK, PAGE_BREAK);
+        let found = snippets(&text, &["索引"], MAX_SNIPPETS);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].page, Some(2));
+        assert_eq!(found[0].highlights.len(), 2);
+
+        let long = format!("{}{}{}索引", "甲".repeat(200), PAGE_BREAK, "乙".repeat(200));
+        let found = snippets(&long, &["索引"], MAX_SNIPPETS);
+        assert_eq!(found[0].page, Some(2));
+        assert!(found[0].text.ends_with("索引"));
+
+        let leading = leading_snippet(&long).unwrap();
+        assert_eq!(leading.page, Some(1));
+        assert!(leading.highlights.is_empty());
+        assert_eq!(leading_snippet(" \n "), None);
+    }
+}
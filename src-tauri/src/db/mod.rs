@@ -2,15 +2,31 @@
 //!
 //! 提供 SQLite 数据库操作封装
 
-use crate::models::{Canvas, ChatMessage, ChatSession, Citation, MessageRole, MindMap, Note, OutputType, Presentation, Project, ProjectIcon, RecentAccess, SearchResult, Source, SourceType, Workspace};
+use crate::models::{ActivityAction, ActivityEntry, BadJsonRow, Canvas, DanglingRow, FileReference, ChatMessage, ChatSession, Citation, ContentKind, ContentSnapshot, MessageRole, MindMap, MindMapData, Note, OutputType, Presentation, Project, ProjectIcon, ProjectStats, RecentAccess, RelatedItems, SavedSearch, SearchResult, SearchSnippet, SearchSort, Source, SourceType, SyncChange, TrashItem, TrashedFile, Workspace};
 use crate::services::embedding::cosine_similarity;
 use chrono::{DateTime, Utc};
-use rusqlite::{params, Connection};
+use rusqlite::backup::Progress;
+use crate::services::{extract_mindmap_text, extract_ppt_file_text, SearchQuery};
+use fts::{
+    FtsTarget, CANVAS_FTS, CHAT_FTS, MINDMAP_FTS, NOTE_FTS, PRESENTATION_FTS, PROJECT_FTS,
+    SOURCE_FTS,
+};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, DatabaseName, OpenFlags, OptionalExtension};
 use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
-use std::sync::Mutex;
+use std::sync::{Mutex, RwLock};
 use thiserror::Error;
 
+mod fts;
+mod paths;
+mod tx;
+
+pub use paths::PathMapper;
+pub use tx::DbTx;
+pub(crate) use fts::find_matches;
+
 /// 数据库错误类型
 #[derive(Error, Debug)]
 pub enum DbError {
@@ -20,13 +36,70 @@ pub enum DbError {
     NotFound(String),
     #[error("数据已存在: {0}")]
     AlreadyExists(String),
+    #[error("数据序列化失败: {0}")]
+    Serialization(String),
     #[error("锁获取失败")]
     LockError,
 }
 
+/// 使用外部内容表的全文索引
+const CONTENT_FTS_TABLES: [&str; 3] = ["projects_fts", "sources_fts", "notes_fts"];
+
+/// 独立维护（不使用外部内容表）的全文索引：(索引表, 索引表中的条目 ID 列, 数据表)
+const STANDALONE_FTS_TABLES: [(&str, &str, &str); 4] = [
+    ("canvases_fts", "canvas_id", "canvases"),
+    ("mindmaps_fts", "mindmap_id", "mindmaps"),
+    ("presentations_fts", "presentation_id", "presentations"),
+    ("chat_fts", "session_id", "chat_sessions"),
+];
+
+/// 全部全文索引表
+pub const FTS_TABLES: [&str; 7] = [
+    "projects_fts",
+    "sources_fts",
+    "notes_fts",
+    "canvases_fts",
+    "mindmaps_fts",
+    "presentations_fts",
+    "chat_fts",
+];
+
+/// 悬空记录检查：(表, 主键表达式, 项目 ID 表达式, 条件, 说明)
+const DANGLING_ROW_CHECKS: [(&str, &str, &str, &str, &str); 11] = [
+    ("sources", "id", "project_id", "project_id NOT IN (SELECT id FROM projects)", "来源所属项目不存在"),
+    ("notes", "id", "project_id", "project_id NOT IN (SELECT id FROM projects)", "笔记所属项目不存在"),
+    ("canvases", "id", "project_id", "project_id NOT IN (SELECT id FROM projects)", "画布所属项目不存在"),
+    ("mindmaps", "id", "project_id", "project_id NOT IN (SELECT id FROM projects)", "思维导图所属项目不存在"),
+    ("presentations", "id", "project_id", "project_id NOT IN (SELECT id FROM projects)", "PPT 所属项目不存在"),
+    ("chat_sessions", "id", "project_id", "project_id NOT IN (SELECT id FROM projects)", "对话所属项目不存在"),
+    ("chat_messages", "id", "NULL", "session_id NOT IN (SELECT id FROM chat_sessions)", "消息所属对话不存在"),
+    ("source_embeddings", "source_id", "project_id", "source_id NOT IN (SELECT id FROM sources)", "向量对应的来源不存在"),
+    ("note_embeddings", "note_id", "project_id", "note_id NOT IN (SELECT id FROM notes)", "向量对应的笔记不存在"),
+    (
+        "item_tags",
+        "kind || '/' || item_id || '/' || tag",
+        "NULL",
+        "(kind = 'source' AND item_id NOT IN (SELECT id FROM sources))
+         OR (kind = 'note' AND item_id NOT IN (SELECT id FROM notes))
+         OR (kind = 'canvas' AND item_id NOT IN (SELECT id FROM canvases))
+         OR (kind = 'mindmap' AND item_id NOT IN (SELECT id FROM mindmaps))
+         OR (kind = 'presentation' AND item_id NOT IN (SELECT id FROM presentations))
+         OR (kind = 'chat' AND item_id NOT IN (SELECT id FROM chat_sessions))",
+        "标签对应的条目不存在",
+    ),
+    ("recent_accesses", "id", "project_id", "project_id NOT IN (SELECT id FROM projects)", "最近访问的项目不存在"),
+];
+
+/// 相关条目缓存的键：(条目类型, 条目 ID, 是否在整个资料库中查找, 每组条数)
+type RelatedKey = (ContentKind, String, bool, u32);
+
 /// 数据库封装
 pub struct Database {
     conn: Mutex<Connection>,
+    /// 数据文件路径转换器（数据库中保存相对于数据目录的路径）
+    paths: RwLock<PathMapper>,
+    /// 相关条目缓存，值为计算时的向量版本号与结果（版本号变化即失效）
+    related_cache: Mutex<HashMap<RelatedKey, (i64, RelatedItems)>>,
 }
 
 impl Database {
@@ -35,6 +108,8 @@ impl Database {
         let conn = Connection::open(path)?;
         let db = Self {
             conn: Mutex::new(conn),
+            paths: RwLock::new(PathMapper::default()),
+            related_cache: Mutex::new(HashMap::new()),
         };
         db.init_schema()?;
         Ok(db)
@@ -45,14 +120,252 @@ impl Database {
         let conn = Connection::open_in_memory()?;
         let db = Self {
             conn: Mutex::new(conn),
+            paths: RwLock::new(PathMapper::default()),
+            related_cache: Mutex::new(HashMap::new()),
         };
         db.init_schema()?;
         Ok(db)
     }
 
+    /// 设置数据目录
+    ///
+    /// 之后写入的数据文件路径保存为相对于数据目录的路径，读取时解析为绝对路径；
+    /// 同时把旧版本保存的绝对路径迁移为相对路径，返回迁移的记录数。
+    pub fn set_data_dir(&self, base: &Path) -> Result<usize, DbError> {
+        let mapper = PathMapper::new(base.to_path_buf());
+        *self.paths.write().map_err(|_| DbError::LockError)? = mapper.clone();
+        let migrated = self.migrate_relative_paths(&mapper)?;
+        self.backfill_note_contents()?;
+        self.backfill_presentation_texts()?;
+        Ok(migrated)
+    }
+
+    /// 从笔记文件回填尚未索引的笔记正文，返回回填的笔记数
+    ///
+    /// 文件不存在或无法读取的笔记保持未索引状态，下次启动时重试。
+    pub fn backfill_note_contents(&self) -> Result<usize, DbError> {
+        let paths = self.path_mapper();
+        let mut conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let tx = conn.transaction()?;
+        let pending = {
+            let mut stmt = tx.prepare("SELECT id, path FROM notes WHERE text_content IS NULL")?;
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok((row.get::<_, String>(0)?, paths.resolve(&row.get::<_, String>(1)?)))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            rows
+        };
+
+        let db_tx = DbTx::new(&tx, paths.clone());
+        let mut filled = 0;
+        for (id, path) in pending {
+            if let Ok(content) = std::fs::read_to_string(&path) {
+                db_tx.index_note_content(&id, &content)?;
+                filled += 1;
+            }
+        }
+        tx.commit()?;
+        Ok(filled)
+    }
+
+    /// 从 PPT 数据文件回填尚未提取的搜索文本，返回回填的 PPT 数
+    ///
+    /// 文件不存在或无法解析的 PPT 保持未提取状态，下次启动时重试。
+    pub fn backfill_presentation_texts(&self) -> Result<usize, DbError> {
+        let paths = self.path_mapper();
+        let mut conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let tx = conn.transaction()?;
+        let pending = {
+            let mut stmt = tx.prepare(
+                "SELECT pr.id, pr.data_path FROM presentations pr
+                 JOIN presentations_fts ON presentations_fts.presentation_id = pr.id
+                 WHERE presentations_fts.text_content IS NULL",
+            )?;
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok((row.get::<_, String>(0)?, paths.resolve(&row.get::<_, String>(1)?)))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            rows
+        };
+
+        let db_tx = DbTx::new(&tx, paths.clone());
+        let mut filled = 0;
+        for (id, path) in pending {
+            if let Ok(text) = extract_ppt_file_text(Path::new(&path)) {
+                db_tx.update_presentation_text_content(&id, &text)?;
+                filled += 1;
+            }
+        }
+        tx.commit()?;
+        Ok(filled)
+    }
+
+    /// 与另一个已打开的数据库交换连接（切换资料库时使用），返回原数据库
+    ///
+    /// `on_swap` 在持有连接锁期间调用，用于同步替换数据目录等状态，
+    /// 使其他命令不会看到新连接与旧数据目录的组合。回退时将返回值再交换回来即可。
+    pub fn swap(&self, other: Database, on_swap: impl FnOnce()) -> Result<Database, DbError> {
+        let conn = other.conn.into_inner().map_err(|_| DbError::LockError)?;
+        let mapper = other.paths.into_inner().map_err(|_| DbError::LockError)?;
+
+        let mut current = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let mut paths = self.paths.write().map_err(|_| DbError::LockError)?;
+        let previous = Database {
+            conn: Mutex::new(std::mem::replace(&mut *current, conn)),
+            paths: RwLock::new(std::mem::replace(&mut *paths, mapper)),
+            related_cache: Mutex::new(HashMap::new()),
+        };
+        on_swap();
+        drop(paths);
+        drop(current);
+        // 新数据库的向量版本号与缓存无关
+        self.clear_related_cache()?;
+        Ok(previous)
+    }
+
+    /// 使用 SQLite 在线备份 API 将数据库备份到文件（备份期间不阻塞其他连接的读取）
+    pub fn backup_to(&self, dest: &Path) -> Result<(), DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        conn.backup(DatabaseName::Main, dest, None)?;
+        Ok(())
+    }
+
+    /// 使用 SQLite 在线备份 API 从备份文件恢复数据库
+    ///
+    /// 恢复后重新迁移数据文件路径（旧备份中可能保存的是绝对路径），返回迁移的记录数。
+    pub fn restore_from(&self, src: &Path) -> Result<usize, DbError> {
+        {
+            let mut conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+            conn.restore(DatabaseName::Main, src, None::<fn(Progress)>)?;
+        }
+        self.clear_related_cache()?;
+        // 旧版本的备份需要补齐 schema 迁移
+        self.init_schema()?;
+        let migrated = self.migrate_relative_paths(&self.path_mapper())?;
+        self.backfill_note_contents()?;
+        self.backfill_presentation_texts()?;
+        Ok(migrated)
+    }
+
+    /// 检查数据库文件的完整性，返回发现的问题（为空表示完整）
+    ///
+    /// FTS5 索引的检查需要写权限，因此以读写方式打开（文件不存在时不会创建）。
+    pub fn check_integrity(path: &Path) -> Result<Vec<String>, DbError> {
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+        let messages = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(messages.into_iter().filter(|message| message != "ok").collect())
+    }
+
+    fn path_mapper(&self) -> PathMapper {
+        self.paths
+            .read()
+            .map(|paths| paths.clone())
+            .unwrap_or_default()
+    }
+
+    /// 将绝对路径迁移为相对路径
+    fn migrate_relative_paths(&self, mapper: &PathMapper) -> Result<usize, DbError> {
+        const PATH_COLUMNS: [(&str, &str); 7] = [
+            ("projects", "path"),
+            ("sources", "path"),
+            ("sources", "thumbnail_path"),
+            ("notes", "path"),
+            ("canvases", "path"),
+            ("presentations", "data_path"),
+            ("presentations", "thumbnail_path"),
+        ];
+
+        let mut conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let tx = conn.transaction()?;
+        let mut migrated = 0;
+
+        for (table, column) in PATH_COLUMNS {
+            let rows = {
+                let mut stmt = tx.prepare(&format!(
+                    "SELECT rowid, {column} FROM {table} WHERE {column} LIKE '/%' OR {column} LIKE '_:%'"
+                ))?;
+                let rows = stmt
+                    .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+                    .collect::<Result<Vec<_>, _>>()?;
+                rows
+            };
+            for (rowid, path) in rows {
+                if let Some(relative) = mapper.relativize_legacy(&path) {
+                    tx.execute(
+                        &format!("UPDATE {table} SET {column} = ?1 WHERE rowid = ?2"),
+                        params![relative, rowid],
+                    )?;
+                    migrated += 1;
+                }
+            }
+        }
+
+        // 回收站条目：目录、文件映射与快照中的路径
+        let trash_rows = {
+            let mut stmt = tx.prepare("SELECT id, trash_path, snapshot, files FROM trash_items")?;
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                    ))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            rows
+        };
+        let legacy = |path: &str| mapper.relativize_legacy(path).unwrap_or_else(|| path.to_string());
+        for (id, trash_path, snapshot_json, files_json) in trash_rows {
+            let (Ok(mut snapshot), Ok(mut files)) = (
+                serde_json::from_str::<ContentSnapshot>(&snapshot_json),
+                serde_json::from_str::<Vec<TrashedFile>>(&files_json),
+            ) else {
+                continue;
+            };
+            if !Path::new(&trash_path).is_absolute() {
+                continue;
+            }
+            snapshot.map_paths(legacy);
+            if let Some(project) = &mut snapshot.project {
+                project.path = legacy(&project.path);
+            }
+            for file in &mut files {
+                file.original = legacy(&file.original);
+                file.trashed = legacy(&file.trashed);
+            }
+            let snapshot_json = serde_json::to_string(&snapshot)
+                .map_err(|e| DbError::Serialization(e.to_string()))?;
+            let files_json =
+                serde_json::to_string(&files).map_err(|e| DbError::Serialization(e.to_string()))?;
+            tx.execute(
+                "UPDATE trash_items SET trash_path = ?1, snapshot = ?2, files = ?3 WHERE id = ?4",
+                params![legacy(&trash_path), snapshot_json, files_json, id],
+            )?;
+            migrated += 1;
+        }
+
+        tx.commit()?;
+        Ok(migrated)
+    }
+
     /// 初始化数据库 schema
     fn init_schema(&self) -> Result<(), DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        // 思维导图、PPT 与对话的全文索引是后加的，已有数据需要补建索引
+        let has_chat_fts: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'chat_fts'",
+            [],
+            |row| row.get(0),
+        )?;
         conn.execute_batch(include_str!("schema.sql"))?;
 
         // 迁移：为 notes 表添加 output_type 字段（如果不存在）
@@ -69,17 +382,145 @@ impl Database {
             )?;
         }
 
+        // 迁移：为 notes 表添加 title_locked 字段（如果不存在）
+        let has_title_locked: bool = conn
+            .prepare("SELECT COUNT(*) FROM pragma_table_info('notes') WHERE name = 'title_locked'")?
+            .query_row([], |row| row.get::<_, i32>(0))
+            .map(|count| count > 0)
+            .unwrap_or(false);
+
+        if !has_title_locked {
+            conn.execute(
+                "ALTER TABLE notes ADD COLUMN title_locked INTEGER NOT NULL DEFAULT 0",
+                [],
+            )?;
+        }
+
+        // 迁移：为 workspaces 表添加 parent_id 字段（如果不存在）
+        let has_parent_id: bool = conn
+            .prepare("SELECT COUNT(*) FROM pragma_table_info('workspaces') WHERE name = 'parent_id'")?
+            .query_row([], |row| row.get::<_, i32>(0))
+            .map(|count| count > 0)
+            .unwrap_or(false);
+
+        if !has_parent_id {
+            conn.execute("ALTER TABLE workspaces ADD COLUMN parent_id TEXT", [])?;
+        }
+
+        // 迁移：为 projects 表添加 archived_at 字段（如果不存在）
+        let has_archived_at: bool = conn
+            .prepare("SELECT COUNT(*) FROM pragma_table_info('projects') WHERE name = 'archived_at'")?
+            .query_row([], |row| row.get::<_, i32>(0))
+            .map(|count| count > 0)
+            .unwrap_or(false);
+
+        if !has_archived_at {
+            conn.execute("ALTER TABLE projects ADD COLUMN archived_at TEXT", [])?;
+        }
+
+        // 迁移：为 notes 表添加正文字段，笔记全文索引改为同时索引标题与正文
+        // （正文在设置数据目录后从笔记文件回填，见 `backfill_note_contents`）
+        let has_note_text: bool = conn
+            .prepare("SELECT COUNT(*) FROM pragma_table_info('notes') WHERE name = 'text_content'")?
+            .query_row([], |row| row.get::<_, i32>(0))
+            .map(|count| count > 0)
+            .unwrap_or(false);
+
+        if !has_note_text {
+            conn.execute_batch(
+                "BEGIN;
+                 ALTER TABLE notes ADD COLUMN text_content TEXT;
+                 ALTER TABLE notes ADD COLUMN content_hash TEXT;
+                 DROP TRIGGER IF EXISTS notes_ai;
+                 DROP TRIGGER IF EXISTS notes_au;
+                 DROP TRIGGER IF EXISTS notes_ad;
+                 DROP TABLE IF EXISTS notes_fts;",
+            )?;
+            conn.execute_batch(include_str!("schema.sql"))?;
+            conn.execute_batch(
+                "INSERT INTO notes_fts(notes_fts) VALUES('rebuild');
+                 COMMIT;",
+            )?;
+        }
+
+        // 迁移：全文索引改用 trigram 分词器（中文按子串匹配），同时替换旧的 FTS 触发器
+        // （旧触发器对外部内容表执行 UPDATE，改名后索引会损坏）
+        let fts_sql: String = conn.query_row(
+            "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'sources_fts'",
+            [],
+            |row| row.get(0),
+        )?;
+
+        if !fts_sql.contains("trigram") {
+            conn.execute_batch(
+                "BEGIN;
+                 DROP TRIGGER IF EXISTS projects_ai;
+                 DROP TRIGGER IF EXISTS projects_au;
+                 DROP TRIGGER IF EXISTS projects_ad;
+                 DROP TRIGGER IF EXISTS sources_ai;
+                 DROP TRIGGER IF EXISTS sources_au;
+                 DROP TRIGGER IF EXISTS sources_ad;
+                 DROP TRIGGER IF EXISTS notes_ai;
+                 DROP TRIGGER IF EXISTS notes_au;
+                 DROP TRIGGER IF EXISTS notes_ad;
+                 DROP TRIGGER IF EXISTS canvases_ai;
+                 DROP TRIGGER IF EXISTS canvases_au;
+                 DROP TRIGGER IF EXISTS canvases_ad;
+                 DROP TABLE IF EXISTS projects_fts;
+                 DROP TABLE IF EXISTS sources_fts;
+                 DROP TABLE IF EXISTS notes_fts;
+                 ALTER TABLE canvases_fts RENAME TO canvases_fts_old;",
+            )?;
+            conn.execute_batch(include_str!("schema.sql"))?;
+            conn.execute_batch(
+                "INSERT INTO projects_fts(projects_fts) VALUES('rebuild');
+                 INSERT INTO sources_fts(sources_fts) VALUES('rebuild');
+                 INSERT INTO notes_fts(notes_fts) VALUES('rebuild');
+                 INSERT INTO canvases_fts(canvas_id, title, text_content)
+                     SELECT c.id, c.title, o.text_content
+                     FROM canvases_fts_old o JOIN canvases c ON c.id = o.canvas_id;
+                 INSERT INTO canvases_fts(canvas_id, title, text_content)
+                     SELECT id, title, '' FROM canvases
+                     WHERE id NOT IN (SELECT canvas_id FROM canvases_fts);
+                 DROP TABLE canvases_fts_old;
+                 COMMIT;",
+            )?;
+        }
+
+        if !has_chat_fts {
+            for table in ["mindmaps_fts", "presentations_fts", "chat_fts"] {
+                rebuild_standalone_fts(&conn, table)?;
+            }
+        }
+
         Ok(())
     }
 
+    /// 在单个事务中执行一组写操作
+    ///
+    /// 闭包返回 `Err` 时事务回滚。需要与文件写入保持一致时，
+    /// 应在闭包末尾提交暂存文件，文件提交失败即回滚数据库更新。
+    pub fn transaction<T, E, F>(&self, f: F) -> Result<T, E>
+    where
+        F: FnOnce(&DbTx) -> Result<T, E>,
+        E: From<DbError>,
+    {
+        let mut conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let tx = conn.transaction().map_err(DbError::from)?;
+        let result = f(&DbTx::new(&tx, self.path_mapper()))?;
+        tx.commit().map_err(DbError::from)?;
+        Ok(result)
+    }
+
     // ========== Project 操作 ==========
 
     /// 获取所有项目
     pub fn get_all_projects(&self) -> Result<Vec<Project>, DbError> {
+        let paths = self.path_mapper();
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
         let mut stmt = conn.prepare(
             "SELECT id, name, icon_id, icon_name, icon_emoji, icon_color,
-                    workspace, is_starred, created_at, updated_at, sources_count, path
+                    workspace, is_starred, created_at, updated_at, sources_count, path, archived_at
              FROM projects ORDER BY is_starred DESC, updated_at DESC",
         )?;
 
@@ -99,7 +540,8 @@ impl Database {
                     created_at: parse_datetime(&row.get::<_, String>(8)?),
                     updated_at: parse_datetime(&row.get::<_, String>(9)?),
                     sources_count: row.get(10)?,
-                    path: row.get(11)?,
+                    path: paths.resolve(&row.get::<_, String>(11)?),
+                    archived_at: row.get::<_, Option<String>>(12)?.as_deref().map(parse_datetime),
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
@@ -109,10 +551,11 @@ impl Database {
 
     /// 获取单个项目
     pub fn get_project(&self, id: &str) -> Result<Project, DbError> {
+        let paths = self.path_mapper();
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
         let mut stmt = conn.prepare(
             "SELECT id, name, icon_id, icon_name, icon_emoji, icon_color,
-                    workspace, is_starred, created_at, updated_at, sources_count, path
+                    workspace, is_starred, created_at, updated_at, sources_count, path, archived_at
              FROM projects WHERE id = ?1",
         )?;
 
@@ -131,7 +574,8 @@ impl Database {
                 created_at: parse_datetime(&row.get::<_, String>(8)?),
                 updated_at: parse_datetime(&row.get::<_, String>(9)?),
                 sources_count: row.get(10)?,
-                path: row.get(11)?,
+                path: paths.resolve(&row.get::<_, String>(11)?),
+                archived_at: row.get::<_, Option<String>>(12)?.as_deref().map(parse_datetime),
             })
         })
         .map_err(|e| match e {
@@ -145,26 +589,7 @@ impl Database {
     /// 插入项目
     pub fn insert_project(&self, project: &Project) -> Result<(), DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-        conn.execute(
-            "INSERT INTO projects (id, name, icon_id, icon_name, icon_emoji, icon_color,
-                                   workspace, is_starred, created_at, updated_at, sources_count, path)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
-            params![
-                project.id,
-                project.name,
-                project.icon.id,
-                project.icon.name,
-                project.icon.emoji,
-                project.icon.color,
-                project.workspace,
-                project.is_starred as i32,
-                project.created_at.to_rfc3339(),
-                project.updated_at.to_rfc3339(),
-                project.sources_count,
-                project.path,
-            ],
-        )?;
-        Ok(())
+        DbTx::new(&conn, self.path_mapper()).insert_project(project)
     }
 
     /// 检查项目名称是否存在
@@ -204,6 +629,44 @@ impl Database {
         Ok(())
     }
 
+    /// 设置或清除项目的归档时间
+    pub fn update_project_archived(
+        &self,
+        id: &str,
+        archived_at: Option<DateTime<Utc>>,
+    ) -> Result<(), DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let affected = conn.execute(
+            "UPDATE projects SET archived_at = ?1, updated_at = datetime('now') WHERE id = ?2",
+            params![archived_at.map(|at| at.to_rfc3339()), id],
+        )?;
+        if affected == 0 {
+            return Err(DbError::NotFound(format!("项目 {} 不存在", id)));
+        }
+        Ok(())
+    }
+
+    /// 获取条目所属的项目 ID
+    pub fn get_item_project_id(&self, kind: ContentKind, id: &str) -> Result<String, DbError> {
+        let table = match kind {
+            ContentKind::Project => return Ok(id.to_string()),
+            ContentKind::Source => "sources",
+            ContentKind::Note => "notes",
+            ContentKind::Canvas => "canvases",
+            ContentKind::Mindmap => "mindmaps",
+            ContentKind::Presentation => "presentations",
+            ContentKind::ChatSession => "chat_sessions",
+        };
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        conn.query_row(
+            &format!("SELECT project_id FROM {} WHERE id = ?1", table),
+            params![id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or_else(|| DbError::NotFound(format!("{} {} 不存在", kind.as_str(), id)))
+    }
+
     /// 删除项目
     pub fn delete_project(&self, id: &str) -> Result<(), DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
@@ -220,265 +683,664 @@ impl Database {
     pub fn get_all_workspaces(&self) -> Result<Vec<Workspace>, DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
         let mut stmt = conn.prepare(
-            "SELECT id, name, is_system, sort_order FROM workspaces ORDER BY sort_order",
+            "SELECT id, name, is_system, sort_order, parent_id FROM workspaces ORDER BY sort_order",
         )?;
 
         let workspaces = stmt
-            .query_map([], |row| {
-                Ok(Workspace {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    is_system: row.get::<_, i32>(2)? != 0,
-                    order: row.get(3)?,
-                })
-            })?
+            .query_map([], row_to_workspace)?
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(workspaces)
     }
 
+    /// 获取单个工作空间
+    pub fn get_workspace(&self, id: &str) -> Result<Workspace, DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        conn.query_row(
+            "SELECT id, name, is_system, sort_order, parent_id FROM workspaces WHERE id = ?1",
+            params![id],
+            row_to_workspace,
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => {
+                DbError::NotFound(format!("分类 {} 不存在", id))
+            }
+            _ => DbError::Sqlite(e),
+        })
+    }
+
     /// 插入工作空间
     pub fn insert_workspace(&self, workspace: &Workspace) -> Result<(), DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
         conn.execute(
-            "INSERT INTO workspaces (id, name, is_system, sort_order) VALUES (?1, ?2, ?3, ?4)",
+            "INSERT INTO workspaces (id, name, is_system, sort_order, parent_id)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
             params![
                 workspace.id,
                 workspace.name,
                 workspace.is_system as i32,
                 workspace.order,
+                workspace.parent_id,
             ],
         )
-        .map_err(|e| {
-            if let rusqlite::Error::SqliteFailure(err, _) = &e {
-                if err.extended_code == 2067 {
-                    // UNIQUE constraint failed
-                    return DbError::AlreadyExists(format!("分类 {} 已存在", workspace.name));
-                }
-            }
-            DbError::Sqlite(e)
-        })?;
+        .map_err(|e| workspace_name_conflict(e, &workspace.name))?;
         Ok(())
     }
 
-    /// 删除工作空间
-    pub fn delete_workspace(&self, id: &str) -> Result<(), DbError> {
+    /// 重命名工作空间
+    pub fn rename_workspace(&self, id: &str, name: &str) -> Result<(), DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-
-        // 检查是否是系统分类
-        let is_system: i32 = conn
-            .query_row(
-                "SELECT is_system FROM workspaces WHERE id = ?1",
-                params![id],
-                |row| row.get(0),
+        let affected = conn
+            .execute(
+                "UPDATE workspaces SET name = ?1 WHERE id = ?2",
+                params![name, id],
             )
-            .map_err(|e| match e {
-                rusqlite::Error::QueryReturnedNoRows => {
-                    DbError::NotFound(format!("分类 {} 不存在", id))
-                }
-                _ => DbError::Sqlite(e),
-            })?;
+            .map_err(|e| workspace_name_conflict(e, name))?;
+        if affected == 0 {
+            return Err(DbError::NotFound(format!("分类 {} 不存在", id)));
+        }
+        Ok(())
+    }
+
+    /// 设置上级工作空间（`None` 表示移到顶层）
+    pub fn set_workspace_parent(&self, id: &str, parent_id: Option<&str>) -> Result<(), DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let affected = conn.execute(
+            "UPDATE workspaces SET parent_id = ?1 WHERE id = ?2",
+            params![parent_id, id],
+        )?;
+        if affected == 0 {
+            return Err(DbError::NotFound(format!("分类 {} 不存在", id)));
+        }
+        Ok(())
+    }
 
-        if is_system != 0 {
-            return Err(DbError::NotFound("系统分类不能删除".to_string()));
+    /// 调整工作空间顺序
+    ///
+    /// `ids` 可以只包含部分工作空间（例如同一上级下的子分类），
+    /// 它们按给定顺序填回原来所占的位置，其余工作空间位置不变。
+    pub fn reorder_workspaces(&self, ids: &[String]) -> Result<(), DbError> {
+        let mut conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let tx = conn.transaction()?;
+        let current: Vec<String> = tx
+            .prepare("SELECT id FROM workspaces ORDER BY sort_order, rowid")?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        if let Some(missing) = ids.iter().find(|id| !current.contains(id)) {
+            return Err(DbError::NotFound(format!("分类 {} 不存在", missing)));
         }
 
-        conn.execute("DELETE FROM workspaces WHERE id = ?1", params![id])?;
+        let mut reordered = ids.iter();
+        let ordered: Vec<&String> = current
+            .iter()
+            .map(|id| {
+                if ids.contains(id) {
+                    reordered.next().unwrap_or(id)
+                } else {
+                    id
+                }
+            })
+            .collect();
+        for (order, id) in ordered.iter().enumerate() {
+            tx.execute(
+                "UPDATE workspaces SET sort_order = ?1 WHERE id = ?2",
+                params![order as i32, id],
+            )?;
+        }
+        tx.commit()?;
         Ok(())
     }
 
     /// 统计工作空间下的项目数量
     pub fn count_projects_in_workspace(&self, workspace_id: &str) -> Result<u32, DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-        let count: u32 = conn.query_row(
-            "SELECT COUNT(*) FROM projects WHERE workspace = ?1",
-            params![workspace_id],
-            |row| row.get(0),
+        DbTx::new(&conn, self.path_mapper()).count_projects_in_workspace(workspace_id)
+    }
+
+    /// 把项目移到另一个工作空间
+    pub fn update_project_workspace(&self, id: &str, workspace_id: &str) -> Result<(), DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let affected = conn.execute(
+            "UPDATE projects SET workspace = ?1, updated_at = datetime('now') WHERE id = ?2",
+            params![workspace_id, id],
         )?;
-        Ok(count)
+        if affected == 0 {
+            return Err(DbError::NotFound(format!("项目 {} 不存在", id)));
+        }
+        Ok(())
     }
 
-    // ========== Recent Access 操作 ==========
+    // ========== Stats / Activity 操作 ==========
 
-    /// 获取最近访问记录
-    pub fn get_recent_accesses(&self, limit: u32) -> Result<Vec<RecentAccess>, DbError> {
+    /// 统计项目下的内容数量（`storage_bytes` 由调用方根据项目目录计算）
+    pub fn get_project_stats(&self, project_id: &str) -> Result<ProjectStats, DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let count = |sql: &str| -> Result<u32, DbError> {
+            Ok(conn.query_row(sql, params![project_id], |row| row.get(0))?)
+        };
+
+        let mut sources_by_type = BTreeMap::new();
+        let mut stmt =
+            conn.prepare("SELECT type, COUNT(*) FROM sources WHERE project_id = ?1 GROUP BY type")?;
+        let rows = stmt.query_map(params![project_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, u32>(1)?))
+        })?;
+        for row in rows {
+            let (source_type, count) = row?;
+            sources_by_type.insert(source_type, count);
+        }
+
+        Ok(ProjectStats {
+            project_id: project_id.to_string(),
+            sources_count: sources_by_type.values().sum(),
+            sources_by_type,
+            notes_count: count("SELECT COUNT(*) FROM notes WHERE project_id = ?1")?,
+            canvases_count: count("SELECT COUNT(*) FROM canvases WHERE project_id = ?1")?,
+            mindmaps_count: count("SELECT COUNT(*) FROM mindmaps WHERE project_id = ?1")?,
+            presentations_count: count("SELECT COUNT(*) FROM presentations WHERE project_id = ?1")?,
+            chat_sessions_count: count("SELECT COUNT(*) FROM chat_sessions WHERE project_id = ?1")?,
+            chat_messages_count: count(
+                "SELECT COUNT(*) FROM chat_messages m
+                 JOIN chat_sessions s ON s.id = m.session_id
+                 WHERE s.project_id = ?1",
+            )?,
+            storage_bytes: 0,
+        })
+    }
+
+    /// 按项目重新计算 sources_count
+    pub fn recount_project_sources(&self, project_id: &str) -> Result<(), DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        DbTx::new(&conn, self.path_mapper()).recount_project_sources(project_id)
+    }
+
+    /// 获取活动日志（最新的在前）
+    ///
+    /// `project_id` 为空时返回全部项目的活动；`before_id` 用于分页，只返回更早的条目。
+    pub fn get_activity(
+        &self,
+        project_id: Option<&str>,
+        before_id: Option<i64>,
+        limit: u32,
+    ) -> Result<Vec<ActivityEntry>, DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
         let mut stmt = conn.prepare(
-            "SELECT r.id, r.project_id, p.name, r.accessed_at
-             FROM recent_accesses r
-             JOIN projects p ON r.project_id = p.id
-             ORDER BY r.accessed_at DESC
-             LIMIT ?1",
+            "SELECT id, project_id, kind, item_id, action, title, created_at
+             FROM activity_log
+             WHERE (?1 IS NULL OR project_id = ?1) AND (?2 IS NULL OR id < ?2)
+             ORDER BY id DESC
+             LIMIT ?3",
         )?;
-
-        let accesses = stmt
-            .query_map(params![limit], |row| {
-                Ok(RecentAccess {
+        let entries = stmt
+            .query_map(params![project_id, before_id, limit], |row| {
+                Ok(ActivityEntry {
                     id: row.get(0)?,
                     project_id: row.get(1)?,
-                    project_name: row.get(2)?,
-                    accessed_at: parse_datetime(&row.get::<_, String>(3)?),
+                    kind: ContentKind::from_str(&row.get::<_, String>(2)?)
+                        .unwrap_or(ContentKind::Note),
+                    item_id: row.get(3)?,
+                    action: match row.get::<_, String>(4)?.as_str() {
+                        "create" => ActivityAction::Create,
+                        "delete" => ActivityAction::Delete,
+                        _ => ActivityAction::Update,
+                    },
+                    title: row.get(5)?,
+                    created_at: parse_datetime(&row.get::<_, String>(6)?),
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
+    // ========== 一致性检查 ==========
+
+    /// 列出所有记录引用的数据文件（绝对路径）
+    pub fn list_file_references(&self) -> Result<Vec<FileReference>, DbError> {
+        const QUERIES: [(ContentKind, &str, bool); 6] = [
+            (ContentKind::Source, "SELECT id, project_id, name, path FROM sources", false),
+            (
+                ContentKind::Source,
+                "SELECT id, project_id, name, thumbnail_path FROM sources WHERE thumbnail_path IS NOT NULL",
+                true,
+            ),
+            (ContentKind::Note, "SELECT id, project_id, title, path FROM notes", false),
+            (ContentKind::Canvas, "SELECT id, project_id, title, path FROM canvases", false),
+            (
+                ContentKind::Presentation,
+                "SELECT id, project_id, title, data_path FROM presentations",
+                false,
+            ),
+            (
+                ContentKind::Presentation,
+                "SELECT id, project_id, title, thumbnail_path FROM presentations WHERE thumbnail_path IS NOT NULL",
+                true,
+            ),
+        ];
+
+        let paths = self.path_mapper();
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let mut references = Vec::new();
+        for (kind, sql, thumbnail) in QUERIES {
+            let mut stmt = conn.prepare(sql)?;
+            let rows = stmt.query_map([], |row| {
+                Ok(FileReference {
+                    kind,
+                    item_id: row.get(0)?,
+                    project_id: row.get(1)?,
+                    title: row.get(2)?,
+                    path: paths.resolve(&row.get::<_, String>(3)?),
+                    thumbnail,
+                })
+            })?;
+            for row in rows {
+                references.push(row?);
+            }
+        }
+        Ok(references)
+    }
 
-        Ok(accesses)
+    /// 查找悬空记录（所属项目、会话或条目已不存在）
+    pub fn find_dangling_rows(&self) -> Result<Vec<DanglingRow>, DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let mut rows = Vec::new();
+        for (table, key, project, condition, detail) in DANGLING_ROW_CHECKS {
+            let sql = format!("SELECT {}, {} FROM {} WHERE {}", key, project, table, condition);
+            let mut stmt = conn.prepare(&sql)?;
+            let found = stmt.query_map([], |row| {
+                Ok(DanglingRow {
+                    table,
+                    key: row.get(0)?,
+                    project_id: row.get(1)?,
+                    detail,
+                })
+            })?;
+            for row in found {
+                rows.push(row?);
+            }
+        }
+        Ok(rows)
     }
 
-    /// 添加访问记录
-    pub fn add_recent_access(&self, project_id: &str) -> Result<(), DbError> {
+    /// 删除所有悬空记录，返回删除数量
+    pub fn delete_dangling_rows(&self) -> Result<usize, DbError> {
+        let mut conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let tx = conn.transaction()?;
+        let mut deleted = 0;
+        for (table, _, _, condition, _) in DANGLING_ROW_CHECKS {
+            deleted += tx.execute(&format!("DELETE FROM {} WHERE {}", table, condition), [])?;
+        }
+        tx.commit()?;
+        Ok(deleted)
+    }
+
+    /// 查找 sources_count 与实际来源数量不一致的项目：(项目 ID, 记录值, 实际值)
+    pub fn find_sources_count_drift(&self) -> Result<Vec<(String, u32, u32)>, DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-        let id = uuid::Uuid::new_v4().to_string();
+        let mut stmt = conn.prepare(
+            "SELECT p.id, p.sources_count, (SELECT COUNT(*) FROM sources s WHERE s.project_id = p.id) AS actual
+             FROM projects p
+             WHERE p.sources_count != actual",
+        )?;
+        let drift = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(drift)
+    }
 
-        // 删除旧的同项目访问记录
-        conn.execute(
-            "DELETE FROM recent_accesses WHERE project_id = ?1",
-            params![project_id],
+    /// 查找无法解析的 JSON 字段
+    pub fn find_bad_json(&self) -> Result<Vec<BadJsonRow>, DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let mut bad = Vec::new();
+        let mut scan = |table: &'static str,
+                        column: &'static str,
+                        sql: &str,
+                        valid: &dyn Fn(&str) -> bool|
+         -> Result<(), DbError> {
+            let mut stmt = conn.prepare(sql)?;
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?;
+            for row in rows {
+                let (key, json) = row?;
+                if !valid(&json) {
+                    bad.push(BadJsonRow { table, column, key });
+                }
+            }
+            Ok(())
+        };
+
+        scan(
+            "chat_messages",
+            "citations",
+            "SELECT id, citations FROM chat_messages WHERE citations IS NOT NULL",
+            &|json| serde_json::from_str::<Vec<Citation>>(json).is_ok(),
+        )?;
+        scan(
+            "mindmaps",
+            "data",
+            "SELECT id, data FROM mindmaps",
+            &|json| serde_json::from_str::<serde_json::Value>(json).is_ok(),
+        )?;
+        scan(
+            "trash_items",
+            "snapshot",
+            "SELECT id, snapshot FROM trash_items",
+            &|json| serde_json::from_str::<ContentSnapshot>(json).is_ok(),
+        )?;
+        scan(
+            "trash_items",
+            "files",
+            "SELECT id, files FROM trash_items",
+            &|json| serde_json::from_str::<Vec<TrashedFile>>(json).is_ok(),
+        )?;
+        scan(
+            "sync_records",
+            "change",
+            "SELECT kind || '/' || item_id, change FROM sync_records",
+            &|json| serde_json::from_str::<SyncChange>(json).is_ok(),
         )?;
+        Ok(bad)
+    }
 
-        // 插入新记录
+    /// 清除无法解析的对话引用
+    pub fn clear_message_citations(&self, message_id: &str) -> Result<(), DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
         conn.execute(
-            "INSERT INTO recent_accesses (id, project_id, accessed_at) VALUES (?1, ?2, datetime('now'))",
-            params![id, project_id],
+            "UPDATE chat_messages SET citations = NULL WHERE id = ?1",
+            params![message_id],
         )?;
+        Ok(())
+    }
 
-        // 保留最近 10 条记录
+    /// 删除同步记录（下次同步时按新条目处理）
+    pub fn delete_sync_record(&self, kind: &str, item_id: &str) -> Result<(), DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
         conn.execute(
-            "DELETE FROM recent_accesses WHERE id NOT IN (
-                SELECT id FROM recent_accesses ORDER BY accessed_at DESC LIMIT 10
-            )",
-            [],
+            "DELETE FROM sync_records WHERE kind = ?1 AND item_id = ?2",
+            params![kind, item_id],
         )?;
-
         Ok(())
     }
 
-    // ========== Search 操作 ==========
-
-    /// 全文搜索项目
-    pub fn search_projects(&self, query: &str, limit: u32) -> Result<Vec<SearchResult>, DbError> {
+    /// 清除缺失的缩略图引用
+    pub fn clear_thumbnail(&self, kind: ContentKind, id: &str) -> Result<(), DbError> {
+        let table = match kind {
+            ContentKind::Source => "sources",
+            ContentKind::Presentation => "presentations",
+            _ => return Ok(()),
+        };
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-
-        // 使用 LIKE 查询（对中文友好）
-        let mut stmt = conn.prepare(
-            "SELECT id, name, updated_at FROM projects WHERE name LIKE ?1 LIMIT ?2",
+        conn.execute(
+            &format!("UPDATE {} SET thumbnail_path = NULL WHERE id = ?1", table),
+            params![id],
         )?;
+        Ok(())
+    }
 
-        let search_pattern = format!("%{}%", query);
-        let results = stmt
-            .query_map(params![search_pattern, limit], |row| {
-                let id: String = row.get(0)?;
-                let name: String = row.get(1)?;
-                let updated_at: String = row.get(2)?;
-                Ok(SearchResult {
-                    result_type: "project".to_string(),
-                    id: id.clone(),
-                    title: name.clone(),
-                    snippet: name.clone(),
-                    score: None,
-                    project_id: id,
-                    project_name: name,
-                    updated_at: parse_datetime(&updated_at),
-                })
-            })?
-            .collect::<Result<Vec<_>, _>>()?;
-
-        Ok(results)
+    /// 检查全文索引，返回与数据表不一致的索引表名
+    pub fn check_fts(&self) -> Result<Vec<&'static str>, DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let mut drifted = Vec::new();
+        for table in CONTENT_FTS_TABLES {
+            // rank = 1 时同时比对外部内容表
+            let sql = format!("INSERT INTO {0}({0}, rank) VALUES('integrity-check', 1)", table);
+            match conn.execute(&sql, []) {
+                Ok(_) => {}
+                Err(rusqlite::Error::SqliteFailure(err, _))
+                    if err.code == rusqlite::ErrorCode::DatabaseCorrupt =>
+                {
+                    drifted.push(table)
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        for (table, key, source) in STANDALONE_FTS_TABLES {
+            let orphaned: i64 = conn.query_row(
+                &format!(
+                    "SELECT (SELECT COUNT(*) FROM {table} WHERE {key} NOT IN (SELECT id FROM {source}))
+                          + (SELECT COUNT(*) FROM {source} WHERE id NOT IN (SELECT {key} FROM {table}))"
+                ),
+                [],
+                |row| row.get(0),
+            )?;
+            if orphaned > 0 {
+                drifted.push(table);
+            }
+        }
+        Ok(drifted)
     }
 
-    /// 搜索来源（使用 FTS5 全文搜索）
-    pub fn search_sources(&self, query: &str, limit: u32) -> Result<Vec<SearchResult>, DbError> {
+    /// 重建全文索引
+    ///
+    /// 画布与 PPT 的文本来自数据文件，这里只清理孤立记录并补齐缺失的条目（文本为空），
+    /// 文本由调用方重新提取；思维导图与对话的文本按数据表重新生成。
+    pub fn rebuild_fts(&self, table: &str) -> Result<(), DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        if CONTENT_FTS_TABLES.contains(&table) {
+            conn.execute(
+                &format!("INSERT INTO {0}({0}) VALUES('rebuild')", table),
+                [],
+            )?;
+            Ok(())
+        } else {
+            rebuild_standalone_fts(&conn, table)
+        }
+    }
+
+    // ========== Recent Access 操作 ==========
 
-        // 使用 LIKE 查询搜索来源名称和文本内容
+    /// 获取最近访问记录
+    pub fn get_recent_accesses(&self, limit: u32) -> Result<Vec<RecentAccess>, DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
         let mut stmt = conn.prepare(
-            "SELECT s.id, s.name, s.project_id, p.name as project_name,
-                    SUBSTR(s.text_content, 1, 200) as snippet, s.updated_at
-             FROM sources s
-             JOIN projects p ON s.project_id = p.id
-             WHERE s.name LIKE ?1 OR s.text_content LIKE ?1
-             ORDER BY s.updated_at DESC
-             LIMIT ?2",
+            "SELECT r.id, r.project_id, p.name, r.accessed_at
+             FROM recent_accesses r
+             JOIN projects p ON r.project_id = p.id
+             ORDER BY r.accessed_at DESC
+             LIMIT ?1",
         )?;
 
-        let search_pattern = format!("%{}%", query);
-        let results = stmt
-            .query_map(params![search_pattern, limit], |row| {
-                Ok(SearchResult {
-                    result_type: "source".to_string(),
+        let accesses = stmt
+            .query_map(params![limit], |row| {
+                Ok(RecentAccess {
                     id: row.get(0)?,
-                    title: row.get(1)?,
-                    snippet: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
-                    score: None,
-                    project_id: row.get(2)?,
-                    project_name: row.get(3)?,
-                    updated_at: parse_datetime(&row.get::<_, String>(5)?),
+                    project_id: row.get(1)?,
+                    project_name: row.get(2)?,
+                    accessed_at: parse_datetime(&row.get::<_, String>(3)?),
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(results)
+        Ok(accesses)
     }
 
-    /// 搜索笔记
-    pub fn search_notes(&self, query: &str, limit: u32) -> Result<Vec<SearchResult>, DbError> {
+    /// 添加访问记录
+    pub fn add_recent_access(&self, project_id: &str) -> Result<(), DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let id = uuid::Uuid::new_v4().to_string();
 
-        let mut stmt = conn.prepare(
-            "SELECT n.id, n.title, n.project_id, p.name as project_name, n.updated_at
-             FROM notes n
-             JOIN projects p ON n.project_id = p.id
-             WHERE n.title LIKE ?1
-             ORDER BY n.updated_at DESC
-             LIMIT ?2",
+        // 删除旧的同项目访问记录
+        conn.execute(
+            "DELETE FROM recent_accesses WHERE project_id = ?1",
+            params![project_id],
         )?;
 
-        let search_pattern = format!("%{}%", query);
-        let results = stmt
-            .query_map(params![search_pattern, limit], |row| {
-                let title: String = row.get(1)?;
-                Ok(SearchResult {
-                    result_type: "note".to_string(),
-                    id: row.get(0)?,
-                    title: title.clone(),
-                    snippet: title,
-                    score: None,
-                    project_id: row.get(2)?,
-                    project_name: row.get(3)?,
-                    updated_at: parse_datetime(&row.get::<_, String>(4)?),
-                })
-            })?
-            .collect::<Result<Vec<_>, _>>()?;
+        // 插入新记录
+        conn.execute(
+            "INSERT INTO recent_accesses (id, project_id, accessed_at) VALUES (?1, ?2, datetime('now'))",
+            params![id, project_id],
+        )?;
 
-        Ok(results)
+        // 保留最近 10 条记录
+        conn.execute(
+            "DELETE FROM recent_accesses WHERE id NOT IN (
+                SELECT id FROM recent_accesses ORDER BY accessed_at DESC LIMIT 10
+            )",
+            [],
+        )?;
+
+        Ok(())
     }
 
-    /// 搜索画布
-    pub fn search_canvases(&self, query: &str, limit: u32) -> Result<Vec<SearchResult>, DbError> {
-        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+    // ========== Search 操作 ==========
+    //
+    // `project_id` 为 Some 时只搜索该项目（即使已归档），否则按 `include_archived` 决定是否包含归档项目。
 
-        let mut stmt = conn.prepare(
-            "SELECT c.id, c.title, c.project_id, p.name as project_name, c.updated_at
-             FROM canvases c
-             JOIN projects p ON c.project_id = p.id
-             WHERE c.title LIKE ?1
-             ORDER BY c.updated_at DESC
-             LIMIT ?2",
-        )?;
+    /// 全文搜索项目名称
+    pub fn search_projects(
+        &self,
+        query: &str,
+        project_id: Option<&str>,
+        limit: u32,
+        include_archived: bool,
+    ) -> Result<Vec<SearchResult>, DbError> {
+        match SearchQuery::plain(query) {
+            Some(query) => self.search_fts(&PROJECT_FTS, &query, project_id, limit, SearchSort::Relevance, include_archived),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// 全文搜索来源（名称与提取文本）
+    pub fn search_sources(
+        &self,
+        query: &str,
+        project_id: Option<&str>,
+        limit: u32,
+        include_archived: bool,
+    ) -> Result<Vec<SearchResult>, DbError> {
+        match SearchQuery::plain(query) {
+            Some(query) => self.search_fts(&SOURCE_FTS, &query, project_id, limit, SearchSort::Relevance, include_archived),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// 全文搜索笔记（标题与正文）
+    pub fn search_notes(
+        &self,
+        query: &str,
+        project_id: Option<&str>,
+        limit: u32,
+        include_archived: bool,
+    ) -> Result<Vec<SearchResult>, DbError> {
+        match SearchQuery::plain(query) {
+            Some(query) => self.search_fts(&NOTE_FTS, &query, project_id, limit, SearchSort::Relevance, include_archived),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// 全文搜索画布（标题与文本元素）
+    pub fn search_canvases(
+        &self,
+        query: &str,
+        project_id: Option<&str>,
+        limit: u32,
+        include_archived: bool,
+    ) -> Result<Vec<SearchResult>, DbError> {
+        match SearchQuery::plain(query) {
+            Some(query) => self.search_fts(&CANVAS_FTS, &query, project_id, limit, SearchSort::Relevance, include_archived),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// 全文搜索思维导图（标题与节点文本、备注、标签）
+    pub fn search_mindmaps(
+        &self,
+        query: &str,
+        project_id: Option<&str>,
+        limit: u32,
+        include_archived: bool,
+    ) -> Result<Vec<SearchResult>, DbError> {
+        match SearchQuery::plain(query) {
+            Some(query) => self.search_fts(&MINDMAP_FTS, &query, project_id, limit, SearchSort::Relevance, include_archived),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// 全文搜索PPT（标题、文本元素与演讲者备注）
+    pub fn search_presentations(
+        &self,
+        query: &str,
+        project_id: Option<&str>,
+        limit: u32,
+        include_archived: bool,
+    ) -> Result<Vec<SearchResult>, DbError> {
+        match SearchQuery::plain(query) {
+            Some(query) => self.search_fts(&PRESENTATION_FTS, &query, project_id, limit, SearchSort::Relevance, include_archived),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// 全文搜索对话（会话标题与消息内容）
+    pub fn search_chats(
+        &self,
+        query: &str,
+        project_id: Option<&str>,
+        limit: u32,
+        include_archived: bool,
+    ) -> Result<Vec<SearchResult>, DbError> {
+        match SearchQuery::plain(query) {
+            Some(query) => self.search_fts(&CHAT_FTS, &query, project_id, limit, SearchSort::Relevance, include_archived),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// 在一种内容的全文索引中检索，`score` 越大越相关（bm25）
+    fn search_fts(
+        &self,
+        target: &FtsTarget,
+        query: &SearchQuery,
+        project_id: Option<&str>,
+        limit: u32,
+        sort: SearchSort,
+        include_archived: bool,
+    ) -> Result<Vec<SearchResult>, DbError> {
+        let clauses = fts::compile(query, target);
+        // 最后按 ID（第 1 列）排序，保证分页稳定
+        let order = match sort {
+            SearchSort::Relevance => format!("score DESC, {} DESC, 1", target.updated_at),
+            SearchSort::Date => format!("{} DESC, 1", target.updated_at),
+        };
+        let sql = format!(
+            "SELECT {select}, {score} AS score
+             FROM {from}
+             WHERE {condition}
+               AND (?{archived} OR p.archived_at IS NULL OR ?{project} IS NOT NULL)
+               AND (?{project} IS NULL OR p.id = ?{project})
+             ORDER BY {order}
+             LIMIT ?{limit}",
+            select = target.select,
+            score = clauses.score,
+            from = target.from,
+            condition = clauses.condition,
+            archived = clauses.next_param,
+            project = clauses.next_param + 1,
+            limit = clauses.next_param + 2,
+        );
+
+        let mut params = clauses.params;
+        params.push(Value::Integer(include_archived as i64));
+        params.push(project_id.map_or(Value::Null, |id| Value::Text(id.to_string())));
+        params.push(Value::Integer(limit as i64));
 
-        let search_pattern = format!("%{}%", query);
+        let terms = query.positive_terms();
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let mut stmt = conn.prepare(&sql)?;
         let results = stmt
-            .query_map(params![search_pattern, limit], |row| {
+            .query_map(params_from_iter(params), |row| {
                 let title: String = row.get(1)?;
+                let text: Option<String> = row.get(4)?;
+                let (snippet, snippets) = build_snippets(text.as_deref(), &terms, &title);
                 Ok(SearchResult {
-                    result_type: "canvas".to_string(),
+                    result_type: target.result_type.to_string(),
                     id: row.get(0)?,
-                    title: title.clone(),
-                    snippet: title,
-                    score: None,
+                    title,
+                    snippet,
+                    score: Some(row.get::<_, f64>(6)? as f32),
                     project_id: row.get(2)?,
                     project_name: row.get(3)?,
-                    updated_at: parse_datetime(&row.get::<_, String>(4)?),
+                    updated_at: parse_datetime(&row.get::<_, String>(5)?),
+                    score_breakdown: None,
+                    snippets,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
@@ -486,79 +1348,66 @@ impl Database {
         Ok(results)
     }
 
-    /// 搜索画布内容（使用 FTS5 全文搜索文本元素）
-    pub fn search_canvases_content(&self, query: &str, limit: u32) -> Result<Vec<SearchResult>, DbError> {
-        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-
-        // 尝试用 canvases_fts 表搜索
-        let stmt = conn.prepare(
-            "SELECT c.id, c.title, c.project_id, p.name as project_name, c.updated_at
-             FROM canvases_fts f
-             JOIN canvases c ON f.canvas_id = c.id
-             JOIN projects p ON c.project_id = p.id
-             WHERE canvases_fts MATCH ?1
-             ORDER BY c.updated_at DESC
-             LIMIT ?2",
-        );
-
-        match stmt {
-            Ok(mut stmt) => {
-                let results = stmt
-                    .query_map(params![query, limit], |row| {
-                        let title: String = row.get(1)?;
-                        Ok(SearchResult {
-                            result_type: "canvas".to_string(),
-                            id: row.get(0)?,
-                            title: title.clone(),
-                            snippet: title,
-                            score: None,
-                            project_id: row.get(2)?,
-                            project_name: row.get(3)?,
-                            updated_at: parse_datetime(&row.get::<_, String>(4)?),
-                        })
-                    })?
-                    .collect::<Result<Vec<_>, _>>()?;
-                Ok(results)
-            }
-            Err(_) => {
-                // 如果 FTS 表不存在，回退到普通搜索
-                self.search_canvases(query, limit)
-            }
-        }
-    }
-
-    /// 统一搜索（搜索项目、来源、笔记、画布）
-    pub fn search_all(&self, query: &str, limit: u32) -> Result<Vec<SearchResult>, DbError> {
+    /// 统一搜索（搜索项目、来源、笔记、画布），支持查询语法中的字段过滤
+    ///
+    /// 各类型的结果合并后按 `sort` 排序，跳过前 `offset` 条后返回至多 `limit` 条。
+    pub fn search_all(
+        &self,
+        query: &SearchQuery,
+        project_id: Option<&str>,
+        limit: u32,
+        offset: u32,
+        sort: SearchSort,
+        include_archived: bool,
+    ) -> Result<Vec<SearchResult>, DbError> {
+        // 每种类型都取足当前页之前的全部候选，合并排序后再分页
+        let window = offset.saturating_add(limit);
         let mut results = Vec::new();
+        for target in [
+            &PROJECT_FTS,
+            &SOURCE_FTS,
+            &NOTE_FTS,
+            &CANVAS_FTS,
+            &MINDMAP_FTS,
+            &PRESENTATION_FTS,
+            &CHAT_FTS,
+        ] {
+            results.extend(self.search_fts(target, query, project_id, window, sort, include_archived)?);
+        }
 
-        // 每种类型分配部分配额
-        let per_type_limit = (limit / 4).max(2);
-
-        // 搜索项目
-        results.extend(self.search_projects(query, per_type_limit)?);
-
-        // 搜索来源
-        results.extend(self.search_sources(query, per_type_limit)?);
-
-        // 搜索笔记
-        results.extend(self.search_notes(query, per_type_limit)?);
-
-        // 搜索画布
-        results.extend(self.search_canvases(query, per_type_limit)?);
-
-        // 限制总数
-        results.truncate(limit as usize);
+        results.sort_by(|a, b| {
+            let by_date = b
+                .updated_at
+                .cmp(&a.updated_at)
+                .then_with(|| a.result_type.cmp(&b.result_type))
+                .then_with(|| a.id.cmp(&b.id));
+            match sort {
+                SearchSort::Relevance => b
+                    .score
+                    .partial_cmp(&a.score)
+                    .unwrap_or(Ordering::Equal)
+                    .then(by_date),
+                SearchSort::Date => by_date,
+            }
+        });
 
-        Ok(results)
+        Ok(results
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
     }
 
     // ========== 语义检索 ==========
 
-    /// 语义搜索来源
+    /// 语义搜索来源，摘要截取正文中 `terms` 的命中位置
     pub fn search_sources_semantic(
         &self,
         query_embedding: &[f32],
+        terms: &[&str],
+        project_id: Option<&str>,
         limit: u32,
+        include_archived: bool,
     ) -> Result<Vec<SearchResult>, DbError> {
         if query_embedding.is_empty() {
             return Ok(Vec::new());
@@ -570,10 +1419,12 @@ impl Database {
                     s.text_content, e.embedding, s.updated_at
              FROM source_embeddings e
              JOIN sources s ON e.source_id = s.id
-             JOIN projects p ON s.project_id = p.id",
+             JOIN projects p ON s.project_id = p.id
+             WHERE (?1 OR p.archived_at IS NULL OR ?2 IS NOT NULL)
+               AND (?2 IS NULL OR e.project_id = ?2)",
         )?;
 
-        let rows = stmt.query_map([], |row| {
+        let rows = stmt.query_map(params![include_archived, project_id], |row| {
             Ok((
                 row.get::<_, String>(0)?,
                 row.get::<_, String>(1)?,
@@ -593,19 +1444,21 @@ impl Database {
                 continue;
             }
             let score = cosine_similarity(query_embedding, &embedding).max(0.0).min(1.0);
-            let snippet = build_snippet(text_content);
+            let (snippet, snippets) = build_snippets(text_content.as_deref(), terms, &name);
 
             scored.push((
                 score,
                 SearchResult {
                     result_type: "source".to_string(),
                     id,
-                    title: name.clone(),
-                    snippet: if snippet.is_empty() { name } else { snippet },
+                    title: name,
+                    snippet,
                     score: Some(score),
                     project_id,
                     project_name,
                     updated_at: parse_datetime(&updated_at),
+                    score_breakdown: None,
+                    snippets,
                 },
             ));
         }
@@ -616,11 +1469,14 @@ impl Database {
         Ok(scored.into_iter().map(|(_, result)| result).collect())
     }
 
-    /// 语义搜索笔记
+    /// 语义搜索笔记，摘要截取正文中 `terms` 的命中位置
     pub fn search_notes_semantic(
         &self,
         query_embedding: &[f32],
+        terms: &[&str],
+        project_id: Option<&str>,
         limit: u32,
+        include_archived: bool,
     ) -> Result<Vec<SearchResult>, DbError> {
         if query_embedding.is_empty() {
             return Ok(Vec::new());
@@ -628,13 +1484,16 @@ impl Database {
 
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
         let mut stmt = conn.prepare(
-            "SELECT n.id, n.title, n.project_id, p.name as project_name, e.embedding, n.updated_at
+            "SELECT n.id, n.title, n.project_id, p.name as project_name, e.embedding, n.updated_at,
+                    n.text_content
              FROM note_embeddings e
              JOIN notes n ON e.note_id = n.id
-             JOIN projects p ON n.project_id = p.id",
+             JOIN projects p ON n.project_id = p.id
+             WHERE (?1 OR p.archived_at IS NULL OR ?2 IS NOT NULL)
+               AND (?2 IS NULL OR e.project_id = ?2)",
         )?;
 
-        let rows = stmt.query_map([], |row| {
+        let rows = stmt.query_map(params![include_archived, project_id], |row| {
             Ok((
                 row.get::<_, String>(0)?,
                 row.get::<_, String>(1)?,
@@ -642,29 +1501,33 @@ impl Database {
                 row.get::<_, String>(3)?,
                 row.get::<_, Vec<u8>>(4)?,
                 row.get::<_, String>(5)?,
+                row.get::<_, Option<String>>(6)?,
             ))
         })?;
 
         let mut scored = Vec::new();
         for row in rows {
-            let (id, title, project_id, project_name, embedding_blob, updated_at) = row?;
+            let (id, title, project_id, project_name, embedding_blob, updated_at, text_content) = row?;
             let embedding = deserialize_embedding(&embedding_blob);
             if embedding.is_empty() {
                 continue;
             }
             let score = cosine_similarity(query_embedding, &embedding).max(0.0).min(1.0);
+            let (snippet, snippets) = build_snippets(text_content.as_deref(), terms, &title);
 
             scored.push((
                 score,
                 SearchResult {
                     result_type: "note".to_string(),
                     id,
-                    title: title.clone(),
-                    snippet: title,
+                    title,
+                    snippet,
                     score: Some(score),
                     project_id,
                     project_name,
                     updated_at: parse_datetime(&updated_at),
+                    score_breakdown: None,
+                    snippets,
                 },
             ));
         }
@@ -675,6 +1538,88 @@ impl Database {
         Ok(scored.into_iter().map(|(_, result)| result).collect())
     }
 
+    /// 相关条目推荐：与来源或笔记的向量最相似的来源与笔记（不含自身），每组最多 `limit` 条
+    ///
+    /// 默认只在条目所属项目中查找，`library_wide` 为 true 时在整个资料库（不含归档项目）中查找；
+    /// 条目没有向量或类型不是来源、笔记时返回空结果。结果按向量版本号缓存，
+    /// 任一向量写入或删除（`upsert_*_embedding` 等）或项目归档状态变化后失效。
+    pub fn related_items(
+        &self,
+        kind: ContentKind,
+        id: &str,
+        library_wide: bool,
+        limit: u32,
+    ) -> Result<RelatedItems, DbError> {
+        let key: RelatedKey = (kind, id.to_string(), library_wide, limit);
+        let version = self.embedding_version()?;
+        {
+            let mut cache = self.related_cache.lock().map_err(|_| DbError::LockError)?;
+            match cache.get(&key) {
+                Some((cached, items)) if *cached == version => return Ok(items.clone()),
+                // 版本号变化后所有缓存都已过期
+                Some(_) => cache.clear(),
+                None => {}
+            }
+        }
+
+        let sql = match kind {
+            ContentKind::Source => "SELECT embedding, project_id FROM source_embeddings WHERE source_id = ?1",
+            ContentKind::Note => "SELECT embedding, project_id FROM note_embeddings WHERE note_id = ?1",
+            _ => return Ok(RelatedItems::default()),
+        };
+        let stored: Option<(Vec<u8>, String)> = {
+            let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+            conn.query_row(sql, params![id], |row| Ok((row.get(0)?, row.get(1)?)))
+                .optional()?
+        };
+
+        let mut items = RelatedItems::default();
+        if let Some((blob, project_id)) = stored {
+            let embedding = deserialize_embedding(&blob);
+            let scope = if library_wide { None } else { Some(project_id.as_str()) };
+            // 多取一条，排除条目自身后仍有 limit 条
+            let related = |results: Vec<SearchResult>, own: ContentKind| {
+                results
+                    .into_iter()
+                    .filter(|result| !(kind == own && result.id == id))
+                    .filter(|result| result.score.unwrap_or(0.0) > 0.0)
+                    .take(limit as usize)
+                    .collect::<Vec<_>>()
+            };
+            items.sources = related(
+                self.search_sources_semantic(&embedding, &[], scope, limit + 1, false)?,
+                ContentKind::Source,
+            );
+            items.notes = related(
+                self.search_notes_semantic(&embedding, &[], scope, limit + 1, false)?,
+                ContentKind::Note,
+            );
+        }
+
+        self.related_cache
+            .lock()
+            .map_err(|_| DbError::LockError)?
+            .insert(key, (version, items.clone()));
+        Ok(items)
+    }
+
+    /// 当前向量版本号（来源或笔记向量每次增删改、项目归档状态变化后递增）
+    fn embedding_version(&self) -> Result<i64, DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let version = conn.query_row("SELECT version FROM embedding_version WHERE id = 1", [], |row| {
+            row.get(0)
+        })?;
+        Ok(version)
+    }
+
+    fn clear_related_cache(&self) -> Result<(), DbError> {
+        self.related_cache
+            .lock()
+            .map_err(|_| DbError::LockError)?
+            .clear();
+        Ok(())
+    }
+
     /// 更新来源向量
     pub fn upsert_source_embedding(
         &self,
@@ -683,17 +1628,7 @@ impl Database {
         embedding: &[f32],
     ) -> Result<(), DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-        let blob = serialize_embedding(embedding);
-        conn.execute(
-            "INSERT INTO source_embeddings (source_id, project_id, embedding, updated_at)
-             VALUES (?1, ?2, ?3, datetime('now'))
-             ON CONFLICT(source_id) DO UPDATE SET
-               embedding = excluded.embedding,
-               project_id = excluded.project_id,
-               updated_at = excluded.updated_at",
-            params![source_id, project_id, blob],
-        )?;
-        Ok(())
+        DbTx::new(&conn, self.path_mapper()).upsert_source_embedding(source_id, project_id, embedding)
     }
 
     /// 删除来源向量
@@ -714,43 +1649,32 @@ impl Database {
         embedding: &[f32],
     ) -> Result<(), DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-        let blob = serialize_embedding(embedding);
-        conn.execute(
-            "INSERT INTO note_embeddings (note_id, project_id, embedding, updated_at)
-             VALUES (?1, ?2, ?3, datetime('now'))
-             ON CONFLICT(note_id) DO UPDATE SET
-               embedding = excluded.embedding,
-               project_id = excluded.project_id,
-               updated_at = excluded.updated_at",
-            params![note_id, project_id, blob],
-        )?;
-        Ok(())
+        DbTx::new(&conn, self.path_mapper()).upsert_note_embedding(note_id, project_id, embedding)
     }
 
     /// 删除笔记向量
     pub fn delete_note_embedding(&self, note_id: &str) -> Result<(), DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-        conn.execute(
-            "DELETE FROM note_embeddings WHERE note_id = ?1",
-            params![note_id],
-        )?;
-        Ok(())
+        DbTx::new(&conn, self.path_mapper()).delete_note_embedding(note_id)
     }
 
-    /// 获取缺失向量的来源列表
+    /// 获取缺失向量的来源列表（不含归档项目，最多 `limit` 条）
     pub fn list_sources_missing_embeddings(
         &self,
+        limit: u32,
     ) -> Result<Vec<(String, String, String, Option<String>)>, DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
         let mut stmt = conn.prepare(
             "SELECT s.id, s.project_id, s.name, s.text_content
              FROM sources s
+             JOIN projects p ON s.project_id = p.id
              LEFT JOIN source_embeddings e ON e.source_id = s.id
-             WHERE e.source_id IS NULL",
+             WHERE e.source_id IS NULL AND p.archived_at IS NULL
+             LIMIT ?1",
         )?;
 
         let results = stmt
-            .query_map([], |row| {
+            .query_map([limit], |row| {
                 Ok((
                     row.get::<_, String>(0)?,
                     row.get::<_, String>(1)?,
@@ -763,25 +1687,29 @@ impl Database {
         Ok(results)
     }
 
-    /// 获取缺失向量的笔记列表
+    /// 获取缺失向量的笔记列表（不含归档项目，最多 `limit` 条）
     pub fn list_notes_missing_embeddings(
         &self,
+        limit: u32,
     ) -> Result<Vec<(String, String, String, String)>, DbError> {
+        let paths = self.path_mapper();
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
         let mut stmt = conn.prepare(
             "SELECT n.id, n.project_id, n.title, n.path
              FROM notes n
+             JOIN projects p ON n.project_id = p.id
              LEFT JOIN note_embeddings e ON e.note_id = n.id
-             WHERE e.note_id IS NULL",
+             WHERE e.note_id IS NULL AND p.archived_at IS NULL
+             LIMIT ?1",
         )?;
 
         let results = stmt
-            .query_map([], |row| {
+            .query_map([limit], |row| {
                 Ok((
                     row.get::<_, String>(0)?,
                     row.get::<_, String>(1)?,
                     row.get::<_, String>(2)?,
-                    row.get::<_, String>(3)?,
+                    paths.resolve(&row.get::<_, String>(3)?),
                 ))
             })?
             .collect::<Result<Vec<_>, _>>()?;
@@ -789,99 +1717,244 @@ impl Database {
         Ok(results)
     }
 
-    // ========== Source 操作 ==========
+    /// 统计缺失向量的来源与笔记数（不含归档项目）
+    pub fn count_missing_embeddings(&self) -> Result<u32, DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let count = conn.query_row(
+            "SELECT
+               (SELECT COUNT(*) FROM sources s
+                JOIN projects p ON s.project_id = p.id
+                LEFT JOIN source_embeddings e ON e.source_id = s.id
+                WHERE e.source_id IS NULL AND p.archived_at IS NULL)
+             + (SELECT COUNT(*) FROM notes n
+                JOIN projects p ON n.project_id = p.id
+                LEFT JOIN note_embeddings e ON e.note_id = n.id
+                WHERE e.note_id IS NULL AND p.archived_at IS NULL)",
+            [],
+            |row| row.get::<_, u32>(0),
+        )?;
+        Ok(count)
+    }
 
-    /// 获取项目的所有来源
-    pub fn get_sources_by_project(&self, project_id: &str) -> Result<Vec<Source>, DbError> {
+    /// 删除全部来源与笔记向量（向量化方法变更后由后台重新生成）
+    pub fn clear_embeddings(&self) -> Result<(), DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-        let mut stmt = conn.prepare(
-            "SELECT id, project_id, name, type, path, size, mime_type,
-                    thumbnail_path, created_at, updated_at
-             FROM sources WHERE project_id = ?1 ORDER BY created_at DESC",
+        conn.execute_batch(
+            "DELETE FROM source_embeddings;
+             DELETE FROM note_embeddings;",
         )?;
+        Ok(())
+    }
 
-        let sources = stmt
-            .query_map(params![project_id], |row| {
-                Ok(Source {
-                    id: row.get(0)?,
-                    project_id: row.get(1)?,
-                    name: row.get(2)?,
-                    source_type: SourceType::from_str(&row.get::<_, String>(3)?)
-                        .unwrap_or(SourceType::Markdown),
-                    path: row.get(4)?,
-                    size: row.get(5)?,
-                    mime_type: row.get(6)?,
-                    thumbnail_path: row.get(7)?,
-                    created_at: parse_datetime(&row.get::<_, String>(8)?),
-                    updated_at: parse_datetime(&row.get::<_, String>(9)?),
-                })
-            })?
-            .collect::<Result<Vec<_>, _>>()?;
+    // ========== Saved Search 操作 ==========
 
-        Ok(sources)
+    /// 获取全部保存的搜索（按名称排序）
+    pub fn list_saved_searches(&self) -> Result<Vec<SavedSearch>, DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM saved_searches ORDER BY name COLLATE NOCASE, created_at",
+            SAVED_SEARCH_COLUMNS
+        ))?;
+        let searches = stmt
+            .query_map([], row_to_saved_search)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(searches)
     }
 
-    /// 获取单个来源
-    pub fn get_source(&self, id: &str) -> Result<Source, DbError> {
+    /// 获取单个保存的搜索
+    pub fn get_saved_search(&self, id: &str) -> Result<SavedSearch, DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-        let mut stmt = conn.prepare(
-            "SELECT id, project_id, name, type, path, size, mime_type,
-                    thumbnail_path, created_at, updated_at
-             FROM sources WHERE id = ?1",
-        )?;
-
-        stmt.query_row(params![id], |row| {
-            Ok(Source {
-                id: row.get(0)?,
-                project_id: row.get(1)?,
-                name: row.get(2)?,
-                source_type: SourceType::from_str(&row.get::<_, String>(3)?)
-                    .unwrap_or(SourceType::Markdown),
-                path: row.get(4)?,
-                size: row.get(5)?,
-                mime_type: row.get(6)?,
-                thumbnail_path: row.get(7)?,
-                created_at: parse_datetime(&row.get::<_, String>(8)?),
-                updated_at: parse_datetime(&row.get::<_, String>(9)?),
-            })
-        })
+        conn.query_row(
+            &format!("SELECT {} FROM saved_searches WHERE id = ?1", SAVED_SEARCH_COLUMNS),
+            params![id],
+            row_to_saved_search,
+        )
         .map_err(|e| match e {
             rusqlite::Error::QueryReturnedNoRows => {
-                DbError::NotFound(format!("来源 {} 不存在", id))
+                DbError::NotFound(format!("保存的搜索 {} 不存在", id))
             }
             _ => DbError::Sqlite(e),
         })
     }
 
-    /// 插入来源
-    pub fn insert_source(&self, source: &Source) -> Result<(), DbError> {
-        self.insert_source_with_content(source, None)
+    /// 插入保存的搜索
+    pub fn insert_saved_search(&self, search: &SavedSearch) -> Result<(), DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        conn.execute(
+            "INSERT INTO saved_searches (id, name, query, project_id, include_archived, sort, track_new, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                search.id,
+                search.name,
+                search.query,
+                search.project_id,
+                search.include_archived,
+                search.sort.as_str(),
+                search.track_new,
+                search.created_at.to_rfc3339(),
+                search.updated_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
     }
 
-    /// 插入来源（带文本内容）
-    pub fn insert_source_with_content(&self, source: &Source, text_content: Option<&str>) -> Result<(), DbError> {
+    /// 更新保存的搜索的名称与条件
+    ///
+    /// 查询、范围或归档选项变化后，上次运行的命中记录不再可比，一并清空。
+    pub fn update_saved_search(&self, search: &SavedSearch) -> Result<(), DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-        conn.execute(
-            "INSERT INTO sources (id, project_id, name, type, path, size, mime_type,
-                                  thumbnail_path, text_content, created_at, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        let affected = conn.execute(
+            "UPDATE saved_searches SET
+                 last_matches = CASE
+                     WHEN query IS ?2 AND project_id IS ?3 AND include_archived IS ?4 THEN last_matches
+                 END,
+                 name = ?1, query = ?2, project_id = ?3, include_archived = ?4,
+                 sort = ?5, track_new = ?6, updated_at = datetime('now')
+             WHERE id = ?7",
             params![
-                source.id,
-                source.project_id,
-                source.name,
-                source.source_type.as_str(),
-                source.path,
-                source.size,
-                source.mime_type,
-                source.thumbnail_path,
-                text_content,
-                source.created_at.to_rfc3339(),
-                source.updated_at.to_rfc3339(),
+                search.name,
+                search.query,
+                search.project_id,
+                search.include_archived,
+                search.sort.as_str(),
+                search.track_new,
+                search.id,
             ],
         )?;
+        if affected == 0 {
+            return Err(DbError::NotFound(format!("保存的搜索 {} 不存在", search.id)));
+        }
+        Ok(())
+    }
+
+    /// 删除保存的搜索
+    pub fn delete_saved_search(&self, id: &str) -> Result<(), DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let affected = conn.execute("DELETE FROM saved_searches WHERE id = ?1", params![id])?;
+        if affected == 0 {
+            return Err(DbError::NotFound(format!("保存的搜索 {} 不存在", id)));
+        }
+        Ok(())
+    }
+
+    /// 获取保存的搜索上次运行命中的条目（"类型:ID"），没有记录时为 None
+    pub fn get_saved_search_matches(&self, id: &str) -> Result<Option<Vec<String>>, DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let matches: Option<String> = conn
+            .query_row(
+                "SELECT last_matches FROM saved_searches WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => {
+                    DbError::NotFound(format!("保存的搜索 {} 不存在", id))
+                }
+                _ => DbError::Sqlite(e),
+            })?;
+        // 记录损坏时按没有记录处理，下次运行重新记录
+        Ok(matches.and_then(|json| serde_json::from_str(&json).ok()))
+    }
+
+    /// 记录一次运行：运行时间与命中的条目（`matches` 为 None 时清空命中记录）
+    pub fn record_saved_search_run(
+        &self,
+        id: &str,
+        run_at: DateTime<Utc>,
+        matches: Option<&[String]>,
+    ) -> Result<(), DbError> {
+        let matches = matches
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| DbError::Serialization(e.to_string()))?;
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let affected = conn.execute(
+            "UPDATE saved_searches SET last_run_at = ?1, last_matches = ?2 WHERE id = ?3",
+            params![run_at.to_rfc3339(), matches, id],
+        )?;
+        if affected == 0 {
+            return Err(DbError::NotFound(format!("保存的搜索 {} 不存在", id)));
+        }
         Ok(())
     }
 
+    // ========== Source 操作 ==========
+
+    /// 获取项目的所有来源
+    pub fn get_sources_by_project(&self, project_id: &str) -> Result<Vec<Source>, DbError> {
+        let paths = self.path_mapper();
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let mut stmt = conn.prepare(
+            "SELECT id, project_id, name, type, path, size, mime_type,
+                    thumbnail_path, created_at, updated_at
+             FROM sources WHERE project_id = ?1 ORDER BY created_at DESC",
+        )?;
+
+        let sources = stmt
+            .query_map(params![project_id], |row| {
+                Ok(Source {
+                    id: row.get(0)?,
+                    project_id: row.get(1)?,
+                    name: row.get(2)?,
+                    source_type: SourceType::from_str(&row.get::<_, String>(3)?)
+                        .unwrap_or(SourceType::Markdown),
+                    path: paths.resolve(&row.get::<_, String>(4)?),
+                    size: row.get(5)?,
+                    mime_type: row.get(6)?,
+                    thumbnail_path: paths.resolve_opt(row.get(7)?),
+                    created_at: parse_datetime(&row.get::<_, String>(8)?),
+                    updated_at: parse_datetime(&row.get::<_, String>(9)?),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(sources)
+    }
+
+    /// 获取单个来源
+    pub fn get_source(&self, id: &str) -> Result<Source, DbError> {
+        let paths = self.path_mapper();
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let mut stmt = conn.prepare(
+            "SELECT id, project_id, name, type, path, size, mime_type,
+                    thumbnail_path, created_at, updated_at
+             FROM sources WHERE id = ?1",
+        )?;
+
+        stmt.query_row(params![id], |row| {
+            Ok(Source {
+                id: row.get(0)?,
+                project_id: row.get(1)?,
+                name: row.get(2)?,
+                source_type: SourceType::from_str(&row.get::<_, String>(3)?)
+                    .unwrap_or(SourceType::Markdown),
+                path: paths.resolve(&row.get::<_, String>(4)?),
+                size: row.get(5)?,
+                mime_type: row.get(6)?,
+                thumbnail_path: paths.resolve_opt(row.get(7)?),
+                created_at: parse_datetime(&row.get::<_, String>(8)?),
+                updated_at: parse_datetime(&row.get::<_, String>(9)?),
+            })
+        })
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => {
+                DbError::NotFound(format!("来源 {} 不存在", id))
+            }
+            _ => DbError::Sqlite(e),
+        })
+    }
+
+    /// 插入来源
+    pub fn insert_source(&self, source: &Source) -> Result<(), DbError> {
+        self.insert_source_with_content(source, None)
+    }
+
+    /// 插入来源（带文本内容）
+    pub fn insert_source_with_content(&self, source: &Source, text_content: Option<&str>) -> Result<(), DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        DbTx::new(&conn, self.path_mapper()).insert_source_with_content(source, text_content)
+    }
+
     /// 删除来源
     pub fn delete_source(&self, id: &str) -> Result<String, DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
@@ -899,6 +1972,7 @@ impl Database {
         })?;
 
         conn.execute("DELETE FROM sources WHERE id = ?1", params![id])?;
+        DbTx::new(&conn, self.path_mapper()).delete_tags(ContentKind::Source, id)?;
         Ok(project_id)
     }
 
@@ -922,20 +1996,17 @@ impl Database {
     /// 更新项目来源数量
     pub fn update_project_sources_count(&self, project_id: &str, delta: i32) -> Result<(), DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-        conn.execute(
-            "UPDATE projects SET sources_count = MAX(0, sources_count + ?1), updated_at = datetime('now') WHERE id = ?2",
-            params![delta, project_id],
-        )?;
-        Ok(())
+        DbTx::new(&conn, self.path_mapper()).update_project_sources_count(project_id, delta)
     }
 
     // ========== Note 操作 ==========
 
     /// 获取项目的所有笔记
     pub fn get_notes_by_project(&self, project_id: &str) -> Result<Vec<Note>, DbError> {
+        let paths = self.path_mapper();
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
         let mut stmt = conn.prepare(
-            "SELECT id, project_id, title, path, output_type, created_at, updated_at
+            "SELECT id, project_id, title, path, output_type, title_locked, created_at, updated_at
              FROM notes WHERE project_id = ?1 ORDER BY updated_at DESC",
         )?;
 
@@ -945,10 +2016,11 @@ impl Database {
                     id: row.get(0)?,
                     project_id: row.get(1)?,
                     title: row.get(2)?,
-                    path: row.get(3)?,
+                    path: paths.resolve(&row.get::<_, String>(3)?),
                     output_type: OutputType::from_str(&row.get::<_, String>(4)?),
-                    created_at: parse_datetime(&row.get::<_, String>(5)?),
-                    updated_at: parse_datetime(&row.get::<_, String>(6)?),
+                    title_locked: row.get::<_, i32>(5)? != 0,
+                    created_at: parse_datetime(&row.get::<_, String>(6)?),
+                    updated_at: parse_datetime(&row.get::<_, String>(7)?),
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
@@ -958,9 +2030,10 @@ impl Database {
 
     /// 获取单个笔记
     pub fn get_note(&self, id: &str) -> Result<Note, DbError> {
+        let paths = self.path_mapper();
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
         conn.query_row(
-            "SELECT id, project_id, title, path, output_type, created_at, updated_at
+            "SELECT id, project_id, title, path, output_type, title_locked, created_at, updated_at
              FROM notes WHERE id = ?1",
             params![id],
             |row| {
@@ -968,10 +2041,11 @@ impl Database {
                     id: row.get(0)?,
                     project_id: row.get(1)?,
                     title: row.get(2)?,
-                    path: row.get(3)?,
+                    path: paths.resolve(&row.get::<_, String>(3)?),
                     output_type: OutputType::from_str(&row.get::<_, String>(4)?),
-                    created_at: parse_datetime(&row.get::<_, String>(5)?),
-                    updated_at: parse_datetime(&row.get::<_, String>(6)?),
+                    title_locked: row.get::<_, i32>(5)? != 0,
+                    created_at: parse_datetime(&row.get::<_, String>(6)?),
+                    updated_at: parse_datetime(&row.get::<_, String>(7)?),
                 })
             },
         )
@@ -986,52 +2060,57 @@ impl Database {
     /// 插入笔记
     pub fn insert_note(&self, note: &Note) -> Result<(), DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-        conn.execute(
-            "INSERT INTO notes (id, project_id, title, path, output_type, created_at, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![
-                note.id,
-                note.project_id,
-                note.title,
-                note.path,
-                note.output_type.as_str(),
-                note.created_at.to_rfc3339(),
-                note.updated_at.to_rfc3339(),
-            ],
-        )?;
-        Ok(())
+        DbTx::new(&conn, self.path_mapper()).insert_note(note)
     }
 
     /// 更新笔记标题
     pub fn update_note_title(&self, id: &str, title: &str) -> Result<(), DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-        conn.execute(
-            "UPDATE notes SET title = ?1, updated_at = datetime('now') WHERE id = ?2",
+        DbTx::new(&conn, self.path_mapper()).update_note_title(id, title)
+    }
+
+    /// 更新笔记正文（用于全文搜索）
+    pub fn update_note_content(&self, id: &str, content: &str) -> Result<(), DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        DbTx::new(&conn, self.path_mapper()).update_note_content(id, content)
+    }
+
+    /// 写入笔记正文索引，不更新修改时间
+    pub fn index_note_content(&self, id: &str, content: &str) -> Result<(), DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        DbTx::new(&conn, self.path_mapper()).index_note_content(id, content)
+    }
+
+    /// 手动重命名笔记并锁定标题
+    pub fn rename_note(&self, id: &str, title: &str) -> Result<(), DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let affected = conn.execute(
+            "UPDATE notes SET title = ?1, title_locked = 1, updated_at = datetime('now') WHERE id = ?2",
             params![title, id],
         )?;
+        if affected == 0 {
+            return Err(DbError::NotFound(format!("笔记 {} 不存在", id)));
+        }
+        Ok(())
+    }
+
+    /// 更新笔记标题锁定状态
+    pub fn update_note_title_locked(&self, id: &str, locked: bool) -> Result<(), DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let affected = conn.execute(
+            "UPDATE notes SET title_locked = ?1 WHERE id = ?2",
+            params![locked as i32, id],
+        )?;
+        if affected == 0 {
+            return Err(DbError::NotFound(format!("笔记 {} 不存在", id)));
+        }
         Ok(())
     }
 
     /// 删除笔记
     pub fn delete_note(&self, id: &str) -> Result<String, DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-
-        // 获取 project_id
-        let project_id: String = conn
-            .query_row(
-                "SELECT project_id FROM notes WHERE id = ?1",
-                params![id],
-                |row| row.get(0),
-            )
-            .map_err(|e| match e {
-                rusqlite::Error::QueryReturnedNoRows => {
-                    DbError::NotFound(format!("笔记 {} 不存在", id))
-                }
-                _ => DbError::Sqlite(e),
-            })?;
-
-        conn.execute("DELETE FROM notes WHERE id = ?1", params![id])?;
-        Ok(project_id)
+        DbTx::new(&conn, self.path_mapper()).delete_note(id)
     }
 
     // ========== Chat Session 操作 ==========
@@ -1087,18 +2166,7 @@ impl Database {
     /// 创建对话会话
     pub fn insert_chat_session(&self, session: &ChatSession) -> Result<(), DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-        conn.execute(
-            "INSERT INTO chat_sessions (id, project_id, title, created_at, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![
-                session.id,
-                session.project_id,
-                session.title,
-                session.created_at.to_rfc3339(),
-                session.updated_at.to_rfc3339(),
-            ],
-        )?;
-        Ok(())
+        DbTx::new(&conn, self.path_mapper()).insert_chat_session(session)
     }
 
     /// 更新对话会话标题
@@ -1162,21 +2230,20 @@ impl Database {
     /// 插入对话消息
     pub fn insert_chat_message(&self, message: &ChatMessage) -> Result<(), DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-        let citations_json = message.citations.as_ref().map(|c| serde_json::to_string(c).ok()).flatten();
+        DbTx::new(&conn, self.path_mapper()).insert_chat_message(message)
+    }
 
-        conn.execute(
-            "INSERT INTO chat_messages (id, session_id, role, content, citations, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![
-                message.id,
-                message.session_id,
-                message.role.as_str(),
-                message.content,
-                citations_json,
-                message.created_at.to_rfc3339(),
-            ],
-        )?;
-        Ok(())
+    /// 获取对话消息所属的会话 ID
+    pub fn get_chat_message_session_id(&self, id: &str) -> Result<Option<String>, DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let session_id = conn
+            .query_row(
+                "SELECT session_id FROM chat_messages WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(session_id)
     }
 
     /// 删除对话消息
@@ -1190,6 +2257,7 @@ impl Database {
 
     /// 获取项目的所有 PPT
     pub fn get_presentations_by_project(&self, project_id: &str) -> Result<Vec<Presentation>, DbError> {
+        let paths = self.path_mapper();
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
         let mut stmt = conn.prepare(
             "SELECT id, project_id, title, data_path, thumbnail_path, slide_count, created_at, updated_at
@@ -1202,8 +2270,8 @@ impl Database {
                     id: row.get(0)?,
                     project_id: row.get(1)?,
                     title: row.get(2)?,
-                    data_path: row.get(3)?,
-                    thumbnail_path: row.get(4)?,
+                    data_path: paths.resolve(&row.get::<_, String>(3)?),
+                    thumbnail_path: paths.resolve_opt(row.get(4)?),
                     slide_count: row.get(5)?,
                     created_at: parse_datetime(&row.get::<_, String>(6)?),
                     updated_at: parse_datetime(&row.get::<_, String>(7)?),
@@ -1216,6 +2284,7 @@ impl Database {
 
     /// 获取单个 PPT
     pub fn get_presentation(&self, id: &str) -> Result<Presentation, DbError> {
+        let paths = self.path_mapper();
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
         conn.query_row(
             "SELECT id, project_id, title, data_path, thumbnail_path, slide_count, created_at, updated_at
@@ -1226,8 +2295,8 @@ impl Database {
                     id: row.get(0)?,
                     project_id: row.get(1)?,
                     title: row.get(2)?,
-                    data_path: row.get(3)?,
-                    thumbnail_path: row.get(4)?,
+                    data_path: paths.resolve(&row.get::<_, String>(3)?),
+                    thumbnail_path: paths.resolve_opt(row.get(4)?),
                     slide_count: row.get(5)?,
                     created_at: parse_datetime(&row.get::<_, String>(6)?),
                     updated_at: parse_datetime(&row.get::<_, String>(7)?),
@@ -1245,21 +2314,7 @@ impl Database {
     /// 插入 PPT
     pub fn insert_presentation(&self, presentation: &Presentation) -> Result<(), DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-        conn.execute(
-            "INSERT INTO presentations (id, project_id, title, data_path, thumbnail_path, slide_count, created_at, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-            params![
-                presentation.id,
-                presentation.project_id,
-                presentation.title,
-                presentation.data_path,
-                presentation.thumbnail_path,
-                presentation.slide_count,
-                presentation.created_at.to_rfc3339(),
-                presentation.updated_at.to_rfc3339(),
-            ],
-        )?;
-        Ok(())
+        DbTx::new(&conn, self.path_mapper()).insert_presentation(presentation)
     }
 
     /// 更新 PPT 标题
@@ -1278,14 +2333,13 @@ impl Database {
     /// 更新 PPT 幻灯片数量
     pub fn update_presentation_slide_count(&self, id: &str, slide_count: i32) -> Result<(), DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-        let affected = conn.execute(
-            "UPDATE presentations SET slide_count = ?1, updated_at = datetime('now') WHERE id = ?2",
-            params![slide_count, id],
-        )?;
-        if affected == 0 {
-            return Err(DbError::NotFound(format!("PPT {} 不存在", id)));
-        }
-        Ok(())
+        DbTx::new(&conn, self.path_mapper()).update_presentation_slide_count(id, slide_count)
+    }
+
+    /// 更新 PPT 文本内容（用于全文搜索）
+    pub fn update_presentation_text_content(&self, id: &str, text_content: &str) -> Result<(), DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        DbTx::new(&conn, self.path_mapper()).update_presentation_text_content(id, text_content)
     }
 
     /// 更新 PPT 缩略图路径
@@ -1293,7 +2347,7 @@ impl Database {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
         let affected = conn.execute(
             "UPDATE presentations SET thumbnail_path = ?1, updated_at = datetime('now') WHERE id = ?2",
-            params![thumbnail_path, id],
+            params![self.path_mapper().to_stored(thumbnail_path), id],
         )?;
         if affected == 0 {
             return Err(DbError::NotFound(format!("PPT {} 不存在", id)));
@@ -1327,6 +2381,7 @@ impl Database {
 
     /// 获取项目的所有画布
     pub fn get_canvases_by_project(&self, project_id: &str) -> Result<Vec<Canvas>, DbError> {
+        let paths = self.path_mapper();
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
         let mut stmt = conn.prepare(
             "SELECT id, project_id, title, path, created_at, updated_at
@@ -1339,7 +2394,7 @@ impl Database {
                     id: row.get(0)?,
                     project_id: row.get(1)?,
                     title: row.get(2)?,
-                    path: row.get(3)?,
+                    path: paths.resolve(&row.get::<_, String>(3)?),
                     created_at: parse_datetime(&row.get::<_, String>(4)?),
                     updated_at: parse_datetime(&row.get::<_, String>(5)?),
                 })
@@ -1351,6 +2406,7 @@ impl Database {
 
     /// 获取单个画布
     pub fn get_canvas(&self, id: &str) -> Result<Canvas, DbError> {
+        let paths = self.path_mapper();
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
         conn.query_row(
             "SELECT id, project_id, title, path, created_at, updated_at
@@ -1361,7 +2417,7 @@ impl Database {
                     id: row.get(0)?,
                     project_id: row.get(1)?,
                     title: row.get(2)?,
-                    path: row.get(3)?,
+                    path: paths.resolve(&row.get::<_, String>(3)?),
                     created_at: parse_datetime(&row.get::<_, String>(4)?),
                     updated_at: parse_datetime(&row.get::<_, String>(5)?),
                 })
@@ -1378,19 +2434,7 @@ impl Database {
     /// 插入画布
     pub fn insert_canvas(&self, canvas: &Canvas) -> Result<(), DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-        conn.execute(
-            "INSERT INTO canvases (id, project_id, title, path, created_at, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![
-                canvas.id,
-                canvas.project_id,
-                canvas.title,
-                canvas.path,
-                canvas.created_at.to_rfc3339(),
-                canvas.updated_at.to_rfc3339(),
-            ],
-        )?;
-        Ok(())
+        DbTx::new(&conn, self.path_mapper()).insert_canvas(canvas)
     }
 
     /// 更新画布标题
@@ -1409,45 +2453,13 @@ impl Database {
     /// 更新画布 updated_at 时间
     pub fn touch_canvas(&self, id: &str) -> Result<(), DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-        let affected = conn.execute(
-            "UPDATE canvases SET updated_at = datetime('now') WHERE id = ?1",
-            params![id],
-        )?;
-        if affected == 0 {
-            return Err(DbError::NotFound(format!("画布 {} 不存在", id)));
-        }
-        Ok(())
+        DbTx::new(&conn, self.path_mapper()).touch_canvas(id)
     }
 
     /// 更新画布文本内容（用于全文搜索）
     pub fn update_canvas_text_content(&self, id: &str, text_content: &str) -> Result<(), DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-
-        // 获取画布标题用于更新 FTS
-        let title: String = conn
-            .query_row(
-                "SELECT title FROM canvases WHERE id = ?1",
-                params![id],
-                |row| row.get(0),
-            )
-            .map_err(|e| match e {
-                rusqlite::Error::QueryReturnedNoRows => {
-                    DbError::NotFound(format!("画布 {} 不存在", id))
-                }
-                _ => DbError::Sqlite(e),
-            })?;
-
-        // 更新 FTS 表 - 先删除旧记录，再插入新记录
-        let _ = conn.execute(
-            "DELETE FROM canvases_fts WHERE canvas_id = ?1",
-            params![id],
-        );
-        let _ = conn.execute(
-            "INSERT INTO canvases_fts(canvas_id, title, text_content) VALUES (?1, ?2, ?3)",
-            params![id, title, text_content],
-        );
-
-        Ok(())
+        DbTx::new(&conn, self.path_mapper()).update_canvas_text_content(id, text_content)
     }
 
     /// 删除画布
@@ -1545,29 +2557,27 @@ impl Database {
     /// 插入思维导图
     pub fn insert_mindmap(&self, mindmap: &MindMap, data: &str) -> Result<(), DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-        conn.execute(
-            "INSERT INTO mindmaps (id, project_id, title, theme, layout, data, created_at, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-            params![
-                mindmap.id,
-                mindmap.project_id,
-                mindmap.title,
-                mindmap.theme,
-                mindmap.layout,
-                data,
-                mindmap.created_at.to_rfc3339(),
-                mindmap.updated_at.to_rfc3339(),
-            ],
-        )?;
-        Ok(())
+        DbTx::new(&conn, self.path_mapper()).insert_mindmap(mindmap, data)
     }
 
     /// 更新思维导图数据
     pub fn update_mindmap_data(&self, id: &str, data: &str) -> Result<(), DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        DbTx::new(&conn, self.path_mapper()).update_mindmap_data(id, data)
+    }
+
+    /// 更新思维导图元数据（标题、主题、布局）
+    pub fn update_mindmap_meta(&self, id: &str, title: Option<&str>, theme: Option<&str>, layout: Option<&str>) -> Result<(), DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        DbTx::new(&conn, self.path_mapper()).update_mindmap_meta(id, title, theme, layout)
+    }
+
+    /// 更新思维导图标题
+    pub fn update_mindmap_title(&self, id: &str, title: &str) -> Result<(), DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
         let affected = conn.execute(
-            "UPDATE mindmaps SET data = ?1, updated_at = datetime('now') WHERE id = ?2",
-            params![data, id],
+            "UPDATE mindmaps SET title = ?1, updated_at = datetime('now') WHERE id = ?2",
+            params![title, id],
         )?;
         if affected == 0 {
             return Err(DbError::NotFound(format!("思维导图 {} 不存在", id)));
@@ -1575,102 +2585,289 @@ impl Database {
         Ok(())
     }
 
-    /// 更新思维导图元数据（标题、主题、布局）
-    pub fn update_mindmap_meta(&self, id: &str, title: Option<&str>, theme: Option<&str>, layout: Option<&str>) -> Result<(), DbError> {
+    /// 删除思维导图
+    pub fn delete_mindmap(&self, id: &str) -> Result<String, DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
 
-        // 根据参数组合构建更新语句
-        let affected = match (title, theme, layout) {
-            (Some(t), Some(th), Some(l)) => {
-                conn.execute(
-                    "UPDATE mindmaps SET title = ?1, theme = ?2, layout = ?3, updated_at = datetime('now') WHERE id = ?4",
-                    params![t, th, l, id],
-                )?
-            }
-            (Some(t), Some(th), None) => {
-                conn.execute(
-                    "UPDATE mindmaps SET title = ?1, theme = ?2, updated_at = datetime('now') WHERE id = ?3",
-                    params![t, th, id],
-                )?
-            }
-            (Some(t), None, Some(l)) => {
-                conn.execute(
-                    "UPDATE mindmaps SET title = ?1, layout = ?2, updated_at = datetime('now') WHERE id = ?3",
-                    params![t, l, id],
-                )?
-            }
-            (None, Some(th), Some(l)) => {
-                conn.execute(
-                    "UPDATE mindmaps SET theme = ?1, layout = ?2, updated_at = datetime('now') WHERE id = ?3",
-                    params![th, l, id],
-                )?
-            }
-            (Some(t), None, None) => {
-                conn.execute(
-                    "UPDATE mindmaps SET title = ?1, updated_at = datetime('now') WHERE id = ?2",
-                    params![t, id],
-                )?
-            }
-            (None, Some(th), None) => {
-                conn.execute(
-                    "UPDATE mindmaps SET theme = ?1, updated_at = datetime('now') WHERE id = ?2",
-                    params![th, id],
-                )?
-            }
-            (None, None, Some(l)) => {
-                conn.execute(
-                    "UPDATE mindmaps SET layout = ?1, updated_at = datetime('now') WHERE id = ?2",
-                    params![l, id],
-                )?
-            }
-            (None, None, None) => {
-                conn.execute(
-                    "UPDATE mindmaps SET updated_at = datetime('now') WHERE id = ?1",
-                    params![id],
-                )?
-            }
-        };
+        // 获取 project_id
+        let project_id: String = conn
+            .query_row(
+                "SELECT project_id FROM mindmaps WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => {
+                    DbError::NotFound(format!("思维导图 {} 不存在", id))
+                }
+                _ => DbError::Sqlite(e),
+            })?;
 
-        if affected == 0 {
-            return Err(DbError::NotFound(format!("思维导图 {} 不存在", id)));
-        }
-        Ok(())
+        conn.execute("DELETE FROM mindmaps WHERE id = ?1", params![id])?;
+        Ok(project_id)
     }
 
-    /// 更新思维导图标题
-    pub fn update_mindmap_title(&self, id: &str, title: &str) -> Result<(), DbError> {
+    // ==================== 回收站操作 ====================
+
+    /// 读取条目（或整个项目）的内容快照
+    pub fn get_snapshot(&self, kind: ContentKind, id: &str) -> Result<ContentSnapshot, DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
-        let affected = conn.execute(
-            "UPDATE mindmaps SET title = ?1, updated_at = datetime('now') WHERE id = ?2",
-            params![title, id],
+        DbTx::new(&conn, self.path_mapper()).snapshot(kind, id)
+    }
+
+    /// 获取回收站条目列表
+    ///
+    /// 指定项目时只返回该项目内的条目；否则返回所有可见条目，
+    /// 已被整体删除的项目内部条目随项目一起隐藏
+    pub fn get_trash_items(&self, project_id: Option<&str>) -> Result<Vec<TrashItem>, DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let mut stmt = conn.prepare(
+            "SELECT id, project_id, kind, item_id, title, trash_path, deleted_at
+             FROM trash_items
+             WHERE (?1 IS NULL AND (kind = 'project' OR project_id IN (SELECT id FROM projects)))
+                OR (project_id = ?1 AND kind != 'project')
+             ORDER BY deleted_at DESC",
         )?;
-        if affected == 0 {
-            return Err(DbError::NotFound(format!("思维导图 {} 不存在", id)));
-        }
-        Ok(())
+
+        let paths = self.path_mapper();
+        let items = stmt
+            .query_map(params![project_id], |row| trash_item_from_row(row, &paths))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(items)
     }
 
-    /// 删除思维导图
-    pub fn delete_mindmap(&self, id: &str) -> Result<String, DbError> {
+    /// 获取所有回收站条目（含已删除项目内部的条目）
+    pub fn get_all_trash_items(&self) -> Result<Vec<TrashItem>, DbError> {
         let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let mut stmt = conn.prepare(
+            "SELECT id, project_id, kind, item_id, title, trash_path, deleted_at
+             FROM trash_items ORDER BY deleted_at DESC",
+        )?;
 
-        // 获取 project_id
-        let project_id: String = conn
+        let paths = self.path_mapper();
+        let items = stmt
+            .query_map([], |row| trash_item_from_row(row, &paths))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(items)
+    }
+
+    /// 获取回收站条目及其快照、文件映射
+    pub fn get_trash_entry(
+        &self,
+        id: &str,
+    ) -> Result<(TrashItem, ContentSnapshot, Vec<TrashedFile>), DbError> {
+        let paths = self.path_mapper();
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let (item, snapshot_json, files_json) = conn
             .query_row(
-                "SELECT project_id FROM mindmaps WHERE id = ?1",
+                "SELECT id, project_id, kind, item_id, title, trash_path, deleted_at, snapshot, files
+                 FROM trash_items WHERE id = ?1",
                 params![id],
-                |row| row.get(0),
+                |row| {
+                    Ok((
+                        trash_item_from_row(row, &paths)?,
+                        row.get::<_, String>(7)?,
+                        row.get::<_, String>(8)?,
+                    ))
+                },
             )
             .map_err(|e| match e {
                 rusqlite::Error::QueryReturnedNoRows => {
-                    DbError::NotFound(format!("思维导图 {} 不存在", id))
+                    DbError::NotFound(format!("回收站条目 {} 不存在", id))
                 }
                 _ => DbError::Sqlite(e),
             })?;
 
-        conn.execute("DELETE FROM mindmaps WHERE id = ?1", params![id])?;
-        Ok(project_id)
+        let mut snapshot: ContentSnapshot = serde_json::from_str(&snapshot_json)
+            .map_err(|e| DbError::Serialization(e.to_string()))?;
+        let mut files: Vec<TrashedFile> = serde_json::from_str(&files_json)
+            .map_err(|e| DbError::Serialization(e.to_string()))?;
+        paths.resolve_snapshot(&mut snapshot);
+        for file in &mut files {
+            file.original = paths.resolve(&file.original);
+            file.trashed = paths.resolve(&file.trashed);
+        }
+
+        Ok((item, snapshot, files))
+    }
+
+    // ==================== 标签操作 ====================
+
+    /// 获取条目的标签
+    pub fn get_tags(&self, kind: ContentKind, item_id: &str) -> Result<Vec<String>, DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let mut stmt = conn.prepare(
+            "SELECT tag FROM item_tags WHERE kind = ?1 AND item_id = ?2 ORDER BY tag",
+        )?;
+        let tags = stmt
+            .query_map(params![kind.as_str(), item_id], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(tags)
     }
+
+    /// 获取项目中使用过的全部标签（来源与笔记）
+    pub fn get_project_tags(&self, project_id: &str) -> Result<Vec<String>, DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let mut stmt = conn.prepare(
+            "SELECT DISTINCT t.tag FROM item_tags t
+             LEFT JOIN sources s ON t.kind = 'source' AND s.id = t.item_id
+             LEFT JOIN notes n ON t.kind = 'note' AND n.id = t.item_id
+             WHERE s.project_id = ?1 OR n.project_id = ?1
+             ORDER BY t.tag",
+        )?;
+        let tags = stmt
+            .query_map(params![project_id], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(tags)
+    }
+
+    /// 获取带有指定标签的条目 ID
+    pub fn get_tagged_item_ids(&self, kind: ContentKind, tag: &str) -> Result<Vec<String>, DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let mut stmt = conn.prepare(
+            "SELECT item_id FROM item_tags WHERE kind = ?1 AND tag = ?2 ORDER BY created_at",
+        )?;
+        let ids = stmt
+            .query_map(params![kind.as_str(), tag], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(ids)
+    }
+
+    // ==================== 设置操作 ====================
+
+    /// 读取设置项
+    pub fn get_setting(&self, key: &str) -> Result<Option<String>, DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let value = conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(value)
+    }
+
+    /// 写入设置项
+    pub fn set_setting(&self, key: &str, value: &str) -> Result<(), DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        conn.execute(
+            "INSERT INTO settings (key, value, updated_at) VALUES (?1, ?2, datetime('now'))
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    // ==================== 同步操作 ====================
+
+    /// 获取某类内容的全部 ID
+    pub fn list_content_ids(&self, kind: ContentKind) -> Result<Vec<String>, DbError> {
+        let table = match kind {
+            ContentKind::Project => "projects",
+            ContentKind::Source => "sources",
+            ContentKind::Note => "notes",
+            ContentKind::Canvas => "canvases",
+            ContentKind::Mindmap => "mindmaps",
+            ContentKind::Presentation => "presentations",
+            ContentKind::ChatSession => "chat_sessions",
+        };
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let mut stmt = conn.prepare(&format!("SELECT id FROM {} ORDER BY created_at", table))?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(ids)
+    }
+
+    /// 读取单条记录的快照（项目只包含项目本身）
+    pub fn get_record_snapshot(
+        &self,
+        kind: ContentKind,
+        id: &str,
+    ) -> Result<ContentSnapshot, DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        DbTx::new(&conn, self.path_mapper()).record_snapshot(kind, id)
+    }
+
+    /// 获取全部条目上次同步时的版本
+    pub fn get_sync_records(&self) -> Result<Vec<SyncChange>, DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let mut stmt = conn.prepare("SELECT change FROM sync_records")?;
+        let rows = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.iter()
+            .map(|json| {
+                serde_json::from_str(json).map_err(|e| DbError::Serialization(e.to_string()))
+            })
+            .collect()
+    }
+
+    /// 获取已应用的其他设备变更序号
+    pub fn get_sync_cursor(&self, device_id: &str) -> Result<u64, DbError> {
+        let conn = self.conn.lock().map_err(|_| DbError::LockError)?;
+        let seq = conn
+            .query_row(
+                "SELECT seq FROM sync_cursors WHERE device_id = ?1",
+                params![device_id],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()?;
+        Ok(seq.unwrap_or(0) as u64)
+    }
+}
+
+const SAVED_SEARCH_COLUMNS: &str = "id, name, query, project_id, include_archived, sort, track_new, last_run_at, created_at, updated_at";
+
+fn row_to_saved_search(row: &rusqlite::Row<'_>) -> rusqlite::Result<SavedSearch> {
+    Ok(SavedSearch {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        query: row.get(2)?,
+        project_id: row.get(3)?,
+        include_archived: row.get(4)?,
+        sort: SearchSort::parse(&row.get::<_, String>(5)?).unwrap_or_default(),
+        track_new: row.get(6)?,
+        last_run_at: row.get::<_, Option<String>>(7)?.map(|s| parse_datetime(&s)),
+        created_at: parse_datetime(&row.get::<_, String>(8)?),
+        updated_at: parse_datetime(&row.get::<_, String>(9)?),
+    })
+}
+
+fn row_to_workspace(row: &rusqlite::Row<'_>) -> rusqlite::Result<Workspace> {
+    Ok(Workspace {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        is_system: row.get::<_, i32>(2)? != 0,
+        order: row.get(3)?,
+        parent_id: row.get(4)?,
+    })
+}
+
+/// 工作空间名称唯一约束冲突时返回 AlreadyExists
+fn workspace_name_conflict(e: rusqlite::Error, name: &str) -> DbError {
+    if let rusqlite::Error::SqliteFailure(err, _) = &e {
+        if err.extended_code == 2067 {
+            // UNIQUE constraint failed
+            return DbError::AlreadyExists(format!("分类 {} 已存在", name));
+        }
+    }
+    DbError::Sqlite(e)
+}
+
+fn trash_item_from_row(row: &rusqlite::Row<'_>, paths: &PathMapper) -> rusqlite::Result<TrashItem> {
+    Ok(TrashItem {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        kind: ContentKind::from_str(&row.get::<_, String>(2)?).unwrap_or(ContentKind::Note),
+        item_id: row.get(3)?,
+        title: row.get(4)?,
+        trash_path: paths.resolve(&row.get::<_, String>(5)?),
+        deleted_at: parse_datetime(&row.get::<_, String>(6)?),
+    })
 }
 
 /// 解析日期时间字符串，支持 RFC 3339 和 SQLite 默认格式
@@ -1712,21 +2909,92 @@ fn deserialize_embedding(data: &[u8]) -> Vec<f32> {
         .collect()
 }
 
-fn build_snippet(text: Option<String>) -> String {
-    let Some(value) = text else {
-        return String::new();
+/// 重建独立维护的全文索引（见 `Database::rebuild_fts`）
+fn rebuild_standalone_fts(conn: &Connection, table: &str) -> Result<(), DbError> {
+    let Some((_, key, source)) = STANDALONE_FTS_TABLES
+        .iter()
+        .find(|(name, _, _)| *name == table)
+    else {
+        return Err(DbError::NotFound(format!("全文索引 {} 不存在", table)));
     };
-    let trimmed = value.trim();
-    if trimmed.is_empty() {
-        return String::new();
+
+    match table {
+        "mindmaps_fts" => {
+            let mut stmt = conn.prepare("SELECT id, title, data FROM mindmaps")?;
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            conn.execute("DELETE FROM mindmaps_fts", [])?;
+            for (id, title, data) in rows {
+                conn.execute(
+                    "INSERT INTO mindmaps_fts(mindmap_id, title, text_content) VALUES (?1, ?2, ?3)",
+                    params![id, title, mindmap_text(&data)],
+                )?;
+            }
+        }
+        "chat_fts" => {
+            // 与 chat_messages 触发器一致：会话内全部消息按时间拼接
+            conn.execute_batch(
+                "DELETE FROM chat_fts;
+                 INSERT INTO chat_fts(session_id, title, text_content)
+                     SELECT s.id, s.title, (
+                         SELECT IFNULL(group_concat(content, char(10)), '') FROM (
+                             SELECT content FROM chat_messages
+                             WHERE session_id = s.id ORDER BY created_at, rowid
+                         )
+                     ) FROM chat_sessions s;",
+            )?;
+        }
+        _ => {
+            // 画布与 PPT 文本从数据文件提取，这里只清理孤立记录并补齐缺失的条目（文本待提取）
+            conn.execute_batch(&format!(
+                "DELETE FROM {table} WHERE {key} NOT IN (SELECT id FROM {source});
+                 INSERT INTO {table}({key}, title, text_content)
+                     SELECT id, title, NULL FROM {source}
+                     WHERE id NOT IN (SELECT {key} FROM {table});"
+            ))?;
+        }
     }
+    Ok(())
+}
+
+/// 思维导图 JSON 数据中的文本，数据无法解析时为空
+pub(crate) fn mindmap_text(data: &str) -> String {
+    serde_json::from_str::<MindMapData>(data)
+        .map(|data| extract_mindmap_text(&data))
+        .unwrap_or_default()
+}
 
-    trimmed.chars().take(120).collect()
+/// 截取搜索摘要：命中 `terms` 时返回多段摘要，`snippet` 为首段（命中词用 `<mark>` 标记）；
+/// 正文未命中时取正文开头，没有正文时用标题
+fn build_snippets(
+    text: Option<&str>,
+    terms: &[&str],
+    title: &str,
+) -> (String, Vec<SearchSnippet>) {
+    let Some(text) = text else {
+        return (title.to_string(), Vec::new());
+    };
+    let snippets = fts::snippets(text, terms, fts::MAX_SNIPPETS);
+    let snippet = match snippets.first() {
+        Some(first) => fts::mark(first),
+        None => fts::leading_snippet(text)
+            .map(|leading| leading.text)
+            .unwrap_or_else(|| title.to_string()),
+    };
+    (snippet, snippets)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs;
 
     fn create_test_db() -> Database {
         Database::new_in_memory().expect("创建测试数据库失败")
@@ -1743,6 +3011,7 @@ mod tests {
             updated_at: Utc::now(),
             sources_count: 0,
             path: format!("/test/{}", name),
+            archived_at: None,
         }
     }
 
@@ -1835,6 +3104,7 @@ mod tests {
             name: "自定义分类".to_string(),
             is_system: false,
             order: 100,
+            parent_id: None,
         };
 
         db.insert_workspace(&workspace).unwrap();
@@ -1881,7 +3151,816 @@ mod tests {
         db.insert_project(&project2).unwrap();
         db.insert_project(&project3).unwrap();
 
-        let results = db.search_projects("学习", 10).unwrap();
+        let results = db.search_projects("学习", None, 10, false).unwrap();
         assert_eq!(results.len(), 2);
     }
+
+    #[test]
+    fn test_transaction_rollback_on_error() {
+        let db = create_test_db();
+        let project = create_test_project("事务项目");
+        db.insert_project(&project).unwrap();
+
+        let note = Note {
+            id: "note-tx".to_string(),
+            project_id: project.id.clone(),
+            title: "事务笔记".to_string(),
+            path: "/test/note-tx.md".to_string(),
+            output_type: OutputType::Note,
+            title_locked: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        // 闭包返回错误时整体回滚
+        let result: Result<(), DbError> = db.transaction(|tx| {
+            tx.insert_note(&note)?;
+            tx.update_note_title(&note.id, "已修改")?;
+            Err(DbError::NotFound("模拟文件提交失败".to_string()))
+        });
+        assert!(result.is_err());
+        assert!(db.get_note(&note.id).is_err());
+
+        // 成功时全部提交
+        db.transaction(|tx| -> Result<(), DbError> {
+            tx.insert_note(&note)?;
+            tx.update_note_title(&note.id, "已修改")
+        })
+        .unwrap();
+        assert_eq!(db.get_note(&note.id).unwrap().title, "已修改");
+    }
+
+    /// 在数据目录中创建带来源、笔记、画布、PPT 的项目（路径均为数据目录下的绝对路径）
+    fn insert_project_with_files(db: &Database, base: &Path) -> (Project, Source, Note) {
+        let mut project = create_test_project("可迁移项目");
+        let project_dir = base.join("projects").join(&project.id);
+        project.path = project_dir.display().to_string();
+        db.insert_project(&project).unwrap();
+
+        let source = Source {
+            id: "source-rel".to_string(),
+            project_id: project.id.clone(),
+            name: "资料.pdf".to_string(),
+            source_type: SourceType::Pdf,
+            path: project_dir.join("sources").join("source-rel.pdf").display().to_string(),
+            size: 10,
+            mime_type: "application/pdf".to_string(),
+            thumbnail_path: Some(project_dir.join("thumbnails").join("source-rel.png").display().to_string()),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        db.insert_source_with_content(&source, Some("内容")).unwrap();
+
+        let note = Note {
+            id: "note-rel".to_string(),
+            project_id: project.id.clone(),
+            title: "笔记".to_string(),
+            path: project_dir.join("notes").join("note-rel.md").display().to_string(),
+            output_type: OutputType::Note,
+            title_locked: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        db.insert_note(&note).unwrap();
+
+        db.insert_canvas(&Canvas {
+            id: "canvas-rel".to_string(),
+            project_id: project.id.clone(),
+            title: "画布".to_string(),
+            path: project_dir.join("canvases").join("canvas-rel.json").display().to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        })
+        .unwrap();
+
+        db.insert_presentation(&Presentation {
+            id: "ppt-rel".to_string(),
+            project_id: project.id.clone(),
+            title: "PPT".to_string(),
+            data_path: project_dir.join("presentations").join("ppt-rel.json").display().to_string(),
+            thumbnail_path: None,
+            slide_count: 1,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        })
+        .unwrap();
+
+        (project, source, note)
+    }
+
+    #[test]
+    fn test_relative_paths_survive_data_dir_move() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let old_base = temp.path().join("old");
+        let db_path = old_base.join("db").join("desklab.db");
+        fs::create_dir_all(db_path.parent().unwrap()).unwrap();
+
+        let (project, source, note) = {
+            let db = Database::new(&db_path).unwrap();
+            db.set_data_dir(&old_base).unwrap();
+            let inserted = insert_project_with_files(&db, &old_base);
+
+            // 数据库中保存的是相对路径
+            let conn = db.conn.lock().unwrap();
+            let stored: String = conn
+                .query_row("SELECT path FROM sources WHERE id = 'source-rel'", [], |row| row.get(0))
+                .unwrap();
+            assert_eq!(stored, "projects/".to_string() + &inserted.0.id + "/sources/source-rel.pdf");
+            inserted
+        };
+
+        // 移动整个数据目录后重新打开
+        let new_base = temp.path().join("moved");
+        fs::rename(&old_base, &new_base).unwrap();
+        let db = Database::new(&new_base.join("db").join("desklab.db")).unwrap();
+        assert_eq!(db.set_data_dir(&new_base).unwrap(), 0);
+
+        let project_dir = new_base.join("projects").join(&project.id);
+        assert_eq!(db.get_project(&project.id).unwrap().path, project_dir.display().to_string());
+        let moved_source = db.get_source(&source.id).unwrap();
+        assert_eq!(
+            moved_source.path,
+            project_dir.join("sources").join("source-rel.pdf").display().to_string()
+        );
+        assert_eq!(
+            moved_source.thumbnail_path.unwrap(),
+            project_dir.join("thumbnails").join("source-rel.png").display().to_string()
+        );
+        assert_eq!(
+            db.get_note(&note.id).unwrap().path,
+            project_dir.join("notes").join("note-rel.md").display().to_string()
+        );
+        assert!(db.get_canvas("canvas-rel").unwrap().path.starts_with(&project_dir.display().to_string()));
+        assert!(db
+            .get_presentation("ppt-rel")
+            .unwrap()
+            .data_path
+            .starts_with(&project_dir.display().to_string()));
+    }
+
+    #[test]
+    fn test_migrate_legacy_absolute_paths() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let old_base = temp.path().join("old");
+        let new_base = temp.path().join("new");
+
+        // 旧版本：未设置数据目录，保存绝对路径
+        let db = create_test_db();
+        let (project, source, note) = insert_project_with_files(&db, &old_base);
+        let trash_dir = old_base.join("projects").join(&project.id).join(".trash").join("t1");
+        let item = TrashItem {
+            id: "t1".to_string(),
+            project_id: project.id.clone(),
+            kind: ContentKind::Note,
+            item_id: "note-trashed".to_string(),
+            title: "已删除".to_string(),
+            trash_path: trash_dir.display().to_string(),
+            deleted_at: Utc::now(),
+        };
+        let mut trashed_note = note.clone();
+        trashed_note.id = "note-trashed".to_string();
+        let snapshot = ContentSnapshot {
+            notes: vec![crate::models::NoteRecord {
+                note: trashed_note.clone(),
+                text_content: None,
+                embedding: None,
+            }],
+            ..Default::default()
+        };
+        let files = vec![TrashedFile {
+            original: trashed_note.path.clone(),
+            trashed: trash_dir.join("files").join("0_note.md").display().to_string(),
+        }];
+        db.transaction(|tx| tx.insert_trash_item(&item, &snapshot, &files)).unwrap();
+
+        // 数据目录已移动到新位置后升级
+        assert_eq!(db.set_data_dir(&new_base).unwrap(), 7);
+        assert_eq!(db.set_data_dir(&new_base).unwrap(), 0);
+
+        let project_dir = new_base.join("projects").join(&project.id);
+        assert_eq!(db.get_project(&project.id).unwrap().path, project_dir.display().to_string());
+        assert!(db.get_source(&source.id).unwrap().path.starts_with(&project_dir.display().to_string()));
+
+        let (item, snapshot, files) = db.get_trash_entry("t1").unwrap();
+        let new_trash_dir = project_dir.join(".trash").join("t1");
+        assert_eq!(item.trash_path, new_trash_dir.display().to_string());
+        assert!(snapshot.notes[0].note.path.starts_with(&project_dir.display().to_string()));
+        assert_eq!(
+            files[0].trashed,
+            new_trash_dir.join("files").join("0_note.md").display().to_string()
+        );
+    }
+
+    #[test]
+    fn test_activity_log() {
+        let db = create_test_db();
+        let project = create_test_project("活动项目");
+        db.insert_project(&project).unwrap();
+
+        let now = Utc::now();
+        let mindmap = MindMap {
+            id: "mindmap-1".to_string(),
+            project_id: project.id.clone(),
+            title: "会议纪要".to_string(),
+            theme: "default".to_string(),
+            layout: "logicalStructure".to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+        db.insert_mindmap(&mindmap, "{}").unwrap();
+
+        // 创建后 5 分钟内的修改合并到创建事件中
+        db.update_mindmap_title("mindmap-1", "会议纪要（初稿）").unwrap();
+        let actions = |db: &Database| -> Vec<(ContentKind, ActivityAction)> {
+            db.get_activity(Some(&project.id), None, 50)
+                .unwrap()
+                .into_iter()
+                .map(|entry| (entry.kind, entry.action))
+                .collect()
+        };
+        assert_eq!(
+            actions(&db),
+            vec![
+                (ContentKind::Mindmap, ActivityAction::Create),
+                (ContentKind::Project, ActivityAction::Create),
+            ]
+        );
+
+        // 只调整来源数量不算项目修改
+        db.update_project_sources_count(&project.id, 1).unwrap();
+        assert_eq!(actions(&db).len(), 2);
+
+        db.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE activity_log SET created_at = datetime('now', '-1 hour')",
+                [],
+            )
+            .unwrap();
+        db.update_mindmap_title("mindmap-1", "会议纪要（定稿）").unwrap();
+        db.update_mindmap_title("mindmap-1", "会议纪要").unwrap();
+        db.delete_mindmap("mindmap-1").unwrap();
+
+        let feed = db.get_activity(Some(&project.id), None, 50).unwrap();
+        assert_eq!(feed.len(), 4);
+        assert_eq!(feed[0].action, ActivityAction::Delete);
+        assert_eq!(feed[0].title, "会议纪要");
+        assert_eq!(feed[1].action, ActivityAction::Update);
+        assert_eq!(feed[1].title, "会议纪要（定稿）");
+
+        // 分页与全库动态
+        let page = db.get_activity(None, Some(feed[1].id), 1).unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].id, feed[2].id);
+        assert!(db.get_activity(Some("other"), None, 50).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_activity_log_for_content_saves_and_replaced_snapshots() {
+        let db = create_test_db();
+        let project = create_test_project("活动项目");
+        db.insert_project(&project).unwrap();
+        let note = Note {
+            id: "note-1".to_string(),
+            project_id: project.id.clone(),
+            title: "周报".to_string(),
+            path: "/test/note-1.md".to_string(),
+            output_type: OutputType::Note,
+            title_locked: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        db.insert_note(&note).unwrap();
+        let age_activity = |db: &Database| {
+            db.conn
+                .lock()
+                .unwrap()
+                .execute(
+                    "UPDATE activity_log SET created_at = datetime('now', '-1 hour')",
+                    [],
+                )
+                .unwrap();
+        };
+        let note_actions = |db: &Database| -> Vec<ActivityAction> {
+            db.get_activity(Some(&project.id), None, 50)
+                .unwrap()
+                .into_iter()
+                .filter(|entry| entry.kind == ContentKind::Note)
+                .map(|entry| entry.action)
+                .collect()
+        };
+
+        // 保存正文记为修改
+        age_activity(&db);
+        db.update_note_content("note-1", "本周进展").unwrap();
+        assert_eq!(
+            note_actions(&db),
+            vec![ActivityAction::Update, ActivityAction::Create]
+        );
+
+        // 替换快照（同步应用另一端的版本）只记一条修改，5 分钟内不重复记录
+        let mut snapshot = db.get_record_snapshot(ContentKind::Note, "note-1").unwrap();
+        snapshot.notes[0].note.title = "周报（定稿）".to_string();
+        let replace = |db: &Database, new: &ContentSnapshot| {
+            let old = db.get_record_snapshot(ContentKind::Note, "note-1").unwrap();
+            db.transaction(|tx| tx.replace_snapshot(&old, new)).unwrap();
+        };
+        replace(&db, &snapshot);
+        assert_eq!(note_actions(&db).len(), 2);
+
+        age_activity(&db);
+        replace(&db, &snapshot);
+        let feed = db.get_activity(Some(&project.id), None, 50).unwrap();
+        assert_eq!(feed[0].action, ActivityAction::Update);
+        assert_eq!(feed[0].title, "周报（定稿）");
+        assert_eq!(
+            note_actions(&db),
+            vec![
+                ActivityAction::Update,
+                ActivityAction::Update,
+                ActivityAction::Create
+            ]
+        );
+        assert_eq!(db.get_note("note-1").unwrap().title, "周报（定稿）");
+
+        // 原地更新：未变的向量不重写，标签只增删差异部分
+        db.transaction(|tx| tx.add_tag(ContentKind::Note, "note-1", "周报"))
+            .unwrap();
+        db.upsert_note_embedding("note-1", &project.id, &[0.1, 0.2])
+            .unwrap();
+        let version = db.embedding_version().unwrap();
+        let mut snapshot = db.get_record_snapshot(ContentKind::Note, "note-1").unwrap();
+        snapshot.tags[0].tag = "定稿".to_string();
+        replace(&db, &snapshot);
+        assert_eq!(db.embedding_version().unwrap(), version);
+        assert_eq!(db.get_tags(ContentKind::Note, "note-1").unwrap(), vec!["定稿"]);
+    }
+
+    #[test]
+    fn test_fts_search_ranks_cjk_matches() {
+        let db = create_test_db();
+        let project = create_test_project("检索项目");
+        db.insert_project(&project).unwrap();
+
+        let insert = |id: &str, name: &str, text: &str| {
+            let source = Source {
+                id: id.to_string(),
+                project_id: project.id.clone(),
+                name: name.to_string(),
+                source_type: SourceType::Markdown,
+                path: format!("/test/{}.md", id),
+                size: 0,
+                mime_type: "text/markdown".to_string(),
+                thumbnail_path: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            };
+            db.insert_source_with_content(&source, Some(text)).unwrap();
+        };
+        insert("body", "会议纪要.md", "讨论了知识图谱的构建方案");
+        insert("title", "知识图谱综述.md", "一篇综述");
+        insert("other", "采购清单.md", "显示器、键盘");
+
+        // 标题命中权重更高，得分降序
+        let results = db.search_sources("知识图谱", None, 10, false).unwrap();
+        let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["title", "body"]);
+        assert!(results[0].score.unwrap() > results[1].score.unwrap());
+        assert!(results[1].score.unwrap() > 0.0);
+
+        // 多个词须同时命中；两字词走 LIKE 过滤
+        assert_eq!(db.search_sources("知识图谱 构建方案", None, 10, false).unwrap().len(), 1);
+        assert_eq!(db.search_sources("图谱", None, 10, false).unwrap().len(), 2);
+        assert_eq!(db.search_sources("键盘 显示器", None, 10, false).unwrap()[0].id, "other");
+        assert!(db.search_sources("  ", None, 10, false).unwrap().is_empty());
+        // 查询中的 FTS 语法字符按普通文本处理
+        assert!(db.search_sources("\"知识 OR", None, 10, false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fts_survives_cjk_rename() {
+        let db = create_test_db();
+        let project = create_test_project("改名项目");
+        db.insert_project(&project).unwrap();
+        let note = Note {
+            id: "n1".to_string(),
+            project_id: project.id.clone(),
+            title: "产品需求文档".to_string(),
+            path: "/test/n1.md".to_string(),
+            output_type: OutputType::Note,
+            title_locked: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        db.insert_note(&note).unwrap();
+
+        db.rename_note("n1", "技术方案评审").unwrap();
+        db.update_project_name(&project.id, "重命名后的项目").unwrap();
+
+        assert!(db.search_notes("需求文档", None, 10, false).unwrap().is_empty());
+        assert_eq!(db.search_notes("方案评审", None, 10, false).unwrap().len(), 1);
+        assert_eq!(db.search_projects("重命名", None, 10, false).unwrap().len(), 1);
+        assert!(db.check_fts().unwrap().is_empty());
+
+        db.delete_project(&project.id).unwrap();
+        assert!(db.search_notes("方案评审", None, 10, false).unwrap().is_empty());
+        assert!(db.check_fts().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_search_note_bodies() {
+        let db = create_test_db();
+        let project = create_test_project("正文项目");
+        db.insert_project(&project).unwrap();
+        let insert = |id: &str, title: &str, content: &str| {
+            db.insert_note(&Note {
+                id: id.to_string(),
+                project_id: project.id.clone(),
+                title: title.to_string(),
+                path: format!("/test/{}.md", id),
+                output_type: OutputType::Note,
+                title_locked: false,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .unwrap();
+            db.update_note_content(id, content).unwrap();
+        };
+        insert("body", "周会记录", "# 周会记录\n\n本周完成了向量检索的灰度发布。");
+        insert("title", "向量检索设计", "待补充");
+
+        // 标题命中排在正文命中之前，正文命中返回高亮摘要
+        let results = db.search_notes("向量检索", None, 10, false).unwrap();
+        let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["title", "body"]);
+        assert_eq!(results[0].snippet, "待补充");
+        assert_eq!(
+            results[1].snippet,
+            "# 周会记录 本周完成了<mark>向量检索</mark>的灰度发布。"
+        );
+        assert_eq!(db.search_notes("灰度", None, 10, false).unwrap()[0].id, "body");
+
+        // 正文更新后旧内容不再命中；内容不变时不重写索引
+        db.update_note_content("body", "改为讨论权限模型").unwrap();
+        db.update_note_content("body", "改为讨论权限模型").unwrap();
+        assert!(db.search_notes("灰度发布", None, 10, false).unwrap().is_empty());
+        assert_eq!(db.search_notes("权限模型", None, 10, false).unwrap().len(), 1);
+        assert!(db.check_fts().unwrap().is_empty());
+
+        // 回收站快照保留正文索引
+        let snapshot = db.get_snapshot(ContentKind::Note, "body").unwrap();
+        db.delete_note("body").unwrap();
+        assert!(db.search_notes("权限模型", None, 10, false).unwrap().is_empty());
+        db.transaction(|tx| tx.restore_snapshot(&snapshot)).unwrap();
+        assert_eq!(db.search_notes("权限模型", None, 10, false).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_search_snippets_with_pages() {
+        let db = create_test_db();
+        let project = create_test_project("分页项目");
+        db.insert_project(&project).unwrap();
+        let source = Source {
+            id: "pdf".to_string(),
+            project_id: project.id.clone(),
+            name: "报告.pdf".to_string(),
+            source_type: SourceType::Pdf,
+            path: "/test/报告.pdf".to_string(),
+            size: 0,
+            mime_type: "application/pdf".to_string(),
+            thumbnail_path: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let text = format!(
+            "摘要{}{}第二页介绍离线同步。{}{}第三页总结离线同步的冲突处理。",
+            crate::services::PAGE_BREAK,
+            "正文".repeat(100),
+            crate::services::PAGE_BREAK,
+            "附录".repeat(100),
+        );
+        db.insert_source_with_content(&source, Some(&text)).unwrap();
+
+        // 每处命中各截取一段摘要，并给出所在页码
+        let results = db.search_sources("离线同步", None, 10, false).unwrap();
+        let snippets = &results[0].snippets;
+        assert_eq!(snippets.len(), 2);
+        assert_eq!(snippets[0].page, Some(2));
+        assert_eq!(snippets[1].page, Some(3));
+        assert!(snippets.iter().all(|snippet| snippet.highlights.len() == 1));
+        assert!(results[0].snippet.contains("第二页介绍<mark>离线同步</mark>"));
+
+        // 短词走 LIKE 过滤，同样截取命中位置
+        let results = db.search_sources("附录", None, 10, false).unwrap();
+        assert_eq!(results[0].snippets[0].page, Some(3));
+
+        // 标题命中而正文未命中时取正文开头
+        let results = db.search_sources("报告", None, 10, false).unwrap();
+        assert!(results[0].snippets.is_empty());
+        assert!(results[0].snippet.starts_with("摘要 正文"));
+    }
+
+    #[test]
+    fn test_backfill_note_contents() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let db = Database::new(&temp.path().join("desklab.db")).unwrap();
+        let project = create_test_project("回填项目");
+        db.insert_project(&project).unwrap();
+        let note_path = temp.path().join("projects").join("n1.md");
+        fs::create_dir_all(note_path.parent().unwrap()).unwrap();
+        fs::write(&note_path, "# 会议\n\n讨论了离线同步方案").unwrap();
+        db.insert_note(&Note {
+            id: "n1".to_string(),
+            project_id: project.id.clone(),
+            title: "会议".to_string(),
+            path: note_path.display().to_string(),
+            output_type: OutputType::Note,
+            title_locked: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        })
+        .unwrap();
+
+        // 模拟旧版本数据库：笔记表没有正文字段，索引只有标题
+        {
+            let conn = db.conn.lock().unwrap();
+            conn.execute_batch(
+                "DROP TRIGGER notes_ai; DROP TRIGGER notes_au; DROP TRIGGER notes_ad;
+                 DROP TABLE notes_fts;
+                 ALTER TABLE notes DROP COLUMN text_content;
+                 ALTER TABLE notes DROP COLUMN content_hash;
+                 CREATE VIRTUAL TABLE notes_fts USING fts5(title, content='notes', content_rowid='rowid', tokenize='trigram');
+                 CREATE TRIGGER notes_ad AFTER DELETE ON notes BEGIN
+                     INSERT INTO notes_fts(notes_fts, rowid, title) VALUES ('delete', OLD.rowid, OLD.title);
+                 END;
+                 INSERT INTO notes_fts(notes_fts) VALUES('rebuild');",
+            )
+            .unwrap();
+        }
+        drop(db);
+
+        let db = Database::new(&temp.path().join("desklab.db")).unwrap();
+        assert!(db.search_notes("离线同步", None, 10, false).unwrap().is_empty());
+        assert_eq!(db.set_data_dir(temp.path()).unwrap(), 1);
+        let results = db.search_notes("离线同步", None, 10, false).unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].snippet.contains("<mark>离线同步</mark>"));
+        assert!(db.check_fts().unwrap().is_empty());
+
+        // 已回填的笔记不再重复读取文件
+        fs::remove_file(&note_path).unwrap();
+        assert_eq!(db.backfill_note_contents().unwrap(), 0);
+        assert_eq!(db.search_notes("离线同步", None, 10, false).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_migrate_fts_to_trigram() {
+        let db = create_test_db();
+        let project = create_test_project("旧版项目");
+        db.insert_project(&project).unwrap();
+
+        // 模拟旧版本数据库：默认分词器与 UPDATE 触发器
+        {
+            let conn = db.conn.lock().unwrap();
+            conn.execute_batch(
+                "DROP TRIGGER projects_ai; DROP TRIGGER projects_au; DROP TRIGGER projects_ad;
+                 DROP TRIGGER canvases_ai; DROP TRIGGER canvases_au; DROP TRIGGER canvases_ad;
+                 DROP TABLE projects_fts; DROP TABLE sources_fts; DROP TABLE canvases_fts;
+                 CREATE VIRTUAL TABLE projects_fts USING fts5(name, content='projects', content_rowid='rowid');
+                 CREATE VIRTUAL TABLE sources_fts USING fts5(name, text_content, content='sources', content_rowid='rowid');
+                 CREATE VIRTUAL TABLE canvases_fts USING fts5(canvas_id, title, text_content);
+                 CREATE TRIGGER projects_au AFTER UPDATE ON projects BEGIN
+                     UPDATE projects_fts SET name = NEW.name WHERE rowid = OLD.rowid;
+                 END;
+                 CREATE TRIGGER canvases_ad AFTER DELETE ON canvases BEGIN
+                     DELETE FROM canvases_fts WHERE canvas_id = OLD.id;
+                 END;
+                 INSERT INTO projects_fts(projects_fts) VALUES('rebuild');",
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO canvases (id, project_id, title, path) VALUES ('c1', ?1, '旧画布', '/c1.json')",
+                params![project.id],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO canvases_fts (canvas_id, title, text_content) VALUES ('c1', '旧画布', '系统架构草图')",
+                [],
+            )
+            .unwrap();
+        }
+
+        db.init_schema().unwrap();
+
+        let sql: String = db
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT sql FROM sqlite_master WHERE name = 'projects_fts'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert!(sql.contains("trigram"));
+        assert_eq!(db.search_projects("旧版项目", None, 10, false).unwrap().len(), 1);
+        // 画布索引中运行时提取的文本被保留
+        assert_eq!(db.search_canvases("架构草图", None, 10, false).unwrap().len(), 1);
+
+        db.update_project_name(&project.id, "新版项目名称").unwrap();
+        assert_eq!(db.search_projects("新版项目", None, 10, false).unwrap().len(), 1);
+        assert!(db.check_fts().unwrap().is_empty());
+
+        // 再次初始化不重复迁移
+        db.init_schema().unwrap();
+        assert_eq!(db.search_canvases("架构草图", None, 10, false).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_detect_bad_json_and_fts_drift() {
+        let db = create_test_db();
+        let project = create_test_project("索引项目");
+        db.insert_project(&project).unwrap();
+        assert!(db.find_bad_json().unwrap().is_empty());
+        assert!(db.check_fts().unwrap().is_empty());
+
+        {
+            let conn = db.conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO chat_sessions (id, project_id, title) VALUES ('s1', ?1, '对话')",
+                params![project.id],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO chat_messages (id, session_id, role, content, citations)
+                 VALUES ('m1', 's1', 'assistant', '回答', '[{\"index\":')",
+                [],
+            )
+            .unwrap();
+            // 索引中出现数据表中不存在的行
+            conn.execute("INSERT INTO notes_fts (rowid, title) VALUES (999, '幽灵笔记')", [])
+                .unwrap();
+            conn.execute(
+                "INSERT INTO canvases_fts (canvas_id, title, text_content) VALUES ('gone', '旧画布', '')",
+                [],
+            )
+            .unwrap();
+        }
+
+        let bad = db.find_bad_json().unwrap();
+        assert_eq!(
+            bad,
+            vec![BadJsonRow {
+                table: "chat_messages",
+                column: "citations",
+                key: "m1".to_string(),
+            }]
+        );
+        db.clear_message_citations("m1").unwrap();
+        assert!(db.find_bad_json().unwrap().is_empty());
+
+        assert_eq!(db.check_fts().unwrap(), vec!["notes_fts", "canvases_fts"]);
+
+        // 关闭外键约束时写入的悬空记录（旧版本数据库或外部工具修改）
+        {
+            let conn = db.conn.lock().unwrap();
+            conn.execute_batch(
+                "PRAGMA foreign_keys = OFF;
+                 INSERT INTO source_embeddings (source_id, project_id, embedding) VALUES ('gone', 'gone', x'00');
+                 INSERT INTO mindmaps (id, project_id, title) VALUES ('orphan-map', 'gone', '导图');
+                 PRAGMA foreign_keys = ON;",
+            )
+            .unwrap();
+        }
+        let dangling = db.find_dangling_rows().unwrap();
+        assert_eq!(
+            dangling.iter().map(|row| row.table).collect::<Vec<_>>(),
+            vec!["mindmaps", "source_embeddings"]
+        );
+        assert_eq!(db.delete_dangling_rows().unwrap(), 2);
+        assert!(db.find_dangling_rows().unwrap().is_empty());
+        db.rebuild_fts("notes_fts").unwrap();
+        db.rebuild_fts("canvases_fts").unwrap();
+        assert!(db.check_fts().unwrap().is_empty());
+        assert!(db.rebuild_fts("unknown").is_err());
+    }
+
+    #[test]
+    fn test_search_mindmaps_presentations_and_chats() {
+        let db = create_test_db();
+        let project = create_test_project("内容项目");
+        db.insert_project(&project).unwrap();
+        let now = Utc::now();
+
+        // 思维导图：节点文本、备注与标签随数据写入索引
+        let mindmap = MindMap {
+            id: "m1".to_string(),
+            project_id: project.id.clone(),
+            title: "研究路线".to_string(),
+            theme: "default".to_string(),
+            layout: "logicalStructure".to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+        let map_data = |child: &str| {
+            serde_json::json!({
+                "root": {
+                    "data": {"text": "中心主题"},
+                    "children": [{"data": {"text": child, "note": "参考综述", "tag": ["待读"]}}]
+                }
+            })
+            .to_string()
+        };
+        db.insert_mindmap(&mindmap, &map_data("召回策略")).unwrap();
+        assert_eq!(db.search_mindmaps("召回策略", None, 10, false).unwrap()[0].id, "m1");
+        assert_eq!(db.search_mindmaps("参考综述", None, 10, false).unwrap().len(), 1);
+        db.update_mindmap_data("m1", &map_data("排序模型")).unwrap();
+        assert!(db.search_mindmaps("召回策略", None, 10, false).unwrap().is_empty());
+        assert_eq!(db.search_mindmaps("排序模型", None, 10, false).unwrap().len(), 1);
+
+        // 对话：会话正文随消息增删同步
+        db.insert_chat_session(&ChatSession {
+            id: "s1".to_string(),
+            project_id: project.id.clone(),
+            title: "新对话".to_string(),
+            created_at: now,
+            updated_at: now,
+        })
+        .unwrap();
+        for (id, content) in [("c1", "如何评估检索质量"), ("c2", "可以用召回率和 nDCG")] {
+            db.insert_chat_message(&ChatMessage {
+                id: id.to_string(),
+                session_id: "s1".to_string(),
+                role: MessageRole::User,
+                content: content.to_string(),
+                citations: None,
+                created_at: now,
+            })
+            .unwrap();
+        }
+        let results = db.search_chats("召回率", None, 10, false).unwrap();
+        assert_eq!(results[0].result_type, "chat");
+        assert_eq!(results[0].snippet, "如何评估检索质量 可以用<mark>召回率</mark>和 nDCG");
+        db.delete_chat_message("c2").unwrap();
+        assert!(db.search_chats("召回率", None, 10, false).unwrap().is_empty());
+
+        // PPT：尚未提取文本的在启动时从数据文件回填
+        let dir = tempfile::tempdir().unwrap();
+        let data_path = dir.path().join("p1.json");
+        fs::write(
+            &data_path,
+            serde_json::json!({
+                "slides": [{"id": "s1", "elements": [], "remark": "<p>先讲结论</p>"}]
+            })
+            .to_string(),
+        )
+        .unwrap();
+        db.insert_presentation(&Presentation {
+            id: "p1".to_string(),
+            project_id: project.id.clone(),
+            title: "季度汇报".to_string(),
+            data_path: data_path.display().to_string(),
+            thumbnail_path: None,
+            slide_count: 1,
+            created_at: now,
+            updated_at: now,
+        })
+        .unwrap();
+        assert!(db.search_presentations("先讲结论", None, 10, false).unwrap().is_empty());
+        assert_eq!(db.backfill_presentation_texts().unwrap(), 1);
+        assert_eq!(db.backfill_presentation_texts().unwrap(), 0);
+        assert_eq!(db.search_presentations("先讲结论", None, 10, false).unwrap().len(), 1);
+
+        // 按快照写入（归档导入、模板、复制项目、同步）时从数据文件提取文本
+        let snapshot = db.get_snapshot(ContentKind::Presentation, "p1").unwrap();
+        db.transaction(|tx| tx.delete_snapshot(&snapshot)).unwrap();
+        assert!(db.search_presentations("先讲结论", None, 10, false).unwrap().is_empty());
+        db.transaction(|tx| tx.restore_snapshot(&snapshot)).unwrap();
+        assert_eq!(db.search_presentations("先讲结论", None, 10, false).unwrap().len(), 1);
+
+        let query = SearchQuery::parse("type:mindmap OR type:presentation OR type:chat")
+            .unwrap()
+            .unwrap();
+        let types: Vec<String> = db
+            .search_all(&query, None, 10, 0, SearchSort::Relevance, false)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.result_type)
+            .collect();
+        assert_eq!(types.len(), 3);
+        assert!(db.check_fts().unwrap().is_empty());
+
+        // 旧版本数据库没有这些索引，升级时按现有数据补建
+        {
+            let conn = db.conn.lock().unwrap();
+            conn.execute_batch(
+                "DROP TABLE mindmaps_fts; DROP TABLE presentations_fts; DROP TABLE chat_fts;",
+            )
+            .unwrap();
+        }
+        db.init_schema().unwrap();
+        assert_eq!(db.search_mindmaps("排序模型", None, 10, false).unwrap().len(), 1);
+        assert_eq!(db.search_chats("检索质量", None, 10, false).unwrap().len(), 1);
+        assert_eq!(db.backfill_presentation_texts().unwrap(), 1);
+        assert!(db.check_fts().unwrap().is_empty());
+    }
 }
@@ -0,0 +1,163 @@
+//! 数据文件路径的存储形式
+//!
+//! 数据库中保存相对于数据目录（`FileService::base_path`）的路径，读取时再解析为绝对路径，
+//! 移动数据目录或在其他机器上恢复备份后记录仍然有效。
+
+use crate::models::ContentSnapshot;
+use std::path::{Component, Path, PathBuf};
+
+/// 数据文件路径转换器
+///
+/// 未设置数据目录时（如单元测试中的内存数据库）路径原样存取。
+#[derive(Debug, Clone, Default)]
+pub struct PathMapper {
+    base: Option<PathBuf>,
+}
+
+impl PathMapper {
+    pub fn new(base: PathBuf) -> Self {
+        Self { base: Some(base) }
+    }
+
+    /// 转换为存储形式：数据目录内的路径保存为以 `/` 分隔的相对路径，其余保持原样
+    pub fn to_stored(&self, path: &str) -> String {
+        let Some(base) = &self.base else {
+            return path.to_string();
+        };
+        match Path::new(path).strip_prefix(base) {
+            Ok(relative) if Path::new(path).is_absolute() => join_components(relative),
+            _ => path.to_string(),
+        }
+    }
+
+    pub fn to_stored_opt(&self, path: Option<&str>) -> Option<String> {
+        path.map(|p| self.to_stored(p))
+    }
+
+    /// 解析存储的路径：相对路径拼接到数据目录下，绝对路径保持原样
+    pub fn resolve(&self, stored: &str) -> String {
+        match &self.base {
+            Some(base) if !stored.is_empty() && Path::new(stored).is_relative() => {
+                base.join(stored).display().to_string()
+            }
+            _ => stored.to_string(),
+        }
+    }
+
+    pub fn resolve_opt(&self, stored: Option<String>) -> Option<String> {
+        stored.map(|p| self.resolve(&p))
+    }
+
+    /// 将快照中的路径（含项目目录）转换为存储形式
+    pub fn store_snapshot(&self, snapshot: &mut ContentSnapshot) {
+        snapshot.map_paths(|path| self.to_stored(path));
+        if let Some(project) = &mut snapshot.project {
+            project.path = self.to_stored(&project.path);
+        }
+    }
+
+    /// 解析快照中存储的路径（含项目目录）
+    pub fn resolve_snapshot(&self, snapshot: &mut ContentSnapshot) {
+        snapshot.map_paths(|path| self.resolve(path));
+        if let Some(project) = &mut snapshot.project {
+            project.path = self.resolve(&project.path);
+        }
+    }
+
+    /// 旧版本保存的绝对路径转换为相对路径
+    ///
+    /// 数据目录可能已被移动，因此除了匹配当前数据目录前缀外，还按数据目录的固定布局
+    /// （`projects/{id}/...` 与 `.trash/...`）识别。无法识别时返回 None。
+    pub fn relativize_legacy(&self, stored: &str) -> Option<String> {
+        let path = Path::new(stored);
+        if !path.is_absolute() {
+            return None;
+        }
+        if let Some(base) = &self.base {
+            if let Ok(relative) = path.strip_prefix(base) {
+                return Some(join_components(relative));
+            }
+        }
+
+        let components: Vec<Component> = path.components().collect();
+        let position = |name: &str| components.iter().rposition(|c| c.as_os_str() == name);
+        // 项目回收站位于 projects/{id}/.trash，资料库回收站位于 .trash
+        let anchor = match position(".trash") {
+            Some(index) if index >= 2 && components[index - 2].as_os_str() == "projects" => {
+                index - 2
+            }
+            Some(index) => index,
+            None => position("projects")?,
+        };
+        if anchor + 1 >= components.len() {
+            return None;
+        }
+        let relative: PathBuf = components[anchor..].iter().collect();
+        Some(join_components(&relative))
+    }
+}
+
+fn join_components(relative: &Path) -> String {
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_stored_and_resolve() {
+        let mapper = PathMapper::new(PathBuf::from("/data/desklab"));
+        assert_eq!(
+            mapper.to_stored("/data/desklab/projects/p1/notes/n1.md"),
+            "projects/p1/notes/n1.md"
+        );
+        // 数据目录之外的路径保持原样
+        assert_eq!(mapper.to_stored("/other/file.pdf"), "/other/file.pdf");
+        assert_eq!(mapper.to_stored(""), "");
+
+        assert_eq!(
+            mapper.resolve("projects/p1/notes/n1.md"),
+            Path::new("/data/desklab/projects/p1/notes/n1.md").display().to_string()
+        );
+        assert_eq!(mapper.resolve("/other/file.pdf"), "/other/file.pdf");
+        assert_eq!(mapper.resolve(""), "");
+
+        // 未设置数据目录时原样存取
+        let identity = PathMapper::default();
+        assert_eq!(identity.to_stored("/a/b.md"), "/a/b.md");
+        assert_eq!(identity.resolve("a/b.md"), "a/b.md");
+    }
+
+    #[test]
+    fn test_relativize_legacy() {
+        let mapper = PathMapper::new(PathBuf::from("/new/location"));
+        assert_eq!(
+            mapper.relativize_legacy("/new/location/projects/p1").as_deref(),
+            Some("projects/p1")
+        );
+        // 数据目录已被移动时按目录布局识别
+        assert_eq!(
+            mapper
+                .relativize_legacy("/old/projects/desklab/projects/p1/sources/a.pdf")
+                .as_deref(),
+            Some("projects/p1/sources/a.pdf")
+        );
+        assert_eq!(
+            mapper.relativize_legacy("/old/desklab/.trash/t1/files/0_p1").as_deref(),
+            Some(".trash/t1/files/0_p1")
+        );
+        assert_eq!(
+            mapper
+                .relativize_legacy("/old/desklab/projects/p1/.trash/t2/files/0_a.md")
+                .as_deref(),
+            Some("projects/p1/.trash/t2/files/0_a.md")
+        );
+        assert_eq!(mapper.relativize_legacy("projects/p1"), None);
+        assert_eq!(mapper.relativize_legacy("/somewhere/else.pdf"), None);
+    }
+}
@@ -15,7 +15,8 @@ CREATE TABLE IF NOT EXISTS projects (
     created_at TEXT NOT NULL DEFAULT (datetime('now')),
     updated_at TEXT NOT NULL DEFAULT (datetime('now')),
     sources_count INTEGER NOT NULL DEFAULT 0,
-    path TEXT NOT NULL
+    path TEXT NOT NULL,
+    archived_at TEXT
 );
 
 -- 项目名称索引
@@ -32,7 +33,8 @@ CREATE TABLE IF NOT EXISTS workspaces (
     id TEXT PRIMARY KEY,
     name TEXT NOT NULL UNIQUE,
     is_system INTEGER NOT NULL DEFAULT 0,
-    sort_order INTEGER NOT NULL DEFAULT 0
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    parent_id TEXT
 );
 
 -- 预设工作空间
@@ -54,10 +56,12 @@ CREATE TABLE IF NOT EXISTS recent_accesses (
 CREATE INDEX IF NOT EXISTS idx_recent_accessed ON recent_accesses(accessed_at DESC);
 
 -- 全文搜索虚拟表（项目名称）
+-- 全文索引均使用 trigram 分词器：中文按子串匹配，无需分词
 CREATE VIRTUAL TABLE IF NOT EXISTS projects_fts USING fts5(
     name,
     content='projects',
-    content_rowid='rowid'
+    content_rowid='rowid',
+    tokenize='trigram'
 );
 
 -- 触发器：插入项目时同步 FTS
@@ -65,14 +69,15 @@ CREATE TRIGGER IF NOT EXISTS projects_ai AFTER INSERT ON projects BEGIN
     INSERT INTO projects_fts(rowid, name) VALUES (NEW.rowid, NEW.name);
 END;
 
--- 触发器：更新项目时同步 FTS
-CREATE TRIGGER IF NOT EXISTS projects_au AFTER UPDATE ON projects BEGIN
-    UPDATE projects_fts SET name = NEW.name WHERE rowid = OLD.rowid;
+-- 触发器：更新项目时同步 FTS（外部内容表需先用旧值删除索引再插入新值）
+CREATE TRIGGER IF NOT EXISTS projects_au AFTER UPDATE OF name ON projects BEGIN
+    INSERT INTO projects_fts(projects_fts, rowid, name) VALUES ('delete', OLD.rowid, OLD.name);
+    INSERT INTO projects_fts(rowid, name) VALUES (NEW.rowid, NEW.name);
 END;
 
 -- 触发器：删除项目时同步 FTS
 CREATE TRIGGER IF NOT EXISTS projects_ad AFTER DELETE ON projects BEGIN
-    DELETE FROM projects_fts WHERE rowid = OLD.rowid;
+    INSERT INTO projects_fts(projects_fts, rowid, name) VALUES ('delete', OLD.rowid, OLD.name);
 END;
 
 -- 来源文件表
@@ -104,6 +109,9 @@ CREATE TABLE IF NOT EXISTS notes (
     title TEXT NOT NULL,
     path TEXT NOT NULL,
     output_type TEXT NOT NULL DEFAULT 'note',  -- 'note' | 'summary' | 'ppt' | 'report' | 'mindmap'
+    title_locked INTEGER NOT NULL DEFAULT 0,   -- 手动重命名后锁定标题
+    text_content TEXT,                         -- 正文（全文索引用，NULL 表示尚未从文件读取）
+    content_hash TEXT,                         -- 正文 SHA-256，内容未变时跳过索引更新
     created_at TEXT NOT NULL DEFAULT (datetime('now')),
     updated_at TEXT NOT NULL DEFAULT (datetime('now')),
     FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
@@ -120,7 +128,8 @@ CREATE VIRTUAL TABLE IF NOT EXISTS sources_fts USING fts5(
     name,
     text_content,
     content='sources',
-    content_rowid='rowid'
+    content_rowid='rowid',
+    tokenize='trigram'
 );
 
 -- 触发器：插入来源时同步 FTS
@@ -129,35 +138,43 @@ CREATE TRIGGER IF NOT EXISTS sources_ai AFTER INSERT ON sources BEGIN
 END;
 
 -- 触发器：更新来源时同步 FTS
-CREATE TRIGGER IF NOT EXISTS sources_au AFTER UPDATE ON sources BEGIN
-    UPDATE sources_fts SET name = NEW.name, text_content = NEW.text_content WHERE rowid = OLD.rowid;
+CREATE TRIGGER IF NOT EXISTS sources_au AFTER UPDATE OF name, text_content ON sources BEGIN
+    INSERT INTO sources_fts(sources_fts, rowid, name, text_content)
+        VALUES ('delete', OLD.rowid, OLD.name, OLD.text_content);
+    INSERT INTO sources_fts(rowid, name, text_content) VALUES (NEW.rowid, NEW.name, NEW.text_content);
 END;
 
 -- 触发器：删除来源时同步 FTS
 CREATE TRIGGER IF NOT EXISTS sources_ad AFTER DELETE ON sources BEGIN
-    DELETE FROM sources_fts WHERE rowid = OLD.rowid;
+    INSERT INTO sources_fts(sources_fts, rowid, name, text_content)
+        VALUES ('delete', OLD.rowid, OLD.name, OLD.text_content);
 END;
 
 -- 笔记全文搜索虚拟表
 CREATE VIRTUAL TABLE IF NOT EXISTS notes_fts USING fts5(
     title,
+    text_content,
     content='notes',
-    content_rowid='rowid'
+    content_rowid='rowid',
+    tokenize='trigram'
 );
 
 -- 触发器：插入笔记时同步 FTS
 CREATE TRIGGER IF NOT EXISTS notes_ai AFTER INSERT ON notes BEGIN
-    INSERT INTO notes_fts(rowid, title) VALUES (NEW.rowid, NEW.title);
+    INSERT INTO notes_fts(rowid, title, text_content) VALUES (NEW.rowid, NEW.title, NEW.text_content);
 END;
 
 -- 触发器：更新笔记时同步 FTS
-CREATE TRIGGER IF NOT EXISTS notes_au AFTER UPDATE ON notes BEGIN
-    UPDATE notes_fts SET title = NEW.title WHERE rowid = OLD.rowid;
+CREATE TRIGGER IF NOT EXISTS notes_au AFTER UPDATE OF title, text_content ON notes BEGIN
+    INSERT INTO notes_fts(notes_fts, rowid, title, text_content)
+        VALUES ('delete', OLD.rowid, OLD.title, OLD.text_content);
+    INSERT INTO notes_fts(rowid, title, text_content) VALUES (NEW.rowid, NEW.title, NEW.text_content);
 END;
 
 -- 触发器：删除笔记时同步 FTS
 CREATE TRIGGER IF NOT EXISTS notes_ad AFTER DELETE ON notes BEGIN
-    DELETE FROM notes_fts WHERE rowid = OLD.rowid;
+    INSERT INTO notes_fts(notes_fts, rowid, title, text_content)
+        VALUES ('delete', OLD.rowid, OLD.title, OLD.text_content);
 END;
 
 -- 来源向量索引表
@@ -184,6 +201,45 @@ CREATE TABLE IF NOT EXISTS note_embeddings (
 
 CREATE INDEX IF NOT EXISTS idx_note_embeddings_project ON note_embeddings(project_id);
 
+-- 向量版本号：来源或笔记向量增删改时递增（含级联删除），项目归档或取消归档时也递增；相关条目缓存据此失效
+CREATE TABLE IF NOT EXISTS embedding_version (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    version INTEGER NOT NULL
+);
+
+INSERT OR IGNORE INTO embedding_version (id, version) VALUES (1, 0);
+
+CREATE TRIGGER IF NOT EXISTS source_embeddings_version_ai AFTER INSERT ON source_embeddings BEGIN
+    UPDATE embedding_version SET version = version + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS source_embeddings_version_au AFTER UPDATE ON source_embeddings BEGIN
+    UPDATE embedding_version SET version = version + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS source_embeddings_version_ad AFTER DELETE ON source_embeddings BEGIN
+    UPDATE embedding_version SET version = version + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS note_embeddings_version_ai AFTER INSERT ON note_embeddings BEGIN
+    UPDATE embedding_version SET version = version + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS note_embeddings_version_au AFTER UPDATE ON note_embeddings BEGIN
+    UPDATE embedding_version SET version = version + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS note_embeddings_version_ad AFTER DELETE ON note_embeddings BEGIN
+    UPDATE embedding_version SET version = version + 1;
+END;
+
+-- 资料库范围的相关条目不含归档项目
+CREATE TRIGGER IF NOT EXISTS projects_archived_version_au AFTER UPDATE OF archived_at ON projects
+WHEN NEW.archived_at IS NOT OLD.archived_at
+BEGIN
+    UPDATE embedding_version SET version = version + 1;
+END;
+
 -- 对话会话表
 CREATE TABLE IF NOT EXISTS chat_sessions (
     id TEXT PRIMARY KEY,
@@ -259,12 +315,346 @@ CREATE INDEX IF NOT EXISTS idx_mindmaps_updated ON mindmaps(updated_at DESC);
 
 -- 画布全文搜索虚拟表（独立表，不使用 content= 因为文本内容是运行时提取的）
 CREATE VIRTUAL TABLE IF NOT EXISTS canvases_fts USING fts5(
-    canvas_id,
+    canvas_id UNINDEXED,
     title,
-    text_content
+    text_content,
+    tokenize='trigram'
 );
 
+-- 触发器：创建画布时写入 FTS 记录（文本内容在保存画布时更新）
+CREATE TRIGGER IF NOT EXISTS canvases_ai AFTER INSERT ON canvases BEGIN
+    INSERT INTO canvases_fts(canvas_id, title, text_content) VALUES (NEW.id, NEW.title, '');
+END;
+
+-- 触发器：重命名画布时同步 FTS 标题
+CREATE TRIGGER IF NOT EXISTS canvases_au AFTER UPDATE OF title ON canvases BEGIN
+    UPDATE canvases_fts SET title = NEW.title WHERE canvas_id = NEW.id;
+END;
+
 -- 触发器：删除画布时同步删除 FTS 记录
 CREATE TRIGGER IF NOT EXISTS canvases_ad AFTER DELETE ON canvases BEGIN
     DELETE FROM canvases_fts WHERE canvas_id = OLD.id;
 END;
+
+-- 思维导图全文搜索虚拟表（节点文本在写入导图数据时提取）
+CREATE VIRTUAL TABLE IF NOT EXISTS mindmaps_fts USING fts5(
+    mindmap_id UNINDEXED,
+    title,
+    text_content,
+    tokenize='trigram'
+);
+
+CREATE TRIGGER IF NOT EXISTS mindmaps_ai AFTER INSERT ON mindmaps BEGIN
+    INSERT INTO mindmaps_fts(mindmap_id, title, text_content) VALUES (NEW.id, NEW.title, '');
+END;
+
+CREATE TRIGGER IF NOT EXISTS mindmaps_au AFTER UPDATE OF title ON mindmaps BEGIN
+    UPDATE mindmaps_fts SET title = NEW.title WHERE mindmap_id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS mindmaps_ad AFTER DELETE ON mindmaps BEGIN
+    DELETE FROM mindmaps_fts WHERE mindmap_id = OLD.id;
+END;
+
+-- PPT 全文搜索虚拟表（幻灯片文本在保存 PPT 时从数据文件提取，尚未提取时为 NULL）
+CREATE VIRTUAL TABLE IF NOT EXISTS presentations_fts USING fts5(
+    presentation_id UNINDEXED,
+    title,
+    text_content,
+    tokenize='trigram'
+);
+
+CREATE TRIGGER IF NOT EXISTS presentations_ai AFTER INSERT ON presentations BEGIN
+    INSERT INTO presentations_fts(presentation_id, title, text_content) VALUES (NEW.id, NEW.title, NULL);
+END;
+
+CREATE TRIGGER IF NOT EXISTS presentations_au AFTER UPDATE OF title ON presentations BEGIN
+    UPDATE presentations_fts SET title = NEW.title WHERE presentation_id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS presentations_ad AFTER DELETE ON presentations BEGIN
+    DELETE FROM presentations_fts WHERE presentation_id = OLD.id;
+END;
+
+-- 对话全文搜索虚拟表（每个会话一条记录，正文为全部消息按时间拼接）
+CREATE VIRTUAL TABLE IF NOT EXISTS chat_fts USING fts5(
+    session_id UNINDEXED,
+    title,
+    text_content,
+    tokenize='trigram'
+);
+
+CREATE TRIGGER IF NOT EXISTS chat_sessions_ai AFTER INSERT ON chat_sessions BEGIN
+    INSERT INTO chat_fts(session_id, title, text_content) VALUES (NEW.id, NEW.title, '');
+END;
+
+CREATE TRIGGER IF NOT EXISTS chat_sessions_au AFTER UPDATE OF title ON chat_sessions BEGIN
+    UPDATE chat_fts SET title = NEW.title WHERE session_id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS chat_sessions_ad AFTER DELETE ON chat_sessions BEGIN
+    DELETE FROM chat_fts WHERE session_id = OLD.id;
+END;
+
+-- 触发器：消息增删改时重新拼接所属会话的正文
+CREATE TRIGGER IF NOT EXISTS chat_messages_ai AFTER INSERT ON chat_messages BEGIN
+    UPDATE chat_fts SET text_content = (
+        SELECT IFNULL(group_concat(content, char(10)), '') FROM (
+            SELECT content FROM chat_messages
+            WHERE session_id = NEW.session_id ORDER BY created_at, rowid
+        )
+    ) WHERE session_id = NEW.session_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS chat_messages_au AFTER UPDATE OF content ON chat_messages BEGIN
+    UPDATE chat_fts SET text_content = (
+        SELECT IFNULL(group_concat(content, char(10)), '') FROM (
+            SELECT content FROM chat_messages
+            WHERE session_id = NEW.session_id ORDER BY created_at, rowid
+        )
+    ) WHERE session_id = NEW.session_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS chat_messages_ad AFTER DELETE ON chat_messages BEGIN
+    UPDATE chat_fts SET text_content = (
+        SELECT IFNULL(group_concat(content, char(10)), '') FROM (
+            SELECT content FROM chat_messages
+            WHERE session_id = OLD.session_id ORDER BY created_at, rowid
+        )
+    ) WHERE session_id = OLD.session_id;
+END;
+
+-- 回收站表（软删除条目索引，文件移至 .trash 目录）
+CREATE TABLE IF NOT EXISTS trash_items (
+    id TEXT PRIMARY KEY,
+    project_id TEXT NOT NULL,
+    kind TEXT NOT NULL,            -- 'project' | 'source' | 'note' | 'canvas' | 'mindmap' | 'presentation' | 'chat'
+    item_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    trash_path TEXT NOT NULL,      -- 回收站目录
+    snapshot TEXT NOT NULL,        -- 被删除记录的 JSON 快照
+    files TEXT NOT NULL DEFAULT '[]',  -- 原路径与回收站路径映射（JSON）
+    deleted_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_trash_items_project ON trash_items(project_id);
+CREATE INDEX IF NOT EXISTS idx_trash_items_deleted ON trash_items(deleted_at DESC);
+
+-- 应用设置表（键值对）
+CREATE TABLE IF NOT EXISTS settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL,
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- 条目标签表（来源、笔记）
+CREATE TABLE IF NOT EXISTS item_tags (
+    kind TEXT NOT NULL,            -- 'source' | 'note'
+    item_id TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (kind, item_id, tag)
+);
+
+CREATE INDEX IF NOT EXISTS idx_item_tags_tag ON item_tags(tag);
+
+-- 保存的搜索（智能集合）：查询语法与过滤条件，运行时按当前数据求值
+CREATE TABLE IF NOT EXISTS saved_searches (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    query TEXT NOT NULL,           -- 查询语法（字段过滤写在查询中，如 type:source tag:合同）
+    project_id TEXT,               -- 搜索范围，为空时搜索全部项目
+    include_archived INTEGER NOT NULL DEFAULT 0,
+    sort TEXT NOT NULL DEFAULT 'relevance',  -- 'relevance' | 'date'
+    track_new INTEGER NOT NULL DEFAULT 0,    -- 是否报告上次运行以来的新命中
+    last_run_at TEXT,
+    last_matches TEXT,             -- 上次运行命中的条目（JSON 数组，元素为 "类型:ID"），查询条件修改后清空
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- 同步记录：每条记录上次同步时的版本（对应共享文件夹变更日志中的一条变更）
+CREATE TABLE IF NOT EXISTS sync_records (
+    kind TEXT NOT NULL,
+    item_id TEXT NOT NULL,
+    hash TEXT NOT NULL,            -- 上次同步时的内容哈希，已删除时为空
+    change TEXT NOT NULL,          -- 上次同步时的变更记录（JSON，用于笔记三方合并）
+    synced_at TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (kind, item_id)
+);
+
+-- 同步游标：已应用的其他设备变更日志序号
+CREATE TABLE IF NOT EXISTS sync_cursors (
+    device_id TEXT PRIMARY KEY,
+    seq INTEGER NOT NULL
+);
+
+-- 活动日志：项目及其内容的创建、修改、删除事件（由下方触发器记录）
+CREATE TABLE IF NOT EXISTS activity_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id TEXT NOT NULL,
+    kind TEXT NOT NULL,            -- 'project' | 'source' | 'note' | 'canvas' | 'mindmap' | 'presentation' | 'chat'
+    item_id TEXT NOT NULL,
+    action TEXT NOT NULL,          -- 'create' | 'update' | 'delete'
+    title TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_activity_log_project ON activity_log(project_id, id DESC);
+CREATE INDEX IF NOT EXISTS idx_activity_log_item ON activity_log(item_id, id DESC);
+
+-- 修改事件按条目合并：5 分钟内已记录过创建或修改时不再重复记录（避免自动保存刷屏）
+
+CREATE TRIGGER IF NOT EXISTS projects_activity_ai AFTER INSERT ON projects BEGIN
+    INSERT INTO activity_log (project_id, kind, item_id, action, title)
+    VALUES (NEW.id, 'project', NEW.id, 'create', NEW.name);
+END;
+
+CREATE TRIGGER IF NOT EXISTS projects_activity_au AFTER UPDATE ON projects
+WHEN (NEW.name IS NOT OLD.name OR NEW.icon_id IS NOT OLD.icon_id OR NEW.icon_emoji IS NOT OLD.icon_emoji OR NEW.icon_color IS NOT OLD.icon_color OR NEW.workspace IS NOT OLD.workspace)
+    AND NOT EXISTS (
+        SELECT 1 FROM activity_log
+        WHERE item_id = NEW.id AND action IN ('create', 'update')
+          AND created_at > datetime('now', '-5 minutes')
+    )
+BEGIN
+    INSERT INTO activity_log (project_id, kind, item_id, action, title)
+    VALUES (NEW.id, 'project', NEW.id, 'update', NEW.name);
+END;
+
+CREATE TRIGGER IF NOT EXISTS projects_activity_ad AFTER DELETE ON projects BEGIN
+    INSERT INTO activity_log (project_id, kind, item_id, action, title)
+    VALUES (OLD.id, 'project', OLD.id, 'delete', OLD.name);
+END;
+
+CREATE TRIGGER IF NOT EXISTS sources_activity_ai AFTER INSERT ON sources BEGIN
+    INSERT INTO activity_log (project_id, kind, item_id, action, title)
+    VALUES (NEW.project_id, 'source', NEW.id, 'create', NEW.name);
+END;
+
+CREATE TRIGGER IF NOT EXISTS sources_activity_au AFTER UPDATE ON sources
+WHEN (NEW.name IS NOT OLD.name OR NEW.updated_at IS NOT OLD.updated_at)
+    AND NOT EXISTS (
+        SELECT 1 FROM activity_log
+        WHERE item_id = NEW.id AND action IN ('create', 'update')
+          AND created_at > datetime('now', '-5 minutes')
+    )
+BEGIN
+    INSERT INTO activity_log (project_id, kind, item_id, action, title)
+    VALUES (NEW.project_id, 'source', NEW.id, 'update', NEW.name);
+END;
+
+CREATE TRIGGER IF NOT EXISTS sources_activity_ad AFTER DELETE ON sources BEGIN
+    INSERT INTO activity_log (project_id, kind, item_id, action, title)
+    VALUES (OLD.project_id, 'source', OLD.id, 'delete', OLD.name);
+END;
+
+CREATE TRIGGER IF NOT EXISTS notes_activity_ai AFTER INSERT ON notes BEGIN
+    INSERT INTO activity_log (project_id, kind, item_id, action, title)
+    VALUES (NEW.project_id, 'note', NEW.id, 'create', NEW.title);
+END;
+
+CREATE TRIGGER IF NOT EXISTS notes_activity_au AFTER UPDATE ON notes
+WHEN (NEW.title IS NOT OLD.title OR NEW.updated_at IS NOT OLD.updated_at)
+    AND NOT EXISTS (
+        SELECT 1 FROM activity_log
+        WHERE item_id = NEW.id AND action IN ('create', 'update')
+          AND created_at > datetime('now', '-5 minutes')
+    )
+BEGIN
+    INSERT INTO activity_log (project_id, kind, item_id, action, title)
+    VALUES (NEW.project_id, 'note', NEW.id, 'update', NEW.title);
+END;
+
+CREATE TRIGGER IF NOT EXISTS notes_activity_ad AFTER DELETE ON notes BEGIN
+    INSERT INTO activity_log (project_id, kind, item_id, action, title)
+    VALUES (OLD.project_id, 'note', OLD.id, 'delete', OLD.title);
+END;
+
+CREATE TRIGGER IF NOT EXISTS canvases_activity_ai AFTER INSERT ON canvases BEGIN
+    INSERT INTO activity_log (project_id, kind, item_id, action, title)
+    VALUES (NEW.project_id, 'canvas', NEW.id, 'create', NEW.title);
+END;
+
+CREATE TRIGGER IF NOT EXISTS canvases_activity_au AFTER UPDATE ON canvases
+WHEN (NEW.title IS NOT OLD.title OR NEW.updated_at IS NOT OLD.updated_at)
+    AND NOT EXISTS (
+        SELECT 1 FROM activity_log
+        WHERE item_id = NEW.id AND action IN ('create', 'update')
+          AND created_at > datetime('now', '-5 minutes')
+    )
+BEGIN
+    INSERT INTO activity_log (project_id, kind, item_id, action, title)
+    VALUES (NEW.project_id, 'canvas', NEW.id, 'update', NEW.title);
+END;
+
+CREATE TRIGGER IF NOT EXISTS canvases_activity_ad AFTER DELETE ON canvases BEGIN
+    INSERT INTO activity_log (project_id, kind, item_id, action, title)
+    VALUES (OLD.project_id, 'canvas', OLD.id, 'delete', OLD.title);
+END;
+
+CREATE TRIGGER IF NOT EXISTS mindmaps_activity_ai AFTER INSERT ON mindmaps BEGIN
+    INSERT INTO activity_log (project_id, kind, item_id, action, title)
+    VALUES (NEW.project_id, 'mindmap', NEW.id, 'create', NEW.title);
+END;
+
+CREATE TRIGGER IF NOT EXISTS mindmaps_activity_au AFTER UPDATE ON mindmaps
+WHEN (NEW.title IS NOT OLD.title OR NEW.updated_at IS NOT OLD.updated_at)
+    AND NOT EXISTS (
+        SELECT 1 FROM activity_log
+        WHERE item_id = NEW.id AND action IN ('create', 'update')
+          AND created_at > datetime('now', '-5 minutes')
+    )
+BEGIN
+    INSERT INTO activity_log (project_id, kind, item_id, action, title)
+    VALUES (NEW.project_id, 'mindmap', NEW.id, 'update', NEW.title);
+END;
+
+CREATE TRIGGER IF NOT EXISTS mindmaps_activity_ad AFTER DELETE ON mindmaps BEGIN
+    INSERT INTO activity_log (project_id, kind, item_id, action, title)
+    VALUES (OLD.project_id, 'mindmap', OLD.id, 'delete', OLD.title);
+END;
+
+CREATE TRIGGER IF NOT EXISTS presentations_activity_ai AFTER INSERT ON presentations BEGIN
+    INSERT INTO activity_log (project_id, kind, item_id, action, title)
+    VALUES (NEW.project_id, 'presentation', NEW.id, 'create', NEW.title);
+END;
+
+CREATE TRIGGER IF NOT EXISTS presentations_activity_au AFTER UPDATE ON presentations
+WHEN (NEW.title IS NOT OLD.title OR NEW.updated_at IS NOT OLD.updated_at)
+    AND NOT EXISTS (
+        SELECT 1 FROM activity_log
+        WHERE item_id = NEW.id AND action IN ('create', 'update')
+          AND created_at > datetime('now', '-5 minutes')
+    )
+BEGIN
+    INSERT INTO activity_log (project_id, kind, item_id, action, title)
+    VALUES (NEW.project_id, 'presentation', NEW.id, 'update', NEW.title);
+END;
+
+CREATE TRIGGER IF NOT EXISTS presentations_activity_ad AFTER DELETE ON presentations BEGIN
+    INSERT INTO activity_log (project_id, kind, item_id, action, title)
+    VALUES (OLD.project_id, 'presentation', OLD.id, 'delete', OLD.title);
+END;
+
+CREATE TRIGGER IF NOT EXISTS chat_sessions_activity_ai AFTER INSERT ON chat_sessions BEGIN
+    INSERT INTO activity_log (project_id, kind, item_id, action, title)
+    VALUES (NEW.project_id, 'chat', NEW.id, 'create', NEW.title);
+END;
+
+CREATE TRIGGER IF NOT EXISTS chat_sessions_activity_au AFTER UPDATE ON chat_sessions
+WHEN (NEW.title IS NOT OLD.title OR NEW.updated_at IS NOT OLD.updated_at)
+    AND NOT EXISTS (
+        SELECT 1 FROM activity_log
+        WHERE item_id = NEW.id AND action IN ('create', 'update')
+          AND created_at > datetime('now', '-5 minutes')
+    )
+BEGIN
+    INSERT INTO activity_log (project_id, kind, item_id, action, title)
+    VALUES (NEW.project_id, 'chat', NEW.id, 'update', NEW.title);
+END;
+
+CREATE TRIGGER IF NOT EXISTS chat_sessions_activity_ad AFTER DELETE ON chat_sessions BEGIN
+    INSERT INTO activity_log (project_id, kind, item_id, action, title)
+    VALUES (OLD.project_id, 'chat', OLD.id, 'delete', OLD.title);
+END;
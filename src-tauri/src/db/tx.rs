@@ -0,0 +1,1406 @@
+//! 数据库事务句柄
+//!
+//! 提供可在单个事务内组合执行的数据库操作，`Database` 上的同名方法均委托到这里
+
+use super::{
+    deserialize_embedding, parse_datetime, row_to_workspace, serialize_embedding, DbError,
+    PathMapper,
+};
+use crate::models::{
+    Canvas, CanvasRecord, ChatMessage, ChatSession, ChatSessionRecord, Citation, ContentKind,
+    ContentSnapshot, ItemTag, MessageRole, MindMap, MindMapRecord, Note, NoteRecord, OutputType,
+    Presentation, Project, ProjectIcon, Source, SourceRecord, SourceType, SyncChange, TrashItem,
+    TrashedFile, Workspace,
+};
+use crate::services::{extract_ppt_file_text, hash_bytes};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+
+/// 绑定到一个连接（或事务）上的操作集合
+pub struct DbTx<'a> {
+    conn: &'a Connection,
+    paths: PathMapper,
+}
+
+impl<'a> DbTx<'a> {
+    pub(crate) fn new(conn: &'a Connection, paths: PathMapper) -> Self {
+        Self { conn, paths }
+    }
+
+    /// 在保存点中执行操作，失败时只回滚本次操作（批量操作中逐个条目使用）
+    pub fn savepoint<T, E, F>(&self, f: F) -> Result<T, E>
+    where
+        F: FnOnce(&DbTx) -> Result<T, E>,
+        E: From<DbError>,
+    {
+        self.conn
+            .execute_batch("SAVEPOINT batch_item")
+            .map_err(DbError::from)?;
+        match f(self) {
+            Ok(value) => {
+                self.conn
+                    .execute_batch("RELEASE batch_item")
+                    .map_err(DbError::from)?;
+                Ok(value)
+            }
+            Err(e) => {
+                self.conn
+                    .execute_batch("ROLLBACK TO batch_item; RELEASE batch_item")
+                    .map_err(DbError::from)?;
+                Err(e)
+            }
+        }
+    }
+
+    /// 插入来源（带文本内容）
+    pub fn insert_source_with_content(&self, source: &Source, text_content: Option<&str>) -> Result<(), DbError> {
+        self.conn.execute(
+            "INSERT INTO sources (id, project_id, name, type, path, size, mime_type,
+                                  thumbnail_path, text_content, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                source.id,
+                source.project_id,
+                source.name,
+                source.source_type.as_str(),
+                self.paths.to_stored(&source.path),
+                source.size,
+                source.mime_type,
+                self.paths.to_stored_opt(source.thumbnail_path.as_deref()),
+                text_content,
+                source.created_at.to_rfc3339(),
+                source.updated_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 更新项目来源数量
+    pub fn update_project_sources_count(&self, project_id: &str, delta: i32) -> Result<(), DbError> {
+        self.conn.execute(
+            "UPDATE projects SET sources_count = MAX(0, sources_count + ?1), updated_at = datetime('now') WHERE id = ?2",
+            params![delta, project_id],
+        )?;
+        Ok(())
+    }
+
+    /// 更新来源向量
+    pub fn upsert_source_embedding(
+        &self,
+        source_id: &str,
+        project_id: &str,
+        embedding: &[f32],
+    ) -> Result<(), DbError> {
+        let blob = serialize_embedding(embedding);
+        self.conn.execute(
+            "INSERT INTO source_embeddings (source_id, project_id, embedding, updated_at)
+             VALUES (?1, ?2, ?3, datetime('now'))
+             ON CONFLICT(source_id) DO UPDATE SET
+               embedding = excluded.embedding,
+               project_id = excluded.project_id,
+               updated_at = excluded.updated_at",
+            params![source_id, project_id, blob],
+        )?;
+        Ok(())
+    }
+
+    /// 插入笔记
+    pub fn insert_note(&self, note: &Note) -> Result<(), DbError> {
+        self.conn.execute(
+            "INSERT INTO notes (id, project_id, title, path, output_type, title_locked, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                note.id,
+                note.project_id,
+                note.title,
+                self.paths.to_stored(&note.path),
+                note.output_type.as_str(),
+                note.title_locked as i32,
+                note.created_at.to_rfc3339(),
+                note.updated_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 更新笔记标题
+    pub fn update_note_title(&self, id: &str, title: &str) -> Result<(), DbError> {
+        self.conn.execute(
+            "UPDATE notes SET title = ?1, updated_at = datetime('now') WHERE id = ?2",
+            params![title, id],
+        )?;
+        Ok(())
+    }
+
+    /// 更新笔记正文（用于全文搜索），内容变化时同时更新修改时间，未变化时不重写索引
+    pub fn update_note_content(&self, id: &str, content: &str) -> Result<(), DbError> {
+        self.write_note_content(id, content, true)
+    }
+
+    /// 写入笔记正文索引，不更新修改时间（用于回填、恢复与重建索引）
+    pub fn index_note_content(&self, id: &str, content: &str) -> Result<(), DbError> {
+        self.write_note_content(id, content, false)
+    }
+
+    fn write_note_content(&self, id: &str, content: &str, touch: bool) -> Result<(), DbError> {
+        let hash = hash_bytes(content.as_bytes());
+        self.conn.execute(
+            "UPDATE notes SET text_content = ?1, content_hash = ?2,
+               updated_at = CASE WHEN ?4 THEN datetime('now') ELSE updated_at END
+             WHERE id = ?3 AND (content_hash IS NULL OR content_hash != ?2)",
+            params![content, hash, id, touch],
+        )?;
+        Ok(())
+    }
+
+    /// 删除笔记
+    pub fn delete_note(&self, id: &str) -> Result<String, DbError> {
+        // 获取 project_id
+        let project_id: String = self
+            .conn
+            .query_row(
+                "SELECT project_id FROM notes WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => {
+                    DbError::NotFound(format!("笔记 {} 不存在", id))
+                }
+                _ => DbError::Sqlite(e),
+            })?;
+
+        self.conn.execute("DELETE FROM notes WHERE id = ?1", params![id])?;
+        self.delete_tags(ContentKind::Note, id)?;
+        Ok(project_id)
+    }
+
+    /// 将来源移动到另一个项目（同时更新文件路径与向量索引的项目归属）
+    pub fn move_source(
+        &self,
+        id: &str,
+        project_id: &str,
+        path: &str,
+        thumbnail_path: Option<&str>,
+    ) -> Result<(), DbError> {
+        let affected = self.conn.execute(
+            "UPDATE sources SET project_id = ?1, path = ?2, thumbnail_path = ?3, updated_at = datetime('now')
+             WHERE id = ?4",
+            params![
+                project_id,
+                self.paths.to_stored(path),
+                self.paths.to_stored_opt(thumbnail_path),
+                id
+            ],
+        )?;
+        if affected == 0 {
+            return Err(DbError::NotFound(format!("来源 {} 不存在", id)));
+        }
+        self.conn.execute(
+            "UPDATE source_embeddings SET project_id = ?1 WHERE source_id = ?2",
+            params![project_id, id],
+        )?;
+        Ok(())
+    }
+
+    /// 将笔记移动到另一个项目（同时更新文件路径与向量索引的项目归属）
+    pub fn move_note(&self, id: &str, project_id: &str, path: &str) -> Result<(), DbError> {
+        let affected = self.conn.execute(
+            "UPDATE notes SET project_id = ?1, path = ?2, updated_at = datetime('now') WHERE id = ?3",
+            params![project_id, self.paths.to_stored(path), id],
+        )?;
+        if affected == 0 {
+            return Err(DbError::NotFound(format!("笔记 {} 不存在", id)));
+        }
+        self.conn.execute(
+            "UPDATE note_embeddings SET project_id = ?1 WHERE note_id = ?2",
+            params![project_id, id],
+        )?;
+        Ok(())
+    }
+
+    /// 更新笔记向量
+    pub fn upsert_note_embedding(
+        &self,
+        note_id: &str,
+        project_id: &str,
+        embedding: &[f32],
+    ) -> Result<(), DbError> {
+        let blob = serialize_embedding(embedding);
+        self.conn.execute(
+            "INSERT INTO note_embeddings (note_id, project_id, embedding, updated_at)
+             VALUES (?1, ?2, ?3, datetime('now'))
+             ON CONFLICT(note_id) DO UPDATE SET
+               embedding = excluded.embedding,
+               project_id = excluded.project_id,
+               updated_at = excluded.updated_at",
+            params![note_id, project_id, blob],
+        )?;
+        Ok(())
+    }
+
+    /// 删除笔记向量
+    pub fn delete_note_embedding(&self, note_id: &str) -> Result<(), DbError> {
+        self.conn.execute(
+            "DELETE FROM note_embeddings WHERE note_id = ?1",
+            params![note_id],
+        )?;
+        Ok(())
+    }
+
+    /// 插入 PPT
+    pub fn insert_presentation(&self, presentation: &Presentation) -> Result<(), DbError> {
+        self.conn.execute(
+            "INSERT INTO presentations (id, project_id, title, data_path, thumbnail_path, slide_count, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                presentation.id,
+                presentation.project_id,
+                presentation.title,
+                self.paths.to_stored(&presentation.data_path),
+                self.paths.to_stored_opt(presentation.thumbnail_path.as_deref()),
+                presentation.slide_count,
+                presentation.created_at.to_rfc3339(),
+                presentation.updated_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 更新 PPT 幻灯片数量
+    pub fn update_presentation_slide_count(&self, id: &str, slide_count: i32) -> Result<(), DbError> {
+        let affected = self.conn.execute(
+            "UPDATE presentations SET slide_count = ?1, updated_at = datetime('now') WHERE id = ?2",
+            params![slide_count, id],
+        )?;
+        if affected == 0 {
+            return Err(DbError::NotFound(format!("PPT {} 不存在", id)));
+        }
+        Ok(())
+    }
+
+    /// 更新 PPT 文本内容（用于全文搜索）
+    pub fn update_presentation_text_content(&self, id: &str, text_content: &str) -> Result<(), DbError> {
+        let affected = self.conn.execute(
+            "UPDATE presentations_fts SET text_content = ?1 WHERE presentation_id = ?2",
+            params![text_content, id],
+        )?;
+        if affected == 0 {
+            return Err(DbError::NotFound(format!("PPT {} 不存在", id)));
+        }
+        Ok(())
+    }
+
+    /// 从数据文件提取 PPT 的搜索文本写入全文索引（数据文件不存在或无法解析时跳过）
+    pub fn index_presentation_file(&self, presentation: &Presentation) -> Result<(), DbError> {
+        match extract_ppt_file_text(Path::new(&presentation.data_path)) {
+            Ok(text) => self.update_presentation_text_content(&presentation.id, &text),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// 插入画布
+    pub fn insert_canvas(&self, canvas: &Canvas) -> Result<(), DbError> {
+        self.conn.execute(
+            "INSERT INTO canvases (id, project_id, title, path, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                canvas.id,
+                canvas.project_id,
+                canvas.title,
+                self.paths.to_stored(&canvas.path),
+                canvas.created_at.to_rfc3339(),
+                canvas.updated_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 更新画布 updated_at 时间
+    pub fn touch_canvas(&self, id: &str) -> Result<(), DbError> {
+        let affected = self.conn.execute(
+            "UPDATE canvases SET updated_at = datetime('now') WHERE id = ?1",
+            params![id],
+        )?;
+        if affected == 0 {
+            return Err(DbError::NotFound(format!("画布 {} 不存在", id)));
+        }
+        Ok(())
+    }
+
+    /// 更新画布文本内容（用于全文搜索）
+    pub fn update_canvas_text_content(&self, id: &str, text_content: &str) -> Result<(), DbError> {
+        // 获取画布标题用于更新 FTS
+        let title: String = self
+            .conn
+            .query_row(
+                "SELECT title FROM canvases WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => {
+                    DbError::NotFound(format!("画布 {} 不存在", id))
+                }
+                _ => DbError::Sqlite(e),
+            })?;
+
+        // 更新 FTS 表 - 先删除旧记录，再插入新记录
+        let _ = self.conn.execute(
+            "DELETE FROM canvases_fts WHERE canvas_id = ?1",
+            params![id],
+        );
+        let _ = self.conn.execute(
+            "INSERT INTO canvases_fts(canvas_id, title, text_content) VALUES (?1, ?2, ?3)",
+            params![id, title, text_content],
+        );
+
+        Ok(())
+    }
+
+    /// 插入思维导图
+    pub fn insert_mindmap(&self, mindmap: &MindMap, data: &str) -> Result<(), DbError> {
+        self.conn.execute(
+            "INSERT INTO mindmaps (id, project_id, title, theme, layout, data, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                mindmap.id,
+                mindmap.project_id,
+                mindmap.title,
+                mindmap.theme,
+                mindmap.layout,
+                data,
+                mindmap.created_at.to_rfc3339(),
+                mindmap.updated_at.to_rfc3339(),
+            ],
+        )?;
+        self.update_mindmap_text_content(&mindmap.id, data)
+    }
+
+    /// 更新思维导图数据
+    pub fn update_mindmap_data(&self, id: &str, data: &str) -> Result<(), DbError> {
+        let affected = self.conn.execute(
+            "UPDATE mindmaps SET data = ?1, updated_at = datetime('now') WHERE id = ?2",
+            params![data, id],
+        )?;
+        if affected == 0 {
+            return Err(DbError::NotFound(format!("思维导图 {} 不存在", id)));
+        }
+        self.update_mindmap_text_content(id, data)
+    }
+
+    /// 从导图数据中提取文本，更新全文索引
+    fn update_mindmap_text_content(&self, id: &str, data: &str) -> Result<(), DbError> {
+        self.conn.execute(
+            "UPDATE mindmaps_fts SET text_content = ?1 WHERE mindmap_id = ?2",
+            params![super::mindmap_text(data), id],
+        )?;
+        Ok(())
+    }
+
+    /// 更新思维导图元数据（标题、主题、布局）
+    pub fn update_mindmap_meta(&self, id: &str, title: Option<&str>, theme: Option<&str>, layout: Option<&str>) -> Result<(), DbError> {
+        // 根据参数组合构建更新语句
+        let affected = match (title, theme, layout) {
+            (Some(t), Some(th), Some(l)) => {
+                self.conn.execute(
+                    "UPDATE mindmaps SET title = ?1, theme = ?2, layout = ?3, updated_at = datetime('now') WHERE id = ?4",
+                    params![t, th, l, id],
+                )?
+            }
+            (Some(t), Some(th), None) => {
+                self.conn.execute(
+                    "UPDATE mindmaps SET title = ?1, theme = ?2, updated_at = datetime('now') WHERE id = ?3",
+                    params![t, th, id],
+                )?
+            }
+            (Some(t), None, Some(l)) => {
+                self.conn.execute(
+                    "UPDATE mindmaps SET title = ?1, layout = ?2, updated_at = datetime('now') WHERE id = ?3",
+                    params![t, l, id],
+                )?
+            }
+            (None, Some(th), Some(l)) => {
+                self.conn.execute(
+                    "UPDATE mindmaps SET theme = ?1, layout = ?2, updated_at = datetime('now') WHERE id = ?3",
+                    params![th, l, id],
+                )?
+            }
+            (Some(t), None, None) => {
+                self.conn.execute(
+                    "UPDATE mindmaps SET title = ?1, updated_at = datetime('now') WHERE id = ?2",
+                    params![t, id],
+                )?
+            }
+            (None, Some(th), None) => {
+                self.conn.execute(
+                    "UPDATE mindmaps SET theme = ?1, updated_at = datetime('now') WHERE id = ?2",
+                    params![th, id],
+                )?
+            }
+            (None, None, Some(l)) => {
+                self.conn.execute(
+                    "UPDATE mindmaps SET layout = ?1, updated_at = datetime('now') WHERE id = ?2",
+                    params![l, id],
+                )?
+            }
+            (None, None, None) => {
+                self.conn.execute(
+                    "UPDATE mindmaps SET updated_at = datetime('now') WHERE id = ?1",
+                    params![id],
+                )?
+            }
+        };
+
+        if affected == 0 {
+            return Err(DbError::NotFound(format!("思维导图 {} 不存在", id)));
+        }
+        Ok(())
+    }
+
+    /// 获取单个工作空间
+    pub fn get_workspace(&self, id: &str) -> Result<Workspace, DbError> {
+        self.conn
+            .query_row(
+                "SELECT id, name, is_system, sort_order, parent_id FROM workspaces WHERE id = ?1",
+                params![id],
+                row_to_workspace,
+            )
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => {
+                    DbError::NotFound(format!("分类 {} 不存在", id))
+                }
+                _ => DbError::Sqlite(e),
+            })
+    }
+
+    /// 统计工作空间下的项目数量
+    pub fn count_projects_in_workspace(&self, workspace_id: &str) -> Result<u32, DbError> {
+        let count: u32 = self.conn.query_row(
+            "SELECT COUNT(*) FROM projects WHERE workspace = ?1",
+            params![workspace_id],
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
+    /// 删除工作空间，并把其中的项目移到 `reassign_to`
+    ///
+    /// 子分类挂到被删除工作空间的上级下。不检查系统分类，由调用方校验。
+    pub fn delete_workspace_reassigning(
+        &self,
+        id: &str,
+        reassign_to: Option<&str>,
+    ) -> Result<(), DbError> {
+        let workspace = self.get_workspace(id)?;
+        if let Some(target) = reassign_to {
+            self.conn.execute(
+                "UPDATE projects SET workspace = ?1, updated_at = datetime('now') WHERE workspace = ?2",
+                params![target, id],
+            )?;
+        }
+        self.conn.execute(
+            "UPDATE workspaces SET parent_id = ?1 WHERE parent_id = ?2",
+            params![workspace.parent_id, id],
+        )?;
+        self.conn
+            .execute("DELETE FROM workspaces WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// 插入项目
+    pub fn insert_project(&self, project: &Project) -> Result<(), DbError> {
+        self.conn.execute(
+            "INSERT INTO projects (id, name, icon_id, icon_name, icon_emoji, icon_color,
+                                   workspace, is_starred, created_at, updated_at, sources_count, path,
+                                   archived_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                project.id,
+                project.name,
+                project.icon.id,
+                project.icon.name,
+                project.icon.emoji,
+                project.icon.color,
+                project.workspace,
+                project.is_starred as i32,
+                project.created_at.to_rfc3339(),
+                project.updated_at.to_rfc3339(),
+                project.sources_count,
+                self.paths.to_stored(&project.path),
+                project.archived_at.map(|at| at.to_rfc3339()),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 更新项目的全部字段（不含来源数量，来源数量由来源增删维护）
+    pub fn update_project(&self, project: &Project) -> Result<(), DbError> {
+        let updated = self.conn.execute(
+            "UPDATE projects SET name = ?2, icon_id = ?3, icon_name = ?4, icon_emoji = ?5,
+                    icon_color = ?6, workspace = ?7, is_starred = ?8, created_at = ?9,
+                    updated_at = ?10, path = ?11, archived_at = ?12
+             WHERE id = ?1",
+            params![
+                project.id,
+                project.name,
+                project.icon.id,
+                project.icon.name,
+                project.icon.emoji,
+                project.icon.color,
+                project.workspace,
+                project.is_starred as i32,
+                project.created_at.to_rfc3339(),
+                project.updated_at.to_rfc3339(),
+                self.paths.to_stored(&project.path),
+                project.archived_at.map(|at| at.to_rfc3339()),
+            ],
+        )?;
+        if updated == 0 {
+            return Err(DbError::NotFound(format!("项目 {} 不存在", project.id)));
+        }
+        Ok(())
+    }
+
+    /// 按实际来源数重新计算项目的来源数量
+    pub fn recount_project_sources(&self, project_id: &str) -> Result<(), DbError> {
+        self.conn.execute(
+            "UPDATE projects SET sources_count = (SELECT COUNT(*) FROM sources WHERE project_id = ?1)
+             WHERE id = ?1",
+            params![project_id],
+        )?;
+        Ok(())
+    }
+
+    /// 创建对话会话
+    pub fn insert_chat_session(&self, session: &ChatSession) -> Result<(), DbError> {
+        self.conn.execute(
+            "INSERT INTO chat_sessions (id, project_id, title, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                session.id,
+                session.project_id,
+                session.title,
+                session.created_at.to_rfc3339(),
+                session.updated_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 插入对话消息
+    pub fn insert_chat_message(&self, message: &ChatMessage) -> Result<(), DbError> {
+        let citations_json = message.citations.as_ref().map(|c| serde_json::to_string(c).ok()).flatten();
+
+        self.conn.execute(
+            "INSERT INTO chat_messages (id, session_id, role, content, citations, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                message.id,
+                message.session_id,
+                message.role.as_str(),
+                message.content,
+                citations_json,
+                message.created_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    // ========== 标签 ==========
+
+    /// 为条目添加标签（已存在时忽略）
+    pub fn add_tag(&self, kind: ContentKind, item_id: &str, tag: &str) -> Result<(), DbError> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO item_tags (kind, item_id, tag) VALUES (?1, ?2, ?3)",
+            params![kind.as_str(), item_id, tag],
+        )?;
+        Ok(())
+    }
+
+    /// 移除条目的标签
+    pub fn remove_tag(&self, kind: ContentKind, item_id: &str, tag: &str) -> Result<(), DbError> {
+        self.conn.execute(
+            "DELETE FROM item_tags WHERE kind = ?1 AND item_id = ?2 AND tag = ?3",
+            params![kind.as_str(), item_id, tag],
+        )?;
+        Ok(())
+    }
+
+    /// 删除条目的全部标签
+    pub fn delete_tags(&self, kind: ContentKind, item_id: &str) -> Result<(), DbError> {
+        self.conn.execute(
+            "DELETE FROM item_tags WHERE kind = ?1 AND item_id = ?2",
+            params![kind.as_str(), item_id],
+        )?;
+        Ok(())
+    }
+
+    fn load_tags(&self, kind: ContentKind, item_id: &str) -> Result<Vec<ItemTag>, DbError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT tag FROM item_tags WHERE kind = ?1 AND item_id = ?2 ORDER BY tag",
+        )?;
+        let tags = stmt
+            .query_map(params![kind.as_str(), item_id], |row| {
+                Ok(ItemTag {
+                    kind,
+                    item_id: item_id.to_string(),
+                    tag: row.get(0)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(tags)
+    }
+
+    // ========== 内容快照 ==========
+
+    /// 读取条目（或整个项目）的内容快照
+    pub fn snapshot(&self, kind: ContentKind, id: &str) -> Result<ContentSnapshot, DbError> {
+        let mut snapshot = ContentSnapshot::default();
+        match kind {
+            ContentKind::Project => {
+                snapshot.project = Some(self.load_project(id)?);
+                snapshot.sources = self.load_sources("project_id", id)?;
+                snapshot.notes = self.load_notes("project_id", id)?;
+                snapshot.canvases = self.load_canvases("project_id", id)?;
+                snapshot.mindmaps = self.load_mindmaps("project_id", id)?;
+                snapshot.presentations = self.load_presentations("project_id", id)?;
+                snapshot.chat_sessions = self.load_chat_sessions("project_id", id)?;
+            }
+            ContentKind::Source => snapshot.sources = self.load_sources("id", id)?,
+            ContentKind::Note => snapshot.notes = self.load_notes("id", id)?,
+            ContentKind::Canvas => snapshot.canvases = self.load_canvases("id", id)?,
+            ContentKind::Mindmap => snapshot.mindmaps = self.load_mindmaps("id", id)?,
+            ContentKind::Presentation => {
+                snapshot.presentations = self.load_presentations("id", id)?
+            }
+            ContentKind::ChatSession => {
+                snapshot.chat_sessions = self.load_chat_sessions("id", id)?
+            }
+        }
+
+        if snapshot.describe(kind).is_none() {
+            return Err(DbError::NotFound(format!("{} {} 不存在", kind.as_str(), id)));
+        }
+
+        for record in &snapshot.sources {
+            let tags = self.load_tags(ContentKind::Source, &record.source.id)?;
+            snapshot.tags.extend(tags);
+        }
+        for record in &snapshot.notes {
+            let tags = self.load_tags(ContentKind::Note, &record.note.id)?;
+            snapshot.tags.extend(tags);
+        }
+        Ok(snapshot)
+    }
+
+    /// 读取单条记录的快照（项目只包含项目本身，不含其下内容）
+    pub fn record_snapshot(&self, kind: ContentKind, id: &str) -> Result<ContentSnapshot, DbError> {
+        match kind {
+            ContentKind::Project => Ok(ContentSnapshot {
+                project: Some(self.load_project(id)?),
+                ..ContentSnapshot::default()
+            }),
+            _ => self.snapshot(kind, id),
+        }
+    }
+
+    /// 删除快照中的全部记录（含向量、全文索引、对话消息、标签）
+    pub fn delete_snapshot(&self, snapshot: &ContentSnapshot) -> Result<(), DbError> {
+        for tag in &snapshot.tags {
+            self.remove_tag(tag.kind, &tag.item_id, &tag.tag)?;
+        }
+        for record in &snapshot.sources {
+            let id = &record.source.id;
+            self.conn.execute("DELETE FROM source_embeddings WHERE source_id = ?1", params![id])?;
+            self.conn.execute("DELETE FROM sources WHERE id = ?1", params![id])?;
+        }
+        for record in &snapshot.notes {
+            let id = &record.note.id;
+            self.conn.execute("DELETE FROM note_embeddings WHERE note_id = ?1", params![id])?;
+            self.conn.execute("DELETE FROM notes WHERE id = ?1", params![id])?;
+        }
+        for record in &snapshot.canvases {
+            self.conn.execute("DELETE FROM canvases WHERE id = ?1", params![record.canvas.id])?;
+        }
+        for record in &snapshot.mindmaps {
+            self.conn.execute("DELETE FROM mindmaps WHERE id = ?1", params![record.mindmap.id])?;
+        }
+        for presentation in &snapshot.presentations {
+            self.conn.execute("DELETE FROM presentations WHERE id = ?1", params![presentation.id])?;
+        }
+        for record in &snapshot.chat_sessions {
+            let id = &record.session.id;
+            self.conn.execute("DELETE FROM chat_messages WHERE session_id = ?1", params![id])?;
+            self.conn.execute("DELETE FROM chat_sessions WHERE id = ?1", params![id])?;
+        }
+        if let Some(project) = &snapshot.project {
+            self.conn.execute("DELETE FROM recent_accesses WHERE project_id = ?1", params![project.id])?;
+            self.conn.execute("DELETE FROM projects WHERE id = ?1", params![project.id])?;
+        }
+        Ok(())
+    }
+
+    /// 用新版本快照原地更新已有记录（同步应用另一设备的版本时使用）
+    ///
+    /// 两版都有的记录用 UPDATE 写入，由触发器记录修改事件；只在旧版中的记录删除，只在新版中的记录插入。
+    /// 全文索引文本、向量与标签只写入有变化的部分。PPT 搜索文本由调用方在数据文件就位后提取。
+    pub fn replace_snapshot(&self, old: &ContentSnapshot, new: &ContentSnapshot) -> Result<(), DbError> {
+        let same_tag = |a: &ItemTag, b: &ItemTag| a.kind == b.kind && a.item_id == b.item_id && a.tag == b.tag;
+        let removed = ContentSnapshot {
+            project: None,
+            sources: missing_from(&old.sources, &new.sources, |r| &r.source.id),
+            notes: missing_from(&old.notes, &new.notes, |r| &r.note.id),
+            canvases: missing_from(&old.canvases, &new.canvases, |r| &r.canvas.id),
+            mindmaps: missing_from(&old.mindmaps, &new.mindmaps, |r| &r.mindmap.id),
+            presentations: missing_from(&old.presentations, &new.presentations, |p| &p.id),
+            chat_sessions: missing_from(&old.chat_sessions, &new.chat_sessions, |r| &r.session.id),
+            tags: old
+                .tags
+                .iter()
+                .filter(|tag| !new.tags.iter().any(|other| same_tag(tag, other)))
+                .cloned()
+                .collect(),
+        };
+        let added = ContentSnapshot {
+            project: None,
+            sources: missing_from(&new.sources, &old.sources, |r| &r.source.id),
+            notes: missing_from(&new.notes, &old.notes, |r| &r.note.id),
+            canvases: missing_from(&new.canvases, &old.canvases, |r| &r.canvas.id),
+            mindmaps: missing_from(&new.mindmaps, &old.mindmaps, |r| &r.mindmap.id),
+            presentations: missing_from(&new.presentations, &old.presentations, |p| &p.id),
+            chat_sessions: missing_from(&new.chat_sessions, &old.chat_sessions, |r| &r.session.id),
+            tags: new
+                .tags
+                .iter()
+                .filter(|tag| !old.tags.iter().any(|other| same_tag(tag, other)))
+                .cloned()
+                .collect(),
+        };
+        self.delete_snapshot(&removed)?;
+
+        if let (Some(_), Some(project)) = (&old.project, &new.project) {
+            self.update_project(project)?;
+        }
+        for record in &new.sources {
+            if let Some(previous) = old.sources.iter().find(|r| r.source.id == record.source.id) {
+                self.update_source_record(previous, record)?;
+            }
+        }
+        for record in &new.notes {
+            if let Some(previous) = old.notes.iter().find(|r| r.note.id == record.note.id) {
+                self.update_note_record(previous, record)?;
+            }
+        }
+        for record in &new.canvases {
+            if let Some(previous) = old.canvases.iter().find(|r| r.canvas.id == record.canvas.id) {
+                self.update_canvas_record(previous, record)?;
+            }
+        }
+        for record in &new.mindmaps {
+            if let Some(previous) = old.mindmaps.iter().find(|r| r.mindmap.id == record.mindmap.id) {
+                self.update_mindmap_record(previous, record)?;
+            }
+        }
+        for presentation in &new.presentations {
+            if let Some(previous) = old.presentations.iter().find(|p| p.id == presentation.id) {
+                self.update_presentation_record(previous, presentation)?;
+            }
+        }
+        for record in &new.chat_sessions {
+            if let Some(previous) = old.chat_sessions.iter().find(|r| r.session.id == record.session.id) {
+                self.update_chat_session_record(previous, record)?;
+            }
+        }
+
+        self.restore_snapshot(&added)
+    }
+
+    fn update_source_record(&self, old: &SourceRecord, new: &SourceRecord) -> Result<(), DbError> {
+        let source = &new.source;
+        self.conn.execute(
+            "UPDATE sources SET project_id = ?2, type = ?3, path = ?4, size = ?5, mime_type = ?6,
+                    thumbnail_path = ?7, created_at = ?8, updated_at = ?9
+             WHERE id = ?1",
+            params![
+                source.id,
+                source.project_id,
+                source.source_type.as_str(),
+                self.paths.to_stored(&source.path),
+                source.size,
+                source.mime_type,
+                self.paths.to_stored_opt(source.thumbnail_path.as_deref()),
+                source.created_at.to_rfc3339(),
+                source.updated_at.to_rfc3339(),
+            ],
+        )?;
+        // 名称与正文变化时才写入，避免重建全文索引
+        if old.source.name != source.name || old.text_content != new.text_content {
+            self.conn.execute(
+                "UPDATE sources SET name = ?2, text_content = ?3 WHERE id = ?1",
+                params![source.id, source.name, new.text_content],
+            )?;
+        }
+        if old.embedding != new.embedding {
+            match &new.embedding {
+                Some(embedding) => self.upsert_source_embedding(&source.id, &source.project_id, embedding)?,
+                None => {
+                    self.conn
+                        .execute("DELETE FROM source_embeddings WHERE source_id = ?1", params![source.id])?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn update_note_record(&self, old: &NoteRecord, new: &NoteRecord) -> Result<(), DbError> {
+        let note = &new.note;
+        self.conn.execute(
+            "UPDATE notes SET project_id = ?2, path = ?3, output_type = ?4, title_locked = ?5,
+                    created_at = ?6, updated_at = ?7
+             WHERE id = ?1",
+            params![
+                note.id,
+                note.project_id,
+                self.paths.to_stored(&note.path),
+                note.output_type.as_str(),
+                note.title_locked as i32,
+                note.created_at.to_rfc3339(),
+                note.updated_at.to_rfc3339(),
+            ],
+        )?;
+        if old.note.title != note.title {
+            self.update_note_title(&note.id, &note.title)?;
+        }
+        if let Some(text_content) = &new.text_content {
+            self.index_note_content(&note.id, text_content)?;
+        }
+        if old.embedding != new.embedding {
+            match &new.embedding {
+                Some(embedding) => self.upsert_note_embedding(&note.id, &note.project_id, embedding)?,
+                None => self.delete_note_embedding(&note.id)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn update_canvas_record(&self, old: &CanvasRecord, new: &CanvasRecord) -> Result<(), DbError> {
+        let canvas = &new.canvas;
+        self.conn.execute(
+            "UPDATE canvases SET project_id = ?2, path = ?3, created_at = ?4, updated_at = ?5 WHERE id = ?1",
+            params![
+                canvas.id,
+                canvas.project_id,
+                self.paths.to_stored(&canvas.path),
+                canvas.created_at.to_rfc3339(),
+                canvas.updated_at.to_rfc3339(),
+            ],
+        )?;
+        if old.canvas.title != canvas.title {
+            self.conn
+                .execute("UPDATE canvases SET title = ?2 WHERE id = ?1", params![canvas.id, canvas.title])?;
+        }
+        if old.text_content != new.text_content {
+            if let Some(text_content) = &new.text_content {
+                self.update_canvas_text_content(&canvas.id, text_content)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn update_mindmap_record(&self, old: &MindMapRecord, new: &MindMapRecord) -> Result<(), DbError> {
+        let mindmap = &new.mindmap;
+        self.conn.execute(
+            "UPDATE mindmaps SET project_id = ?2, theme = ?3, layout = ?4, created_at = ?5, updated_at = ?6
+             WHERE id = ?1",
+            params![
+                mindmap.id,
+                mindmap.project_id,
+                mindmap.theme,
+                mindmap.layout,
+                mindmap.created_at.to_rfc3339(),
+                mindmap.updated_at.to_rfc3339(),
+            ],
+        )?;
+        if old.mindmap.title != mindmap.title {
+            self.conn
+                .execute("UPDATE mindmaps SET title = ?2 WHERE id = ?1", params![mindmap.id, mindmap.title])?;
+        }
+        if old.data != new.data {
+            self.conn
+                .execute("UPDATE mindmaps SET data = ?2 WHERE id = ?1", params![mindmap.id, new.data])?;
+            self.update_mindmap_text_content(&mindmap.id, &new.data)?;
+        }
+        Ok(())
+    }
+
+    fn update_presentation_record(&self, old: &Presentation, new: &Presentation) -> Result<(), DbError> {
+        self.conn.execute(
+            "UPDATE presentations SET project_id = ?2, data_path = ?3, thumbnail_path = ?4, slide_count = ?5,
+                    created_at = ?6, updated_at = ?7
+             WHERE id = ?1",
+            params![
+                new.id,
+                new.project_id,
+                self.paths.to_stored(&new.data_path),
+                self.paths.to_stored_opt(new.thumbnail_path.as_deref()),
+                new.slide_count,
+                new.created_at.to_rfc3339(),
+                new.updated_at.to_rfc3339(),
+            ],
+        )?;
+        if old.title != new.title {
+            self.conn
+                .execute("UPDATE presentations SET title = ?2 WHERE id = ?1", params![new.id, new.title])?;
+        }
+        Ok(())
+    }
+
+    fn update_chat_session_record(&self, old: &ChatSessionRecord, new: &ChatSessionRecord) -> Result<(), DbError> {
+        let session = &new.session;
+        self.conn.execute(
+            "UPDATE chat_sessions SET project_id = ?2, created_at = ?3, updated_at = ?4 WHERE id = ?1",
+            params![
+                session.id,
+                session.project_id,
+                session.created_at.to_rfc3339(),
+                session.updated_at.to_rfc3339(),
+            ],
+        )?;
+        if old.session.title != session.title {
+            self.conn
+                .execute("UPDATE chat_sessions SET title = ?2 WHERE id = ?1", params![session.id, session.title])?;
+        }
+
+        let citations_json = |message: &ChatMessage| {
+            message.citations.as_ref().and_then(|c| serde_json::to_string(c).ok())
+        };
+        for message in &old.messages {
+            if !new.messages.iter().any(|m| m.id == message.id) {
+                self.conn.execute("DELETE FROM chat_messages WHERE id = ?1", params![message.id])?;
+            }
+        }
+        for message in &new.messages {
+            let Some(previous) = old.messages.iter().find(|m| m.id == message.id) else {
+                self.insert_chat_message(message)?;
+                continue;
+            };
+            if previous.content != message.content {
+                self.conn.execute(
+                    "UPDATE chat_messages SET content = ?2 WHERE id = ?1",
+                    params![message.id, message.content],
+                )?;
+            }
+            if previous.role != message.role
+                || citations_json(previous) != citations_json(message)
+                || previous.created_at != message.created_at
+            {
+                self.conn.execute(
+                    "UPDATE chat_messages SET role = ?2, citations = ?3, created_at = ?4 WHERE id = ?1",
+                    params![
+                        message.id,
+                        message.role.as_str(),
+                        citations_json(message),
+                        message.created_at.to_rfc3339(),
+                    ],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 按快照重新写入全部记录
+    pub fn restore_snapshot(&self, snapshot: &ContentSnapshot) -> Result<(), DbError> {
+        if let Some(project) = &snapshot.project {
+            self.insert_project(project)?;
+        }
+        for record in &snapshot.sources {
+            let source = &record.source;
+            self.insert_source_with_content(source, record.text_content.as_deref())?;
+            if let Some(embedding) = &record.embedding {
+                self.upsert_source_embedding(&source.id, &source.project_id, embedding)?;
+            }
+        }
+        for record in &snapshot.notes {
+            let note = &record.note;
+            self.insert_note(note)?;
+            if let Some(text_content) = &record.text_content {
+                self.index_note_content(&note.id, text_content)?;
+            }
+            if let Some(embedding) = &record.embedding {
+                self.upsert_note_embedding(&note.id, &note.project_id, embedding)?;
+            }
+        }
+        for record in &snapshot.canvases {
+            self.insert_canvas(&record.canvas)?;
+            if let Some(text_content) = &record.text_content {
+                self.update_canvas_text_content(&record.canvas.id, text_content)?;
+            }
+        }
+        for record in &snapshot.mindmaps {
+            self.insert_mindmap(&record.mindmap, &record.data)?;
+        }
+        // PPT 的搜索文本不在快照中，从数据文件提取
+        for presentation in &snapshot.presentations {
+            self.insert_presentation(presentation)?;
+            self.index_presentation_file(presentation)?;
+        }
+        for record in &snapshot.chat_sessions {
+            self.insert_chat_session(&record.session)?;
+            for message in &record.messages {
+                self.insert_chat_message(message)?;
+            }
+        }
+        for tag in &snapshot.tags {
+            self.add_tag(tag.kind, &tag.item_id, &tag.tag)?;
+        }
+        Ok(())
+    }
+
+    fn load_project(&self, id: &str) -> Result<Project, DbError> {
+        let paths = &self.paths;
+        self.conn
+            .query_row(
+                "SELECT id, name, icon_id, icon_name, icon_emoji, icon_color,
+                        workspace, is_starred, created_at, updated_at, sources_count, path, archived_at
+                 FROM projects WHERE id = ?1",
+                params![id],
+                |row| {
+                    Ok(Project {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        icon: ProjectIcon {
+                            id: row.get(2)?,
+                            name: row.get(3)?,
+                            emoji: row.get(4)?,
+                            color: row.get(5)?,
+                        },
+                        workspace: row.get(6)?,
+                        is_starred: row.get::<_, i32>(7)? != 0,
+                        created_at: parse_datetime(&row.get::<_, String>(8)?),
+                        updated_at: parse_datetime(&row.get::<_, String>(9)?),
+                        sources_count: row.get(10)?,
+                        path: paths.resolve(&row.get::<_, String>(11)?),
+                        archived_at: row.get::<_, Option<String>>(12)?.as_deref().map(parse_datetime),
+                    })
+                },
+            )
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => {
+                    DbError::NotFound(format!("项目 {} 不存在", id))
+                }
+                _ => DbError::Sqlite(e),
+            })
+    }
+
+    fn load_sources(&self, column: &str, value: &str) -> Result<Vec<SourceRecord>, DbError> {
+        let paths = &self.paths;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT s.id, s.project_id, s.name, s.type, s.path, s.size, s.mime_type,
+                    s.thumbnail_path, s.created_at, s.updated_at, s.text_content, e.embedding
+             FROM sources s
+             LEFT JOIN source_embeddings e ON e.source_id = s.id
+             WHERE s.{} = ?1",
+            column
+        ))?;
+
+        let records = stmt
+            .query_map(params![value], |row| {
+                Ok(SourceRecord {
+                    source: Source {
+                        id: row.get(0)?,
+                        project_id: row.get(1)?,
+                        name: row.get(2)?,
+                        source_type: SourceType::from_str(&row.get::<_, String>(3)?)
+                            .unwrap_or(SourceType::Markdown),
+                        path: paths.resolve(&row.get::<_, String>(4)?),
+                        size: row.get(5)?,
+                        mime_type: row.get(6)?,
+                        thumbnail_path: paths.resolve_opt(row.get(7)?),
+                        created_at: parse_datetime(&row.get::<_, String>(8)?),
+                        updated_at: parse_datetime(&row.get::<_, String>(9)?),
+                    },
+                    text_content: row.get(10)?,
+                    embedding: row
+                        .get::<_, Option<Vec<u8>>>(11)?
+                        .map(|blob| deserialize_embedding(&blob)),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(records)
+    }
+
+    fn load_notes(&self, column: &str, value: &str) -> Result<Vec<NoteRecord>, DbError> {
+        let paths = &self.paths;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT n.id, n.project_id, n.title, n.path, n.output_type, n.title_locked,
+                    n.created_at, n.updated_at, n.text_content, e.embedding
+             FROM notes n
+             LEFT JOIN note_embeddings e ON e.note_id = n.id
+             WHERE n.{} = ?1",
+            column
+        ))?;
+
+        let records = stmt
+            .query_map(params![value], |row| {
+                Ok(NoteRecord {
+                    note: Note {
+                        id: row.get(0)?,
+                        project_id: row.get(1)?,
+                        title: row.get(2)?,
+                        path: paths.resolve(&row.get::<_, String>(3)?),
+                        output_type: OutputType::from_str(&row.get::<_, String>(4)?),
+                        title_locked: row.get::<_, i32>(5)? != 0,
+                        created_at: parse_datetime(&row.get::<_, String>(6)?),
+                        updated_at: parse_datetime(&row.get::<_, String>(7)?),
+                    },
+                    text_content: row.get(8)?,
+                    embedding: row
+                        .get::<_, Option<Vec<u8>>>(9)?
+                        .map(|blob| deserialize_embedding(&blob)),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(records)
+    }
+
+    fn load_canvases(&self, column: &str, value: &str) -> Result<Vec<CanvasRecord>, DbError> {
+        let paths = &self.paths;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, project_id, title, path, created_at, updated_at
+             FROM canvases WHERE {} = ?1",
+            column
+        ))?;
+
+        let canvases = stmt
+            .query_map(params![value], |row| {
+                Ok(Canvas {
+                    id: row.get(0)?,
+                    project_id: row.get(1)?,
+                    title: row.get(2)?,
+                    path: paths.resolve(&row.get::<_, String>(3)?),
+                    created_at: parse_datetime(&row.get::<_, String>(4)?),
+                    updated_at: parse_datetime(&row.get::<_, String>(5)?),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut records = Vec::with_capacity(canvases.len());
+        for canvas in canvases {
+            let text_content = self
+                .conn
+                .query_row(
+                    "SELECT text_content FROM canvases_fts WHERE canvas_id = ?1",
+                    params![canvas.id],
+                    |row| row.get::<_, Option<String>>(0),
+                )
+                .optional()?
+                .flatten();
+            records.push(CanvasRecord { canvas, text_content });
+        }
+
+        Ok(records)
+    }
+
+    fn load_mindmaps(&self, column: &str, value: &str) -> Result<Vec<MindMapRecord>, DbError> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, project_id, title, theme, layout, created_at, updated_at, data
+             FROM mindmaps WHERE {} = ?1",
+            column
+        ))?;
+
+        let records = stmt
+            .query_map(params![value], |row| {
+                Ok(MindMapRecord {
+                    mindmap: MindMap {
+                        id: row.get(0)?,
+                        project_id: row.get(1)?,
+                        title: row.get(2)?,
+                        theme: row.get(3)?,
+                        layout: row.get(4)?,
+                        created_at: parse_datetime(&row.get::<_, String>(5)?),
+                        updated_at: parse_datetime(&row.get::<_, String>(6)?),
+                    },
+                    data: row.get(7)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(records)
+    }
+
+    fn load_presentations(&self, column: &str, value: &str) -> Result<Vec<Presentation>, DbError> {
+        let paths = &self.paths;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, project_id, title, data_path, thumbnail_path, slide_count, created_at, updated_at
+             FROM presentations WHERE {} = ?1",
+            column
+        ))?;
+
+        let presentations = stmt
+            .query_map(params![value], |row| {
+                Ok(Presentation {
+                    id: row.get(0)?,
+                    project_id: row.get(1)?,
+                    title: row.get(2)?,
+                    data_path: paths.resolve(&row.get::<_, String>(3)?),
+                    thumbnail_path: paths.resolve_opt(row.get(4)?),
+                    slide_count: row.get(5)?,
+                    created_at: parse_datetime(&row.get::<_, String>(6)?),
+                    updated_at: parse_datetime(&row.get::<_, String>(7)?),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(presentations)
+    }
+
+    fn load_chat_sessions(&self, column: &str, value: &str) -> Result<Vec<ChatSessionRecord>, DbError> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, project_id, title, created_at, updated_at
+             FROM chat_sessions WHERE {} = ?1",
+            column
+        ))?;
+
+        let sessions = stmt
+            .query_map(params![value], |row| {
+                Ok(ChatSession {
+                    id: row.get(0)?,
+                    project_id: row.get(1)?,
+                    title: row.get(2)?,
+                    created_at: parse_datetime(&row.get::<_, String>(3)?),
+                    updated_at: parse_datetime(&row.get::<_, String>(4)?),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut message_stmt = self.conn.prepare(
+            "SELECT id, session_id, role, content, citations, created_at
+             FROM chat_messages WHERE session_id = ?1 ORDER BY created_at ASC",
+        )?;
+
+        let mut records = Vec::with_capacity(sessions.len());
+        for session in sessions {
+            let messages = message_stmt
+                .query_map(params![session.id], |row| {
+                    let citations_json: Option<String> = row.get(4)?;
+                    let citations: Option<Vec<Citation>> =
+                        citations_json.and_then(|json| serde_json::from_str(&json).ok());
+
+                    Ok(ChatMessage {
+                        id: row.get(0)?,
+                        session_id: row.get(1)?,
+                        role: MessageRole::from_str(&row.get::<_, String>(2)?)
+                            .unwrap_or(MessageRole::User),
+                        content: row.get(3)?,
+                        citations,
+                        created_at: parse_datetime(&row.get::<_, String>(5)?),
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            records.push(ChatSessionRecord { session, messages });
+        }
+
+        Ok(records)
+    }
+
+    // ========== 回收站 ==========
+
+    /// 写入回收站条目（含快照与文件映射）
+    pub fn insert_trash_item(
+        &self,
+        item: &TrashItem,
+        snapshot: &ContentSnapshot,
+        files: &[TrashedFile],
+    ) -> Result<(), DbError> {
+        // 快照与文件映射中的路径同样保存为相对路径
+        let mut snapshot = snapshot.clone();
+        self.paths.store_snapshot(&mut snapshot);
+        let files: Vec<TrashedFile> = files
+            .iter()
+            .map(|file| TrashedFile {
+                original: self.paths.to_stored(&file.original),
+                trashed: self.paths.to_stored(&file.trashed),
+            })
+            .collect();
+
+        let snapshot_json = serde_json::to_string(&snapshot)
+            .map_err(|e| DbError::Serialization(e.to_string()))?;
+        let files_json =
+            serde_json::to_string(&files).map_err(|e| DbError::Serialization(e.to_string()))?;
+
+        self.conn.execute(
+            "INSERT INTO trash_items (id, project_id, kind, item_id, title, trash_path, snapshot, files, deleted_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                item.id,
+                item.project_id,
+                item.kind.as_str(),
+                item.item_id,
+                item.title,
+                self.paths.to_stored(&item.trash_path),
+                snapshot_json,
+                files_json,
+                item.deleted_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 删除回收站条目
+    pub fn delete_trash_item(&self, id: &str) -> Result<(), DbError> {
+        let affected = self
+            .conn
+            .execute("DELETE FROM trash_items WHERE id = ?1", params![id])?;
+        if affected == 0 {
+            return Err(DbError::NotFound(format!("回收站条目 {} 不存在", id)));
+        }
+        Ok(())
+    }
+
+    /// 删除项目下的全部回收站条目（项目被彻底删除时使用）
+    pub fn delete_trash_items_by_project(&self, project_id: &str) -> Result<usize, DbError> {
+        let affected = self.conn.execute(
+            "DELETE FROM trash_items WHERE project_id = ?1",
+            params![project_id],
+        )?;
+        Ok(affected)
+    }
+
+    // ========== 同步 ==========
+
+    /// 记录条目上次同步时的版本
+    pub fn set_sync_record(&self, change: &SyncChange) -> Result<(), DbError> {
+        let change_json =
+            serde_json::to_string(change).map_err(|e| DbError::Serialization(e.to_string()))?;
+        self.conn.execute(
+            "INSERT INTO sync_records (kind, item_id, hash, change, synced_at)
+             VALUES (?1, ?2, ?3, ?4, datetime('now'))
+             ON CONFLICT(kind, item_id) DO UPDATE SET
+                hash = excluded.hash, change = excluded.change, synced_at = excluded.synced_at",
+            params![change.kind.as_str(), change.item_id, change.hash, change_json],
+        )?;
+        Ok(())
+    }
+
+    /// 记录已应用的其他设备变更序号
+    pub fn set_sync_cursor(&self, device_id: &str, seq: u64) -> Result<(), DbError> {
+        self.conn.execute(
+            "INSERT INTO sync_cursors (device_id, seq) VALUES (?1, ?2)
+             ON CONFLICT(device_id) DO UPDATE SET seq = excluded.seq",
+            params![device_id, seq as i64],
+        )?;
+        Ok(())
+    }
+}
+
+/// `records` 中 ID 不在 `others` 里的记录
+fn missing_from<T: Clone>(records: &[T], others: &[T], id: impl Fn(&T) -> &String) -> Vec<T> {
+    records
+        .iter()
+        .filter(|record| !others.iter().any(|other| id(other) == id(record)))
+        .cloned()
+        .collect()
+}
@@ -4,43 +4,60 @@
 
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use desklab_lib::commands::library::open_startup_library;
 use desklab_lib::commands::project::AppState;
-use desklab_lib::db::Database;
-use desklab_lib::services::{get_default_data_dir, get_default_db_path, FileService};
-use std::fs;
+use desklab_lib::services::{get_default_config_dir, get_default_data_dir, LibraryRegistry};
 use std::sync::Arc;
+use tauri::Emitter;
 
 fn main() {
     eprintln!("[main] DeskLab 启动中...");
 
-    // 初始化数据目录
-    let data_dir = get_default_data_dir().expect("无法获取数据目录");
-    let db_path = get_default_db_path().expect("无法获取数据库路径");
-    eprintln!("[main] 数据目录: {:?}", data_dir);
-    eprintln!("[main] 数据库路径: {:?}", db_path);
+    // 加载资料库注册表（默认资料库使用默认数据目录）
+    let data_dir = get_default_data_dir().unwrap_or_else(|| fatal("无法获取数据目录"));
+    let config_dir = get_default_config_dir().unwrap_or_else(|| data_dir.clone());
+    let libraries = LibraryRegistry::load(&config_dir, data_dir)
+        .unwrap_or_else(|e| fatal(&format!("加载资料库配置失败: {}", e)));
 
-    // 确保目录存在
-    if let Some(parent) = db_path.parent() {
-        fs::create_dir_all(parent).expect("创建数据库目录失败");
-    }
-
-    // 初始化数据库
-    let db = Database::new(&db_path).expect("数据库初始化失败");
-    eprintln!("[main] 数据库初始化成功");
-
-    // 初始化文件服务
-    let file_service = FileService::new(data_dir.clone());
-    file_service.init_base_dirs().expect("初始化项目目录失败");
-    eprintln!("[main] 文件服务初始化成功");
+    // 打开启动参数指定的资料库（--library <ID 或名称>），否则打开上次打开的资料库
+    let args: Vec<String> = std::env::args().collect();
+    let requested = args
+        .iter()
+        .position(|arg| arg == "--library")
+        .and_then(|index| args.get(index + 1))
+        .map(String::as_str);
+    let (library, db, file_service) = open_startup_library(&libraries, requested)
+        .unwrap_or_else(|e| fatal(&format!("打开资料库失败: {}", e)));
+    eprintln!("[main] 资料库: {} ({})", library.name, library.data_dir);
 
     // 创建应用状态
-    let state = Arc::new(AppState { db, file_service });
+    let state = Arc::new(AppState::new(db, file_service));
     eprintln!("[main] 应用状态创建成功，准备启动 Tauri...");
 
+    // 清理超过保留期限的回收站条目
+    match desklab_lib::commands::trash::purge_expired_trash(&state) {
+        Ok(count) if count > 0 => eprintln!("[main] 已清理 {} 个过期回收站条目", count),
+        Ok(_) => {}
+        Err(e) => eprintln!("[main] 清理回收站失败: {}", e),
+    }
+
+    // 启动定时备份
+    desklab_lib::commands::backup::spawn_backup_scheduler(state.clone());
+    let embedding_state = state.clone();
+
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .manage(state)
+        .manage(libraries)
+        .setup(move |app| {
+            // 启动后台向量生成，进度通过事件通知前端
+            let handle = app.handle().clone();
+            desklab_lib::commands::embedding::spawn_embedding_worker(embedding_state, move |progress| {
+                let _ = handle.emit(desklab_lib::commands::embedding::EMBEDDING_PROGRESS_EVENT, progress);
+            });
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             // Project Commands
             desklab_lib::commands::project::project_list,
@@ -49,16 +66,40 @@ fn main() {
             desklab_lib::commands::project::project_rename,
             desklab_lib::commands::project::project_delete,
             desklab_lib::commands::project::project_star,
+            desklab_lib::commands::project::project_archive,
+            desklab_lib::commands::project::project_unarchive,
+            desklab_lib::commands::project::project_move,
+            desklab_lib::commands::project::project_duplicate,
+            desklab_lib::commands::project::project_stats,
+            desklab_lib::commands::activity::activity_feed,
+            desklab_lib::commands::maintenance::library_check,
+            desklab_lib::commands::maintenance::library_repair,
+            desklab_lib::commands::template::template_list,
+            desklab_lib::commands::template::template_save,
+            desklab_lib::commands::template::template_delete,
+            desklab_lib::commands::template::project_create_from_template,
             // Workspace Commands
             desklab_lib::commands::workspace::workspace_list,
             desklab_lib::commands::workspace::workspace_create,
             desklab_lib::commands::workspace::workspace_delete,
+            desklab_lib::commands::workspace::workspace_rename,
+            desklab_lib::commands::workspace::workspace_reorder,
+            desklab_lib::commands::workspace::workspace_set_parent,
             // Search Commands
             desklab_lib::commands::search::search_global,
             desklab_lib::commands::search::search_sources,
             desklab_lib::commands::search::search_semantic,
+            desklab_lib::commands::search::search_rebuild_index,
+            desklab_lib::commands::search::related_items,
+            desklab_lib::commands::embedding::embedding_status,
             desklab_lib::commands::search::recent_list,
             desklab_lib::commands::search::recent_add,
+            // Saved Search Commands
+            desklab_lib::commands::saved_search::saved_search_list,
+            desklab_lib::commands::saved_search::saved_search_create,
+            desklab_lib::commands::saved_search::saved_search_update,
+            desklab_lib::commands::saved_search::saved_search_delete,
+            desklab_lib::commands::saved_search::saved_search_run,
             // Source Commands
             desklab_lib::commands::source::source_import,
             desklab_lib::commands::source::source_import_folder,
@@ -66,6 +107,7 @@ fn main() {
             desklab_lib::commands::source::source_get,
             desklab_lib::commands::source::source_delete,
             desklab_lib::commands::source::source_get_content,
+            desklab_lib::commands::source::source_find_in,
             // Note Commands
             desklab_lib::commands::note::note_list,
             desklab_lib::commands::note::note_get,
@@ -74,7 +116,10 @@ fn main() {
             desklab_lib::commands::note::note_save,
             desklab_lib::commands::note::note_delete,
             desklab_lib::commands::note::note_rename,
+            desklab_lib::commands::note::note_unlock_title,
             desklab_lib::commands::note::note_to_source,
+            desklab_lib::commands::note::note_merge,
+            desklab_lib::commands::note::note_split,
             // Chat Commands
             desklab_lib::commands::chat::chat_session_list,
             desklab_lib::commands::chat::chat_session_get,
@@ -122,7 +167,53 @@ fn main() {
             desklab_lib::commands::mindmap::mindmap_set_theme,
             desklab_lib::commands::mindmap::mindmap_set_layout,
             desklab_lib::commands::mindmap::mindmap_delete,
+            // Trash Commands
+            desklab_lib::commands::trash::trash_list,
+            desklab_lib::commands::trash::trash_restore,
+            desklab_lib::commands::trash::trash_purge,
+            desklab_lib::commands::trash::trash_empty,
+            desklab_lib::commands::trash::trash_purge_expired,
+            desklab_lib::commands::trash::trash_get_retention_days,
+            desklab_lib::commands::trash::trash_set_retention_days,
+            // Project Archive Commands
+            desklab_lib::commands::archive::project_export_archive,
+            desklab_lib::commands::archive::project_import_archive,
+            // Batch Commands
+            desklab_lib::commands::batch::batch_delete,
+            desklab_lib::commands::batch::batch_move,
+            desklab_lib::commands::batch::batch_tag,
+            desklab_lib::commands::batch::batch_note_to_source,
+            // Tag Commands
+            desklab_lib::commands::tag::tag_get,
+            desklab_lib::commands::tag::tag_list,
+            desklab_lib::commands::tag::tag_find,
+            // Backup Commands
+            desklab_lib::commands::backup::backup_get_settings,
+            desklab_lib::commands::backup::backup_set_settings,
+            desklab_lib::commands::backup::backup_now,
+            desklab_lib::commands::backup::backup_list,
+            desklab_lib::commands::backup::backup_restore,
+            // Sync Commands
+            desklab_lib::commands::sync::sync_get_settings,
+            desklab_lib::commands::sync::sync_set_settings,
+            desklab_lib::commands::sync::sync_now,
+            // Library Commands
+            desklab_lib::commands::library::library_list,
+            desklab_lib::commands::library::library_current,
+            desklab_lib::commands::library::library_create,
+            desklab_lib::commands::library::library_open,
         ])
         .run(tauri::generate_context!())
-        .expect("启动 DeskLab 失败");
+        .unwrap_or_else(|e| fatal(&format!("启动 DeskLab 失败: {}", e)));
+}
+
+/// 启动失败时弹窗提示并退出（此时 Tauri 尚未运行，直接使用系统对话框）
+fn fatal(message: &str) -> ! {
+    eprintln!("[main] {}", message);
+    rfd::MessageDialog::new()
+        .set_level(rfd::MessageLevel::Error)
+        .set_title("DeskLab 无法启动")
+        .set_description(message)
+        .show();
+    std::process::exit(1);
 }
@@ -0,0 +1,28 @@
+//! 活动日志数据模型
+
+use super::ContentKind;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 活动类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivityAction {
+    Create,
+    Update,
+    Delete,
+}
+
+/// 活动日志条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityEntry {
+    pub id: i64,
+    pub project_id: String,
+    pub kind: ContentKind,
+    pub item_id: String,
+    pub action: ActivityAction,
+    /// 事件发生时条目的标题
+    pub title: String,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,21 @@
+//! 项目归档数据模型
+
+use super::ContentSnapshot;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 当前项目归档格式版本
+pub const PROJECT_ARCHIVE_VERSION: u32 = 1;
+
+/// 项目归档清单（归档根目录下的 manifest.json）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveManifest {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub include_embeddings: bool,
+    /// 归档中包含的数据文件（归档内路径）
+    pub files: Vec<String>,
+    /// 项目内容快照，其中的文件路径均为归档内路径
+    pub snapshot: ContentSnapshot,
+}
@@ -0,0 +1,68 @@
+//! 备份数据模型
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 当前备份格式版本
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+/// 定时备份设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupSettings {
+    pub enabled: bool,
+    /// 备份目录
+    pub directory: Option<String>,
+    /// 备份间隔（小时）
+    pub interval_hours: u32,
+    /// 保留的备份份数
+    pub keep: u32,
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: None,
+            interval_hours: 24,
+            keep: 7,
+        }
+    }
+}
+
+/// 备份中的数据文件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupFileEntry {
+    /// 相对于数据目录的路径（以 `/` 分隔）
+    pub path: String,
+    pub size: u64,
+    /// 修改时间（Unix 毫秒），用于增量备份时判断文件是否变化
+    pub modified: i64,
+}
+
+/// 备份清单（备份目录下的 backup.json）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    pub files: Vec<BackupFileEntry>,
+    /// 实际复制的文件数（其余文件以硬链接与上一份备份共享）
+    #[serde(default)]
+    pub copied_count: usize,
+}
+
+/// 备份信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    /// 备份目录
+    pub path: String,
+    pub created_at: DateTime<Utc>,
+    pub files_count: usize,
+    /// 数据文件总大小（字节，不含数据库）
+    pub total_size: u64,
+    /// 实际复制的文件数（其余与上一份备份共享）
+    pub copied_count: usize,
+}
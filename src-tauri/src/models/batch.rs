@@ -0,0 +1,51 @@
+//! 批量操作数据模型
+
+use serde::{Deserialize, Serialize};
+
+/// 批量操作中单个条目的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchItemResult {
+    pub id: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+impl BatchItemResult {
+    pub fn ok(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            success: true,
+            error: None,
+        }
+    }
+
+    pub fn failed(id: &str, error: impl ToString) -> Self {
+        Self {
+            id: id.to_string(),
+            success: false,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// 批量操作结果（按请求顺序返回每个条目的结果）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchResult {
+    pub results: Vec<BatchItemResult>,
+    pub succeeded: u32,
+    pub failed: u32,
+}
+
+impl BatchResult {
+    pub fn new(results: Vec<BatchItemResult>) -> Self {
+        let succeeded = results.iter().filter(|r| r.success).count() as u32;
+        let failed = results.len() as u32 - succeeded;
+        Self {
+            results,
+            succeeded,
+            failed,
+        }
+    }
+}
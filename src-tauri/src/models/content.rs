@@ -0,0 +1,303 @@
+//! 内容类型与内容快照模型
+//!
+//! 快照保存一组数据库记录的完整副本，用于回收站恢复等场景
+
+use super::{
+    Canvas, ChatMessage, ChatSession, ItemTag, MindMap, Note, Presentation, Project, Source,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 内容类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentKind {
+    Project,
+    Source,
+    Note,
+    Canvas,
+    Mindmap,
+    Presentation,
+    #[serde(rename = "chat")]
+    ChatSession,
+}
+
+impl ContentKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentKind::Project => "project",
+            ContentKind::Source => "source",
+            ContentKind::Note => "note",
+            ContentKind::Canvas => "canvas",
+            ContentKind::Mindmap => "mindmap",
+            ContentKind::Presentation => "presentation",
+            ContentKind::ChatSession => "chat",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "project" => Some(ContentKind::Project),
+            "source" => Some(ContentKind::Source),
+            "note" => Some(ContentKind::Note),
+            "canvas" => Some(ContentKind::Canvas),
+            "mindmap" => Some(ContentKind::Mindmap),
+            "presentation" => Some(ContentKind::Presentation),
+            "chat" => Some(ContentKind::ChatSession),
+            _ => None,
+        }
+    }
+}
+
+/// 来源记录（含提取文本与向量）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceRecord {
+    pub source: Source,
+    pub text_content: Option<String>,
+    pub embedding: Option<Vec<f32>>,
+}
+
+/// 笔记记录（含正文索引与向量）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteRecord {
+    pub note: Note,
+    pub text_content: Option<String>,
+    pub embedding: Option<Vec<f32>>,
+}
+
+/// 画布记录（含全文索引文本）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CanvasRecord {
+    pub canvas: Canvas,
+    pub text_content: Option<String>,
+}
+
+/// 思维导图记录（含导图 JSON 数据）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MindMapRecord {
+    pub mindmap: MindMap,
+    pub data: String,
+}
+
+/// 对话会话记录（含全部消息）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatSessionRecord {
+    pub session: ChatSession,
+    pub messages: Vec<ChatMessage>,
+}
+
+/// 内容快照
+///
+/// 单个条目的快照只包含对应的一条记录；项目快照包含项目本身及其下全部内容
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentSnapshot {
+    #[serde(default)]
+    pub project: Option<Project>,
+    #[serde(default)]
+    pub sources: Vec<SourceRecord>,
+    #[serde(default)]
+    pub notes: Vec<NoteRecord>,
+    #[serde(default)]
+    pub canvases: Vec<CanvasRecord>,
+    #[serde(default)]
+    pub mindmaps: Vec<MindMapRecord>,
+    #[serde(default)]
+    pub presentations: Vec<Presentation>,
+    #[serde(default)]
+    pub chat_sessions: Vec<ChatSessionRecord>,
+    /// 快照中来源与笔记的标签
+    #[serde(default)]
+    pub tags: Vec<ItemTag>,
+}
+
+impl ContentSnapshot {
+    /// 快照中引用的所有数据文件路径（来源、缩略图、笔记、画布、PPT）
+    pub fn file_paths(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+        for record in &self.sources {
+            paths.push(record.source.path.clone());
+            paths.extend(record.source.thumbnail_path.clone());
+        }
+        for record in &self.notes {
+            paths.push(record.note.path.clone());
+        }
+        for record in &self.canvases {
+            paths.push(record.canvas.path.clone());
+        }
+        for presentation in &self.presentations {
+            paths.push(presentation.data_path.clone());
+            paths.extend(presentation.thumbnail_path.clone());
+        }
+        paths
+    }
+
+    /// 改写快照中引用的所有数据文件路径
+    pub fn map_paths(&mut self, mut f: impl FnMut(&str) -> String) {
+        for record in &mut self.sources {
+            record.source.path = f(&record.source.path);
+            if let Some(thumb) = &record.source.thumbnail_path {
+                record.source.thumbnail_path = Some(f(thumb));
+            }
+        }
+        for record in &mut self.notes {
+            record.note.path = f(&record.note.path);
+        }
+        for record in &mut self.canvases {
+            record.canvas.path = f(&record.canvas.path);
+        }
+        for presentation in &mut self.presentations {
+            presentation.data_path = f(&presentation.data_path);
+            if let Some(thumb) = &presentation.thumbnail_path {
+                presentation.thumbnail_path = Some(f(thumb));
+            }
+        }
+    }
+
+    /// 为快照中的项目及全部条目分配新 ID，并同步更新相互引用，返回 旧 ID -> 新 ID 映射
+    pub fn reassign_ids(&mut self, project_id: &str) -> HashMap<String, String> {
+        let mut ids = HashMap::new();
+        let mut assign = |id: &mut String| {
+            let new_id = uuid::Uuid::new_v4().to_string();
+            ids.insert(std::mem::replace(id, new_id.clone()), new_id);
+        };
+
+        for record in &mut self.sources {
+            assign(&mut record.source.id);
+            record.source.project_id = project_id.to_string();
+        }
+        for record in &mut self.notes {
+            assign(&mut record.note.id);
+            record.note.project_id = project_id.to_string();
+        }
+        for record in &mut self.canvases {
+            assign(&mut record.canvas.id);
+            record.canvas.project_id = project_id.to_string();
+        }
+        for record in &mut self.mindmaps {
+            assign(&mut record.mindmap.id);
+            record.mindmap.project_id = project_id.to_string();
+        }
+        for presentation in &mut self.presentations {
+            assign(&mut presentation.id);
+            presentation.project_id = project_id.to_string();
+        }
+        for record in &mut self.chat_sessions {
+            assign(&mut record.session.id);
+            record.session.project_id = project_id.to_string();
+            for message in &mut record.messages {
+                assign(&mut message.id);
+            }
+        }
+        if let Some(project) = &mut self.project {
+            let old_id = std::mem::replace(&mut project.id, project_id.to_string());
+            ids.insert(old_id, project_id.to_string());
+        }
+
+        // 更新对话消息、引用与标签中的 ID
+        for record in &mut self.chat_sessions {
+            for message in &mut record.messages {
+                message.session_id = record.session.id.clone();
+                for citation in message.citations.iter_mut().flatten() {
+                    if let Some(new_id) = ids.get(&citation.source_id) {
+                        citation.source_id = new_id.clone();
+                    }
+                }
+            }
+        }
+        for tag in &mut self.tags {
+            if let Some(new_id) = ids.get(&tag.item_id) {
+                tag.item_id = new_id.clone();
+            }
+        }
+
+        ids
+    }
+
+    /// 移除快照中的来源及其标签；对话保留，只去掉消息中对来源的引用
+    pub fn remove_sources(&mut self) {
+        self.sources.clear();
+        for record in &mut self.chat_sessions {
+            for message in &mut record.messages {
+                message.citations = None;
+            }
+        }
+        self.tags.retain(|tag| tag.kind != ContentKind::Source);
+        if let Some(project) = &mut self.project {
+            project.sources_count = 0;
+        }
+    }
+
+    /// 快照中主条目的修改时间
+    pub fn updated_at(&self, kind: ContentKind) -> Option<DateTime<Utc>> {
+        match kind {
+            ContentKind::Project => self.project.as_ref().map(|p| p.updated_at),
+            ContentKind::Source => self.sources.first().map(|r| r.source.updated_at),
+            ContentKind::Note => self.notes.first().map(|r| r.note.updated_at),
+            ContentKind::Canvas => self.canvases.first().map(|r| r.canvas.updated_at),
+            ContentKind::Mindmap => self.mindmaps.first().map(|r| r.mindmap.updated_at),
+            ContentKind::Presentation => self.presentations.first().map(|p| p.updated_at),
+            ContentKind::ChatSession => self.chat_sessions.first().map(|r| r.session.updated_at),
+        }
+    }
+
+    /// 快照中主条目的 (项目 ID, 标题)
+    pub fn describe(&self, kind: ContentKind) -> Option<(String, String)> {
+        match kind {
+            ContentKind::Project => self.project.as_ref().map(|p| (p.id.clone(), p.name.clone())),
+            ContentKind::Source => self
+                .sources
+                .first()
+                .map(|r| (r.source.project_id.clone(), r.source.name.clone())),
+            ContentKind::Note => self
+                .notes
+                .first()
+                .map(|r| (r.note.project_id.clone(), r.note.title.clone())),
+            ContentKind::Canvas => self
+                .canvases
+                .first()
+                .map(|r| (r.canvas.project_id.clone(), r.canvas.title.clone())),
+            ContentKind::Mindmap => self
+                .mindmaps
+                .first()
+                .map(|r| (r.mindmap.project_id.clone(), r.mindmap.title.clone())),
+            ContentKind::Presentation => self
+                .presentations
+                .first()
+                .map(|p| (p.project_id.clone(), p.title.clone())),
+            ContentKind::ChatSession => self
+                .chat_sessions
+                .first()
+                .map(|r| (r.session.project_id.clone(), r.session.title.clone())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_kind_roundtrip() {
+        for kind in [
+            ContentKind::Project,
+            ContentKind::Source,
+            ContentKind::Note,
+            ContentKind::Canvas,
+            ContentKind::Mindmap,
+            ContentKind::Presentation,
+            ContentKind::ChatSession,
+        ] {
+            assert_eq!(ContentKind::from_str(kind.as_str()), Some(kind));
+            let json = serde_json::to_string(&kind).unwrap();
+            assert_eq!(json, format!("\"{}\"", kind.as_str()));
+        }
+        assert_eq!(ContentKind::from_str("unknown"), None);
+    }
+}
@@ -0,0 +1,25 @@
+//! 资料库数据模型
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 资料库（拥有独立的数据目录与数据库）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Library {
+    pub id: String,
+    pub name: String,
+    /// 数据目录（数据库位于其下的 db/desklab.db）
+    pub data_dir: String,
+    pub created_at: DateTime<Utc>,
+    pub last_opened_at: Option<DateTime<Utc>>,
+}
+
+/// 创建资料库的数据
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateLibraryData {
+    pub name: String,
+    /// 数据目录，未指定时放在默认数据目录的 libraries/{id} 下
+    pub data_dir: Option<String>,
+}
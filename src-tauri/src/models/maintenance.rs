@@ -0,0 +1,91 @@
+//! 资料库一致性检查数据模型
+
+use super::ContentKind;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 一致性问题类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum LibraryIssueKind {
+    /// 记录引用的文件不存在
+    MissingFile,
+    /// 项目目录中没有记录引用的文件
+    OrphanFile,
+    /// 项目目录存在但没有对应的项目记录
+    OrphanProjectDir,
+    /// 项目记录存在但项目目录不存在
+    MissingProjectDir,
+    /// 所属项目、会话或条目已不存在的记录
+    DanglingRow,
+    /// 项目 sources_count 与实际来源数量不一致
+    CountDrift,
+    /// 无法解析的 JSON 数据（数据库字段或数据文件）
+    BadJson,
+    /// 全文索引与数据表不一致
+    FtsDrift,
+}
+
+/// 一致性问题
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryIssue {
+    pub kind: LibraryIssueKind,
+    pub project_id: Option<String>,
+    pub item_id: Option<String>,
+    pub path: Option<String>,
+    pub detail: String,
+    /// `library_repair` 能否自动修复
+    pub repairable: bool,
+}
+
+/// 一致性检查结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryCheckReport {
+    pub checked_at: DateTime<Utc>,
+    pub projects_checked: usize,
+    pub files_checked: usize,
+    pub issues: Vec<LibraryIssue>,
+}
+
+/// 修复结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryRepairReport {
+    /// 已修复的问题
+    pub repaired: Vec<LibraryIssue>,
+    /// 修复后复查仍存在的问题
+    pub remaining: Vec<LibraryIssue>,
+    /// 孤立文件移入的目录（没有移动文件时为空）
+    pub lost_found_dir: Option<String>,
+}
+
+/// 数据库记录引用的数据文件
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileReference {
+    pub kind: ContentKind,
+    pub item_id: String,
+    pub project_id: String,
+    pub title: String,
+    pub path: String,
+    /// 是否为缩略图（缩略图缺失时可以直接清除引用）
+    pub thumbnail: bool,
+}
+
+/// 数据库中的悬空记录
+#[derive(Debug, Clone, PartialEq)]
+pub struct DanglingRow {
+    pub table: &'static str,
+    pub key: String,
+    pub project_id: Option<String>,
+    pub detail: &'static str,
+}
+
+/// 无法解析的 JSON 字段
+#[derive(Debug, Clone, PartialEq)]
+pub struct BadJsonRow {
+    pub table: &'static str,
+    pub column: &'static str,
+    pub key: String,
+}
@@ -1,17 +1,41 @@
 //! 数据模型模块
 
+pub mod activity;
+pub mod archive;
+pub mod backup;
+pub mod batch;
 pub mod canvas;
 pub mod chat;
+pub mod content;
+pub mod library;
+pub mod maintenance;
 pub mod mindmap;
 pub mod note;
 pub mod presentation;
 pub mod project;
+pub mod saved_search;
 pub mod source;
+pub mod sync;
+pub mod tag;
+pub mod template;
+pub mod trash;
 
+pub use activity::*;
+pub use archive::*;
+pub use backup::*;
+pub use batch::*;
 pub use canvas::*;
 pub use chat::*;
+pub use content::*;
+pub use library::*;
+pub use maintenance::*;
 pub use mindmap::*;
 pub use note::*;
 pub use presentation::*;
 pub use project::*;
+pub use saved_search::*;
 pub use source::*;
+pub use sync::*;
+pub use tag::*;
+pub use template::*;
+pub use trash::*;
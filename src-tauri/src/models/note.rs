@@ -46,6 +46,9 @@ pub struct Note {
     pub title: String,
     pub path: String,
     pub output_type: OutputType,
+    /// 标题是否已被手动锁定（锁定后保存内容不再自动更新标题）
+    #[serde(default)]
+    pub title_locked: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
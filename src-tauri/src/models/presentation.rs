@@ -86,6 +86,9 @@ pub struct PptistSlide {
     pub id: String,
     pub elements: Vec<PptistElement>,
     pub background: Option<SlideBackground>,
+    /// 演讲者备注（HTML）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remark: Option<String>,
 }
 
 /// 幻灯片背景
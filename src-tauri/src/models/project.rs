@@ -4,6 +4,7 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// 项目实体 - 聚合根
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +22,9 @@ pub struct Project {
     #[serde(rename = "sourcesCount")]
     pub sources_count: u32,
     pub path: String,
+    /// 归档时间，为空表示未归档（归档项目只读）
+    #[serde(rename = "archivedAt", default)]
+    pub archived_at: Option<DateTime<Utc>>,
 }
 
 /// 项目图标 - 值对象
@@ -51,6 +55,24 @@ pub struct CreateProjectData {
     pub workspace: String,
 }
 
+/// 项目统计（由数据库记录实时计算）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectStats {
+    pub project_id: String,
+    pub sources_count: u32,
+    /// 各类型来源数量（键为来源类型，如 "pdf"）
+    pub sources_by_type: BTreeMap<String, u32>,
+    pub notes_count: u32,
+    pub canvases_count: u32,
+    pub mindmaps_count: u32,
+    pub presentations_count: u32,
+    pub chat_sessions_count: u32,
+    pub chat_messages_count: u32,
+    /// 项目目录占用的磁盘空间（字节）
+    pub storage_bytes: u64,
+}
+
 /// 工作空间分类
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Workspace {
@@ -59,6 +81,9 @@ pub struct Workspace {
     #[serde(rename = "isSystem")]
     pub is_system: bool,
     pub order: i32,
+    /// 上级工作空间（为空时位于顶层）
+    #[serde(rename = "parentId", default)]
+    pub parent_id: Option<String>,
 }
 
 /// 最近访问记录
@@ -89,6 +114,118 @@ pub struct SearchResult {
     pub project_name: String,
     #[serde(rename = "updatedAt")]
     pub updated_at: DateTime<Utc>,
+    /// 混合检索的得分明细（仅混合检索结果包含）
+    #[serde(
+        rename = "scoreBreakdown",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub score_breakdown: Option<ScoreBreakdown>,
+    /// 正文中命中位置附近的摘要（按出现顺序，正文未命中时为空）
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub snippets: Vec<SearchSnippet>,
+}
+
+/// 语义搜索结果
+///
+/// 后台仍在生成向量时 `indexing` 为 true，结果只包含已生成向量的条目，`pending` 为剩余条目数。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SemanticSearchResults {
+    pub results: Vec<SearchResult>,
+    pub indexing: bool,
+    pub pending: u32,
+}
+
+/// 后台向量生成进度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct EmbeddingProgress {
+    /// 本轮已生成的条目数
+    pub embedded: u32,
+    /// 剩余条目数
+    pub pending: u32,
+}
+
+/// 相关条目推荐（按类型分组，各组按向量相似度降序）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RelatedItems {
+    pub sources: Vec<SearchResult>,
+    pub notes: Vec<SearchResult>,
+}
+
+/// 搜索摘要片段
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchSnippet {
+    pub text: String,
+    /// 命中词在 `text` 中的位置 `[起, 止)`，以字符（Unicode 标量值）计
+    pub highlights: Vec<(usize, usize)>,
+    /// 所在页码（从 1 开始，仅分页提取的 PDF 正文包含）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+}
+
+/// 文档内查找的命中位置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextMatch {
+    /// 在正文中的位置 `[起, 止)`，以字符（Unicode 标量值）计
+    pub start: usize,
+    pub end: usize,
+    /// 所在页码（从 1 开始，仅分页提取的 PDF 正文包含）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    /// 命中位置前后的上下文
+    pub context: SearchSnippet,
+}
+
+/// 搜索结果排序方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchSort {
+    /// 按相关度（同分时较新的在前）
+    #[default]
+    Relevance,
+    /// 按更新时间（新的在前）
+    Date,
+}
+
+impl SearchSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchSort::Relevance => "relevance",
+            SearchSort::Date => "date",
+        }
+    }
+
+    /// 从 `as_str` 的结果解析
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "relevance" => Some(SearchSort::Relevance),
+            "date" => Some(SearchSort::Date),
+            _ => None,
+        }
+    }
+}
+
+/// 混合检索得分明细
+///
+/// 全文检索与向量检索各自给出排名（从 1 开始），按倒数排名融合（RRF）得到 `fused`，
+/// 结果的 `score` 为 `fused` 相对理论最大值的比例。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScoreBreakdown {
+    /// 全文检索排名
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lexical_rank: Option<u32>,
+    /// 全文检索得分（bm25 取负值，短词为命中列权重之和）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lexical_score: Option<f32>,
+    /// 向量检索排名
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub semantic_rank: Option<u32>,
+    /// 向量余弦相似度
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub semantic_score: Option<f32>,
+    /// RRF 融合得分
+    pub fused: f32,
 }
 
 #[cfg(test)]
@@ -107,6 +244,7 @@ mod tests {
             updated_at: Utc::now(),
             sources_count: 0,
             path: "/path/to/project".to_string(),
+            archived_at: None,
         };
 
         let json = serde_json::to_string(&project).expect("序列化失败");
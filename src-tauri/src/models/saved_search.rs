@@ -0,0 +1,39 @@
+//! 保存的搜索数据模型
+
+use super::{SearchResult, SearchSort};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 保存的搜索（智能集合）
+///
+/// 保存查询语法与过滤条件，运行时按当前数据求值，结果即集合内容。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedSearch {
+    pub id: String,
+    pub name: String,
+    /// 查询语法（见 `services::search_query`）
+    pub query: String,
+    /// 搜索范围，为 None 时搜索全部项目
+    pub project_id: Option<String>,
+    pub include_archived: bool,
+    pub sort: SearchSort,
+    /// 是否报告上次运行以来的新命中
+    pub track_new: bool,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 保存的搜索的一次运行结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedSearchRun {
+    /// 运行后的保存的搜索（`last_run_at` 已更新）
+    pub search: SavedSearch,
+    pub results: Vec<SearchResult>,
+    /// 上次运行的时间，首次运行为 None
+    pub since: Option<DateTime<Utc>>,
+    /// 上次运行以来新命中的条目 ID（未开启 `track_new`、首次运行或查询条件修改后为空）
+    pub new_ids: Vec<String>,
+}
@@ -0,0 +1,104 @@
+//! 文件夹同步数据模型
+
+use super::{ContentKind, ContentSnapshot};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 当前同步文件夹格式版本
+pub const SYNC_FORMAT_VERSION: u32 = 1;
+
+/// 同步设置
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncSettings {
+    /// 共享文件夹（Syncthing、NAS 或 U 盘中的任意目录）
+    pub folder: Option<String>,
+    /// 本机设备名（用于冲突副本标题）
+    pub device_name: String,
+}
+
+/// 同步记录引用的数据文件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncFile {
+    /// 相对于数据目录的路径（以 `/` 分隔）
+    pub path: String,
+    /// 文件内容的 SHA-256（共享文件夹 blobs/ 中的文件名）
+    pub blob: String,
+}
+
+/// 变更日志中的一条记录变更
+///
+/// 每台设备只追加写入自己的日志文件（changes/{device_id}.jsonl），
+/// 因此共享文件夹中的日志不会被同步工具判定为冲突。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncChange {
+    /// 设备内递增的序号
+    pub seq: u64,
+    pub device_id: String,
+    pub device_name: String,
+    pub kind: ContentKind,
+    pub item_id: String,
+    /// 记录的修改时间（后写者优先的依据）
+    pub changed_at: DateTime<Utc>,
+    /// 记录版本的内容哈希，删除时为空
+    pub hash: String,
+    /// 本次变更基于的版本（上次同步时的哈希），新建时为 None
+    pub base_hash: Option<String>,
+    /// 记录快照（不含向量，文件路径为相对路径），删除时为 None
+    pub snapshot: Option<ContentSnapshot>,
+    #[serde(default)]
+    pub files: Vec<SyncFile>,
+}
+
+impl SyncChange {
+    pub fn is_deletion(&self) -> bool {
+        self.snapshot.is_none()
+    }
+}
+
+/// 同步冲突的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncResolution {
+    /// 两端修改了不同字段，已自动合并
+    Merged,
+    /// 保留较新的版本，另一版本保存为冲突副本
+    ConflictCopy,
+    /// 保留较新的版本（并发修改已检测到）
+    LastWriterWins,
+    /// 本机修改的记录在另一设备上被删除，保留本机修改
+    KeptLocalEdit,
+}
+
+/// 同步冲突
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncConflict {
+    pub kind: ContentKind,
+    pub item_id: String,
+    pub title: String,
+    /// 另一端的设备名
+    pub remote_device: String,
+    pub resolution: SyncResolution,
+    /// 是否保留了另一端的版本
+    pub remote_won: bool,
+    /// 冲突副本的 ID（仅 ConflictCopy）
+    pub copy_id: Option<String>,
+}
+
+/// 同步结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    /// 推送到共享文件夹的变更数
+    pub pushed: usize,
+    /// 应用的其他设备变更数
+    pub pulled: usize,
+    pub conflicts: Vec<SyncConflict>,
+    /// 被跳过的无效变更（如路径越出项目目录）
+    #[serde(default)]
+    pub errors: Vec<String>,
+    pub synced_at: DateTime<Utc>,
+}
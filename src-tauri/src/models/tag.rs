@@ -0,0 +1,13 @@
+//! 标签数据模型
+
+use super::ContentKind;
+use serde::{Deserialize, Serialize};
+
+/// 条目标签
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemTag {
+    pub kind: ContentKind,
+    pub item_id: String,
+    pub tag: String,
+}
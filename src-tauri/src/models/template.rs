@@ -0,0 +1,18 @@
+//! 项目模板数据模型
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 项目模板（保存在资料库 templates/ 目录下的项目归档）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectTemplate {
+    pub id: String,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub sources_count: usize,
+    pub notes_count: usize,
+    pub canvases_count: usize,
+    pub mindmaps_count: usize,
+    pub presentations_count: usize,
+}
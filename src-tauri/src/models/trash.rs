@@ -0,0 +1,27 @@
+//! 回收站数据模型
+
+use super::ContentKind;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 回收站条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashItem {
+    pub id: String,
+    pub project_id: String,
+    pub kind: ContentKind,
+    pub item_id: String,
+    pub title: String,
+    /// 回收站中该条目的目录
+    pub trash_path: String,
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// 被移入回收站的文件（原路径 -> 回收站路径）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashedFile {
+    pub original: String,
+    pub trashed: String,
+}
@@ -0,0 +1,244 @@
+//! 项目归档服务
+//!
+//! 提供项目归档（zip）的读写：根目录下的 manifest.json 与 files/ 目录中的数据文件；
+//! 以及归档项目时来源目录的压缩与解压
+
+use crate::models::ArchiveManifest;
+use crate::services::is_safe_relative_path;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use zip::write::FileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+/// 归档清单文件名
+pub const ARCHIVE_MANIFEST_NAME: &str = "manifest.json";
+
+/// 项目归档错误类型
+#[derive(Error, Debug)]
+pub enum ArchiveError {
+    #[error("IO 错误: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("ZIP 错误: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("清单格式错误: {0}")]
+    Manifest(#[from] serde_json::Error),
+    #[error("无效的归档: {0}")]
+    Invalid(String),
+}
+
+/// 项目归档服务
+pub struct ArchiveService;
+
+impl ArchiveService {
+    /// 写入项目归档
+    ///
+    /// `files` 为 (归档内路径, 磁盘路径) 列表。先写入同目录的临时文件，完成后再替换目标文件。
+    pub fn write(
+        output_path: &Path,
+        manifest: &ArchiveManifest,
+        files: &[(String, PathBuf)],
+    ) -> Result<(), ArchiveError> {
+        let temp_path = output_path.with_extension("part");
+        let result = Self::write_to(&temp_path, manifest, files)
+            .and_then(|_| fs::rename(&temp_path, output_path).map_err(ArchiveError::from));
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        result
+    }
+
+    fn write_to(
+        path: &Path,
+        manifest: &ArchiveManifest,
+        files: &[(String, PathBuf)],
+    ) -> Result<(), ArchiveError> {
+        let file = File::create(path)?;
+        let mut zip = ZipWriter::new(file);
+        let options = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+        zip.start_file(ARCHIVE_MANIFEST_NAME, options)?;
+        zip.write_all(serde_json::to_string_pretty(manifest)?.as_bytes())?;
+
+        for (name, disk_path) in files {
+            Self::ensure_safe_name(name)?;
+            zip.start_file(name.as_str(), options)?;
+            let mut source = File::open(disk_path)?;
+            io::copy(&mut source, &mut zip)?;
+        }
+
+        zip.finish()?.sync_all()?;
+        Ok(())
+    }
+
+    /// 读取归档清单
+    pub fn read_manifest(archive_path: &Path) -> Result<ArchiveManifest, ArchiveError> {
+        let mut archive = ZipArchive::new(File::open(archive_path)?)?;
+        let mut entry = archive.by_name(ARCHIVE_MANIFEST_NAME).map_err(|_| {
+            ArchiveError::Invalid(format!("缺少 {}", ARCHIVE_MANIFEST_NAME))
+        })?;
+        let mut json = String::new();
+        entry.read_to_string(&mut json)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    /// 解压归档中的数据文件，`entries` 为 (归档内路径, 目标路径) 列表
+    pub fn extract(archive_path: &Path, entries: &[(String, PathBuf)]) -> Result<(), ArchiveError> {
+        let mut archive = ZipArchive::new(File::open(archive_path)?)?;
+        for (name, dest) in entries {
+            Self::ensure_safe_name(name)?;
+            let mut entry = archive
+                .by_name(name)
+                .map_err(|_| ArchiveError::Invalid(format!("缺少文件 {}", name)))?;
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut output = File::create(dest)?;
+            io::copy(&mut entry, &mut output)?;
+            output.sync_all()?;
+        }
+        Ok(())
+    }
+
+    /// 把目录中的文件打包为 zip（归档内路径为相对目录的路径），返回打包的文件数
+    ///
+    /// `skip` 中的一级子目录不打包。先写入同目录的临时文件，完成后再替换目标文件。
+    pub fn pack_dir(dir: &Path, output_path: &Path, skip: &[&str]) -> Result<usize, ArchiveError> {
+        let temp_path = output_path.with_extension("part");
+        let result = Self::pack_dir_to(dir, &temp_path, skip).and_then(|count| {
+            fs::rename(&temp_path, output_path)?;
+            Ok(count)
+        });
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        result
+    }
+
+    fn pack_dir_to(dir: &Path, path: &Path, skip: &[&str]) -> Result<usize, ArchiveError> {
+        let file = File::create(path)?;
+        let mut zip = ZipWriter::new(file);
+        let options = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+        let mut count = 0;
+        let walker = walkdir::WalkDir::new(dir).into_iter().filter_entry(|e| {
+            !(e.depth() == 1 && e.file_type().is_dir() && skip.iter().any(|s| e.file_name() == *s))
+        });
+        for entry in walker {
+            let entry = entry.map_err(io::Error::from)?;
+            if !entry.file_type().is_file() || entry.path() == path {
+                continue;
+            }
+            let name = entry
+                .path()
+                .strip_prefix(dir)
+                .map_err(|_| ArchiveError::Invalid(entry.path().display().to_string()))?
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            Self::ensure_safe_name(&name)?;
+            zip.start_file(name.as_str(), options)?;
+            io::copy(&mut File::open(entry.path())?, &mut zip)?;
+            count += 1;
+        }
+
+        zip.finish()?.sync_all()?;
+        Ok(count)
+    }
+
+    /// 把 `pack_dir` 生成的 zip 解压到目录，返回解压的文件数
+    pub fn unpack_dir(archive_path: &Path, dir: &Path) -> Result<usize, ArchiveError> {
+        let archive = ZipArchive::new(File::open(archive_path)?)?;
+        let names: Vec<String> = archive.file_names().map(|name| name.to_string()).collect();
+        let entries: Vec<(String, PathBuf)> = names
+            .into_iter()
+            .filter(|name| !name.ends_with('/'))
+            .map(|name| {
+                let dest = dir.join(&name);
+                (name, dest)
+            })
+            .collect();
+        drop(archive);
+        Self::extract(archive_path, &entries)?;
+        Ok(entries.len())
+    }
+
+    /// 归档内路径只能由普通路径段组成（防止解压到归档目录之外）
+    fn ensure_safe_name(name: &str) -> Result<(), ArchiveError> {
+        if is_safe_relative_path(Path::new(name)) {
+            Ok(())
+        } else {
+            Err(ArchiveError::Invalid(format!("不安全的文件路径: {}", name)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ContentSnapshot, PROJECT_ARCHIVE_VERSION};
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_and_extract_archive() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_path = temp_dir.path().join("note.md");
+        fs::write(&data_path, "# 笔记").unwrap();
+
+        let manifest = ArchiveManifest {
+            version: PROJECT_ARCHIVE_VERSION,
+            exported_at: Utc::now(),
+            include_embeddings: false,
+            files: vec!["files/notes/a.md".to_string()],
+            snapshot: ContentSnapshot::default(),
+        };
+        let archive_path = temp_dir.path().join("project.zip");
+        ArchiveService::write(
+            &archive_path,
+            &manifest,
+            &[("files/notes/a.md".to_string(), data_path)],
+        )
+        .unwrap();
+        assert!(!archive_path.with_extension("part").exists());
+
+        let read = ArchiveService::read_manifest(&archive_path).unwrap();
+        assert_eq!(read.files, manifest.files);
+
+        let dest = temp_dir.path().join("out").join("a.md");
+        ArchiveService::extract(&archive_path, &[("files/notes/a.md".to_string(), dest.clone())])
+            .unwrap();
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "# 笔记");
+    }
+
+    #[test]
+    fn test_pack_and_unpack_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("sources");
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::create_dir_all(dir.join("thumbnails")).unwrap();
+        fs::write(dir.join("a.md"), "# 来源").unwrap();
+        fs::write(dir.join("nested").join("b.txt"), "正文").unwrap();
+        fs::write(dir.join("thumbnails").join("a_thumb.jpg"), "jpg").unwrap();
+
+        let packed = dir.join("sources.zip");
+        assert_eq!(ArchiveService::pack_dir(&dir, &packed, &["thumbnails"]).unwrap(), 2);
+        assert!(!packed.with_extension("part").exists());
+
+        let out = temp_dir.path().join("out");
+        assert_eq!(ArchiveService::unpack_dir(&packed, &out).unwrap(), 2);
+        assert_eq!(fs::read_to_string(out.join("a.md")).unwrap(), "# 来源");
+        assert_eq!(fs::read_to_string(out.join("nested").join("b.txt")).unwrap(), "正文");
+        assert!(!out.join("thumbnails").exists());
+    }
+
+    #[test]
+    fn test_rejects_unsafe_names() {
+        assert!(ArchiveService::ensure_safe_name("files/notes/a.md").is_ok());
+        assert!(ArchiveService::ensure_safe_name("../evil.md").is_err());
+        assert!(ArchiveService::ensure_safe_name("/etc/passwd").is_err());
+        assert!(ArchiveService::ensure_safe_name("").is_err());
+    }
+}
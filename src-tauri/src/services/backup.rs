@@ -0,0 +1,499 @@
+//! 备份服务
+//!
+//! 每份备份是备份目录下的一个子目录：数据库通过 SQLite 在线备份 API 复制为 desklab.db，
+//! 数据目录中的 projects/、.trash/ 与 templates/ 复制到 files/ 下。与上一份备份相比未变化的文件
+//! 以硬链接共享，因此每份备份都是完整快照，可以独立删除。
+
+use crate::db::{Database, DbError};
+use crate::models::{BackupFileEntry, BackupInfo, BackupManifest, BACKUP_FORMAT_VERSION};
+use crate::services::is_safe_relative_path;
+use chrono::{DateTime, Utc};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use walkdir::WalkDir;
+
+/// 备份清单文件名
+pub const BACKUP_MANIFEST_NAME: &str = "backup.json";
+
+/// 备份中的数据库文件名
+const BACKUP_DB_NAME: &str = "desklab.db";
+
+/// 备份中存放数据文件的目录
+const BACKUP_FILES_DIR: &str = "files";
+
+/// 备份目录名前缀
+const BACKUP_DIR_PREFIX: &str = "desklab-backup-";
+
+/// 写入中的备份目录后缀（完成后重命名）
+const BACKUP_PART_SUFFIX: &str = ".part";
+
+/// 需要备份的数据目录子目录（数据库单独备份）
+const BACKUP_DATA_DIRS: [&str; 3] = ["projects", ".trash", "templates"];
+
+/// 备份错误类型
+#[derive(Error, Debug)]
+pub enum BackupError {
+    #[error("IO 错误: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("数据库错误: {0}")]
+    Database(#[from] DbError),
+    #[error("备份清单格式错误: {0}")]
+    Manifest(#[from] serde_json::Error),
+    #[error("无效的备份: {0}")]
+    Invalid(String),
+    #[error("备份完整性检查失败: {0}")]
+    Integrity(String),
+}
+
+/// 备份服务
+pub struct BackupService;
+
+impl BackupService {
+    /// 创建备份
+    ///
+    /// 先写入 `.part` 目录，全部完成后再重命名，未完成的备份不会被当作有效备份。
+    pub fn create(
+        db: &Database,
+        data_dir: &Path,
+        backup_root: &Path,
+    ) -> Result<BackupInfo, BackupError> {
+        fs::create_dir_all(backup_root)?;
+        Self::remove_partial(backup_root)?;
+
+        let created_at = Utc::now();
+        let name = format!("{}{}", BACKUP_DIR_PREFIX, created_at.format("%Y%m%d-%H%M%S-%3f"));
+        let final_path = backup_root.join(&name);
+        let part_path = backup_root.join(format!("{}{}", name, BACKUP_PART_SUFFIX));
+        let previous = Self::generations(backup_root)?.into_iter().next();
+
+        let result = Self::write_generation(db, data_dir, &part_path, previous.as_ref(), created_at)
+            .and_then(|manifest| {
+                fs::rename(&part_path, &final_path)?;
+                Ok(manifest)
+            });
+        match result {
+            Ok(manifest) => Ok(Self::info(&final_path, &manifest)),
+            Err(e) => {
+                let _ = fs::remove_dir_all(&part_path);
+                Err(e)
+            }
+        }
+    }
+
+    /// 获取备份目录中的全部备份（最新的在前）
+    pub fn list(backup_root: &Path) -> Result<Vec<BackupInfo>, BackupError> {
+        Ok(Self::generations(backup_root)?
+            .iter()
+            .map(|(path, manifest)| Self::info(path, manifest))
+            .collect())
+    }
+
+    /// 只保留最新的 `keep` 份备份，返回删除的份数
+    pub fn rotate(backup_root: &Path, keep: usize) -> Result<usize, BackupError> {
+        let generations = Self::generations(backup_root)?;
+        let mut removed = 0;
+        for (path, _) in generations.iter().skip(keep.max(1)) {
+            fs::remove_dir_all(path)?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    /// 校验备份：数据库完整性检查，并确认清单中的数据文件齐全
+    pub fn verify(backup_path: &Path) -> Result<BackupManifest, BackupError> {
+        let manifest = Self::read_manifest(backup_path)?;
+        if manifest.version > BACKUP_FORMAT_VERSION {
+            return Err(BackupError::Invalid(format!(
+                "不支持的备份版本: {}",
+                manifest.version
+            )));
+        }
+
+        let db_path = backup_path.join(BACKUP_DB_NAME);
+        if !db_path.is_file() {
+            return Err(BackupError::Invalid(format!("缺少 {}", BACKUP_DB_NAME)));
+        }
+        let problems = Database::check_integrity(&db_path)
+            .map_err(|e| BackupError::Integrity(e.to_string()))?;
+        if !problems.is_empty() {
+            return Err(BackupError::Integrity(problems.join("; ")));
+        }
+
+        let files_dir = backup_path.join(BACKUP_FILES_DIR);
+        for file in &manifest.files {
+            // 清单中的路径只能位于备份的数据目录下，防止读写备份与数据目录之外的文件
+            let path = Path::new(&file.path);
+            let in_data_dirs = path
+                .components()
+                .next()
+                .is_some_and(|first| BACKUP_DATA_DIRS.iter().any(|dir| first.as_os_str() == *dir));
+            if !is_safe_relative_path(path) || !in_data_dirs {
+                return Err(BackupError::Invalid(format!("不安全的文件路径: {}", file.path)));
+            }
+            let intact = fs::metadata(files_dir.join(&file.path))
+                .map(|metadata| metadata.is_file() && metadata.len() == file.size)
+                .unwrap_or(false);
+            if !intact {
+                return Err(BackupError::Integrity(format!("文件缺失或已损坏: {}", file.path)));
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// 从备份恢复数据库与数据文件
+    ///
+    /// 先校验备份并把数据文件复制到数据目录下的临时目录，再替换现有目录并恢复数据库；
+    /// 数据库恢复失败时换回原有目录。未能完全换回时保留临时目录（其中的 old/ 为原有数据）。
+    pub fn restore(
+        db: &Database,
+        data_dir: &Path,
+        backup_path: &Path,
+    ) -> Result<BackupInfo, BackupError> {
+        let manifest = Self::verify(backup_path)?;
+
+        let staging = data_dir.join(format!(".restore-{}", uuid::Uuid::new_v4()));
+        let mut rolled_back = true;
+        let result = Self::restore_with_staging(
+            db,
+            data_dir,
+            backup_path,
+            &manifest,
+            &staging,
+            &mut rolled_back,
+        );
+        if rolled_back {
+            let _ = fs::remove_dir_all(&staging);
+        } else {
+            eprintln!("[backup] 恢复失败且未能完全换回原有数据，原有数据保留在 {}", staging.display());
+        }
+        result.map(|_| Self::info(backup_path, &manifest))
+    }
+
+    fn restore_with_staging(
+        db: &Database,
+        data_dir: &Path,
+        backup_path: &Path,
+        manifest: &BackupManifest,
+        staging: &Path,
+        rolled_back: &mut bool,
+    ) -> Result<(), BackupError> {
+        let incoming = staging.join("new");
+        let previous = staging.join("old");
+        let files_dir = backup_path.join(BACKUP_FILES_DIR);
+        for file in &manifest.files {
+            let dest = incoming.join(&file.path);
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(files_dir.join(&file.path), &dest)?;
+        }
+
+        fs::create_dir_all(&previous)?;
+        let mut swapped = Vec::new();
+        let swap_result = BACKUP_DATA_DIRS.iter().try_for_each(|dir| {
+            let current = data_dir.join(dir);
+            if current.exists() {
+                fs::rename(&current, previous.join(dir))?;
+            }
+            swapped.push(*dir);
+            let restored = incoming.join(dir);
+            if restored.exists() {
+                fs::rename(&restored, &current)?;
+            } else {
+                fs::create_dir_all(&current)?;
+            }
+            Ok::<_, io::Error>(())
+        });
+
+        let result = swap_result
+            .map_err(BackupError::from)
+            .and_then(|_| Ok(db.restore_from(&backup_path.join(BACKUP_DB_NAME))?));
+        if result.is_err() {
+            for dir in swapped {
+                let current = data_dir.join(dir);
+                if current.exists() && fs::remove_dir_all(&current).is_err() {
+                    *rolled_back = false;
+                    continue;
+                }
+                if previous.join(dir).exists() && fs::rename(previous.join(dir), &current).is_err() {
+                    *rolled_back = false;
+                }
+            }
+        }
+        result.map(|_| ())
+    }
+
+    fn write_generation(
+        db: &Database,
+        data_dir: &Path,
+        part_path: &Path,
+        previous: Option<&(PathBuf, BackupManifest)>,
+        created_at: DateTime<Utc>,
+    ) -> Result<BackupManifest, BackupError> {
+        let files_dir = part_path.join(BACKUP_FILES_DIR);
+        fs::create_dir_all(&files_dir)?;
+        db.backup_to(&part_path.join(BACKUP_DB_NAME))?;
+
+        let previous_files: HashMap<&str, &BackupFileEntry> = previous
+            .map(|(_, manifest)| {
+                manifest
+                    .files
+                    .iter()
+                    .map(|file| (file.path.as_str(), file))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut files = Vec::new();
+        let mut copied_count = 0;
+        for dir in BACKUP_DATA_DIRS {
+            let root = data_dir.join(dir);
+            if !root.exists() {
+                continue;
+            }
+            for entry in WalkDir::new(&root) {
+                let entry = entry.map_err(io::Error::from)?;
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+                let relative = entry
+                    .path()
+                    .strip_prefix(data_dir)
+                    .map_err(|e| BackupError::Invalid(e.to_string()))?;
+                let metadata = entry.metadata().map_err(io::Error::from)?;
+                let file = BackupFileEntry {
+                    path: relative
+                        .components()
+                        .map(|c| c.as_os_str().to_string_lossy().to_string())
+                        .collect::<Vec<_>>()
+                        .join("/"),
+                    size: metadata.len(),
+                    modified: metadata
+                        .modified()
+                        .map(|time| DateTime::<Utc>::from(time).timestamp_millis())
+                        .unwrap_or(0),
+                };
+
+                let dest = files_dir.join(relative);
+                if let Some(parent) = dest.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                // 未变化的文件与上一份备份共享（硬链接），失败时回退为复制
+                let unchanged = previous
+                    .zip(previous_files.get(file.path.as_str()))
+                    .filter(|(_, old)| old.size == file.size && old.modified == file.modified)
+                    .map(|((path, _), _)| path.join(BACKUP_FILES_DIR).join(relative));
+                let linked = unchanged.is_some_and(|source| fs::hard_link(source, &dest).is_ok());
+                if !linked {
+                    fs::copy(entry.path(), &dest)?;
+                    copied_count += 1;
+                }
+                files.push(file);
+            }
+        }
+
+        let manifest = BackupManifest {
+            version: BACKUP_FORMAT_VERSION,
+            created_at,
+            files,
+            copied_count,
+        };
+        fs::write(
+            part_path.join(BACKUP_MANIFEST_NAME),
+            serde_json::to_string_pretty(&manifest)?,
+        )?;
+        Ok(manifest)
+    }
+
+    /// 备份目录中的有效备份（最新的在前）
+    fn generations(backup_root: &Path) -> Result<Vec<(PathBuf, BackupManifest)>, BackupError> {
+        if !backup_root.exists() {
+            return Ok(Vec::new());
+        }
+        let mut generations = Vec::new();
+        for entry in fs::read_dir(backup_root)? {
+            let path = entry?.path();
+            let is_backup = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.starts_with(BACKUP_DIR_PREFIX) && !name.ends_with(BACKUP_PART_SUFFIX)
+                });
+            if !is_backup || !path.is_dir() {
+                continue;
+            }
+            if let Ok(manifest) = Self::read_manifest(&path) {
+                generations.push((path, manifest));
+            }
+        }
+        generations.sort_by_key(|(_, manifest)| Reverse(manifest.created_at));
+        Ok(generations)
+    }
+
+    /// 清理上次中断留下的未完成备份
+    fn remove_partial(backup_root: &Path) -> Result<(), BackupError> {
+        for entry in fs::read_dir(backup_root)? {
+            let path = entry?.path();
+            let partial = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.starts_with(BACKUP_DIR_PREFIX) && name.ends_with(BACKUP_PART_SUFFIX)
+                });
+            if partial && path.is_dir() {
+                fs::remove_dir_all(&path)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn read_manifest(backup_path: &Path) -> Result<BackupManifest, BackupError> {
+        let manifest_path = backup_path.join(BACKUP_MANIFEST_NAME);
+        if !manifest_path.is_file() {
+            return Err(BackupError::Invalid(format!("缺少 {}", BACKUP_MANIFEST_NAME)));
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(manifest_path)?)?)
+    }
+
+    fn info(path: &Path, manifest: &BackupManifest) -> BackupInfo {
+        BackupInfo {
+            path: path.display().to_string(),
+            created_at: manifest.created_at,
+            files_count: manifest.files.len(),
+            total_size: manifest.files.iter().map(|file| file.size).sum(),
+            copied_count: manifest.copied_count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, Database, PathBuf, PathBuf) {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().join("data");
+        fs::create_dir_all(data_dir.join("projects").join("p1")).unwrap();
+        fs::write(data_dir.join("projects/p1/a.md"), "# A").unwrap();
+        fs::write(data_dir.join("projects/p1/b.md"), "# B").unwrap();
+        let db = Database::new_in_memory().unwrap();
+        db.set_setting("marker", "original").unwrap();
+        let backup_root = temp_dir.path().join("backups");
+        (temp_dir, db, data_dir, backup_root)
+    }
+
+    #[test]
+    fn test_incremental_backup_and_rotation() {
+        let (_temp_dir, db, data_dir, backup_root) = setup();
+
+        let first = BackupService::create(&db, &data_dir, &backup_root).unwrap();
+        assert_eq!(first.files_count, 2);
+        assert_eq!(first.copied_count, 2);
+
+        // 只有变化的文件会被复制
+        fs::write(data_dir.join("projects/p1/b.md"), "# B 已修改").unwrap();
+        let second = BackupService::create(&db, &data_dir, &backup_root).unwrap();
+        assert_eq!(second.files_count, 2);
+        assert_eq!(second.copied_count, 1);
+        assert_eq!(
+            fs::read_to_string(Path::new(&second.path).join("files/projects/p1/b.md")).unwrap(),
+            "# B 已修改"
+        );
+        BackupService::verify(Path::new(&second.path)).unwrap();
+
+        BackupService::create(&db, &data_dir, &backup_root).unwrap();
+        assert_eq!(BackupService::list(&backup_root).unwrap().len(), 3);
+        assert_eq!(BackupService::rotate(&backup_root, 2).unwrap(), 1);
+        let remaining = BackupService::list(&backup_root).unwrap();
+        assert_eq!(remaining.len(), 2);
+        assert!(remaining.iter().all(|info| info.path != first.path));
+        // 删除旧备份不影响共享文件的新备份
+        BackupService::verify(Path::new(&remaining[1].path)).unwrap();
+    }
+
+    #[test]
+    fn test_restore_backup() {
+        let (_temp_dir, db, data_dir, backup_root) = setup();
+        let backup = BackupService::create(&db, &data_dir, &backup_root).unwrap();
+
+        db.set_setting("marker", "changed").unwrap();
+        fs::write(data_dir.join("projects/p1/a.md"), "# 已修改").unwrap();
+        fs::write(data_dir.join("projects/p1/new.md"), "# 新文件").unwrap();
+
+        BackupService::restore(&db, &data_dir, Path::new(&backup.path)).unwrap();
+        assert_eq!(db.get_setting("marker").unwrap().as_deref(), Some("original"));
+        assert_eq!(fs::read_to_string(data_dir.join("projects/p1/a.md")).unwrap(), "# A");
+        assert!(!data_dir.join("projects/p1/new.md").exists());
+        // 临时目录已清理
+        let leftovers: Vec<_> = fs::read_dir(&data_dir)
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(".restore-"))
+            .collect();
+        assert!(leftovers.is_empty());
+    }
+
+    #[test]
+    fn test_restore_rejects_unsafe_paths() {
+        let (temp_dir, db, data_dir, backup_root) = setup();
+        let backup = BackupService::create(&db, &data_dir, &backup_root).unwrap();
+        let backup_path = PathBuf::from(&backup.path);
+        let outside = temp_dir.path().join("outside.md");
+        fs::write(&outside, "# A").unwrap();
+
+        let original = BackupService::read_manifest(&backup_path).unwrap();
+        for path in [
+            "../../outside.md".to_string(),
+            outside.display().to_string(),
+            "db/desklab.db".to_string(),
+        ] {
+            let mut manifest = original.clone();
+            manifest.files[0].path = path;
+            manifest.files[0].size = 3;
+            fs::write(
+                backup_path.join(BACKUP_MANIFEST_NAME),
+                serde_json::to_string(&manifest).unwrap(),
+            )
+            .unwrap();
+            assert!(matches!(
+                BackupService::restore(&db, &data_dir, &backup_path),
+                Err(BackupError::Invalid(_))
+            ));
+        }
+        assert_eq!(fs::read_to_string(&outside).unwrap(), "# A");
+        assert_eq!(fs::read_to_string(data_dir.join("projects/p1/a.md")).unwrap(), "# A");
+    }
+
+    #[test]
+    fn test_restore_rejects_damaged_backup() {
+        let (_temp_dir, db, data_dir, backup_root) = setup();
+        let backup = BackupService::create(&db, &data_dir, &backup_root).unwrap();
+        let backup_path = PathBuf::from(&backup.path);
+
+        // 缺失数据文件
+        fs::remove_file(backup_path.join("files/projects/p1/a.md")).unwrap();
+        assert!(matches!(
+            BackupService::restore(&db, &data_dir, &backup_path),
+            Err(BackupError::Integrity(_))
+        ));
+
+        // 数据库文件损坏
+        let backup = BackupService::create(&db, &data_dir, &backup_root).unwrap();
+        let backup_path = PathBuf::from(&backup.path);
+        fs::write(backup_path.join(BACKUP_DB_NAME), "not a database").unwrap();
+        assert!(matches!(
+            BackupService::restore(&db, &data_dir, &backup_path),
+            Err(BackupError::Integrity(_))
+        ));
+
+        // 恢复失败时现有数据保持不变
+        db.set_setting("marker", "current").unwrap();
+        assert_eq!(db.get_setting("marker").unwrap().as_deref(), Some("current"));
+        assert_eq!(fs::read_to_string(data_dir.join("projects/p1/a.md")).unwrap(), "# A");
+    }
+}
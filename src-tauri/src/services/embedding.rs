@@ -6,6 +6,8 @@ use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 
 pub const EMBEDDING_DIM: usize = 384;
+/// 向量化方法标识，修改向量化算法或维度时需要更新（已有向量会在后台重新生成）
+pub const EMBEDDING_MODEL: &str = "hashed-bow-384";
 const MAX_TEXT_CHARS: usize = 20_000;
 
 pub fn embed_text(text: &str) -> Vec<f32> {
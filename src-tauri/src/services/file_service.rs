@@ -3,7 +3,9 @@
 //! 提供项目文件夹的创建、删除等操作
 
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::RwLock;
 use thiserror::Error;
 
 /// 文件服务错误类型
@@ -19,23 +21,37 @@ pub enum FileError {
 
 /// 文件服务
 pub struct FileService {
-    base_path: PathBuf,
+    /// 数据目录（切换资料库时替换）
+    base_path: RwLock<PathBuf>,
 }
 
 impl FileService {
     /// 创建新的文件服务
     pub fn new(base_path: PathBuf) -> Self {
-        Self { base_path }
+        Self {
+            base_path: RwLock::new(base_path),
+        }
     }
 
     /// 获取基础路径
-    pub fn base_path(&self) -> &Path {
-        &self.base_path
+    pub fn base_path(&self) -> PathBuf {
+        self.base_path
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// 切换基础路径（切换资料库时使用）
+    pub fn set_base_path(&self, base_path: PathBuf) {
+        *self
+            .base_path
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = base_path;
     }
 
     /// 获取项目根目录
     pub fn projects_dir(&self) -> PathBuf {
-        self.base_path.join("projects")
+        self.base_path().join("projects")
     }
 
     /// 获取项目目录
@@ -43,6 +59,11 @@ impl FileService {
         self.projects_dir().join(project_id)
     }
 
+    /// 获取项目模板目录
+    pub fn templates_dir(&self) -> PathBuf {
+        self.base_path().join("templates")
+    }
+
     /// 初始化基础目录结构
     pub fn init_base_dirs(&self) -> Result<(), FileError> {
         let projects_dir = self.projects_dir();
@@ -93,11 +114,34 @@ impl FileService {
         self.project_dir(project_id).exists()
     }
 
+    /// 项目目录占用的磁盘空间（字节，目录不存在时为 0）
+    pub fn project_size(&self, project_id: &str) -> Result<u64, FileError> {
+        let mut size = 0;
+        for entry in walkdir::WalkDir::new(self.project_dir(project_id)) {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) if e.io_error().map(|io| io.kind()) == Some(std::io::ErrorKind::NotFound) => {
+                    continue
+                }
+                Err(e) => return Err(FileError::Io(e.into())),
+            };
+            if entry.file_type().is_file() {
+                size += entry.metadata().map_err(std::io::Error::from)?.len();
+            }
+        }
+        Ok(size)
+    }
+
     /// 获取项目来源目录
     pub fn get_sources_dir(&self, project_id: &str) -> PathBuf {
         self.project_dir(project_id).join("sources")
     }
 
+    /// 归档项目压缩后的来源文件包
+    pub fn archived_sources_path(&self, project_id: &str) -> PathBuf {
+        self.project_dir(project_id).join("sources.zip")
+    }
+
     /// 获取项目缩略图目录
     pub fn get_thumbnails_dir(&self, project_id: &str) -> PathBuf {
         self.get_sources_dir(project_id).join("thumbnails")
@@ -107,6 +151,141 @@ impl FileService {
     pub fn get_notes_dir(&self, project_id: &str) -> PathBuf {
         self.project_dir(project_id).join("notes")
     }
+
+    /// 获取项目回收站目录
+    pub fn project_trash_dir(&self, project_id: &str) -> PathBuf {
+        self.project_dir(project_id).join(".trash")
+    }
+
+    /// 获取库级回收站目录（存放被整体删除的项目）
+    pub fn library_trash_dir(&self) -> PathBuf {
+        self.base_path().join(".trash")
+    }
+
+    /// 移动文件或目录
+    ///
+    /// 优先使用 rename；跨文件系统时回退为复制后删除
+    pub fn move_path(&self, from: &Path, to: &Path) -> Result<(), FileError> {
+        if !from.exists() {
+            return Err(FileError::PathNotFound(from.display().to_string()));
+        }
+        if to.exists() {
+            return Err(FileError::PathExists(to.display().to_string()));
+        }
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        if fs::rename(from, to).is_ok() {
+            return Ok(());
+        }
+
+        if from.is_dir() {
+            copy_dir_recursive(from, to)?;
+            fs::remove_dir_all(from)?;
+        } else {
+            fs::copy(from, to)?;
+            fs::remove_file(from)?;
+        }
+        Ok(())
+    }
+
+    /// 原子写入文件
+    ///
+    /// 先写入同目录下的临时文件并 fsync，再通过 rename 替换目标文件，
+    /// 崩溃时目标文件要么是旧内容，要么是完整的新内容。
+    pub fn write_atomic(&self, path: &Path, contents: &[u8]) -> Result<(), FileError> {
+        self.stage_write(path, contents)?.commit()
+    }
+
+    /// 暂存写入：内容已落盘到临时文件，调用 [`StagedFile::commit`] 后才替换目标文件
+    ///
+    /// 用于与数据库事务配合：先暂存文件，数据库更新成功后再提交文件，
+    /// 提交失败则回滚事务；未提交的暂存文件在 drop 时自动清理。
+    pub fn stage_write(&self, path: &Path, contents: &[u8]) -> Result<StagedFile, FileError> {
+        let parent = path
+            .parent()
+            .ok_or_else(|| FileError::PathNotFound(path.display().to_string()))?;
+        fs::create_dir_all(parent)?;
+
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let temp_path = parent.join(format!(".{}.{}.tmp", file_name, uuid::Uuid::new_v4()));
+
+        let staged = StagedFile {
+            temp_path,
+            target_path: path.to_path_buf(),
+            committed: false,
+        };
+
+        let mut file = fs::File::create(&staged.temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+
+        Ok(staged)
+    }
+}
+
+/// 已写入临时文件、等待提交的文件
+pub struct StagedFile {
+    temp_path: PathBuf,
+    target_path: PathBuf,
+    committed: bool,
+}
+
+impl StagedFile {
+    /// 目标文件路径
+    pub fn target_path(&self) -> &Path {
+        &self.target_path
+    }
+
+    /// 提交：将临时文件 rename 到目标路径，并同步父目录
+    pub fn commit(mut self) -> Result<(), FileError> {
+        fs::rename(&self.temp_path, &self.target_path)?;
+        self.committed = true;
+        sync_parent_dir(&self.target_path);
+        Ok(())
+    }
+}
+
+impl Drop for StagedFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+/// 递归复制目录
+fn copy_dir_recursive(from: &Path, to: &Path) -> Result<(), FileError> {
+    for entry in walkdir::WalkDir::new(from) {
+        let entry = entry.map_err(|e| FileError::Io(e.into()))?;
+        let relative = entry
+            .path()
+            .strip_prefix(from)
+            .map_err(|_| FileError::PathNotFound(entry.path().display().to_string()))?;
+        let target = to.join(relative);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// 同步父目录，确保 rename 已持久化（仅 Unix 支持对目录 fsync）
+fn sync_parent_dir(path: &Path) {
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        if let Ok(dir) = fs::File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
 }
 
 /// 获取默认的 DeskLab 数据目录
@@ -120,7 +299,20 @@ pub fn get_default_data_dir() -> Option<PathBuf> {
 
 /// 获取默认的数据库路径
 pub fn get_default_db_path() -> Option<PathBuf> {
-    get_default_data_dir().map(|p| p.join("db").join("desklab.db"))
+    get_default_data_dir().map(|p| db_path_for(&p))
+}
+
+/// 获取数据目录对应的数据库路径
+pub fn db_path_for(data_dir: &Path) -> PathBuf {
+    data_dir.join("db").join("desklab.db")
+}
+
+/// 路径非空且只由普通路径段组成（不含根目录、盘符、`.` 与 `..`），拼接到目录后不会越出该目录
+pub fn is_safe_relative_path(path: &Path) -> bool {
+    path.components().next().is_some()
+        && path
+            .components()
+            .all(|component| matches!(component, std::path::Component::Normal(_)))
 }
 
 #[cfg(test)]
@@ -195,6 +387,63 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_write_atomic() {
+        let (service, _temp) = create_test_service();
+        let path = service.projects_dir().join("p1").join("notes").join("note.md");
+
+        service.write_atomic(&path, b"first").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+
+        service.write_atomic(&path, b"second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+
+        // 不应残留临时文件
+        let entries: Vec<_> = fs::read_dir(path.parent().unwrap()).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn test_staged_write_dropped_without_commit() {
+        let (service, _temp) = create_test_service();
+        let path = service.projects_dir().join("canvas.json");
+        service.write_atomic(&path, b"{\"old\":true}").unwrap();
+
+        let staged = service.stage_write(&path, b"{\"new\":").unwrap();
+        assert_eq!(staged.target_path(), path.as_path());
+        drop(staged);
+
+        // 未提交时目标文件保持旧内容，临时文件被清理
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"old\":true}");
+        let entries: Vec<_> = fs::read_dir(service.projects_dir()).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn test_move_path() {
+        let (service, _temp) = create_test_service();
+        service.init_base_dirs().unwrap();
+        service.create_project_dir("p1").unwrap();
+
+        let note = service.get_notes_dir("p1").join("n1.md");
+        fs::write(&note, "内容").unwrap();
+
+        let trashed = service.project_trash_dir("p1").join("t1").join("n1.md");
+        service.move_path(&note, &trashed).unwrap();
+        assert!(!note.exists());
+        assert_eq!(fs::read_to_string(&trashed).unwrap(), "内容");
+
+        // 目标已存在时拒绝覆盖
+        fs::write(&note, "新内容").unwrap();
+        assert!(service.move_path(&note, &trashed).is_err());
+
+        // 整个项目目录移入库级回收站
+        let project_trash = service.library_trash_dir().join("t2").join("project");
+        service.move_path(&service.project_dir("p1"), &project_trash).unwrap();
+        assert!(!service.project_exists("p1"));
+        assert!(project_trash.join(".trash").join("t1").join("n1.md").exists());
+    }
+
     #[test]
     fn test_project_exists() {
         let (service, _temp) = create_test_service();
@@ -0,0 +1,335 @@
+//! 资料库注册表
+//!
+//! 在应用配置目录的 libraries.json 中记录全部资料库与上次打开的资料库。
+//! 每个资料库拥有独立的数据目录与数据库，注册表本身不属于任何资料库。
+
+use crate::models::Library;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use thiserror::Error;
+
+/// 默认资料库 ID（对应默认数据目录）
+pub const DEFAULT_LIBRARY_ID: &str = "default";
+
+/// 注册表文件名
+const LIBRARY_REGISTRY_FILE: &str = "libraries.json";
+
+/// 资料库错误类型
+#[derive(Error, Debug)]
+pub enum LibraryError {
+    #[error("IO 错误: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("资料库配置格式错误: {0}")]
+    Format(#[from] serde_json::Error),
+    #[error("资料库不存在: {0}")]
+    NotFound(String),
+    #[error("资料库名称已存在: {0}")]
+    NameExists(String),
+    #[error("数据目录已被资料库 {0} 使用")]
+    DataDirInUse(String),
+    #[error("验证错误: {0}")]
+    Validation(String),
+}
+
+/// 注册表文件内容
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LibraryConfig {
+    libraries: Vec<Library>,
+    last_opened: Option<String>,
+}
+
+/// 资料库注册表
+pub struct LibraryRegistry {
+    config_path: PathBuf,
+    default_data_dir: PathBuf,
+    config: Mutex<LibraryConfig>,
+    /// 当前打开的资料库（启动时回退到默认资料库的情况下与上次打开的不同）
+    current: Mutex<Option<String>>,
+}
+
+impl LibraryRegistry {
+    /// 加载注册表
+    ///
+    /// `config_dir` 为存放 libraries.json 的目录；注册表中始终包含指向
+    /// `default_data_dir` 的默认资料库。配置文件格式错误时将其改名保留，
+    /// 并以只含默认资料库的注册表启动。
+    pub fn load(config_dir: &Path, default_data_dir: PathBuf) -> Result<Self, LibraryError> {
+        let config_path = config_dir.join(LIBRARY_REGISTRY_FILE);
+        let mut config: LibraryConfig = if config_path.exists() {
+            match serde_json::from_str(&fs::read_to_string(&config_path)?) {
+                Ok(config) => config,
+                Err(e) => {
+                    let bad_path = config_path.with_extension(format!(
+                        "json.{}.bad",
+                        Utc::now().format("%Y%m%d%H%M%S")
+                    ));
+                    fs::rename(&config_path, &bad_path)?;
+                    eprintln!(
+                        "[library] 资料库配置格式错误: {}，已另存为 {} 并使用默认资料库",
+                        e,
+                        bad_path.display()
+                    );
+                    LibraryConfig::default()
+                }
+            }
+        } else {
+            LibraryConfig::default()
+        };
+
+        if !config.libraries.iter().any(|l| l.id == DEFAULT_LIBRARY_ID) {
+            config.libraries.insert(
+                0,
+                Library {
+                    id: DEFAULT_LIBRARY_ID.to_string(),
+                    name: "默认资料库".to_string(),
+                    data_dir: default_data_dir.display().to_string(),
+                    created_at: Utc::now(),
+                    last_opened_at: None,
+                },
+            );
+        }
+
+        Ok(Self {
+            config_path,
+            default_data_dir,
+            config: Mutex::new(config),
+            current: Mutex::new(None),
+        })
+    }
+
+    /// 获取全部资料库
+    pub fn list(&self) -> Vec<Library> {
+        self.lock().libraries.clone()
+    }
+
+    /// 获取资料库
+    pub fn get(&self, id: &str) -> Result<Library, LibraryError> {
+        self.lock()
+            .libraries
+            .iter()
+            .find(|l| l.id == id)
+            .cloned()
+            .ok_or_else(|| LibraryError::NotFound(id.to_string()))
+    }
+
+    /// 按 ID 或名称查找资料库（用于启动参数）
+    pub fn find(&self, id_or_name: &str) -> Result<Library, LibraryError> {
+        self.lock()
+            .libraries
+            .iter()
+            .find(|l| l.id == id_or_name || l.name == id_or_name)
+            .cloned()
+            .ok_or_else(|| LibraryError::NotFound(id_or_name.to_string()))
+    }
+
+    /// 上次打开的资料库，未记录或已不存在时返回默认资料库
+    pub fn last_opened(&self) -> Library {
+        let config = self.lock();
+        let last = config.last_opened.as_deref().unwrap_or(DEFAULT_LIBRARY_ID);
+        config
+            .libraries
+            .iter()
+            .find(|l| l.id == last)
+            .or_else(|| config.libraries.iter().find(|l| l.id == DEFAULT_LIBRARY_ID))
+            .cloned()
+            .expect("注册表中始终包含默认资料库")
+    }
+
+    /// 当前打开的资料库
+    pub fn current(&self) -> Library {
+        let current = self
+            .current
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone();
+        current
+            .and_then(|id| self.get(&id).ok())
+            .unwrap_or_else(|| self.last_opened())
+    }
+
+    /// 设置当前资料库（不记录为上次打开的资料库）
+    pub fn set_current(&self, id: &str) -> Result<Library, LibraryError> {
+        let library = self.get(id)?;
+        *self
+            .current
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(id.to_string());
+        Ok(library)
+    }
+
+    /// 注册新资料库（不会切换到该资料库）
+    ///
+    /// 数据目录可以是已有资料库的数据目录（例如从另一台机器复制而来），
+    /// 但不能与注册表中的其他资料库重复。
+    pub fn create(&self, name: &str, data_dir: Option<&str>) -> Result<Library, LibraryError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(LibraryError::Validation("资料库名称不能为空".to_string()));
+        }
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let data_dir = match data_dir.map(str::trim).filter(|d| !d.is_empty()) {
+            Some(dir) if Path::new(dir).is_absolute() => PathBuf::from(dir),
+            Some(dir) => {
+                return Err(LibraryError::Validation(format!("数据目录必须是绝对路径: {}", dir)))
+            }
+            None => self.default_data_dir.join("libraries").join(&id),
+        };
+
+        let mut config = self.lock();
+        if config.libraries.iter().any(|l| l.name == name) {
+            return Err(LibraryError::NameExists(name.to_string()));
+        }
+        if let Some(existing) = config
+            .libraries
+            .iter()
+            .find(|l| Path::new(&l.data_dir) == data_dir)
+        {
+            return Err(LibraryError::DataDirInUse(existing.name.clone()));
+        }
+
+        fs::create_dir_all(&data_dir)?;
+        let library = Library {
+            id,
+            name: name.to_string(),
+            data_dir: data_dir.display().to_string(),
+            created_at: Utc::now(),
+            last_opened_at: None,
+        };
+        config.libraries.push(library.clone());
+        self.save(&config)?;
+        Ok(library)
+    }
+
+    /// 记录资料库已打开（下次启动时默认打开）
+    pub fn mark_opened(&self, id: &str) -> Result<Library, LibraryError> {
+        let mut config = self.lock();
+        let library = config
+            .libraries
+            .iter_mut()
+            .find(|l| l.id == id)
+            .ok_or_else(|| LibraryError::NotFound(id.to_string()))?;
+        library.last_opened_at = Some(Utc::now());
+        config.last_opened = Some(id.to_string());
+        self.save(&config)?;
+        drop(config);
+        self.set_current(id)
+    }
+
+    /// 写入注册表文件（先写临时文件再替换）
+    fn save(&self, config: &LibraryConfig) -> Result<(), LibraryError> {
+        if let Some(parent) = self.config_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temp_path = self.config_path.with_extension("json.tmp");
+        fs::write(&temp_path, serde_json::to_string_pretty(config)?)?;
+        fs::rename(&temp_path, &self.config_path)?;
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LibraryConfig> {
+        self.config.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// 获取默认的应用配置目录（存放资料库注册表）
+pub fn get_default_config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|p| p.join("DeskLab"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_default_library_and_create() {
+        let temp_dir = TempDir::new().unwrap();
+        let default_dir = temp_dir.path().join("DeskLab");
+        let registry = LibraryRegistry::load(temp_dir.path(), default_dir.clone()).unwrap();
+
+        let libraries = registry.list();
+        assert_eq!(libraries.len(), 1);
+        assert_eq!(libraries[0].id, DEFAULT_LIBRARY_ID);
+        assert_eq!(registry.last_opened().id, DEFAULT_LIBRARY_ID);
+
+        // 未指定数据目录时放在默认数据目录下
+        let work = registry.create("工作", None).unwrap();
+        assert!(Path::new(&work.data_dir).starts_with(default_dir.join("libraries")));
+        assert!(Path::new(&work.data_dir).is_dir());
+
+        let secure_dir = temp_dir.path().join("secure");
+        let secure = registry
+            .create("加密", Some(secure_dir.to_str().unwrap()))
+            .unwrap();
+        assert_eq!(Path::new(&secure.data_dir), secure_dir);
+
+        assert!(matches!(
+            registry.create("工作", None),
+            Err(LibraryError::NameExists(_))
+        ));
+        assert!(matches!(
+            registry.create("重复目录", Some(secure_dir.to_str().unwrap())),
+            Err(LibraryError::DataDirInUse(_))
+        ));
+        assert!(matches!(
+            registry.create("相对路径", Some("relative/dir")),
+            Err(LibraryError::Validation(_))
+        ));
+        assert!(matches!(registry.create("  ", None), Err(LibraryError::Validation(_))));
+        assert_eq!(registry.find("加密").unwrap().id, secure.id);
+    }
+
+    #[test]
+    fn test_last_opened_persists() {
+        let temp_dir = TempDir::new().unwrap();
+        let default_dir = temp_dir.path().join("DeskLab");
+        let registry = LibraryRegistry::load(temp_dir.path(), default_dir.clone()).unwrap();
+        let work = registry.create("工作", None).unwrap();
+        let opened = registry.mark_opened(&work.id).unwrap();
+        assert!(opened.last_opened_at.is_some());
+
+        let reloaded = LibraryRegistry::load(temp_dir.path(), default_dir).unwrap();
+        assert_eq!(reloaded.list().len(), 2);
+        assert_eq!(reloaded.last_opened().id, work.id);
+        assert_eq!(reloaded.current().id, work.id);
+
+        // 仅设置当前资料库时不改变上次打开的记录
+        reloaded.set_current(DEFAULT_LIBRARY_ID).unwrap();
+        assert_eq!(reloaded.current().id, DEFAULT_LIBRARY_ID);
+        assert_eq!(reloaded.last_opened().id, work.id);
+
+        assert!(matches!(
+            reloaded.mark_opened("missing"),
+            Err(LibraryError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_corrupt_registry_falls_back_to_default() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join(LIBRARY_REGISTRY_FILE);
+        fs::write(&config_path, "{ not json").unwrap();
+
+        let registry = LibraryRegistry::load(temp_dir.path(), temp_dir.path().join("DeskLab")).unwrap();
+        assert_eq!(registry.list().len(), 1);
+        assert_eq!(registry.last_opened().id, DEFAULT_LIBRARY_ID);
+
+        // 损坏的配置文件改名保留
+        assert!(!config_path.exists());
+        let kept: Vec<_> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|name| name.starts_with("libraries.json.") && name.ends_with(".bad"))
+            .collect();
+        assert_eq!(kept.len(), 1);
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join(&kept[0])).unwrap(),
+            "{ not json"
+        );
+    }
+}
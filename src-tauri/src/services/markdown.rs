@@ -0,0 +1,407 @@
+//! Markdown 解析辅助
+//!
+//! 提供笔记标题解析等轻量级 Markdown 处理功能
+
+/// 从 Markdown 内容中解析笔记标题
+///
+/// 优先级：
+/// 1. Front matter 中的 `title:` 字段
+/// 2. 正文中第一个一级标题（ATX `# 标题` 或 Setext `标题\n===`）
+///
+/// 围栏代码块（``` 或 ~~~）和缩进代码块中的内容会被忽略。
+pub fn resolve_note_title(content: &str) -> Option<String> {
+    let content = content.trim_start_matches('\u{feff}');
+    let (front_matter, body) = split_front_matter(content);
+
+    if let Some(title) = front_matter.and_then(front_matter_title) {
+        return Some(title);
+    }
+
+    first_h1(body)
+}
+
+/// 拆分 YAML front matter 与正文
+///
+/// 仅当文档第一行为 `---` 且存在闭合的 `---` 或 `...` 时才视为 front matter
+fn split_front_matter(content: &str) -> (Option<&str>, &str) {
+    let mut lines = content.split_inclusive('\n');
+    let Some(first) = lines.next() else {
+        return (None, content);
+    };
+    if first.trim_end() != "---" {
+        return (None, content);
+    }
+
+    let start = first.len();
+    let mut offset = start;
+    for line in lines {
+        let trimmed = line.trim_end();
+        if trimmed == "---" || trimmed == "..." {
+            let front_matter = &content[start..offset];
+            let body = &content[offset + line.len()..];
+            return (Some(front_matter), body);
+        }
+        offset += line.len();
+    }
+
+    (None, content)
+}
+
+/// 读取 front matter 中的顶层 `title:` 字段
+fn front_matter_title(front_matter: &str) -> Option<String> {
+    front_matter.lines().find_map(|line| {
+        let value = line.strip_prefix("title:")?;
+        let value = strip_quotes(value.trim());
+        if value.is_empty() {
+            None
+        } else {
+            Some(value.to_string())
+        }
+    })
+}
+
+fn strip_quotes(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote) {
+            return &value[1..value.len() - 1];
+        }
+    }
+    value
+}
+
+/// 查找正文中的第一个一级标题
+fn first_h1(body: &str) -> Option<String> {
+    let mut fence: Option<(char, usize)> = None;
+    let mut paragraph: Vec<&str> = Vec::new();
+
+    for line in body.lines() {
+        // 围栏代码块内只关心闭合围栏
+        if let Some((fence_char, fence_len)) = fence {
+            if is_closing_fence(line, fence_char, fence_len) {
+                fence = None;
+            }
+            continue;
+        }
+
+        let indent = leading_spaces(line);
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() {
+            paragraph.clear();
+            continue;
+        }
+
+        // 缩进代码块（段落内的续行除外）
+        if indent >= 4 {
+            if !paragraph.is_empty() {
+                paragraph.push(trimmed);
+            }
+            continue;
+        }
+
+        if let Some(opening) = opening_fence(trimmed) {
+            fence = Some(opening);
+            paragraph.clear();
+            continue;
+        }
+
+        if let Some((level, text)) = atx_heading(trimmed) {
+            if level == 1 && !text.is_empty() {
+                return Some(text.to_string());
+            }
+            paragraph.clear();
+            continue;
+        }
+
+        if !paragraph.is_empty() && is_setext_underline(trimmed, '=') {
+            return Some(paragraph.join(" "));
+        }
+
+        // Setext 二级标题和分隔线会结束当前段落
+        if is_setext_underline(trimmed, '-') || is_thematic_break(trimmed) {
+            paragraph.clear();
+            continue;
+        }
+
+        paragraph.push(trimmed);
+    }
+
+    None
+}
+
+fn leading_spaces(line: &str) -> usize {
+    let mut count = 0;
+    for ch in line.chars() {
+        match ch {
+            ' ' => count += 1,
+            '\t' => count += 4,
+            _ => break,
+        }
+    }
+    count
+}
+
+/// 解析 ATX 标题，返回 (级别, 标题文本)
+fn atx_heading(trimmed: &str) -> Option<(usize, &str)> {
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+
+    let rest = &trimmed[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+
+    // 去除可选的结尾 # 序列
+    let mut text = rest.trim();
+    let without_closing = text.trim_end_matches('#');
+    if without_closing.is_empty() || without_closing.ends_with([' ', '\t']) {
+        text = without_closing.trim_end();
+    }
+
+    Some((level, text))
+}
+
+fn opening_fence(trimmed: &str) -> Option<(char, usize)> {
+    let fence_char = trimmed.chars().next()?;
+    if fence_char != '`' && fence_char != '~' {
+        return None;
+    }
+
+    let len = trimmed.chars().take_while(|c| *c == fence_char).count();
+    if len < 3 {
+        return None;
+    }
+
+    // 反引号围栏的信息字符串中不能包含反引号
+    if fence_char == '`' && trimmed[len..].contains('`') {
+        return None;
+    }
+
+    Some((fence_char, len))
+}
+
+fn is_closing_fence(line: &str, fence_char: char, fence_len: usize) -> bool {
+    if leading_spaces(line) >= 4 {
+        return false;
+    }
+    let trimmed = line.trim();
+    let len = trimmed.chars().take_while(|c| *c == fence_char).count();
+    len >= fence_len && trimmed[len..].trim().is_empty()
+}
+
+fn is_setext_underline(trimmed: &str, marker: char) -> bool {
+    !trimmed.is_empty() && trimmed.chars().all(|c| c == marker)
+}
+
+fn is_thematic_break(trimmed: &str) -> bool {
+    ['-', '*', '_'].iter().any(|marker| {
+        let mut count = 0;
+        for ch in trimmed.chars() {
+            if ch == *marker {
+                count += 1;
+            } else if ch != ' ' && ch != '\t' {
+                return false;
+            }
+        }
+        count >= 3
+    })
+}
+
+/// 按标题拆分出的 Markdown 段落
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownSection {
+    /// 段落标题（第一个标题之前的前言部分为 None）
+    pub title: Option<String>,
+    /// 段落内容（包含标题行）
+    pub content: String,
+}
+
+/// 按 ATX 标题拆分 Markdown 内容
+///
+/// 级别不高于 `level` 的标题都会开始新的段落，代码块中的 `#` 行不会被当作标题。
+/// front matter 会被丢弃，空白的前言部分会被忽略。
+pub fn split_sections(content: &str, level: usize) -> Vec<MarkdownSection> {
+    let content = content.trim_start_matches('\u{feff}');
+    let (_, body) = split_front_matter(content);
+
+    let mut sections = Vec::new();
+    let mut current = MarkdownSection {
+        title: None,
+        content: String::new(),
+    };
+    let mut fence: Option<(char, usize)> = None;
+
+    for line in body.split_inclusive('\n') {
+        if let Some((fence_char, fence_len)) = fence {
+            if is_closing_fence(line, fence_char, fence_len) {
+                fence = None;
+            }
+        } else if leading_spaces(line) < 4 {
+            let trimmed = line.trim();
+            if let Some(opening) = opening_fence(trimmed) {
+                fence = Some(opening);
+            } else if let Some((heading_level, text)) = atx_heading(trimmed) {
+                if heading_level <= level {
+                    push_section(&mut sections, current);
+                    current = MarkdownSection {
+                        title: Some(text.to_string()),
+                        content: String::new(),
+                    };
+                }
+            }
+        }
+        current.content.push_str(line);
+    }
+    push_section(&mut sections, current);
+
+    sections
+}
+
+fn push_section(sections: &mut Vec<MarkdownSection>, mut section: MarkdownSection) {
+    if section.title.is_none() && section.content.trim().is_empty() {
+        return;
+    }
+    section.content = format!("{}\n", section.content.trim());
+    sections.push(section);
+}
+
+/// 合并多篇 Markdown 文档，每篇文档前插入一级标题作为分隔
+///
+/// 文档的 front matter 会被去除；文档以相同的一级标题开头时不重复输出该标题。
+pub fn merge_documents(documents: &[(String, String)]) -> String {
+    let mut merged = String::new();
+
+    for (title, content) in documents {
+        let content = content.trim_start_matches('\u{feff}');
+        let (_, body) = split_front_matter(content);
+        let body = body.trim_start();
+
+        // 去掉与分隔标题重复的开头一级标题
+        let first_line = body.lines().next().unwrap_or_default();
+        let body = match atx_heading(first_line.trim()) {
+            Some((1, text)) if text == title.trim() => body[first_line.len()..].trim(),
+            _ => body.trim(),
+        };
+
+        if !merged.is_empty() {
+            merged.push('\n');
+        }
+        merged.push_str(&format!("# {}\n", title.trim()));
+        if !body.is_empty() {
+            merged.push('\n');
+            merged.push_str(body);
+            merged.push('\n');
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_atx_heading() {
+        assert_eq!(
+            resolve_note_title("# 我的笔记\n\n这是内容"),
+            Some("我的笔记".to_string())
+        );
+        assert_eq!(
+            resolve_note_title("## 二级标题\n# 一级标题"),
+            Some("一级标题".to_string())
+        );
+        assert_eq!(
+            resolve_note_title("# 带结尾符号 ##"),
+            Some("带结尾符号".to_string())
+        );
+        assert_eq!(resolve_note_title("#没有空格"), None);
+        assert_eq!(resolve_note_title("没有标题的内容"), None);
+        assert_eq!(resolve_note_title(""), None);
+    }
+
+    #[test]
+    fn test_resolve_ignores_fenced_code() {
+        let content = "```bash\n# 这是注释\n```\n\n# 真正的标题";
+        assert_eq!(resolve_note_title(content), Some("真正的标题".to_string()));
+
+        let content = "~~~~\n# 注释\n~~~\n# 仍在代码块内\n~~~~\n";
+        assert_eq!(resolve_note_title(content), None);
+    }
+
+    #[test]
+    fn test_resolve_ignores_indented_code() {
+        let content = "    # 缩进代码\n\n正文";
+        assert_eq!(resolve_note_title(content), None);
+    }
+
+    #[test]
+    fn test_resolve_setext_heading() {
+        assert_eq!(
+            resolve_note_title("会议纪要\n====\n\n内容"),
+            Some("会议纪要".to_string())
+        );
+        assert_eq!(
+            resolve_note_title("第一行\n第二行\n===\n"),
+            Some("第一行 第二行".to_string())
+        );
+        // Setext 二级标题不作为标题
+        assert_eq!(resolve_note_title("副标题\n---\n"), None);
+        // 空行后的 === 不构成标题
+        assert_eq!(resolve_note_title("段落\n\n===\n"), None);
+    }
+
+    #[test]
+    fn test_resolve_front_matter_title() {
+        let content = "---\ntitle: \"季度报告\"\ntags: [a, b]\n---\n# 正文标题\n";
+        assert_eq!(resolve_note_title(content), Some("季度报告".to_string()));
+
+        // front matter 中没有 title 时回退到正文标题，且不会把 front matter 当作段落
+        let content = "---\nauthor: 张三\n---\n# 正文标题\n";
+        assert_eq!(resolve_note_title(content), Some("正文标题".to_string()));
+
+        // 未闭合的 front matter 按普通内容处理
+        let content = "---\ntitle: 未闭合\n";
+        assert_eq!(resolve_note_title(content), None);
+    }
+
+    #[test]
+    fn test_split_sections() {
+        let content = "---\ntitle: 长笔记\n---\n前言\n\n## 第一节\n内容一\n```\n## 代码\n```\n### 小节\n## 第二节\n内容二\n";
+        let sections = split_sections(content, 2);
+        assert_eq!(sections.len(), 3);
+        assert_eq!(sections[0].title, None);
+        assert_eq!(sections[0].content, "前言\n");
+        assert_eq!(sections[1].title.as_deref(), Some("第一节"));
+        assert_eq!(
+            sections[1].content,
+            "## 第一节\n内容一\n```\n## 代码\n```\n### 小节\n"
+        );
+        assert_eq!(sections[2].title.as_deref(), Some("第二节"));
+
+        // 更高级别的标题同样开始新段落，空白前言被忽略
+        let sections = split_sections("\n# 一\n## 二\n### 三\n", 2);
+        let titles: Vec<_> = sections.iter().map(|s| s.title.as_deref()).collect();
+        assert_eq!(titles, vec![Some("一"), Some("二")]);
+    }
+
+    #[test]
+    fn test_merge_documents() {
+        let merged = merge_documents(&[
+            ("周一会议".to_string(), "# 周一会议\n\n讨论排期".to_string()),
+            ("周二会议".to_string(), "---\nauthor: 张三\n---\n决定上线".to_string()),
+            ("空笔记".to_string(), String::new()),
+        ]);
+        assert_eq!(
+            merged,
+            "# 周一会议\n\n讨论排期\n\n# 周二会议\n\n决定上线\n\n# 空笔记\n"
+        );
+
+        // 合并结果可以按一级标题拆回原来的笔记
+        let sections = split_sections(&merged, 1);
+        let titles: Vec<_> = sections.iter().map(|s| s.title.clone().unwrap()).collect();
+        assert_eq!(titles, vec!["周一会议", "周二会议", "空笔记"]);
+    }
+}
@@ -3,13 +3,27 @@
 pub mod file_service;
 pub mod embedding;
 pub mod text_extractor;
+pub mod markdown;
 pub mod keychain;
 pub mod export;
 pub mod ppt_export;
+pub mod archive;
+pub mod library;
+pub mod backup;
+pub mod sync;
+pub mod ranking;
+pub mod search_query;
 
 pub use file_service::*;
 pub use embedding::*;
 pub use text_extractor::*;
+pub use markdown::*;
 pub use keychain::*;
 pub use export::*;
 pub use ppt_export::*;
+pub use archive::*;
+pub use library::*;
+pub use backup::*;
+pub use sync::*;
+pub use ranking::*;
+pub use search_query::*;
@@ -545,6 +545,7 @@ mod tests {
                         color: Some("#ffffff".to_string()),
                         image: None,
                     }),
+                    remark: None,
                 },
             ],
             theme: Some(PptTheme::default()),
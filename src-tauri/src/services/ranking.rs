@@ -0,0 +1,179 @@
+//! 检索结果融合
+//!
+//! 将全文检索（bm25）与向量检索的排名按倒数排名融合（Reciprocal Rank Fusion，RRF）
+//! 合并为一个列表。两路得分的量纲不同，只使用排名即可直接合并。
+
+use crate::models::{ScoreBreakdown, SearchResult};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// RRF 平滑常数（常用取值 60，越大排名靠后的结果衰减越慢）
+pub const RRF_K: f32 = 60.0;
+
+/// 两路检索在融合中的权重
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HybridWeights {
+    pub lexical: f32,
+    pub semantic: f32,
+}
+
+impl Default for HybridWeights {
+    fn default() -> Self {
+        Self {
+            lexical: 1.0,
+            semantic: 1.0,
+        }
+    }
+}
+
+/// 融合全文检索与向量检索的结果
+///
+/// `lexical` 与 `semantic` 中的每个列表各自按相关度降序排列（全文检索的得分在不同内容类型间
+/// 不可比，通常每种类型一个列表）。同一条目（类型 + ID）在每个列表中的排名贡献
+/// `weight / (RRF_K + rank)`，累加后降序排列，同分时较新的在前。
+///
+/// 结果的 `score` 为融合得分相对理论最大值（两路都排第一）的比例，
+/// 摘要优先使用全文检索的高亮摘要。
+pub fn fuse_rankings(
+    lexical: Vec<Vec<SearchResult>>,
+    semantic: Vec<Vec<SearchResult>>,
+    weights: HybridWeights,
+    limit: usize,
+) -> Vec<SearchResult> {
+    let mut fused: HashMap<(String, String), SearchResult> = HashMap::new();
+
+    let mut add = |results: Vec<SearchResult>, is_lexical: bool| {
+        for (index, result) in results.into_iter().enumerate() {
+            let rank = index as u32 + 1;
+            let weight = if is_lexical {
+                weights.lexical
+            } else {
+                weights.semantic
+            };
+            let key = (result.result_type.clone(), result.id.clone());
+            let score = result.score;
+            let entry = fused.entry(key).or_insert_with(|| SearchResult {
+                score_breakdown: Some(ScoreBreakdown::default()),
+                ..result
+            });
+            let breakdown = entry.score_breakdown.get_or_insert_with(Default::default);
+            breakdown.fused += weight / (RRF_K + rank as f32);
+            if is_lexical {
+                breakdown.lexical_rank = Some(rank);
+                breakdown.lexical_score = score;
+            } else {
+                breakdown.semantic_rank = Some(rank);
+                breakdown.semantic_score = score;
+            }
+        }
+    };
+
+    // 先加入全文检索结果，条目的摘要来自全文检索
+    for results in lexical {
+        add(results, true);
+    }
+    for results in semantic {
+        add(results, false);
+    }
+
+    let max_fused = (weights.lexical + weights.semantic) / (RRF_K + 1.0);
+    let mut results: Vec<SearchResult> = fused
+        .into_values()
+        .map(|mut result| {
+            let fused = result
+                .score_breakdown
+                .as_ref()
+                .map(|breakdown| breakdown.fused)
+                .unwrap_or(0.0);
+            result.score = Some(if max_fused > 0.0 {
+                fused / max_fused
+            } else {
+                0.0
+            });
+            result
+        })
+        .collect();
+
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| b.updated_at.cmp(&a.updated_at))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    results.truncate(limit);
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn result(result_type: &str, id: &str, score: f32) -> SearchResult {
+        SearchResult {
+            result_type: result_type.to_string(),
+            id: id.to_string(),
+            title: id.to_string(),
+            snippet: format!("{} 摘要", id),
+            score: Some(score),
+            project_id: "p1".to_string(),
+            project_name: "项目".to_string(),
+            updated_at: Utc::now(),
+            score_breakdown: None,
+            snippets: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_fuse_rankings_combines_ranks() {
+        let lexical = vec![
+            vec![result("source", "a", 8.0), result("source", "b", 5.0)],
+            vec![result("note", "a", 3.0)],
+        ];
+        let semantic = vec![vec![
+            result("source", "b", 0.9),
+            result("source", "c", 0.8),
+            result("source", "a", 0.2),
+        ]];
+
+        let fused = fuse_rankings(lexical, semantic, HybridWeights::default(), 10);
+        let keys: Vec<(&str, &str)> = fused
+            .iter()
+            .map(|r| (r.result_type.as_str(), r.id.as_str()))
+            .collect();
+        // 两路都命中的排在只命中一路的之前；同 ID 不同类型分别计分
+        assert_eq!(keys[..2], [("source", "b"), ("source", "a")]);
+        assert_eq!(fused.len(), 4);
+
+        let b = &fused[0];
+        let breakdown = b.score_breakdown.as_ref().unwrap();
+        assert_eq!(breakdown.lexical_rank, Some(2));
+        assert_eq!(breakdown.lexical_score, Some(5.0));
+        assert_eq!(breakdown.semantic_rank, Some(1));
+        assert_eq!(breakdown.semantic_score, Some(0.9));
+        assert!((breakdown.fused - (1.0 / 62.0 + 1.0 / 61.0)).abs() < 1e-6);
+        assert!(b.score.unwrap() < 1.0 && b.score.unwrap() > 0.9);
+        assert_eq!(b.snippet, "b 摘要");
+
+        let c = fused.iter().find(|r| r.id == "c").unwrap();
+        assert_eq!(c.score_breakdown.as_ref().unwrap().lexical_rank, None);
+    }
+
+    #[test]
+    fn test_fuse_rankings_weights_and_limit() {
+        let lexical = vec![vec![result("note", "x", 1.0)]];
+        let semantic = vec![vec![result("note", "y", 0.5)]];
+        let weights = HybridWeights {
+            lexical: 0.5,
+            semantic: 1.5,
+        };
+
+        let fused = fuse_rankings(lexical, semantic, weights, 1);
+        assert_eq!(fused.len(), 1);
+        assert_eq!(fused[0].id, "y");
+        assert!((fused[0].score.unwrap() - 0.75).abs() < 1e-6);
+
+        assert!(fuse_rankings(Vec::new(), Vec::new(), weights, 10).is_empty());
+    }
+}
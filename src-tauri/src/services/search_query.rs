@@ -0,0 +1,399 @@
+//! 搜索查询语法
+//!
+//! 将搜索框输入解析为语法树，由数据库层编译为 SQL 与 FTS5 检索条件。
+//!
+//! 支持的语法：
+//! - 普通词：`向量 检索`，多个条件之间为 AND
+//! - 短语：`"知识 图谱"`，引号内按原样匹配（可包含空格）
+//! - 字段过滤：`type:source`、`project:"项目名"`、`workspace:法务`、`tag:待读`、
+//!   `after:2026-01-01`、`before:2026-02-01`、`ext:pdf`
+//! - 排除：`-草稿`、`-type:canvas`
+//! - 或：`type:note OR type:source`，OR 的优先级高于相邻条件之间的 AND
+//!
+//! 未知字段（如 `http://` 中的冒号）按普通词处理。
+
+use chrono::NaiveDate;
+use thiserror::Error;
+
+/// 查询解析错误
+#[derive(Error, Debug, PartialEq)]
+pub enum QueryError {
+    #[error("引号未闭合")]
+    UnclosedQuote,
+    #[error("{0} 缺少取值")]
+    MissingValue(String),
+    #[error("未知的内容类型: {0}")]
+    UnknownType(String),
+    #[error("无效的日期（应为 YYYY-MM-DD）: {0}")]
+    InvalidDate(String),
+    #[error("OR 两侧缺少条件")]
+    DanglingOr,
+}
+
+/// 可按 `type:` 过滤的内容类型
+pub const SEARCH_TYPES: [&str; 7] = [
+    "project",
+    "source",
+    "note",
+    "canvas",
+    "mindmap",
+    "presentation",
+    "chat",
+];
+
+/// 字段过滤条件
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryFilter {
+    /// 内容类型（见 [`SEARCH_TYPES`]）
+    Type(String),
+    /// 所属项目（名称或 ID）
+    Project(String),
+    /// 所属项目的工作空间（名称或 ID）
+    Workspace(String),
+    /// 标签
+    Tag(String),
+    /// 更新日期不早于该日
+    After(NaiveDate),
+    /// 更新日期早于该日
+    Before(NaiveDate),
+    /// 文件扩展名（不含点，小写）
+    Ext(String),
+}
+
+/// 查询语法树节点
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryNode {
+    /// 文本（词或短语）
+    Text(String),
+    Filter(QueryFilter),
+    Not(Box<QueryNode>),
+    And(Vec<QueryNode>),
+    Or(Vec<QueryNode>),
+}
+
+/// 解析后的搜索查询
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchQuery {
+    pub root: QueryNode,
+}
+
+impl SearchQuery {
+    /// 解析查询语法，输入为空时返回 None
+    pub fn parse(input: &str) -> Result<Option<Self>, QueryError> {
+        let tokens = tokenize(input)?;
+
+        // 相邻条件为 AND，OR 连接其两侧的条件
+        let mut groups: Vec<Vec<QueryNode>> = Vec::new();
+        let mut pending_or = false;
+        for token in tokens {
+            match token {
+                Token::Or => {
+                    if groups.is_empty() || pending_or {
+                        return Err(QueryError::DanglingOr);
+                    }
+                    pending_or = true;
+                }
+                Token::Node(node) => {
+                    match groups.last_mut() {
+                        Some(group) if pending_or => group.push(node),
+                        _ => groups.push(vec![node]),
+                    }
+                    pending_or = false;
+                }
+            }
+        }
+        if pending_or {
+            return Err(QueryError::DanglingOr);
+        }
+
+        let mut nodes: Vec<QueryNode> = groups
+            .into_iter()
+            .map(|mut group| {
+                if group.len() == 1 {
+                    group.remove(0)
+                } else {
+                    QueryNode::Or(group)
+                }
+            })
+            .collect();
+        Ok(match nodes.len() {
+            0 => None,
+            1 => Some(Self {
+                root: nodes.remove(0),
+            }),
+            _ => Some(Self {
+                root: QueryNode::And(nodes),
+            }),
+        })
+    }
+
+    /// 不解析语法，按空白拆分为词（各词为 AND 关系），输入为空时返回 None
+    pub fn plain(input: &str) -> Option<Self> {
+        let mut words: Vec<QueryNode> = Vec::new();
+        for word in input.split_whitespace() {
+            let node = QueryNode::Text(word.to_string());
+            if !words.contains(&node) {
+                words.push(node);
+            }
+        }
+        match words.len() {
+            0 => None,
+            1 => Some(Self {
+                root: words.remove(0),
+            }),
+            _ => Some(Self {
+                root: QueryNode::And(words),
+            }),
+        }
+    }
+
+    /// 参与相关度计算与摘要高亮的文本（不含被排除的文本），已去重
+    pub fn positive_terms(&self) -> Vec<&str> {
+        let mut terms = Vec::new();
+        collect_terms(&self.root, &mut terms);
+        terms
+    }
+}
+
+fn collect_terms<'a>(node: &'a QueryNode, terms: &mut Vec<&'a str>) {
+    match node {
+        QueryNode::Text(text) => {
+            if !terms.contains(&text.as_str()) {
+                terms.push(text);
+            }
+        }
+        QueryNode::And(nodes) | QueryNode::Or(nodes) => {
+            for node in nodes {
+                collect_terms(node, terms);
+            }
+        }
+        QueryNode::Filter(_) | QueryNode::Not(_) => {}
+    }
+}
+
+enum Token {
+    Or,
+    Node(QueryNode),
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, QueryError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        if chars[pos].is_whitespace() {
+            pos += 1;
+            continue;
+        }
+
+        // 单独的 `-` 按普通词处理
+        let negated =
+            chars[pos] == '-' && chars.get(pos + 1).is_some_and(|next| !next.is_whitespace());
+        if negated {
+            pos += 1;
+        }
+
+        let node = if chars[pos] == '"' {
+            let (phrase, end) = read_quoted(&chars, pos)?;
+            pos = end;
+            if phrase.trim().is_empty() {
+                continue;
+            }
+            QueryNode::Text(phrase)
+        } else {
+            let start = pos;
+            while pos < chars.len() && !chars[pos].is_whitespace() && chars[pos] != ':' {
+                pos += 1;
+            }
+            let word: String = chars[start..pos].iter().collect();
+
+            if pos < chars.len() && chars[pos] == ':' && is_field(&word) {
+                // 字段取值可以加引号
+                pos += 1;
+                let value = if chars.get(pos) == Some(&'"') {
+                    let (value, end) = read_quoted(&chars, pos)?;
+                    pos = end;
+                    value
+                } else {
+                    let value_start = pos;
+                    while pos < chars.len() && !chars[pos].is_whitespace() {
+                        pos += 1;
+                    }
+                    chars[value_start..pos].iter().collect()
+                };
+                QueryNode::Filter(parse_filter(&word, value.trim())?)
+            } else {
+                // 不是字段，冒号及之后的内容属于同一个词
+                while pos < chars.len() && !chars[pos].is_whitespace() {
+                    pos += 1;
+                }
+                let word: String = chars[start..pos].iter().collect();
+                if word == "OR" && !negated {
+                    tokens.push(Token::Or);
+                    continue;
+                }
+                QueryNode::Text(word)
+            }
+        };
+
+        tokens.push(Token::Node(if negated {
+            QueryNode::Not(Box::new(node))
+        } else {
+            node
+        }));
+    }
+
+    Ok(tokens)
+}
+
+/// 读取从 `start`（引号位置）开始的引号内容，返回内容与闭合引号之后的位置
+fn read_quoted(chars: &[char], start: usize) -> Result<(String, usize), QueryError> {
+    let end = chars[start + 1..]
+        .iter()
+        .position(|c| *c == '"')
+        .map(|offset| start + 1 + offset)
+        .ok_or(QueryError::UnclosedQuote)?;
+    Ok((chars[start + 1..end].iter().collect(), end + 1))
+}
+
+fn is_field(name: &str) -> bool {
+    matches!(
+        name,
+        "type" | "project" | "workspace" | "tag" | "after" | "before" | "ext"
+    )
+}
+
+fn parse_filter(field: &str, value: &str) -> Result<QueryFilter, QueryError> {
+    if value.is_empty() {
+        return Err(QueryError::MissingValue(format!("{}:", field)));
+    }
+    let parse_date = |value: &str| {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map_err(|_| QueryError::InvalidDate(value.to_string()))
+    };
+
+    Ok(match field {
+        "type" => {
+            let value = value.to_lowercase();
+            if !SEARCH_TYPES.contains(&value.as_str()) {
+                return Err(QueryError::UnknownType(value));
+            }
+            QueryFilter::Type(value)
+        }
+        "project" => QueryFilter::Project(value.to_string()),
+        "workspace" => QueryFilter::Workspace(value.to_string()),
+        "tag" => QueryFilter::Tag(value.to_string()),
+        "after" => QueryFilter::After(parse_date(value)?),
+        "before" => QueryFilter::Before(parse_date(value)?),
+        _ => QueryFilter::Ext(value.trim_start_matches('.').to_lowercase()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(value: &str) -> QueryNode {
+        QueryNode::Text(value.to_string())
+    }
+
+    #[test]
+    fn test_parse_fields_phrases_and_exclusions() {
+        let query = SearchQuery::parse(
+            r#"type:source project:"季度 复盘" workspace:法务 tag:待读 after:2026-01-01 ext:.PDF "知识 图谱" -草稿"#,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            query.root,
+            QueryNode::And(vec![
+                QueryNode::Filter(QueryFilter::Type("source".to_string())),
+                QueryNode::Filter(QueryFilter::Project("季度 复盘".to_string())),
+                QueryNode::Filter(QueryFilter::Workspace("法务".to_string())),
+                QueryNode::Filter(QueryFilter::Tag("待读".to_string())),
+                QueryNode::Filter(QueryFilter::After(
+                    NaiveDate::from_ymd_opt(2026, 1, 1).unwrap()
+                )),
+                QueryNode::Filter(QueryFilter::Ext("pdf".to_string())),
+                text("知识 图谱"),
+                QueryNode::Not(Box::new(text("草稿"))),
+            ])
+        );
+        assert_eq!(query.positive_terms(), vec!["知识 图谱"]);
+    }
+
+    #[test]
+    fn test_parse_or_binds_tighter_than_and() {
+        let query = SearchQuery::parse("type:note OR type:source 检索 OR 排序 -type:canvas")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            query.root,
+            QueryNode::And(vec![
+                QueryNode::Or(vec![
+                    QueryNode::Filter(QueryFilter::Type("note".to_string())),
+                    QueryNode::Filter(QueryFilter::Type("source".to_string())),
+                ]),
+                QueryNode::Or(vec![text("检索"), text("排序")]),
+                QueryNode::Not(Box::new(QueryNode::Filter(QueryFilter::Type(
+                    "canvas".to_string()
+                )))),
+            ])
+        );
+        assert_eq!(query.positive_terms(), vec!["检索", "排序"]);
+    }
+
+    #[test]
+    fn test_parse_plain_words() {
+        assert_eq!(SearchQuery::parse("   ").unwrap(), None);
+        assert_eq!(SearchQuery::parse(r#""  ""#).unwrap(), None);
+
+        // 未知字段、小写 or、单独的减号都是普通词
+        let query = SearchQuery::parse("https://example.com or - 单词")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            query.root,
+            QueryNode::And(vec![
+                text("https://example.com"),
+                text("or"),
+                text("-"),
+                text("单词"),
+            ])
+        );
+
+        let plain = SearchQuery::plain("type:note 检索 检索").unwrap();
+        assert_eq!(
+            plain.root,
+            QueryNode::And(vec![text("type:note"), text("检索")])
+        );
+        assert_eq!(SearchQuery::plain(" "), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            SearchQuery::parse(r#""未闭合"#),
+            Err(QueryError::UnclosedQuote)
+        );
+        assert_eq!(
+            SearchQuery::parse("type:video"),
+            Err(QueryError::UnknownType("video".to_string()))
+        );
+        assert_eq!(
+            SearchQuery::parse("after:2026-13-01"),
+            Err(QueryError::InvalidDate("2026-13-01".to_string()))
+        );
+        assert_eq!(
+            SearchQuery::parse("tag: 待读"),
+            Err(QueryError::MissingValue("tag:".to_string()))
+        );
+        assert_eq!(SearchQuery::parse("OR 检索"), Err(QueryError::DanglingOr));
+        assert_eq!(SearchQuery::parse("检索 OR"), Err(QueryError::DanglingOr));
+        assert_eq!(
+            SearchQuery::parse("检索 OR OR 排序"),
+            Err(QueryError::DanglingOr)
+        );
+    }
+}
@@ -0,0 +1,271 @@
+//! 同步文件夹
+//!
+//! 共享文件夹布局：
+//! - desklab-sync.json：格式版本
+//! - changes/{device_id}.jsonl：各设备只追加写入的变更日志
+//! - blobs/{hash 前两位}/{hash}：按内容寻址的数据文件
+//!
+//! 共享文件夹可以是任意目录（Syncthing、NAS 或 U 盘），不需要同步服务。
+
+use crate::models::{SyncChange, SYNC_FORMAT_VERSION};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// 同步文件夹格式文件名
+const SYNC_FORMAT_FILE: &str = "desklab-sync.json";
+
+/// 同步错误类型
+#[derive(Error, Debug)]
+pub enum SyncError {
+    #[error("IO 错误: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("同步数据格式错误: {0}")]
+    Format(#[from] serde_json::Error),
+    #[error("无效的同步文件夹: {0}")]
+    Invalid(String),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SyncFormat {
+    version: u32,
+}
+
+/// 同步文件夹
+pub struct SyncFolder {
+    root: PathBuf,
+}
+
+impl SyncFolder {
+    /// 打开同步文件夹（首次使用时初始化目录结构）
+    pub fn open(root: &Path) -> Result<Self, SyncError> {
+        let format_path = root.join(SYNC_FORMAT_FILE);
+        if format_path.exists() {
+            let format: SyncFormat = serde_json::from_str(&fs::read_to_string(&format_path)?)?;
+            if format.version > SYNC_FORMAT_VERSION {
+                return Err(SyncError::Invalid(format!(
+                    "同步文件夹由更新版本的 DeskLab 创建（格式版本 {}）",
+                    format.version
+                )));
+            }
+        } else {
+            fs::create_dir_all(root)?;
+            let format = SyncFormat {
+                version: SYNC_FORMAT_VERSION,
+            };
+            fs::write(&format_path, serde_json::to_string_pretty(&format)?)?;
+        }
+        fs::create_dir_all(root.join("changes"))?;
+        fs::create_dir_all(root.join("blobs"))?;
+        Ok(Self {
+            root: root.to_path_buf(),
+        })
+    }
+
+    /// 共享文件夹中有变更日志的设备
+    pub fn devices(&self) -> Result<Vec<String>, SyncError> {
+        let mut devices = Vec::new();
+        for entry in fs::read_dir(self.root.join("changes"))? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) == Some("jsonl") {
+                if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    devices.push(stem.to_string());
+                }
+            }
+        }
+        devices.sort();
+        Ok(devices)
+    }
+
+    /// 读取设备在 `after_seq` 之后的变更
+    ///
+    /// 同步工具可能尚未传完日志文件，遇到不完整的行时停止读取，下次同步再继续。
+    pub fn read_changes(
+        &self,
+        device_id: &str,
+        after_seq: u64,
+    ) -> Result<Vec<SyncChange>, SyncError> {
+        let path = self.log_path(device_id);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let mut changes = Vec::new();
+        for line in BufReader::new(File::open(&path)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Ok(change) = serde_json::from_str::<SyncChange>(&line) else {
+                break;
+            };
+            if change.seq > after_seq {
+                changes.push(change);
+            }
+        }
+        Ok(changes)
+    }
+
+    /// 设备日志中最后一条变更的序号
+    pub fn last_seq(&self, device_id: &str) -> Result<u64, SyncError> {
+        Ok(self
+            .read_changes(device_id, 0)?
+            .last()
+            .map(|change| change.seq)
+            .unwrap_or(0))
+    }
+
+    /// 追加本机的变更
+    pub fn append_changes(&self, device_id: &str, changes: &[SyncChange]) -> Result<(), SyncError> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+        let mut buffer = String::new();
+        for change in changes {
+            buffer.push_str(&serde_json::to_string(change)?);
+            buffer.push('\n');
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.log_path(device_id))?;
+        file.write_all(buffer.as_bytes())?;
+        file.sync_all()?;
+        Ok(())
+    }
+
+    /// 把数据文件写入 blobs（内容相同的文件只保存一份），返回内容哈希
+    pub fn put_blob(&self, source: &Path) -> Result<String, SyncError> {
+        let hash = hash_file(source)?;
+        let blob_path = self.blob_path(&hash);
+        if !blob_path.exists() {
+            if let Some(parent) = blob_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let temp_path = blob_path.with_extension("tmp");
+            fs::copy(source, &temp_path)?;
+            fs::rename(&temp_path, &blob_path)?;
+        }
+        Ok(hash)
+    }
+
+    /// 读取 blob 内容并校验哈希
+    pub fn read_blob(&self, hash: &str) -> Result<Vec<u8>, SyncError> {
+        let bytes = fs::read(self.blob_path(hash)).map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => {
+                SyncError::Invalid(format!("数据文件尚未同步到共享文件夹: {}", hash))
+            }
+            _ => SyncError::Io(e),
+        })?;
+        if hash_bytes(&bytes) != hash {
+            return Err(SyncError::Invalid(format!("数据文件不完整: {}", hash)));
+        }
+        Ok(bytes)
+    }
+
+    /// blob 是否已在共享文件夹中
+    pub fn has_blob(&self, hash: &str) -> bool {
+        self.blob_path(hash).is_file()
+    }
+
+    fn blob_path(&self, hash: &str) -> PathBuf {
+        let prefix = hash.get(..2).unwrap_or("00");
+        self.root.join("blobs").join(prefix).join(hash)
+    }
+
+    fn log_path(&self, device_id: &str) -> PathBuf {
+        self.root
+            .join("changes")
+            .join(format!("{}.jsonl", device_id))
+    }
+}
+
+/// 计算内容的 SHA-256（十六进制）
+pub fn hash_bytes(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// 计算文件内容的 SHA-256（十六进制）
+pub fn hash_file(path: &Path) -> Result<String, SyncError> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ContentKind;
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    fn change(seq: u64) -> SyncChange {
+        SyncChange {
+            seq,
+            device_id: "device-a".to_string(),
+            device_name: "台式机".to_string(),
+            kind: ContentKind::Note,
+            item_id: format!("note-{}", seq),
+            changed_at: Utc::now(),
+            hash: String::new(),
+            base_hash: None,
+            snapshot: None,
+            files: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_change_log_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let folder = SyncFolder::open(temp_dir.path()).unwrap();
+        folder
+            .append_changes("device-a", &[change(1), change(2)])
+            .unwrap();
+        folder.append_changes("device-a", &[change(3)]).unwrap();
+
+        assert_eq!(folder.devices().unwrap(), vec!["device-a".to_string()]);
+        assert_eq!(folder.last_seq("device-a").unwrap(), 3);
+        let changes = folder.read_changes("device-a", 1).unwrap();
+        assert_eq!(
+            changes.iter().map(|c| c.seq).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert!(folder.read_changes("device-b", 0).unwrap().is_empty());
+
+        // 传输中的不完整行不会被读取
+        let log = temp_dir.path().join("changes/device-a.jsonl");
+        let mut file = OpenOptions::new().append(true).open(&log).unwrap();
+        file.write_all(b"{\"seq\":4,\"devi").unwrap();
+        assert_eq!(folder.last_seq("device-a").unwrap(), 3);
+    }
+
+    #[test]
+    fn test_blobs_are_content_addressed() {
+        let temp_dir = TempDir::new().unwrap();
+        let folder = SyncFolder::open(&temp_dir.path().join("shared")).unwrap();
+        let file_a = temp_dir.path().join("a.md");
+        let file_b = temp_dir.path().join("b.md");
+        fs::write(&file_a, "# 相同内容").unwrap();
+        fs::write(&file_b, "# 相同内容").unwrap();
+
+        let hash_a = folder.put_blob(&file_a).unwrap();
+        let hash_b = folder.put_blob(&file_b).unwrap();
+        assert_eq!(hash_a, hash_b);
+        assert_eq!(hash_a, hash_bytes("# 相同内容".as_bytes()));
+        assert!(folder.has_blob(&hash_a));
+        assert_eq!(folder.read_blob(&hash_a).unwrap(), "# 相同内容".as_bytes());
+        assert!(matches!(
+            folder.read_blob("ffff"),
+            Err(SyncError::Invalid(_))
+        ));
+    }
+}
@@ -1,17 +1,22 @@
 //! 文本提取服务
 //!
-//! 提供 PDF 和 Word 文档的文本提取功能
+//! 提供 PDF 和 Word 文档的文本提取功能，以及思维导图、PPT 的全文搜索文本提取
 
+use crate::models::{MindMapData, MindMapNode, PptData};
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
 
-/// 从 PDF 文件提取文本
+/// PDF 正文中的分页符，搜索摘要据此计算页码
+pub const PAGE_BREAK: char = '\u{0c}';
+
+/// 从 PDF 文件提取文本，各页之间以换页符（`PAGE_BREAK`）分隔
 pub fn extract_pdf_text(path: &Path) -> Result<String, String> {
     let bytes = std::fs::read(path).map_err(|e| format!("读取文件失败: {}", e))?;
 
-    pdf_extract::extract_text_from_mem(&bytes)
-        .map_err(|e| format!("PDF 文本提取失败: {}", e))
+    let pages = pdf_extract::extract_text_from_mem_by_pages(&bytes)
+        .map_err(|e| format!("PDF 文本提取失败: {}", e))?;
+    Ok(pages.join(&PAGE_BREAK.to_string()))
 }
 
 /// 从 Word 文档提取文本
@@ -35,6 +40,85 @@ pub fn extract_docx_text(path: &Path) -> Result<String, String> {
     Ok(extract_text_from_xml(&xml_content))
 }
 
+/// 从思维导图数据提取文本（节点文本、备注与标签），每个节点一行
+pub fn extract_mindmap_text(data: &MindMapData) -> String {
+    let mut lines = Vec::new();
+    collect_mindmap_text(&data.root, &mut lines);
+    lines.join("\n")
+}
+
+fn collect_mindmap_text(node: &MindMapNode, lines: &mut Vec<String>) {
+    let data = &node.data;
+    // 富文本节点的文本为 HTML
+    let text = if data.rich_text == Some(true) {
+        html_to_text(&data.text)
+    } else {
+        data.text.trim().to_string()
+    };
+    let mut parts: Vec<String> = Vec::new();
+    if !text.is_empty() {
+        parts.push(text);
+    }
+    if let Some(note) = data.note.as_deref().map(str::trim).filter(|note| !note.is_empty()) {
+        parts.push(note.to_string());
+    }
+    if let Some(tags) = &data.tag {
+        parts.extend(tags.iter().map(|tag| tag.trim().to_string()).filter(|tag| !tag.is_empty()));
+    }
+    if !parts.is_empty() {
+        lines.push(parts.join(" "));
+    }
+
+    for child in node.children.iter().flatten() {
+        collect_mindmap_text(child, lines);
+    }
+}
+
+/// 从 PPT 数据提取文本（文本元素与演讲者备注），每张幻灯片一行
+pub fn extract_ppt_text(data: &PptData) -> String {
+    let mut lines = Vec::new();
+    for slide in &data.slides {
+        let mut parts: Vec<String> = slide
+            .elements
+            .iter()
+            .filter(|element| element.element_type == "text")
+            .filter_map(|element| element.content.as_deref())
+            .map(html_to_text)
+            .filter(|text| !text.is_empty())
+            .collect();
+        if let Some(remark) = slide.remark.as_deref().map(html_to_text) {
+            if !remark.is_empty() {
+                parts.push(remark);
+            }
+        }
+        if !parts.is_empty() {
+            lines.push(parts.join(" "));
+        }
+    }
+    lines.join("\n")
+}
+
+/// 从 PPT 数据文件提取文本
+pub fn extract_ppt_file_text(path: &Path) -> Result<String, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))?;
+    let data: PptData =
+        serde_json::from_str(&content).map_err(|e| format!("解析 PPT 数据失败: {}", e))?;
+    Ok(extract_ppt_text(&data))
+}
+
+/// 去除 HTML 标签并还原常见字符实体
+fn html_to_text(html: &str) -> String {
+    extract_text_from_xml(html)
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
+
 /// 从 XML 中提取纯文本
 fn extract_text_from_xml(xml: &str) -> String {
     let mut result = String::new();
@@ -86,4 +170,55 @@ mod tests {
         assert!(text.contains("第一段"));
         assert!(text.contains("第二段"));
     }
+
+    #[test]
+    fn test_extract_mindmap_text() {
+        use crate::models::MindMapNodeData;
+        use std::collections::HashMap;
+
+        let node = |data: MindMapNodeData, children: Vec<MindMapNode>| MindMapNode {
+            data,
+            children: Some(children),
+            extra: HashMap::new(),
+        };
+        let mut rich = MindMapNodeData::new("<p><strong>检索</strong>&amp;排序</p>");
+        rich.rich_text = Some(true);
+        rich.note = Some("bm25 与向量".to_string());
+        let mut tagged = MindMapNodeData::new("  ");
+        tagged.tag = Some(vec!["待办".to_string()]);
+
+        let data = MindMapData {
+            root: node(
+                MindMapNodeData::new("中心主题"),
+                vec![node(rich, vec![node(tagged, Vec::new())])],
+            ),
+            theme: None,
+            layout: None,
+        };
+        assert_eq!(
+            extract_mindmap_text(&data),
+            "中心主题\n检索 &排序 bm25 与向量\n待办"
+        );
+    }
+
+    #[test]
+    fn test_extract_ppt_text() {
+        let data: PptData = serde_json::from_value(serde_json::json!({
+            "slides": [
+                {
+                    "id": "s1",
+                    "elements": [
+                        {"id": "e1", "type": "text", "left": 0, "top": 0, "width": 1, "height": 1,
+                         "content": "<p>季度&nbsp;复盘</p>"},
+                        {"id": "e2", "type": "image", "left": 0, "top": 0, "width": 1, "height": 1,
+                         "content": "ignored"}
+                    ],
+                    "remark": "<p>先讲结论</p>"
+                },
+                {"id": "s2", "elements": []}
+            ]
+        }))
+        .unwrap();
+        assert_eq!(extract_ppt_text(&data), "季度 复盘 先讲结论");
+    }
 }
@@ -1,7 +1,14 @@
-import { useState, useEffect, useRef, useCallback, useMemo } from 'react';
-import { safeInvoke } from '../../utils/tauri';
+import { useState, useEffect, useRef, useCallback, useMemo, type ReactNode } from 'react';
+import { safeInvoke, safeListen } from '../../utils/tauri';
 import { useNavigate } from 'react-router-dom';
-import type { SearchResult, SearchFilters } from '../../types';
+import type {
+  EmbeddingProgress,
+  SearchResult,
+  SearchResultType,
+  SearchFilters,
+  SearchSnippet,
+  SemanticSearchResults,
+} from '../../types';
 import './SearchDialog.css';
 
 interface SearchDialogProps {
@@ -14,8 +21,13 @@ const TYPE_OPTIONS = [
   { value: 'source', label: '来源', icon: 'description' },
   { value: 'note', label: '笔记', icon: 'note' },
   { value: 'canvas', label: '画布', icon: 'gesture' },
+  { value: 'mindmap', label: '思维导图', icon: 'account_tree' },
+  { value: 'presentation', label: 'PPT', icon: 'slideshow' },
+  { value: 'chat', label: '对话', icon: 'chat_bubble_outline' },
 ] as const;
 
+const ALL_TYPES: SearchResultType[] = TYPE_OPTIONS.map((opt) => opt.value);
+
 const TIME_OPTIONS = [
   { value: 'all', label: '全部时间' },
   { value: 'today', label: '今天' },
@@ -24,6 +36,31 @@ const TIME_OPTIONS = [
   { value: 'year', label: '最近一年' },
 ] as const;
 
+/** 渲染搜索摘要，命中词由后端用 <mark> 标记 */
+function renderSnippet(snippet: string) {
+  return snippet.split(/(<mark>.*?<\/mark>)/g).map((part, index) =>
+    part.startsWith('<mark>') && part.endsWith('</mark>') ? (
+      <mark key={index}>{part.slice(6, -7)}</mark>
+    ) : (
+      part
+    )
+  );
+}
+
+/** 按命中位置渲染摘要片段（位置按字符计，需按码点切分） */
+function renderHighlights(snippet: SearchSnippet) {
+  const chars = Array.from(snippet.text);
+  const parts: ReactNode[] = [];
+  let pos = 0;
+  snippet.highlights.forEach(([start, end], index) => {
+    parts.push(chars.slice(pos, start).join(''));
+    parts.push(<mark key={index}>{chars.slice(start, end).join('')}</mark>);
+    pos = end;
+  });
+  parts.push(chars.slice(pos).join(''));
+  return parts;
+}
+
 export function SearchDialog({ open, onClose }: SearchDialogProps) {
   const navigate = useNavigate();
   const [query, setQuery] = useState('');
@@ -31,9 +68,11 @@ export function SearchDialog({ open, onClose }: SearchDialogProps) {
   const [loading, setLoading] = useState(false);
   const [selectedIndex, setSelectedIndex] = useState(0);
   const [semanticMode, setSemanticMode] = useState(false);
+  // 语义索引剩余条目数（0 表示已完成）
+  const [indexingPending, setIndexingPending] = useState(0);
   const [showFilters, setShowFilters] = useState(false);
   const [filters, setFilters] = useState<SearchFilters>({
-    types: ['project', 'source', 'note', 'canvas'],
+    types: ALL_TYPES,
     timeRange: 'all',
   });
   const inputRef = useRef<HTMLInputElement>(null);
@@ -83,12 +122,18 @@ export function SearchDialog({ open, onClose }: SearchDialogProps) {
     const timer = setTimeout(async () => {
       setLoading(true);
       try {
-        const command = semanticMode ? 'search_semantic' : 'search_global';
-        const searchResults = await safeInvoke<SearchResult[]>(command, {
+        const args = {
           query: query.trim(),
           limit: 50, // 增加限制以便过滤后有更多结果
-        });
-        setResults(searchResults);
+        };
+        if (semanticMode) {
+          // 后台仍在生成向量时返回部分结果，不等待
+          const semantic = await safeInvoke<SemanticSearchResults>('search_semantic', args);
+          setResults(semantic.results);
+          setIndexingPending(semantic.indexing ? semantic.pending : 0);
+        } else {
+          setResults(await safeInvoke<SearchResult[]>('search_global', args));
+        }
         setSelectedIndex(0);
       } catch (e) {
         console.error('Search failed:', e);
@@ -101,6 +146,18 @@ export function SearchDialog({ open, onClose }: SearchDialogProps) {
     return () => clearTimeout(timer);
   }, [query, semanticMode]);
 
+  // 语义索引生成中时跟踪进度
+  const indexing = semanticMode && indexingPending > 0;
+  useEffect(() => {
+    if (!indexing) return;
+    const unlisten = safeListen<EmbeddingProgress>('embedding-progress', (progress) => {
+      setIndexingPending(progress.pending);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [indexing]);
+
   // 打开时聚焦输入框
   useEffect(() => {
     if (open) {
@@ -108,6 +165,7 @@ export function SearchDialog({ open, onClose }: SearchDialogProps) {
       setResults([]);
       setSelectedIndex(0);
       setSemanticMode(false);
+      setIndexingPending(0);
       setShowFilters(false);
       setTimeout(() => inputRef.current?.focus(), 50);
     }
@@ -148,13 +206,19 @@ export function SearchDialog({ open, onClose }: SearchDialogProps) {
     } else if (result.type === 'canvas') {
       // 画布跳转到对应项目的画布页面
       navigate(`/project/${result.projectId}?tab=canvas&canvasId=${result.id}`);
+    } else if (result.type === 'mindmap') {
+      navigate(`/project/${result.projectId}?tab=mindmap&mindmapId=${result.id}`);
+    } else if (result.type === 'presentation') {
+      navigate(`/project/${result.projectId}?tab=ppt&pptId=${result.id}`);
+    } else if (result.type === 'chat') {
+      navigate(`/project/${result.projectId}?tab=chat&sessionId=${result.id}`);
     } else {
       // source 或 note 跳转到对应项目
       navigate(`/project/${result.projectId}`);
     }
   };
 
-  const toggleTypeFilter = (type: 'project' | 'source' | 'note' | 'canvas') => {
+  const toggleTypeFilter = (type: SearchResultType) => {
     setFilters((prev) => {
       const types = prev.types.includes(type)
         ? prev.types.filter((t) => t !== type)
@@ -175,6 +239,12 @@ export function SearchDialog({ open, onClose }: SearchDialogProps) {
         return 'note';
       case 'canvas':
         return 'gesture';
+      case 'mindmap':
+        return 'account_tree';
+      case 'presentation':
+        return 'slideshow';
+      case 'chat':
+        return 'chat_bubble_outline';
       default:
         return 'search';
     }
@@ -190,6 +260,12 @@ export function SearchDialog({ open, onClose }: SearchDialogProps) {
         return '笔记';
       case 'canvas':
         return '画布';
+      case 'mindmap':
+        return '思维导图';
+      case 'presentation':
+        return 'PPT';
+      case 'chat':
+        return '对话';
       default:
         return '';
     }
@@ -211,7 +287,7 @@ export function SearchDialog({ open, onClose }: SearchDialogProps) {
   };
 
   // 检查是否有激活的过滤器
-  const hasActiveFilters = filters.types.length < 4 || filters.timeRange !== 'all';
+  const hasActiveFilters = filters.types.length < ALL_TYPES.length || filters.timeRange !== 'all';
 
   if (!open) return null;
 
@@ -290,7 +366,7 @@ export function SearchDialog({ open, onClose }: SearchDialogProps) {
               <button
                 className="filter-clear"
                 onClick={() => {
-                  setFilters({ types: ['project', 'source', 'note', 'canvas'], timeRange: 'all' });
+                  setFilters({ types: ALL_TYPES, timeRange: 'all' });
                   setSelectedIndex(0);
                 }}
               >
@@ -301,6 +377,13 @@ export function SearchDialog({ open, onClose }: SearchDialogProps) {
         )}
 
         <div className="search-results">
+          {indexing && (
+            <div className="search-indexing">
+              <span className="material-icon">hourglass_top</span>
+              <span>语义索引生成中（剩余 {indexingPending} 项），部分结果仅来自全文检索</span>
+            </div>
+          )}
+
           {loading && (
             <div className="search-loading">
               <span>搜索中...</span>
@@ -331,9 +414,19 @@ export function SearchDialog({ open, onClose }: SearchDialogProps) {
                     </span>
                     <div className="result-content">
                       <span className="result-title">{result.title}</span>
-                      {result.snippet && result.snippet !== result.title && (
-                        <span className="result-snippet">{result.snippet}</span>
-                      )}
+                      {result.snippets && result.snippets.length > 0
+                        ? result.snippets.map((snippet, snippetIndex) => (
+                            <span key={snippetIndex} className="result-snippet">
+                              {snippet.page !== undefined && (
+                                <span className="result-page">第 {snippet.page} 页</span>
+                              )}
+                              {renderHighlights(snippet)}
+                            </span>
+                          ))
+                        : result.snippet &&
+                          result.snippet !== result.title && (
+                            <span className="result-snippet">{renderSnippet(result.snippet)}</span>
+                          )}
                     </div>
                     {result.score !== undefined && (
                       <span className="result-score">
@@ -27,6 +27,7 @@ export interface Note {
   title: string;
   path: string;
   outputType: OutputType;
+  titleLocked: boolean;
   createdAt: string;
   updatedAt: string;
 }
@@ -42,9 +42,19 @@ export interface RecentAccess {
   accessedAt: string;
 }
 
+// 搜索结果类型
+export type SearchResultType =
+  | 'project'
+  | 'source'
+  | 'note'
+  | 'canvas'
+  | 'mindmap'
+  | 'presentation'
+  | 'chat';
+
 // 搜索结果
 export interface SearchResult {
-  type: 'project' | 'source' | 'note' | 'canvas';
+  type: SearchResultType;
   id: string;
   title: string;
   snippet: string;
@@ -52,14 +62,81 @@ export interface SearchResult {
   projectId: string;
   projectName: string;
   updatedAt: string;
+  scoreBreakdown?: ScoreBreakdown;
+  snippets?: SearchSnippet[];
+}
+
+// 语义搜索结果（search_semantic），indexing 为 true 时后台仍在生成向量，结果不完整
+export interface SemanticSearchResults {
+  results: SearchResult[];
+  indexing: boolean;
+  pending: number;
+}
+
+// 后台向量生成进度（embedding-progress 事件），embedded 为本轮已生成数
+export interface EmbeddingProgress {
+  embedded: number;
+  pending: number;
+}
+
+// 相关条目推荐（related_items），各组按向量相似度降序
+export interface RelatedItems {
+  sources: SearchResult[];
+  notes: SearchResult[];
+}
+
+// 搜索摘要片段，highlights 为命中词的 [起, 止) 位置（按字符计）
+export interface SearchSnippet {
+  text: string;
+  highlights: [number, number][];
+  page?: number;
+}
+
+// 文档内查找的命中位置（source_find_in），start/end 按字符计
+export interface TextMatch {
+  start: number;
+  end: number;
+  page?: number;
+  context: SearchSnippet;
+}
+
+// 混合检索得分明细（排名从 1 开始）
+export interface ScoreBreakdown {
+  lexicalRank?: number;
+  lexicalScore?: number;
+  semanticRank?: number;
+  semanticScore?: number;
+  fused: number;
 }
 
 // 搜索过滤选项
 export interface SearchFilters {
-  types: ('project' | 'source' | 'note' | 'canvas')[];
+  types: SearchResultType[];
   timeRange: 'all' | 'today' | 'week' | 'month' | 'year';
 }
 
+// 保存的搜索（智能集合），query 使用搜索框的查询语法（含 type:/workspace:/tag: 等过滤）
+export interface SavedSearch {
+  id: string;
+  name: string;
+  query: string;
+  projectId: string | null;
+  includeArchived: boolean;
+  sort: 'relevance' | 'date';
+  trackNew: boolean;
+  lastRunAt: string | null;
+  createdAt: string;
+  updatedAt: string;
+}
+
+// 保存的搜索的运行结果，newIds 为上次运行以来新命中的条目
+export interface SavedSearchRun {
+  search: SavedSearch;
+  results: SearchResult[];
+  since: string | null;
+  newIds: string[];
+}
+
 // 排序方式
 export type SortBy = 'updatedAt' | 'name' | 'createdAt';
 export type SortOrder = 'asc' | 'desc';